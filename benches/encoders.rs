@@ -0,0 +1,96 @@
+//! Benchmarks comparing the encoders on generated constraints.
+//!
+//! For each kind of constraint, size and encoder configuration, this benchmark reports the mean encoding time, the number of clauses and the number of auxiliary variables.
+//! Run it with `cargo bench --bench encoders`; an argument filters the benchmarks by name.
+
+use pblib_rs::{AmkEncoder, Comparator, EncoderConfig, PbConstraint, PbEncoder, PB2CNF};
+use std::time::{Duration, Instant};
+
+const SIZES: [usize; 3] = [10, 50, 200];
+
+const MIN_DURATION: Duration = Duration::from_millis(200);
+
+const PB_ENCODERS: [(&str, PbEncoder); 7] = [
+    ("best", PbEncoder::Best),
+    ("bdd", PbEncoder::Bdd),
+    ("swc", PbEncoder::Swc),
+    ("sorting-networks", PbEncoder::SortingNetworks),
+    ("adder", PbEncoder::Adder),
+    ("binary-merge", PbEncoder::BinaryMerge),
+    ("auto", PbEncoder::Auto),
+];
+
+const AMK_ENCODERS: [(&str, AmkEncoder); 3] = [
+    ("best", AmkEncoder::Best),
+    ("bdd", AmkEncoder::Bdd),
+    ("card", AmkEncoder::Card),
+];
+
+fn cardinality_constraint(n: usize) -> PbConstraint {
+    let terms = (1..=i32::try_from(n).unwrap()).map(|l| (1, l)).collect();
+    PbConstraint::new(terms, Comparator::Geq, i64::try_from(n / 2).unwrap())
+}
+
+fn pb_constraint(n: usize) -> PbConstraint {
+    let terms = (1..=i32::try_from(n).unwrap())
+        .map(|l| (i64::from(l % 7 + 1), l))
+        .collect::<Vec<_>>();
+    let sum = terms.iter().map(|t| t.0).sum::<i64>();
+    PbConstraint::new(terms, Comparator::Geq, sum / 2)
+}
+
+fn bench(name: &str, config: EncoderConfig, constraint: &PbConstraint) {
+    let pb2cnf = PB2CNF::with_config(config);
+    let first_aux_var = i32::try_from(constraint.terms().len()).unwrap() + 1;
+    let start = Instant::now();
+    let mut n_runs = 0_u32;
+    let encoding = loop {
+        let encoding = pb2cnf.encode_constraint(constraint, first_aux_var);
+        n_runs += 1;
+        if start.elapsed() >= MIN_DURATION {
+            break encoding;
+        }
+    };
+    let stats = encoding.stats();
+    println!(
+        "{name:<40} {:>12.3?} {:>10} {:>10}",
+        start.elapsed() / n_runs,
+        stats.n_clauses(),
+        stats.n_aux_vars()
+    );
+}
+
+fn main() {
+    let filter = std::env::args().skip(1).find(|a| !a.starts_with('-'));
+    let selected = |name: &str| filter.as_ref().is_none_or(|f| name.contains(f.as_str()));
+    println!(
+        "{:<40} {:>12} {:>10} {:>10}",
+        "benchmark", "time", "clauses", "aux vars"
+    );
+    for n in SIZES {
+        let constraint = cardinality_constraint(n);
+        for (encoder_name, encoder) in AMK_ENCODERS {
+            let name = format!("card/{n}/{encoder_name}");
+            if selected(&name) {
+                bench(
+                    &name,
+                    EncoderConfig::default().with_amk_encoder(encoder),
+                    &constraint,
+                );
+            }
+        }
+    }
+    for n in SIZES {
+        let constraint = pb_constraint(n);
+        for (encoder_name, encoder) in PB_ENCODERS {
+            let name = format!("pb/{n}/{encoder_name}");
+            if selected(&name) {
+                bench(
+                    &name,
+                    EncoderConfig::default().with_pb_encoder(encoder),
+                    &constraint,
+                );
+            }
+        }
+    }
+}
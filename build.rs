@@ -3,6 +3,7 @@
 //! Rust safe bindings for pblib.
 
 use cc::Build;
+use std::{env, process::Command};
 
 const PBLIB_DIR: &str = "pblib";
 const PBLIB_FILES: [&str; 30] = [
@@ -38,6 +39,15 @@ const PBLIB_FILES: [&str; 30] = [
     "pblib/helper.cpp",
 ];
 
+const SYSTEM_PBLIB_VAR: &str = "PBLIB_RS_SYSTEM_PBLIB";
+const SYSTEM_PBLIB_PKG_NAME: &str = "pblib";
+
+const FFI_DEBUG_FLAGS: [&str; 3] = [
+    "-fsanitize=address,undefined",
+    "-fno-omit-frame-pointer",
+    "-fno-sanitize-recover=undefined",
+];
+
 const MINISAT_DIR: &str = "pblib/cli/minisat";
 const MINISAT_FILES: [&str; 4] = [
     "minisat/utils/Options.cc",
@@ -52,12 +62,18 @@ where
     U: AsRef<str>,
 {
     let mut build = Build::new();
-    build
-        .cpp(true)
-        .warnings(true)
-        .extra_warnings(true)
-        .opt_level(3)
-        .define("NDEBUG", "1");
+    build.cpp(true).warnings(true).extra_warnings(true);
+    if ffi_debug_requested() {
+        build
+            .opt_level(1)
+            .debug(true)
+            .define("PBLIB_RS_FFI_DEBUG", "1");
+        for f in FFI_DEBUG_FLAGS {
+            build.flag(f);
+        }
+    } else {
+        build.opt_level(3).define("NDEBUG", "1");
+    }
     for f in flags {
         build.flag_if_supported(f);
     }
@@ -67,9 +83,62 @@ where
     build.compile(output);
 }
 
+/// Whether the `ffi-debug` feature is enabled, in which case the C++ code is built with sanitizers and assertions.
+fn ffi_debug_requested() -> bool {
+    env::var_os("CARGO_FEATURE_FFI_DEBUG").is_some()
+}
+
+/// The flags needed to build and link against a system-installed pblib, as given by pkg-config.
+#[derive(Default)]
+struct SystemPblib {
+    include_dirs: Vec<String>,
+    link_dirs: Vec<String>,
+    libs: Vec<String>,
+}
+
+fn system_pblib_requested() -> bool {
+    println!("cargo:rerun-if-env-changed={SYSTEM_PBLIB_VAR}");
+    env::var(SYSTEM_PBLIB_VAR).is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+fn probe_system_pblib() -> Option<SystemPblib> {
+    println!("cargo:rerun-if-env-changed=PKG_CONFIG");
+    println!("cargo:rerun-if-env-changed=PKG_CONFIG_PATH");
+    let pkg_config = env::var("PKG_CONFIG").unwrap_or_else(|_| "pkg-config".to_string());
+    let output = Command::new(pkg_config)
+        .args(["--cflags", "--libs", SYSTEM_PBLIB_PKG_NAME])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut system_pblib = SystemPblib::default();
+    for flag in String::from_utf8(output.stdout).ok()?.split_whitespace() {
+        if let Some(dir) = flag.strip_prefix("-I") {
+            system_pblib.include_dirs.push(dir.to_string());
+        } else if let Some(dir) = flag.strip_prefix("-L") {
+            system_pblib.link_dirs.push(dir.to_string());
+        } else if let Some(lib) = flag.strip_prefix("-l") {
+            system_pblib.libs.push(lib.to_string());
+        }
+    }
+    Some(system_pblib)
+}
+
+/// Whether the `std` feature is enabled; without it, the crate does not rely on pblib.
+fn std_requested() -> bool {
+    env::var_os("CARGO_FEATURE_STD").is_some()
+}
+
 fn main() {
+    if !std_requested() {
+        return;
+    }
     println!("cargo:rerun-if-changed=src/cpblib.cc");
     println!("cargo:rerun-if-changed=src/cpblib.h");
+    println!("cargo:rerun-if-changed=src/cminisat.cc");
+    println!("cargo:rerun-if-changed=src/cminisat.h");
+    println!("cargo:rerun-if-changed={PBLIB_DIR}/pblib");
     build_dep(
         &["-std=c++11", "-Wno-class-memaccess"],
         &[MINISAT_DIR],
@@ -81,29 +150,64 @@ fn main() {
         "libminisat.a",
     );
 
-    build_dep(
-        &[
-            "-std=c++11",
-            "-Wno-sign-compare",
-            "-Wno-unused-variable",
-            "-Wno-unused-but-set-variable",
-            "-Wno-unused-parameter",
-            "-Wno-unused-function",
-            "-Wno-unused-private-field",
-        ],
-        &[PBLIB_DIR, MINISAT_DIR],
-        PBLIB_FILES
-            .iter()
-            .map(|f| format!("{PBLIB_DIR}/{f}"))
-            .collect::<Vec<String>>()
-            .as_slice(),
-        "libpb.a",
-    );
+    let system_pblib = if system_pblib_requested() {
+        let system_pblib = probe_system_pblib();
+        if system_pblib.is_none() {
+            println!("cargo:warning={SYSTEM_PBLIB_VAR} is set but pkg-config could not find {SYSTEM_PBLIB_PKG_NAME}; building the vendored pblib");
+        }
+        system_pblib
+    } else {
+        None
+    };
 
+    if system_pblib.is_none() {
+        build_dep(
+            &[
+                "-std=c++11",
+                "-Wno-sign-compare",
+                "-Wno-unused-variable",
+                "-Wno-unused-but-set-variable",
+                "-Wno-unused-parameter",
+                "-Wno-unused-function",
+                "-Wno-unused-private-field",
+            ],
+            &[PBLIB_DIR, MINISAT_DIR],
+            PBLIB_FILES
+                .iter()
+                .map(|f| format!("{PBLIB_DIR}/{f}"))
+                .collect::<Vec<String>>()
+                .as_slice(),
+            "libpb.a",
+        );
+    }
+
+    let cpblib_includes = system_pblib
+        .as_ref()
+        .map_or_else(|| vec![PBLIB_DIR.to_string()], |p| p.include_dirs.clone());
     build_dep(
         &["-std=c++11", "-Wno-sign-compare"],
-        &[PBLIB_DIR],
+        &cpblib_includes,
         &["src/cpblib.cc"],
         "libcpblib.a",
     );
+    if let Some(p) = system_pblib {
+        p.link_dirs
+            .iter()
+            .for_each(|d| println!("cargo:rustc-link-search=native={d}"));
+        p.libs
+            .iter()
+            .for_each(|l| println!("cargo:rustc-link-lib={l}"));
+    }
+
+    build_dep(
+        &["-std=c++11", "-Wno-class-memaccess"],
+        &[MINISAT_DIR],
+        &["src/cminisat.cc"],
+        "libcminisat.a",
+    );
+
+    if ffi_debug_requested() {
+        println!("cargo:rustc-link-lib=dylib=asan");
+        println!("cargo:rustc-link-lib=dylib=ubsan");
+    }
 }
@@ -0,0 +1,46 @@
+//! Encodes the constraints generated by pblib's fuzzer and checks the encodings with the bundled MiniSat.
+//!
+//! The first bytes of the input give the seed of the generator and the number of variables.
+//! For each assignment of the variables, the encoding must be satisfiable under this assignment iff the assignment satisfies the constraints.
+//! Run it with `cargo fuzz run encode_constraints` from the root of the repository.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pblib_rs::{reference, ConstraintFuzzer, MiniSat, PB2CNF};
+
+const MAX_VARS: u8 = 8;
+
+fuzz_target!(|data: &[u8]| {
+    let [s0, s1, s2, s3, n, ..] = *data else {
+        return;
+    };
+    let n_vars = i32::from(n % MAX_VARS + 1);
+    let mut fuzzer = ConstraintFuzzer::new(u32::from_le_bytes([s0, s1, s2, s3]), n_vars);
+    let constraints = fuzzer.generate();
+    let pb2cnf = PB2CNF::new();
+    let mut solver = MiniSat::new();
+    let mut next_free_var_id = n_vars + 1;
+    for constraint in &constraints {
+        next_free_var_id = pb2cnf.encode_constraint_into(constraint, next_free_var_id, &mut solver);
+    }
+    for assignment in 0..1_u32 << n_vars {
+        let assumptions = (1..=n_vars)
+            .map(|v| {
+                if assignment & (1 << (v - 1)) == 0 {
+                    -v
+                } else {
+                    v
+                }
+            })
+            .collect::<Vec<_>>();
+        let expected = constraints
+            .iter()
+            .all(|c| reference::evaluate(c, &assumptions));
+        assert_eq!(
+            expected,
+            solver.solve_with_assumptions(&assumptions),
+            "wrong encoding for {constraints:?} under {assumptions:?}"
+        );
+    }
+});
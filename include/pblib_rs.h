@@ -0,0 +1,80 @@
+#ifndef __PBLIB_RS_H
+#define __PBLIB_RS_H
+
+/*
+ * C interface of pblib-rs.
+ *
+ * This interface is available when the crate is built with the "capi" feature, e.g. with
+ * cargo rustc --release --features capi --crate-type staticlib
+ *
+ * Literals are given in the DIMACS format.
+ * Objects returned by the functions of this interface must be freed by the corresponding free function.
+ */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C"
+{
+#endif
+
+    /* The comparators of the constraints. */
+    #define PBLIB_RS_LEQ 0
+    #define PBLIB_RS_GEQ 1
+    #define PBLIB_RS_EQ 2
+    #define PBLIB_RS_LT 3
+    #define PBLIB_RS_GT 4
+
+    typedef struct PbRsEncoder PbRsEncoder;
+
+    typedef struct PbRsEncoding PbRsEncoding;
+
+    /*
+     * Builds an encoder given the encodings to use for PB, AMK and AMO constraints.
+     * The values match the ones of pblib's PB_ENCODER, AMK_ENCODER and AMO_ENCODER enums; 0 lets pblib choose the encoding.
+     * For PB constraints, 6 selects the encoding of each constraint using the default cost model of pblib-rs.
+     * Returns NULL if a value is out of range or if the encoder cannot be allocated.
+     */
+    PbRsEncoder* pblib_rs_encoder_new(int32_t pb, int32_t amk, int32_t amo);
+
+    void pblib_rs_encoder_free(PbRsEncoder* encoder);
+
+    /*
+     * Encodes the constraint sum(weights[i] * literals[i]) <comparator> rhs.
+     * If normalize is not 0, the constraint is normalized before being encoded.
+     * Returns NULL if the input is invalid (NULL pointer, null literal, unknown comparator, invalid first auxiliary variable, weights or bound overflowing 64-bit integers).
+     */
+    PbRsEncoding* pblib_rs_encode_constraint(
+        const PbRsEncoder* encoder,
+        const int64_t* weights,
+        const int32_t* literals,
+        size_t n_terms,
+        int32_t comparator,
+        int64_t rhs,
+        int32_t first_aux_var,
+        int32_t normalize
+    );
+
+    /*
+     * Encodes all the constraints of an instance given in the OPB format, as a NUL-terminated string.
+     * The auxiliary variables are allocated after the variables of the instance.
+     * Returns NULL if the instance cannot be parsed.
+     */
+    PbRsEncoding* pblib_rs_encode_opb(const PbRsEncoder* encoder, const char* opb);
+
+    size_t pblib_rs_encoding_n_clauses(const PbRsEncoding* encoding);
+
+    size_t pblib_rs_encoding_clause_len(const PbRsEncoding* encoding, size_t clause_index);
+
+    /* Returns a pointer to the literals of a clause, valid until the encoding is freed. */
+    const int32_t* pblib_rs_encoding_clause(const PbRsEncoding* encoding, size_t clause_index);
+
+    int32_t pblib_rs_encoding_next_free_var_id(const PbRsEncoding* encoding);
+
+    void pblib_rs_encoding_free(PbRsEncoding* encoding);
+
+#ifdef __cplusplus
+}
+#endif
+#endif
@@ -2,6 +2,8 @@
 #include "adderencoding.h"
 #include "../preencoder.h"
 
+#include <algorithm>
+
 using namespace PBLib;
 using namespace std;
 
@@ -285,7 +287,8 @@ void BDD_Encoder::bddEncode(const SimplePBConstraint& pbconstraint, ClauseDataba
   inputVars = pbconstraint.getWeightedLiterals();
 
 
-  sort(inputVars.begin(), inputVars.end(),WeightedLit::compVariable_des);
+  // stable, so that the encoding does not depend on the sort implementation of the C++ library
+  stable_sort(inputVars.begin(), inputVars.end(),WeightedLit::compVariable_des);
 
   true_lit = auxvars.getVariable();
   formula.addClause(true_lit);
@@ -1,5 +1,6 @@
 #include "preencoder.h"
 
+#include <algorithm>
 #include <vector>
 
 using namespace PBLib;
@@ -114,7 +115,8 @@ void PreEncoder::normalize_variables()
 
 void PreEncoder::join_duplicat_literals()
 {
-  sort(literals.begin(), literals.end(),WeightedLit::compVariable_des_var);
+  // stable, so that the encoding does not depend on the sort implementation of the C++ library
+  stable_sort(literals.begin(), literals.end(),WeightedLit::compVariable_des_var);
   vector<int32_t> del_element;
   for (int i = 1; i < literals.size(); ++i)
   {
@@ -180,7 +182,8 @@ void PreEncoder::check_for_trivial_constraints(ClauseDatabase & formula)
 
 void PreEncoder::sort_literals()
 {
-  sort(literals.begin(), literals.end(),WeightedLit::compVariable_des);
+  // stable, so that the encoding does not depend on the sort implementation of the C++ library
+  stable_sort(literals.begin(), literals.end(),WeightedLit::compVariable_des);
 //   sort(literals.begin(), literals.end(),WeightedLit::compVariable_asc);
 }
 
@@ -0,0 +1,69 @@
+/// A set of literals, indexed by variables to allow constant time lookups.
+pub(crate) struct Assignment(Vec<Option<bool>>);
+
+impl Assignment {
+    /// Builds an assignment from a list of DIMACS literals.
+    ///
+    /// Variables that do not appear in the list are considered as unassigned.
+    pub(crate) fn new(literals: &[i32]) -> Self {
+        let max_var = literals
+            .iter()
+            .map(|l| l.unsigned_abs() as usize)
+            .max()
+            .unwrap_or_default();
+        let mut values = vec![None; max_var + 1];
+        for l in literals {
+            values[l.unsigned_abs() as usize] = Some(*l > 0);
+        }
+        Self(values)
+    }
+
+    /// Returns `true` iff the literal is set to true by this assignment.
+    pub(crate) fn satisfies(&self, literal: i32) -> bool {
+        self.0
+            .get(literal.unsigned_abs() as usize)
+            .copied()
+            .flatten()
+            .is_some_and(|v| v == (literal > 0))
+    }
+
+    /// Returns `true` iff at least one literal of the clause is set to true by this assignment.
+    pub(crate) fn satisfies_clause(&self, clause: &[i32]) -> bool {
+        clause.iter().any(|l| self.satisfies(*l))
+    }
+}
+
+/// Returns the clauses that are not satisfied by the assignment.
+pub(crate) fn falsified_clauses<'a>(clauses: &'a [Vec<i32>], assignment: &[i32]) -> Vec<&'a [i32]> {
+    let assignment = Assignment::new(assignment);
+    clauses
+        .iter()
+        .filter(|c| !assignment.satisfies_clause(c))
+        .map(Vec::as_slice)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_satisfies() {
+        let assignment = Assignment::new(&[1, -3]);
+        assert!(assignment.satisfies(1));
+        assert!(!assignment.satisfies(-1));
+        assert!(!assignment.satisfies(2));
+        assert!(!assignment.satisfies(-2));
+        assert!(assignment.satisfies(-3));
+        assert!(!assignment.satisfies(4));
+    }
+
+    #[test]
+    fn test_falsified_clauses() {
+        let clauses = vec![vec![1, 2], vec![-1, 3], vec![], vec![-3]];
+        assert_eq!(
+            vec![&[-1, 3][..], &[][..]],
+            falsified_clauses(&clauses, &[1, -2, -3])
+        );
+    }
+}
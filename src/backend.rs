@@ -0,0 +1,94 @@
+use crate::{ClauseSink, PbConstraint, PB2CNF};
+
+/// An encoder of Pseudo-Boolean and cardinality constraints.
+///
+/// This trait is implemented by [`PB2CNF`], which relies on pblib, and by the `NativeBackend` of the `native` module, which relies on encoders written in Rust.
+/// Its functions give the clauses to a `dyn ClauseSink`, so that the backend can be chosen at runtime, e.g. for each constraint.
+/// A backend can also be given to [`PB2CNF::with_backend`], which then delegates the encodings to it.
+///
+/// ```
+/// use pblib_rs::{PbConstraint, PbEncoderBackend, PB2CNF};
+///
+/// let backends: Vec<Box<dyn PbEncoderBackend>> = vec![Box::new(PB2CNF::new())];
+/// let mut clauses = Vec::new();
+/// let mut next_free_var_id = 4;
+/// for backend in &backends {
+///     let constraint = PbConstraint::weighted([(2, 1), (3, 2), (4, 3)]).leq(5);
+///     next_free_var_id = backend.encode_constraint_into(&constraint, next_free_var_id, &mut clauses);
+/// }
+/// ```
+pub trait PbEncoderBackend {
+    /// Encodes a [`PbConstraint`], sending the clauses to a [`ClauseSink`].
+    ///
+    /// See [`PB2CNF::encode_constraint`] for more information on the parameters.
+    /// It returns the next free variable id.
+    fn encode_constraint_into(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        sink: &mut dyn ClauseSink,
+    ) -> i32;
+
+    /// Encodes an At-Most-k cardinality constraint, sending the clauses to a [`ClauseSink`].
+    ///
+    /// See [`PB2CNF::encode_at_most_k`] for more information on the parameters.
+    /// It returns the next free variable id.
+    /// The default implementation encodes the constraint as a [`PbConstraint`].
+    fn encode_at_most_k_into(
+        &self,
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+        sink: &mut dyn ClauseSink,
+    ) -> i32 {
+        let constraint = PbConstraint::cardinality(literals.iter().copied()).leq(k);
+        self.encode_constraint_into(&constraint, first_aux_var, sink)
+    }
+
+    /// Encodes an At-Least-k cardinality constraint, sending the clauses to a [`ClauseSink`].
+    ///
+    /// See [`PB2CNF::encode_at_least_k`] for more information on the parameters.
+    /// It returns the next free variable id.
+    /// The default implementation encodes the constraint as a [`PbConstraint`].
+    fn encode_at_least_k_into(
+        &self,
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+        sink: &mut dyn ClauseSink,
+    ) -> i32 {
+        let constraint = PbConstraint::cardinality(literals.iter().copied()).geq(k);
+        self.encode_constraint_into(&constraint, first_aux_var, sink)
+    }
+}
+
+impl PbEncoderBackend for PB2CNF {
+    fn encode_constraint_into(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        mut sink: &mut dyn ClauseSink,
+    ) -> i32 {
+        PB2CNF::encode_constraint_into(self, constraint, first_aux_var, &mut sink)
+    }
+
+    fn encode_at_most_k_into(
+        &self,
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+        mut sink: &mut dyn ClauseSink,
+    ) -> i32 {
+        PB2CNF::encode_at_most_k_into(self, literals, k, first_aux_var, &mut sink)
+    }
+
+    fn encode_at_least_k_into(
+        &self,
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+        mut sink: &mut dyn ClauseSink,
+    ) -> i32 {
+        PB2CNF::encode_at_least_k_into(self, literals, k, first_aux_var, &mut sink)
+    }
+}
@@ -0,0 +1,279 @@
+//! A command-line tool translating Pseudo-Boolean instances into CNF.
+//!
+//! The tool reads an instance in the OPB or WBO format and writes its translation in the DIMACS or WCNF format.
+//! Run `pb2cnf --help` for the list of options.
+
+use pblib_rs::{
+    try_encode_instance, AmkEncoder, AmoEncoder, EncoderConfig, OpbInstance, PbEncoder, PbError,
+    WboInstance,
+};
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+const USAGE: &str = "\
+Usage: pb2cnf [OPTIONS] [INPUT]
+
+Translates a Pseudo-Boolean instance into CNF.
+The instance is read from INPUT, or from the standard input if INPUT is missing or is \"-\".
+
+Options:
+  -o, --output <FILE>          write the formula to FILE instead of the standard output
+      --input-format <FORMAT>  opb or wbo (default: wbo for .wbo files, opb otherwise)
+      --output-format <FORMAT> dimacs, dimacs-projected or wcnf (default: wcnf if the instance has an objective, dimacs otherwise)
+      --pb-encoder <ENCODER>   best, bdd, swc, sorting-networks, adder, binary-merge or auto (default: best)
+      --amk-encoder <ENCODER>  best, bdd or card (default: best)
+      --amo-encoder <ENCODER>  best, nested, bdd, bimander, commander, k-product, binary or pairwise (default: best)
+  -h, --help                   print this help and exit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputFormat {
+    Opb,
+    Wbo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Dimacs,
+    ProjectedDimacs,
+    Wcnf,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Options {
+    input: Option<String>,
+    output: Option<String>,
+    input_format: Option<InputFormat>,
+    output_format: Option<OutputFormat>,
+    config: EncoderConfig,
+    help: bool,
+}
+
+fn parse_value<T: Copy>(option: &str, value: &str, values: &[(&str, T)]) -> Result<T, String> {
+    values
+        .iter()
+        .find(|(name, _)| *name == value)
+        .map(|(_, v)| *v)
+        .ok_or_else(|| format!("invalid value \"{value}\" for option {option}"))
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            options.help = true;
+            continue;
+        }
+        if !arg.starts_with('-') || arg == "-" {
+            if options.input.replace(arg).is_some() {
+                return Err("more than one input file given".to_string());
+            }
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for option {arg}"))?;
+        match arg.as_str() {
+            "-o" | "--output" => options.output = Some(value),
+            "--input-format" => {
+                options.input_format = Some(parse_value(
+                    &arg,
+                    &value,
+                    &[("opb", InputFormat::Opb), ("wbo", InputFormat::Wbo)],
+                )?);
+            }
+            "--output-format" => {
+                options.output_format = Some(parse_value(
+                    &arg,
+                    &value,
+                    &[
+                        ("dimacs", OutputFormat::Dimacs),
+                        ("dimacs-projected", OutputFormat::ProjectedDimacs),
+                        ("wcnf", OutputFormat::Wcnf),
+                    ],
+                )?);
+            }
+            "--pb-encoder" => {
+                options.config = options.config.with_pb_encoder(parse_value(
+                    &arg,
+                    &value,
+                    &[
+                        ("best", PbEncoder::Best),
+                        ("bdd", PbEncoder::Bdd),
+                        ("swc", PbEncoder::Swc),
+                        ("sorting-networks", PbEncoder::SortingNetworks),
+                        ("adder", PbEncoder::Adder),
+                        ("binary-merge", PbEncoder::BinaryMerge),
+                        ("auto", PbEncoder::Auto),
+                    ],
+                )?);
+            }
+            "--amk-encoder" => {
+                options.config = options.config.with_amk_encoder(parse_value(
+                    &arg,
+                    &value,
+                    &[
+                        ("best", AmkEncoder::Best),
+                        ("bdd", AmkEncoder::Bdd),
+                        ("card", AmkEncoder::Card),
+                    ],
+                )?);
+            }
+            "--amo-encoder" => {
+                options.config = options.config.with_amo_encoder(parse_value(
+                    &arg,
+                    &value,
+                    &[
+                        ("best", AmoEncoder::Best),
+                        ("nested", AmoEncoder::Nested),
+                        ("bdd", AmoEncoder::Bdd),
+                        ("bimander", AmoEncoder::Bimander),
+                        ("commander", AmoEncoder::Commander),
+                        ("k-product", AmoEncoder::KProduct),
+                        ("binary", AmoEncoder::Binary),
+                        ("pairwise", AmoEncoder::Pairwise),
+                    ],
+                )?);
+            }
+            _ => return Err(format!("unknown option {arg}")),
+        }
+    }
+    Ok(options)
+}
+
+fn read_instance<R: Read>(reader: R, format: InputFormat) -> Result<OpbInstance, PbError> {
+    match format {
+        InputFormat::Opb => OpbInstance::parse(reader),
+        InputFormat::Wbo => WboInstance::parse(reader).and_then(|wbo| wbo.relax()),
+    }
+}
+
+fn run(options: &Options) -> Result<(), PbError> {
+    let input = options.input.as_deref().filter(|i| *i != "-");
+    let input_format = options.input_format.unwrap_or_else(|| {
+        let extension = input.and_then(|i| Path::new(i).extension());
+        if extension.is_some_and(|e| e.eq_ignore_ascii_case("wbo")) {
+            InputFormat::Wbo
+        } else {
+            InputFormat::Opb
+        }
+    });
+    let instance = match input {
+        Some(path) => read_instance(File::open(path)?, input_format)?,
+        None => read_instance(std::io::stdin().lock(), input_format)?,
+    };
+    let cnf = try_encode_instance(&instance, &options.config)?;
+    let output_format = options
+        .output_format
+        .unwrap_or(if cnf.objective().is_some() {
+            OutputFormat::Wcnf
+        } else {
+            OutputFormat::Dimacs
+        });
+    let mut writer: Box<dyn Write> = match options.output.as_deref() {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let mut writer = BufWriter::new(&mut writer);
+    match output_format {
+        OutputFormat::Dimacs => cnf.formula().write_dimacs(&mut writer)?,
+        OutputFormat::ProjectedDimacs => cnf.write_dimacs_projected(&mut writer)?,
+        OutputFormat::Wcnf => cnf.write_wcnf(&mut writer)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if options.help {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|a| (*a).to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse(&[
+            "--pb-encoder",
+            "adder",
+            "-o",
+            "out.cnf",
+            "--amo-encoder",
+            "pairwise",
+            "in.opb",
+        ])
+        .unwrap();
+        assert_eq!(Some("in.opb"), options.input.as_deref());
+        assert_eq!(Some("out.cnf"), options.output.as_deref());
+        assert_eq!(PbEncoder::Adder, options.config.pb_encoder());
+        assert_eq!(AmkEncoder::Best, options.config.amk_encoder());
+        assert_eq!(AmoEncoder::Pairwise, options.config.amo_encoder());
+        assert!(!options.help);
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(parse(&["--pb-encoder"]).is_err());
+        assert!(parse(&["--pb-encoder", "foo"]).is_err());
+        assert!(parse(&["--foo", "bar"]).is_err());
+        assert!(parse(&["a.opb", "b.opb"]).is_err());
+    }
+
+    #[test]
+    fn test_read_instance() {
+        let wbo = "soft: ;\n[2] +1 x1 >= 1 ;\n+1 x1 +1 x2 <= 1 ;";
+        let instance = read_instance(wbo.as_bytes(), InputFormat::Wbo).unwrap();
+        assert!(instance.objective().is_some());
+        assert!(read_instance(wbo.as_bytes(), InputFormat::Opb).is_err());
+    }
+
+    #[test]
+    fn test_run_overflow() {
+        let dir = std::env::temp_dir().join(format!("pb2cnf-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("overflow.opb");
+        let output = dir.join("overflow.cnf");
+        std::fs::write(
+            &input,
+            "+9223372036854775807 x1 +9223372036854775807 x2 >= 1 ;\n",
+        )
+        .unwrap();
+        let options = Options {
+            input: Some(input.to_string_lossy().to_string()),
+            output: Some(output.to_string_lossy().to_string()),
+            ..Options::default()
+        };
+        let result = run(&options);
+        std::fs::remove_dir_all(&dir).unwrap();
+        match result {
+            Err(e @ PbError::Overflow(_)) => assert!(e.to_string().starts_with("integer overflow")),
+            _ => panic!(),
+        }
+        assert!(!output.exists());
+    }
+}
@@ -0,0 +1,293 @@
+use crate::{ClauseSink, VarPool};
+use std::ops::{BitAnd, BitOr, Not};
+
+/// A propositional formula over literals, to be mixed with Pseudo-Boolean constraints.
+///
+/// Formulas are translated into clauses using the Tseitin transformation: each connective gets an auxiliary variable equivalent to its subformula, allocated by a [`VarPool`].
+/// The literals are given in the DIMACS format, in the same variable space as the Pseudo-Boolean constraints.
+///
+/// The formulas can be built with the constructors of this type, or with the `!`, `&` and `|` operators.
+///
+/// ```
+/// use pblib_rs::{BoolExpr, EncodingContext, PbConstraint};
+///
+/// let x = BoolExpr::lit;
+/// let mut ctx = EncodingContext::new(4);
+/// // x1 -> (x2 | !x3)
+/// ctx.add_expr(&BoolExpr::implies(x(1), x(2) | !x(3))).unwrap();
+/// // y <-> (x3 & x4), and at most one of x1, x2, y
+/// let y = ctx.define(&(x(3) & x(4))).unwrap();
+/// PbConstraint::cardinality([1, 2, y]).leq(1).encode(&mut ctx).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BoolExpr {
+    /// A literal, in the DIMACS format.
+    Lit(i32),
+    /// The negation of a formula.
+    Not(Box<BoolExpr>),
+    /// The conjunction of formulas; the empty conjunction is true.
+    And(Vec<BoolExpr>),
+    /// The disjunction of formulas; the empty disjunction is false.
+    Or(Vec<BoolExpr>),
+    /// The implication between two formulas.
+    Implies(Box<BoolExpr>, Box<BoolExpr>),
+}
+
+impl BoolExpr {
+    /// Builds the formula made of a single literal.
+    #[must_use]
+    pub fn lit(literal: i32) -> Self {
+        Self::Lit(literal)
+    }
+
+    /// Builds the conjunction of formulas.
+    #[must_use]
+    pub fn and(operands: impl IntoIterator<Item = BoolExpr>) -> Self {
+        Self::And(operands.into_iter().collect())
+    }
+
+    /// Builds the disjunction of formulas.
+    #[must_use]
+    pub fn or(operands: impl IntoIterator<Item = BoolExpr>) -> Self {
+        Self::Or(operands.into_iter().collect())
+    }
+
+    /// Builds the implication `premise -> conclusion`.
+    #[must_use]
+    pub fn implies(premise: BoolExpr, conclusion: BoolExpr) -> Self {
+        Self::Implies(Box::new(premise), Box::new(conclusion))
+    }
+
+    /// Returns the literals of the formula, in order of appearance and with repetitions.
+    #[must_use]
+    pub fn literals(&self) -> Vec<i32> {
+        let mut literals = Vec::new();
+        self.collect_literals(&mut literals);
+        literals
+    }
+
+    fn collect_literals(&self, literals: &mut Vec<i32>) {
+        match self {
+            BoolExpr::Lit(l) => literals.push(*l),
+            BoolExpr::Not(e) => e.collect_literals(literals),
+            BoolExpr::And(es) | BoolExpr::Or(es) => {
+                for e in es {
+                    e.collect_literals(literals);
+                }
+            }
+            BoolExpr::Implies(a, b) => {
+                a.collect_literals(literals);
+                b.collect_literals(literals);
+            }
+        }
+    }
+
+    /// Evaluates the formula under an assignment, given as a slice of DIMACS literals.
+    ///
+    /// A literal is true if it belongs to the assignment; other literals are false.
+    #[must_use]
+    pub fn evaluate(&self, assignment: &[i32]) -> bool {
+        match self {
+            BoolExpr::Lit(l) => assignment.contains(l),
+            BoolExpr::Not(e) => !e.evaluate(assignment),
+            BoolExpr::And(es) => es.iter().all(|e| e.evaluate(assignment)),
+            BoolExpr::Or(es) => es.iter().any(|e| e.evaluate(assignment)),
+            BoolExpr::Implies(a, b) => !a.evaluate(assignment) || b.evaluate(assignment),
+        }
+    }
+
+    /// Applies the Tseitin transformation to this formula, and returns a literal equivalent to it.
+    ///
+    /// The clauses defining the auxiliary variables are sent to the sink, and the auxiliary variables are allocated by the pool.
+    /// Literals and negations do not need auxiliary variables.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn encode_into(&self, pool: &mut VarPool, sink: &mut impl ClauseSink) -> i32 {
+        match self {
+            BoolExpr::Lit(l) => *l,
+            BoolExpr::Not(e) => -e.encode_into(pool, sink),
+            _ => {
+                let output = pool.new_aux_var();
+                self.define_into(output, pool, sink);
+                output
+            }
+        }
+    }
+
+    /// Sends to the sink the clauses stating that `output` is equivalent to this formula.
+    ///
+    /// The auxiliary variables of the subformulas are allocated by the pool.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn define_into(&self, output: i32, pool: &mut VarPool, sink: &mut impl ClauseSink) {
+        match self {
+            BoolExpr::Lit(_) | BoolExpr::Not(_) => {
+                let l = self.encode_into(pool, sink);
+                sink.add_clause(&[-output, l]);
+                sink.add_clause(&[output, -l]);
+            }
+            BoolExpr::And(es) => {
+                let ls = es
+                    .iter()
+                    .map(|e| e.encode_into(pool, sink))
+                    .collect::<Vec<_>>();
+                for l in &ls {
+                    sink.add_clause(&[-output, *l]);
+                }
+                let clause = std::iter::once(output)
+                    .chain(ls.iter().map(|l| -l))
+                    .collect::<Vec<_>>();
+                sink.add_clause(&clause);
+            }
+            BoolExpr::Or(es) => {
+                let ls = es
+                    .iter()
+                    .map(|e| e.encode_into(pool, sink))
+                    .collect::<Vec<_>>();
+                for l in &ls {
+                    sink.add_clause(&[output, -l]);
+                }
+                let clause = std::iter::once(-output)
+                    .chain(ls.iter().copied())
+                    .collect::<Vec<_>>();
+                sink.add_clause(&clause);
+            }
+            BoolExpr::Implies(a, b) => {
+                let la = a.encode_into(pool, sink);
+                let lb = b.encode_into(pool, sink);
+                sink.add_clause(&[output, la]);
+                sink.add_clause(&[output, -lb]);
+                sink.add_clause(&[-output, -la, lb]);
+            }
+        }
+    }
+}
+
+impl From<i32> for BoolExpr {
+    fn from(literal: i32) -> Self {
+        Self::Lit(literal)
+    }
+}
+
+impl Not for BoolExpr {
+    type Output = BoolExpr;
+
+    fn not(self) -> Self::Output {
+        match self {
+            BoolExpr::Lit(l) => BoolExpr::Lit(-l),
+            BoolExpr::Not(e) => *e,
+            e => BoolExpr::Not(Box::new(e)),
+        }
+    }
+}
+
+impl BitAnd for BoolExpr {
+    type Output = BoolExpr;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        match self {
+            BoolExpr::And(mut es) => {
+                es.push(rhs);
+                BoolExpr::And(es)
+            }
+            e => BoolExpr::And(vec![e, rhs]),
+        }
+    }
+}
+
+impl BitOr for BoolExpr {
+    type Output = BoolExpr;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        match self {
+            BoolExpr::Or(mut es) => {
+                es.push(rhs);
+                BoolExpr::Or(es)
+            }
+            e => BoolExpr::Or(vec![e, rhs]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MiniSat;
+
+    fn formulas() -> Vec<BoolExpr> {
+        let x = BoolExpr::lit;
+        vec![
+            x(1),
+            !x(1),
+            x(1) & x(-2) & x(3),
+            x(1) | !(x(2) & x(3)),
+            BoolExpr::implies(x(1) | x(2), x(3) & !x(1)),
+            !BoolExpr::implies(x(1), x(2)) | (x(3) & (x(1) | x(-3))),
+            BoolExpr::and([]),
+            BoolExpr::or([]),
+            BoolExpr::or([x(1) & x(2), x(2) & x(3), x(-1) & x(-3)]),
+        ]
+    }
+
+    #[test]
+    fn test_operators() {
+        let x = BoolExpr::lit;
+        assert_eq!(BoolExpr::and([x(1), x(2), x(3)]), x(1) & x(2) & x(3));
+        assert_eq!(BoolExpr::or([x(1), x(2), x(3)]), x(1) | x(2) | x(3));
+        assert_eq!(x(-1), !x(1));
+        assert_eq!(x(1) & x(2), !!(x(1) & x(2)));
+        assert_eq!(BoolExpr::from(3), x(3));
+        assert_eq!(vec![1, 2, 3, 1], (x(1) | !(x(2) & x(3)) | x(1)).literals());
+    }
+
+    #[test]
+    fn test_tseitin() {
+        for f in formulas() {
+            let mut pool = VarPool::new();
+            pool.new_vars(3);
+            let mut clauses = Vec::new();
+            let output = f.encode_into(&mut pool, &mut clauses);
+            assert!(clauses
+                .iter()
+                .flatten()
+                .all(|l| l.abs() <= 3 || pool.is_aux_var(l.abs())));
+            let mut solver = MiniSat::new();
+            for c in &clauses {
+                solver.add_clause(c);
+            }
+            for i in 0..8 {
+                let assignment = (1..=3)
+                    .map(|v| if i & (1 << (v - 1)) == 0 { -v } else { v })
+                    .collect::<Vec<_>>();
+                let expected = f.evaluate(&assignment);
+                let mut assumptions = assignment.clone();
+                assumptions.push(output);
+                assert_eq!(
+                    expected,
+                    solver.solve_with_assumptions(&assumptions),
+                    "{f:?} {assignment:?}"
+                );
+                *assumptions.last_mut().unwrap() = -output;
+                assert_eq!(
+                    !expected,
+                    solver.solve_with_assumptions(&assumptions),
+                    "{f:?} {assignment:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_aux_for_literals() {
+        let mut pool = VarPool::new();
+        pool.new_vars(2);
+        let mut clauses = Vec::new();
+        assert_eq!(-2, (!BoolExpr::lit(2)).encode_into(&mut pool, &mut clauses));
+        assert!(clauses.is_empty());
+        assert_eq!(0, pool.n_aux_vars());
+    }
+}
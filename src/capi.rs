@@ -0,0 +1,387 @@
+use crate::{
+    ffi_debug, AmkEncoder, AmoEncoder, Comparator, EncodeLimits, EncoderConfig, OpbInstance,
+    PbConstraint, PbEncoder, PB2CNF,
+};
+use std::{
+    ffi::{c_char, CStr},
+    panic::{self, AssertUnwindSafe},
+};
+
+const PB_ENCODERS: [PbEncoder; 7] = [
+    PbEncoder::Best,
+    PbEncoder::Bdd,
+    PbEncoder::Swc,
+    PbEncoder::SortingNetworks,
+    PbEncoder::Adder,
+    PbEncoder::BinaryMerge,
+    PbEncoder::Auto,
+];
+
+const AMK_ENCODERS: [AmkEncoder; 3] = [AmkEncoder::Best, AmkEncoder::Bdd, AmkEncoder::Card];
+
+const AMO_ENCODERS: [AmoEncoder; 8] = [
+    AmoEncoder::Best,
+    AmoEncoder::Nested,
+    AmoEncoder::Bdd,
+    AmoEncoder::Bimander,
+    AmoEncoder::Commander,
+    AmoEncoder::KProduct,
+    AmoEncoder::Binary,
+    AmoEncoder::Pairwise,
+];
+
+const COMPARATORS: [Comparator; 5] = [
+    Comparator::Leq,
+    Comparator::Geq,
+    Comparator::Eq,
+    Comparator::Lt,
+    Comparator::Gt,
+];
+
+const ENCODER_KIND: &str = "PbRsEncoder";
+
+const ENCODING_KIND: &str = "PbRsEncoding";
+
+/// The encoder type of the C interface (`PbRsEncoder`).
+pub struct PbRsEncoder(PB2CNF);
+
+/// The encoding type of the C interface (`PbRsEncoding`).
+pub struct PbRsEncoding {
+    clauses: Vec<Vec<i32>>,
+    next_free_var_id: i32,
+}
+
+fn from_index<T: Copy>(values: &[T], index: i32) -> Option<T> {
+    usize::try_from(index)
+        .ok()
+        .and_then(|i| values.get(i).copied())
+}
+
+fn into_raw(encoding: PbRsEncoding) -> *mut PbRsEncoding {
+    let ptr = Box::into_raw(Box::new(encoding));
+    ffi_debug::track(ptr, ENCODING_KIND);
+    ptr
+}
+
+/// Builds an encoder given the encodings to use for PB, AMK and AMO constraints.
+///
+/// Returns a null pointer if a value is out of range or if the encoder cannot be allocated.
+#[no_mangle]
+pub extern "C" fn pblib_rs_encoder_new(pb: i32, amk: i32, amo: i32) -> *mut PbRsEncoder {
+    let (Some(pb), Some(amk), Some(amo)) = (
+        from_index(&PB_ENCODERS, pb),
+        from_index(&AMK_ENCODERS, amk),
+        from_index(&AMO_ENCODERS, amo),
+    ) else {
+        return std::ptr::null_mut();
+    };
+    let config = EncoderConfig::default()
+        .with_pb_encoder(pb)
+        .with_amk_encoder(amk)
+        .with_amo_encoder(amo);
+    match PB2CNF::try_with_config(config) {
+        Ok(pb2cnf) => {
+            let ptr = Box::into_raw(Box::new(PbRsEncoder(pb2cnf)));
+            ffi_debug::track(ptr, ENCODER_KIND);
+            ptr
+        }
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Frees an encoder.
+///
+/// # Safety
+///
+/// The pointer must be null or returned by [`pblib_rs_encoder_new`], and must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn pblib_rs_encoder_free(encoder: *mut PbRsEncoder) {
+    if !encoder.is_null() {
+        ffi_debug::untrack(encoder, ENCODER_KIND);
+        drop(Box::from_raw(encoder));
+    }
+}
+
+/// Encodes a constraint.
+///
+/// Returns a null pointer if the input is invalid, or if the encoding fails.
+///
+/// # Safety
+///
+/// The encoder must be a valid pointer, and `weights` and `literals` must point to arrays of (at least) `n_terms` elements.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn pblib_rs_encode_constraint(
+    encoder: *const PbRsEncoder,
+    weights: *const i64,
+    literals: *const i32,
+    n_terms: usize,
+    comparator: i32,
+    rhs: i64,
+    first_aux_var: i32,
+    normalize: i32,
+) -> *mut PbRsEncoding {
+    if encoder.is_null() || (n_terms > 0 && (weights.is_null() || literals.is_null())) {
+        return std::ptr::null_mut();
+    }
+    let Some(comparator) = from_index(&COMPARATORS, comparator) else {
+        return std::ptr::null_mut();
+    };
+    let (weights, literals) = if n_terms == 0 {
+        (&[][..], &[][..])
+    } else {
+        (
+            std::slice::from_raw_parts(weights, n_terms),
+            std::slice::from_raw_parts(literals, n_terms),
+        )
+    };
+    if literals.iter().any(|l| *l == 0 || *l == i32::MIN) {
+        return std::ptr::null_mut();
+    }
+    let constraint = PbConstraint::new(
+        weights
+            .iter()
+            .copied()
+            .zip(literals.iter().copied())
+            .collect(),
+        comparator,
+        rhs,
+    );
+    ffi_debug::check(encoder, ENCODER_KIND);
+    let pb2cnf = &(*encoder).0;
+    let encoding = panic::catch_unwind(AssertUnwindSafe(|| {
+        let constraints = if normalize == 0 {
+            vec![constraint]
+        } else {
+            constraint.normalize()
+        };
+        try_encode_constraints(pb2cnf, &constraints, first_aux_var)
+    }));
+    match encoding {
+        Ok(Some(encoding)) => into_raw(encoding),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Encodes all the constraints of an instance given in the OPB format.
+///
+/// Returns a null pointer if the instance cannot be parsed, or if the encoding fails.
+///
+/// # Safety
+///
+/// The encoder must be a valid pointer, and `opb` must point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pblib_rs_encode_opb(
+    encoder: *const PbRsEncoder,
+    opb: *const c_char,
+) -> *mut PbRsEncoding {
+    if encoder.is_null() || opb.is_null() {
+        return std::ptr::null_mut();
+    }
+    ffi_debug::check(encoder, ENCODER_KIND);
+    let pb2cnf = &(*encoder).0;
+    let opb = CStr::from_ptr(opb).to_bytes();
+    let encoding = panic::catch_unwind(AssertUnwindSafe(|| {
+        let instance = OpbInstance::parse(opb).ok()?;
+        let first_aux_var = i32::try_from(instance.n_vars()).ok()?.checked_add(1)?;
+        try_encode_constraints(pb2cnf, instance.constraints(), first_aux_var)
+    }));
+    match encoding {
+        Ok(Some(encoding)) => into_raw(encoding),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Encodes a list of constraints one after the other, or returns `None` if one of them cannot be encoded.
+fn try_encode_constraints(
+    pb2cnf: &PB2CNF,
+    constraints: &[PbConstraint],
+    first_aux_var: i32,
+) -> Option<PbRsEncoding> {
+    let mut clauses = Vec::new();
+    let mut next_free_var_id = first_aux_var;
+    for c in constraints {
+        next_free_var_id = pb2cnf
+            .try_encode_constraint_into(c, next_free_var_id, &EncodeLimits::default(), &mut clauses)
+            .ok()?;
+    }
+    Some(PbRsEncoding {
+        clauses,
+        next_free_var_id,
+    })
+}
+
+/// Returns the number of clauses of an encoding.
+///
+/// # Safety
+///
+/// The encoding must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn pblib_rs_encoding_n_clauses(encoding: *const PbRsEncoding) -> usize {
+    ffi_debug::check(encoding, ENCODING_KIND);
+    (*encoding).clauses.len()
+}
+
+/// Returns the length of a clause of an encoding, or 0 if the index is out of range.
+///
+/// # Safety
+///
+/// The encoding must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn pblib_rs_encoding_clause_len(
+    encoding: *const PbRsEncoding,
+    clause_index: usize,
+) -> usize {
+    ffi_debug::check(encoding, ENCODING_KIND);
+    let encoding = &*encoding;
+    encoding.clauses.get(clause_index).map_or(0, Vec::len)
+}
+
+/// Returns a pointer to the literals of a clause of an encoding, or a null pointer if the index is out of range.
+///
+/// # Safety
+///
+/// The encoding must be a valid pointer.
+/// The returned pointer is valid until the encoding is freed.
+#[no_mangle]
+pub unsafe extern "C" fn pblib_rs_encoding_clause(
+    encoding: *const PbRsEncoding,
+    clause_index: usize,
+) -> *const i32 {
+    ffi_debug::check(encoding, ENCODING_KIND);
+    let encoding = &*encoding;
+    encoding
+        .clauses
+        .get(clause_index)
+        .map_or(std::ptr::null(), Vec::as_ptr)
+}
+
+/// Returns the next free variable id of an encoding.
+///
+/// # Safety
+///
+/// The encoding must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn pblib_rs_encoding_next_free_var_id(encoding: *const PbRsEncoding) -> i32 {
+    ffi_debug::check(encoding, ENCODING_KIND);
+    (*encoding).next_free_var_id
+}
+
+/// Frees an encoding.
+///
+/// # Safety
+///
+/// The pointer must be null or returned by an encoding function, and must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn pblib_rs_encoding_free(encoding: *mut PbRsEncoding) {
+    if !encoding.is_null() {
+        ffi_debug::untrack(encoding, ENCODING_KIND);
+        drop(Box::from_raw(encoding));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn clauses(encoding: *const PbRsEncoding) -> Vec<Vec<i32>> {
+        (0..pblib_rs_encoding_n_clauses(encoding))
+            .map(|i| {
+                std::slice::from_raw_parts(
+                    pblib_rs_encoding_clause(encoding, i),
+                    pblib_rs_encoding_clause_len(encoding, i),
+                )
+                .to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_encode_constraint() {
+        let encoder = pblib_rs_encoder_new(0, 0, 0);
+        assert!(!encoder.is_null());
+        let weights = [8, 4, 2, 1];
+        let literals = [1, 2, 3, 4];
+        unsafe {
+            let encoding = pblib_rs_encode_constraint(
+                encoder,
+                weights.as_ptr(),
+                literals.as_ptr(),
+                4,
+                1,
+                6,
+                5,
+                0,
+            );
+            assert!(!encoding.is_null());
+            let expected = PB2CNF::new().encode_geq(&weights, &literals, 6, 5);
+            assert_eq!(expected.clauses(), clauses(encoding));
+            assert_eq!(
+                expected.next_free_var_id(),
+                pblib_rs_encoding_next_free_var_id(encoding)
+            );
+            assert!(pblib_rs_encoding_clause(encoding, usize::MAX).is_null());
+            pblib_rs_encoding_free(encoding);
+            pblib_rs_encoder_free(encoder);
+        }
+    }
+
+    #[test]
+    fn test_invalid_inputs() {
+        assert!(pblib_rs_encoder_new(7, 0, 0).is_null());
+        assert!(pblib_rs_encoder_new(0, -1, 0).is_null());
+        let encoder = pblib_rs_encoder_new(1, 2, 3);
+        let weights = [1, 1];
+        unsafe {
+            let encode = |literals: [i32; 2], comparator: i32, first_aux_var: i32| {
+                pblib_rs_encode_constraint(
+                    encoder,
+                    weights.as_ptr(),
+                    literals.as_ptr(),
+                    2,
+                    comparator,
+                    1,
+                    first_aux_var,
+                    1,
+                )
+            };
+            assert!(encode([1, 2], 5, 3).is_null());
+            assert!(encode([1, 0], 0, 3).is_null());
+            assert!(encode([1, 2], 0, 2).is_null());
+            let encoding = encode([1, -2], 4, 3);
+            assert!(!encoding.is_null());
+            pblib_rs_encoding_free(encoding);
+            assert!(pblib_rs_encode_opb(encoder, c"+1 x1 >= ;".as_ptr()).is_null());
+            let overflow = c"+9223372036854775807 x1 +9223372036854775807 x2 >= 1 ;";
+            assert!(pblib_rs_encode_opb(encoder, overflow.as_ptr()).is_null());
+            let weights = [i64::MAX, i64::MAX];
+            let literals = [1, 2];
+            assert!(pblib_rs_encode_constraint(
+                encoder,
+                weights.as_ptr(),
+                literals.as_ptr(),
+                2,
+                0,
+                1,
+                3,
+                0,
+            )
+            .is_null());
+            pblib_rs_encoder_free(encoder);
+        }
+    }
+
+    #[test]
+    fn test_encode_opb() {
+        let encoder = pblib_rs_encoder_new(0, 0, 0);
+        unsafe {
+            let encoding =
+                pblib_rs_encode_opb(encoder, c"+1 x1 +1 x2 >= 1 ;\n+1 x1 +1 x2 <= 1 ;".as_ptr());
+            assert!(!encoding.is_null());
+            assert!(pblib_rs_encoding_n_clauses(encoding) > 0);
+            assert!(pblib_rs_encoding_next_free_var_id(encoding) >= 3);
+            pblib_rs_encoding_free(encoding);
+            pblib_rs_encoder_free(encoder);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(feature = "std")]
+use crate::CnfFormula;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A destination for the clauses produced by an encoding.
+///
+/// The encoding functions ending with `_into` (e.g. [`encode_leq_into`](crate::PB2CNF::encode_leq_into)) give the clauses one by one to a sink instead of collecting them into an [`EncodingResult`](crate::EncodingResult).
+/// This allows to send the clauses straight to a SAT solver or to a file without building an intermediate vector of clauses.
+/// The clauses are given while pblib's global lock is held (see [`PB2CNF`](crate::PB2CNF#thread-safety)), so a sink must not call encoding functions itself.
+///
+/// ```
+/// use pblib_rs::{ClauseSink, PB2CNF};
+///
+/// // a sink that just counts the clauses
+/// struct ClauseCounter(usize);
+///
+/// impl ClauseSink for ClauseCounter {
+///     fn add_clause(&mut self, _clause: &[i32]) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let pb2cnf = PB2CNF::new();
+/// let mut counter = ClauseCounter(0);
+/// let next_free_var_id = pb2cnf.encode_at_most_k_into(&[1, 2], 1, 3, &mut counter);
+/// assert_eq!(1, counter.0);
+/// assert_eq!(3, next_free_var_id);
+/// ```
+pub trait ClauseSink {
+    /// Adds a clause, given as a slice of DIMACS literals.
+    fn add_clause(&mut self, clause: &[i32]);
+
+    /// Describes the rule that produces the next clauses, until the next call to this function.
+    ///
+    /// The [native](crate::native) encoders call this function before producing the clauses of each part of their structures (e.g. `full adder (1, 2, 3) -> (7, 8)`), while the clauses produced by pblib are not explained.
+    /// The description is only formatted if the sink needs it; the default implementation ignores it.
+    /// See [`EncodingTrace`](crate::EncodingTrace) for a sink recording the descriptions.
+    fn explain(&mut self, _rule: fmt::Arguments<'_>) {}
+}
+
+impl<S: ClauseSink + ?Sized> ClauseSink for &mut S {
+    fn add_clause(&mut self, clause: &[i32]) {
+        (**self).add_clause(clause);
+    }
+
+    fn explain(&mut self, rule: fmt::Arguments<'_>) {
+        (**self).explain(rule);
+    }
+}
+
+impl ClauseSink for Vec<Vec<i32>> {
+    fn add_clause(&mut self, clause: &[i32]) {
+        self.push(clause.to_vec());
+    }
+}
+
+#[cfg(feature = "std")]
+impl ClauseSink for CnfFormula {
+    fn add_clause(&mut self, clause: &[i32]) {
+        CnfFormula::add_clause(self, clause);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PB2CNF;
+
+    #[test]
+    fn test_vec_sink() {
+        let pb2cnf = PB2CNF::new();
+        let mut clauses = Vec::new();
+        let next_free_var_id =
+            pb2cnf.encode_leq_into(&[8, 4, 2, 1], &[1, 2, 3, 4], 6, 5, &mut clauses);
+        let encoding = pb2cnf.encode_leq(&[8, 4, 2, 1], &[1, 2, 3, 4], 6, 5);
+        assert_eq!(encoding.clauses(), clauses);
+        assert_eq!(encoding.next_free_var_id(), next_free_var_id);
+    }
+
+    #[test]
+    fn test_formula_sink() {
+        let pb2cnf = PB2CNF::new();
+        let mut formula = CnfFormula::new();
+        pb2cnf.encode_geq_into(&[1, 1], &[1, 2], 1, 3, &mut formula);
+        pb2cnf.encode_both_into(&[1, 1], &[1, 3], 1, 1, 4, &mut formula);
+        pb2cnf.encode_at_least_k_into(&[2, 3], 1, 4, &mut formula);
+        assert_eq!(4, formula.n_clauses());
+        assert_eq!(3, formula.n_vars());
+    }
+}
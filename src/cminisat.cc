@@ -0,0 +1,80 @@
+#include "cminisat.h"
+
+#include "minisat/core/Solver.h"
+
+using namespace Minisat;
+
+static Minisat::Lit to_minisat_lit(Solver* solver, int32_t literal)
+{
+    int32_t var = (literal > 0 ? literal : -literal) - 1;
+    while (solver->nVars() <= var) {
+        solver->newVar();
+    }
+    return Minisat::mkLit(var, literal < 0);
+}
+
+static void to_minisat_lits(Solver* solver, int32_t* literals, int32_t literals_len, Minisat::vec<Minisat::Lit>& lits)
+{
+    for (int32_t i = 0; i < literals_len; i++) {
+        lits.push(to_minisat_lit(solver, literals[i]));
+    }
+}
+
+extern "C"
+{
+    CMiniSat* newMiniSat()
+    {
+        Solver* solver = new Solver();
+        return reinterpret_cast<CMiniSat*>(solver);
+    }
+
+    int32_t miniSatAddClause(
+        CMiniSat* cminisat,
+        int32_t* literals,
+        int32_t literals_len
+    ) {
+        Solver* solver = reinterpret_cast<Solver*>(cminisat);
+        Minisat::vec<Minisat::Lit> lits;
+        to_minisat_lits(solver, literals, literals_len, lits);
+        return solver->addClause_(lits) ? 1 : 0;
+    }
+
+    int32_t miniSatSolve(
+        CMiniSat* cminisat,
+        int32_t* assumptions,
+        int32_t assumptions_len
+    ) {
+        Solver* solver = reinterpret_cast<Solver*>(cminisat);
+        Minisat::vec<Minisat::Lit> lits;
+        to_minisat_lits(solver, assumptions, assumptions_len, lits);
+        return solver->solve(lits) ? 1 : 0;
+    }
+
+    int32_t miniSatNVars(CMiniSat* cminisat)
+    {
+        Solver* solver = reinterpret_cast<Solver*>(cminisat);
+        return solver->nVars();
+    }
+
+    int32_t miniSatModelValue(CMiniSat* cminisat, int32_t var)
+    {
+        Solver* solver = reinterpret_cast<Solver*>(cminisat);
+        if (var - 1 >= solver->model.size()) {
+            return -1;
+        }
+        lbool value = solver->modelValue(var - 1);
+        if (value == l_True) {
+            return 1;
+        }
+        if (value == l_False) {
+            return 0;
+        }
+        return -1;
+    }
+
+    void deleteMiniSat(CMiniSat* cminisat)
+    {
+        Solver* solver = reinterpret_cast<Solver*>(cminisat);
+        delete solver;
+    }
+}
@@ -0,0 +1,36 @@
+#ifndef __CMINISAT_H
+#define __CMINISAT_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C"
+{
+#endif
+
+    typedef struct CMiniSat CMiniSat;
+
+    CMiniSat* newMiniSat();
+
+    int32_t miniSatAddClause(
+        CMiniSat* cminisat,
+        int32_t* literals,
+        int32_t literals_len
+    );
+
+    int32_t miniSatSolve(
+        CMiniSat* cminisat,
+        int32_t* assumptions,
+        int32_t assumptions_len
+    );
+
+    int32_t miniSatNVars(CMiniSat* cminisat);
+
+    int32_t miniSatModelValue(CMiniSat* cminisat, int32_t var);
+
+    void deleteMiniSat(CMiniSat* cminisat);
+
+#ifdef __cplusplus
+}
+#endif
+#endif
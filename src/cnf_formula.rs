@@ -0,0 +1,437 @@
+use crate::{assignment, dimacs, simplify, EncodingResult, PbError};
+use std::{
+    io::{Read, Write},
+    str::FromStr,
+};
+
+/// A CNF formula made of encodings and user-defined clauses.
+///
+/// This structure accumulates clauses and keeps track of the highest variable index in use.
+/// It is intended to be used when several constraints are encoded and mixed with clauses written by hand.
+///
+/// ```
+/// use pblib_rs::{CnfFormula, PB2CNF};
+///
+/// let pb2cnf = PB2CNF::new();
+/// let mut formula = CnfFormula::new();
+/// formula.add_clause(&[1, -2]);
+/// // we encode x1 + x2 + x3 <= 1, using the first free variable as the first auxiliary one
+/// let encoding = pb2cnf.encode_at_most_k(&[1, 2, 3], 1, formula.next_free_var_id().max(4));
+/// formula.add_encoding(&encoding);
+/// let mut output = Vec::new();
+/// formula.write_dimacs(&mut output).unwrap();
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CnfFormula {
+    clauses: Vec<Vec<i32>>,
+    n_vars: usize,
+}
+
+impl CnfFormula {
+    /// Builds a new, empty formula.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a formula in the DIMACS format.
+    ///
+    /// The number of variables is taken from the `p cnf` header, if any, and increased if needed to take into account all the variables involved in the clauses.
+    /// This allows to load an existing formula and to append new encodings to it, using [`next_free_var_id`](Self::next_free_var_id) as the first auxiliary variable.
+    ///
+    /// ```
+    /// use pblib_rs::{CnfFormula, PB2CNF};
+    ///
+    /// let mut formula = CnfFormula::parse_dimacs("p cnf 3 2\n1 -2 0\n2 3 0\n".as_bytes()).unwrap();
+    /// let pb2cnf = PB2CNF::new();
+    /// let encoding = pb2cnf.encode_at_most_k(&[1, 2, 3], 1, formula.next_free_var_id());
+    /// formula.add_encoding(&encoding);
+    /// let mut output = Vec::new();
+    /// formula.write_dimacs(&mut output).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the reader fails or if the content is not a valid DIMACS formula.
+    pub fn parse_dimacs<R: Read>(mut reader: R) -> Result<Self, PbError> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        let content = dimacs::read_cnf(&content)?;
+        let mut formula = CnfFormula::new();
+        formula.declare_vars(content.n_vars);
+        content.clauses.iter().for_each(|c| formula.add_clause(c));
+        Ok(formula)
+    }
+
+    /// Adds a clause to this formula.
+    ///
+    /// The literals must be given in the DIMACS format.
+    /// The number of variables of the formula is updated if the clause involves a variable that has a higher index than the ones in use.
+    pub fn add_clause(&mut self, clause: &[i32]) {
+        self.n_vars = self.n_vars.max(max_var(clause));
+        self.clauses.push(clause.to_vec());
+    }
+
+    /// Adds the clauses of an encoding to this formula.
+    ///
+    /// The number of variables of the formula is updated to take into account the auxiliary variables of the encoding, even if some of them do not appear in its clauses.
+    pub fn add_encoding(&mut self, encoding: &EncodingResult) {
+        let encoding_n_vars = usize::try_from(encoding.next_free_var_id() - 1).unwrap_or_default();
+        self.n_vars = self.n_vars.max(encoding_n_vars);
+        encoding.clauses().iter().for_each(|c| self.add_clause(c));
+    }
+
+    /// Declares that the formula involves at least `n_vars` variables.
+    ///
+    /// This is useful when some problem variables do not appear in any clause, since they would not be taken into account in the DIMACS header otherwise.
+    pub fn declare_vars(&mut self, n_vars: usize) {
+        self.n_vars = self.n_vars.max(n_vars);
+    }
+
+    /// Returns the clauses of this formula.
+    #[must_use]
+    pub fn clauses(&self) -> &[Vec<i32>] {
+        &self.clauses
+    }
+
+    /// Returns an iterator over the clauses of this formula.
+    pub fn iter(&self) -> std::slice::Iter<'_, Vec<i32>> {
+        self.clauses.iter()
+    }
+
+    /// Consumes this formula and returns its clauses.
+    #[must_use]
+    pub fn into_clauses(self) -> Vec<Vec<i32>> {
+        self.clauses
+    }
+
+    /// Returns the number of variables of this formula, that is the highest variable index in use.
+    #[must_use]
+    pub fn n_vars(&self) -> usize {
+        self.n_vars
+    }
+
+    /// Returns the number of clauses of this formula.
+    #[must_use]
+    pub fn n_clauses(&self) -> usize {
+        self.clauses.len()
+    }
+
+    /// Returns the lowest variable index that is not in use in this formula.
+    ///
+    /// This is the preferred value for the `first_aux_var` parameter of the encoding functions.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    #[must_use]
+    pub fn next_free_var_id(&self) -> i32 {
+        i32::try_from(self.n_vars + 1).unwrap()
+    }
+
+    /// Checks whether an assignment satisfies all the clauses of the formula.
+    ///
+    /// The assignment is given as a list of DIMACS literals, like the models returned by most SAT solvers.
+    /// Variables that do not appear in the assignment are considered as unassigned.
+    #[must_use]
+    pub fn is_model(&self, assignment: &[i32]) -> bool {
+        self.falsified_clauses(assignment).is_empty()
+    }
+
+    /// Returns the clauses of the formula that are not satisfied by an assignment.
+    ///
+    /// See [`is_model`](Self::is_model) for more information about the assignment.
+    #[must_use]
+    pub fn falsified_clauses(&self, assignment: &[i32]) -> Vec<&[i32]> {
+        assignment::falsified_clauses(&self.clauses, assignment)
+    }
+
+    /// Removes the duplicate clauses and the clauses that are subsumed by shorter ones.
+    ///
+    /// Two clauses are considered as duplicates if they share the same set of literals, regardless of the order of the literals.
+    /// The order of the remaining clauses and of their literals is preserved.
+    /// The number of variables is left unchanged.
+    ///
+    /// This function returns the number of removed clauses.
+    pub fn simplify(&mut self) -> usize {
+        simplify::remove_redundant_clauses(&mut self.clauses)
+    }
+
+    /// Removes the tautological clauses and applies unit propagation on the formula.
+    ///
+    /// The clauses satisfied by unit clauses are removed and the falsified literals are removed from the other clauses, until no new unit clause is produced.
+    /// The fixed literals are kept as unit clauses at the end of the formula, so the formula remains equivalent to the initial one.
+    /// In case a conflict is detected, the formula is replaced by a single empty clause.
+    /// The number of variables is left unchanged.
+    ///
+    /// This function returns the fixed literals, in the order they were discovered.
+    pub fn propagate_units(&mut self) -> Vec<i32> {
+        simplify::propagate_units(&mut self.clauses)
+    }
+
+    /// Writes the formula in the DIMACS format, including a correct `p cnf` header.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the writer fails.
+    pub fn write_dimacs<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        dimacs::write_header(writer, self.n_vars, self.clauses.len())?;
+        dimacs::write_clauses(writer, &self.clauses)
+    }
+
+    /// Writes the formula in the DIMACS format, with `c ind` lines giving the variables the models must be projected on.
+    ///
+    /// These lines follow the header, and list the variables ten by ten, each line ending with `0`.
+    /// They are read by projected model counters (e.g. `ganak`, `d4` or `ApproxMC`) as the independent support of the formula.
+    ///
+    /// ```
+    /// use pblib_rs::CnfFormula;
+    ///
+    /// let mut formula = CnfFormula::new();
+    /// formula.add_clause(&[1, 3]);
+    /// formula.add_clause(&[2, -3]);
+    /// let mut output = Vec::new();
+    /// formula.write_dimacs_projected(&mut output, &[1, 2]).unwrap();
+    /// assert_eq!("p cnf 3 2\nc ind 1 2 0\n1 3 0\n2 -3 0\n", String::from_utf8(output).unwrap());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the writer fails.
+    pub fn write_dimacs_projected<W: Write>(
+        &self,
+        writer: &mut W,
+        projection: &[i32],
+    ) -> std::io::Result<()> {
+        dimacs::write_header(writer, self.n_vars, self.clauses.len())?;
+        dimacs::write_independent_support(writer, projection)?;
+        dimacs::write_clauses(writer, &self.clauses)
+    }
+
+    /// Writes the formula in the (new) WCNF format used by `MaxSAT` solvers.
+    ///
+    /// The clauses of the formula are written as hard clauses.
+    /// The objective to minimize is given as a list of terms, each of them being a couple composed of a weight and a literal; typically, these literals are the relaxation literals of soft constraints (see [`SoftConstraint::relax`](crate::SoftConstraint::relax)).
+    /// Each term `w*l` is written as a soft unit clause `~l` of weight `w`, meaning that the weight is paid when `l` is set to true.
+    /// Terms with negative weights are written as soft unit clauses `l` of weight `-w`, which shifts the objective function by a constant; terms with null weights are ignored.
+    ///
+    /// ```
+    /// use pblib_rs::CnfFormula;
+    ///
+    /// let mut formula = CnfFormula::new();
+    /// formula.add_clause(&[1, 2]);
+    /// let mut output = Vec::new();
+    /// formula.write_wcnf(&mut output, &[(3, 1), (2, 2)]).unwrap();
+    /// assert_eq!("h 1 2 0\n3 -1 0\n2 -2 0\n", String::from_utf8(output).unwrap());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the writer fails.
+    pub fn write_wcnf<W: Write>(
+        &self,
+        writer: &mut W,
+        objective: &[(i64, i32)],
+    ) -> std::io::Result<()> {
+        dimacs::write_wcnf(writer, &self.clauses, objective)
+    }
+}
+
+impl FromStr for CnfFormula {
+    type Err = PbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_dimacs(s.as_bytes())
+    }
+}
+
+impl Extend<Vec<i32>> for CnfFormula {
+    fn extend<T: IntoIterator<Item = Vec<i32>>>(&mut self, iter: T) {
+        for clause in iter {
+            self.n_vars = self.n_vars.max(max_var(&clause));
+            self.clauses.push(clause);
+        }
+    }
+}
+
+impl FromIterator<Vec<i32>> for CnfFormula {
+    fn from_iter<T: IntoIterator<Item = Vec<i32>>>(iter: T) -> Self {
+        let mut formula = CnfFormula::new();
+        formula.extend(iter);
+        formula
+    }
+}
+
+impl IntoIterator for CnfFormula {
+    type Item = Vec<i32>;
+
+    type IntoIter = std::vec::IntoIter<Vec<i32>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.clauses.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a CnfFormula {
+    type Item = &'a Vec<i32>;
+
+    type IntoIter = std::slice::Iter<'a, Vec<i32>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+fn max_var(clause: &[i32]) -> usize {
+    clause
+        .iter()
+        .map(|l| l.unsigned_abs() as usize)
+        .max()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PB2CNF;
+
+    #[test]
+    fn test_add_clause() {
+        let mut formula = CnfFormula::new();
+        assert_eq!(0, formula.n_vars());
+        assert_eq!(1, formula.next_free_var_id());
+        formula.add_clause(&[1, -3]);
+        formula.add_clause(&[-2]);
+        assert_eq!(3, formula.n_vars());
+        assert_eq!(2, formula.n_clauses());
+        assert_eq!(4, formula.next_free_var_id());
+    }
+
+    #[test]
+    fn test_add_encoding() {
+        let pb2cnf = PB2CNF::new();
+        let mut formula = CnfFormula::new();
+        formula.add_clause(&[1, 2]);
+        let encoding = pb2cnf.encode_at_most_k(&[1, 2, 3, 4, 5], 2, 6);
+        formula.add_encoding(&encoding);
+        assert_eq!(1 + encoding.clauses().len(), formula.n_clauses());
+        assert_eq!(encoding.next_free_var_id(), formula.next_free_var_id());
+    }
+
+    #[test]
+    fn test_declare_vars() {
+        let mut formula = CnfFormula::new();
+        formula.add_clause(&[1]);
+        formula.declare_vars(3);
+        assert_eq!(3, formula.n_vars());
+        formula.declare_vars(2);
+        assert_eq!(3, formula.n_vars());
+    }
+
+    #[test]
+    fn test_simplify() {
+        let mut formula = CnfFormula::new();
+        formula.add_clause(&[1, -2]);
+        formula.add_clause(&[1, -2, 3]);
+        formula.add_clause(&[-2, 1]);
+        assert_eq!(2, formula.simplify());
+        assert_eq!(&[vec![1, -2]], formula.clauses());
+        assert_eq!(3, formula.n_vars());
+    }
+
+    #[test]
+    fn test_propagate_units() {
+        let mut formula = CnfFormula::new();
+        formula.add_clause(&[-1, 2]);
+        formula.add_clause(&[1]);
+        formula.add_clause(&[2, 3]);
+        formula.add_clause(&[-2, 4, -4]);
+        assert_eq!(vec![1, 2], formula.propagate_units());
+        assert_eq!(&[vec![1], vec![2]], formula.clauses());
+        assert_eq!(4, formula.n_vars());
+    }
+
+    #[test]
+    fn test_from_iter_and_extend() {
+        let pb2cnf = PB2CNF::new();
+        let mut formula = vec![vec![1, -2]].into_iter().collect::<CnfFormula>();
+        assert_eq!(2, formula.n_vars());
+        formula.extend(pb2cnf.encode_at_least_k(&[1, 2, 3], 1, 4));
+        assert_eq!(2, formula.n_clauses());
+        assert_eq!(3, formula.n_vars());
+        assert_eq!(2, (&formula).into_iter().count());
+    }
+
+    #[test]
+    fn test_is_model() {
+        let formula = vec![vec![1, -2], vec![2, 3]]
+            .into_iter()
+            .collect::<CnfFormula>();
+        assert!(formula.is_model(&[1, 2, -3]));
+        assert!(!formula.is_model(&[-1, 2, 3]));
+        assert_eq!(vec![&[1, -2][..]], formula.falsified_clauses(&[-1, 2, 3]));
+    }
+
+    #[test]
+    fn test_write_dimacs() {
+        let mut formula = CnfFormula::new();
+        formula.add_clause(&[1, -2]);
+        formula.add_clause(&[3]);
+        let mut output = Vec::new();
+        formula.write_dimacs(&mut output).unwrap();
+        assert_eq!(
+            "p cnf 3 2\n1 -2 0\n3 0\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_dimacs_projected() {
+        let formula = CnfFormula::from_iter([vec![1, -12]]);
+        let mut output = Vec::new();
+        formula
+            .write_dimacs_projected(&mut output, &(1..=11).collect::<Vec<_>>())
+            .unwrap();
+        assert_eq!(
+            "p cnf 12 1\nc ind 1 2 3 4 5 6 7 8 9 10 0\nc ind 11 0\n1 -12 0\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_wcnf() {
+        let mut formula = CnfFormula::new();
+        formula.add_clause(&[1, -2]);
+        formula.add_clause(&[3]);
+        let mut output = Vec::new();
+        formula
+            .write_wcnf(&mut output, &[(4, 1), (0, 2), (-2, -3)])
+            .unwrap();
+        assert_eq!(
+            "h 1 -2 0\nh 3 0\n4 -1 0\n2 -3 0\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_dimacs_round_trip() {
+        let formula = "c comment\np cnf 4 2\n1 -2 0\n3 0\n"
+            .parse::<CnfFormula>()
+            .unwrap();
+        assert_eq!(4, formula.n_vars());
+        assert_eq!(5, formula.next_free_var_id());
+        let mut output = Vec::new();
+        formula.write_dimacs(&mut output).unwrap();
+        assert_eq!(
+            "p cnf 4 2\n1 -2 0\n3 0\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_dimacs_without_header() {
+        let formula = "1 -7 0".parse::<CnfFormula>().unwrap();
+        assert_eq!(7, formula.n_vars());
+        assert_eq!(&[vec![1, -7]], formula.clauses());
+    }
+}
@@ -0,0 +1,381 @@
+use crate::{CnfFormula, EncoderConfig, OpbInstance, PbError, PB2CNF};
+
+/// The result of the translation of a Pseudo-Boolean instance into CNF.
+///
+/// Such objects are returned by [`encode_instance`].
+/// They contain the CNF formula encoding all the constraints of the instance, the objective function (if any) expressed on CNF variables, and the mapping between the variables of the instance and the ones of the formula.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CnfInstance {
+    formula: CnfFormula,
+    objective: Option<Vec<(i64, i32)>>,
+    var_map: Vec<i32>,
+    provenance: Option<Vec<usize>>,
+}
+
+impl CnfInstance {
+    /// Returns the CNF formula.
+    #[must_use]
+    pub fn formula(&self) -> &CnfFormula {
+        &self.formula
+    }
+
+    /// Consumes this instance and returns its CNF formula.
+    #[must_use]
+    pub fn into_formula(self) -> CnfFormula {
+        self.formula
+    }
+
+    /// Returns the objective function to minimize, if any, expressed on the variables of the CNF formula.
+    #[must_use]
+    pub fn objective(&self) -> Option<&[(i64, i32)]> {
+        self.objective.as_deref()
+    }
+
+    /// Returns the index of the constraint of the original instance that produced each clause of the formula, if it was recorded.
+    ///
+    /// The provenance is only recorded by [`encode_instance_with_provenance`].
+    /// In this case, the value at index `i` is the index (in [`OpbInstance::constraints`]) of the constraint that produced the clause at index `i`.
+    #[must_use]
+    pub fn provenance(&self) -> Option<&[usize]> {
+        self.provenance.as_deref()
+    }
+
+    /// Returns the number of variables of the original instance.
+    #[must_use]
+    pub fn n_original_vars(&self) -> usize {
+        self.var_map.len()
+    }
+
+    /// Returns the mapping from the variables of the original instance to the variables of the CNF formula.
+    ///
+    /// The variable of the CNF formula associated with the original variable `i` is at index `i - 1`.
+    #[must_use]
+    pub fn var_map(&self) -> &[i32] {
+        &self.var_map
+    }
+
+    /// Returns the variables of the CNF formula associated with the variables of the original instance.
+    ///
+    /// The auxiliary variables of the encodings are not always determined by the values of the original variables, so the formula may have several models for a single assignment of the instance.
+    /// The models of the instance are thus counted by projecting the models of the formula on these variables, which form an independent support of the formula.
+    #[must_use]
+    pub fn independent_support(&self) -> &[i32] {
+        &self.var_map
+    }
+
+    /// Writes the formula in the DIMACS format, with `c ind` lines giving its [independent support](Self::independent_support).
+    ///
+    /// The output can be given as is to projected model counters to count the models of the original instance.
+    /// See [`CnfFormula::write_dimacs_projected`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the writer fails.
+    pub fn write_dimacs_projected<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.formula
+            .write_dimacs_projected(writer, self.independent_support())
+    }
+
+    /// Writes this instance in the (new) WCNF format used by `MaxSAT` solvers.
+    ///
+    /// The clauses of the formula are written as hard clauses, and the objective function (if any) is written as soft clauses.
+    /// See [`CnfFormula::write_wcnf`] for more information.
+    /// Applied on the translation of a relaxed WBO instance (see [`WboInstance::relax`](crate::WboInstance::relax)), this produces a `MaxSAT` instance equivalent to the WBO one.
+    ///
+    /// ```
+    /// use pblib_rs::{encode_instance, EncoderConfig, WboInstance};
+    ///
+    /// let wbo = "soft: ;\n[2] +1 x1 >= 1 ;\n[3] +1 x2 >= 1 ;\n+1 x1 +1 x2 <= 1 ;";
+    /// let instance = wbo.parse::<WboInstance>().unwrap().relax().unwrap();
+    /// let cnf = encode_instance(&instance, &EncoderConfig::default());
+    /// let mut output = Vec::new();
+    /// cnf.write_wcnf(&mut output).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the writer fails.
+    pub fn write_wcnf<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.formula
+            .write_wcnf(writer, self.objective().unwrap_or_default())
+    }
+
+    /// Translates a literal of the original instance into a literal of the CNF formula.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the variable of the literal is not a variable of the original instance.
+    #[must_use]
+    pub fn cnf_literal(&self, original_literal: i32) -> i32 {
+        original_literal.signum() * self.var_map[original_literal.unsigned_abs() as usize - 1]
+    }
+
+    /// Translates a model of the CNF formula into an assignment of the original instance variables.
+    ///
+    /// The model is given as a list of DIMACS literals.
+    /// The returned assignment contains the literals of the original instance, in the order of the variables.
+    /// Variables that do not appear in the model are omitted.
+    #[must_use]
+    pub fn original_assignment(&self, model: &[i32]) -> Vec<i32> {
+        let model = crate::assignment::Assignment::new(model);
+        self.var_map
+            .iter()
+            .zip(1..)
+            .filter_map(|(cnf_var, original_var)| {
+                if model.satisfies(*cnf_var) {
+                    Some(original_var)
+                } else if model.satisfies(-cnf_var) {
+                    Some(-original_var)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Translates a whole Pseudo-Boolean instance into CNF.
+///
+/// Each constraint of the instance is encoded using the given configuration.
+/// The auxiliary variables are managed globally: they are allocated after the variables of the instance, and the ones of a constraint never collide with the ones of another constraint.
+///
+/// ```
+/// use pblib_rs::{encode_instance, EncoderConfig, OpbInstance};
+///
+/// let instance = "+1 x1 +1 x2 +1 x3 >= 2 ;\n+1 x1 +1 x2 <= 1 ;".parse::<OpbInstance>().unwrap();
+/// let cnf = encode_instance(&instance, &EncoderConfig::default());
+/// let mut output = Vec::new();
+/// cnf.formula().write_dimacs(&mut output).unwrap();
+/// ```
+///
+/// # Panics
+///
+/// This function panics if the instance cannot be encoded; see [`try_encode_instance`].
+#[must_use]
+pub fn encode_instance(instance: &OpbInstance, config: &EncoderConfig) -> CnfInstance {
+    encode(instance, *config, false).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Translates a whole Pseudo-Boolean instance into CNF, checking the constraints first.
+///
+/// This function behaves like [`encode_instance`], except that the instances that cannot be encoded are reported as errors.
+///
+/// ```
+/// use pblib_rs::{try_encode_instance, EncoderConfig, OpbInstance, PbError};
+///
+/// let instance = "+9223372036854775807 x1 +9223372036854775807 x2 >= 1 ;".parse::<OpbInstance>().unwrap();
+/// assert!(matches!(
+///     try_encode_instance(&instance, &EncoderConfig::default()),
+///     Err(PbError::Overflow(_)),
+/// ));
+/// ```
+///
+/// # Errors
+///
+/// A [`PbError::Overflow`] error is returned if the number of variables does not fit in an `i32`, or if the weights and the bound of a constraint do not fit in 64-bit integers.
+/// A [`PbError::Ffi`] error is returned if pblib raises an exception during the encoding.
+pub fn try_encode_instance(
+    instance: &OpbInstance,
+    config: &EncoderConfig,
+) -> Result<CnfInstance, PbError> {
+    encode(instance, *config, false)
+}
+
+/// Translates a whole Pseudo-Boolean instance into CNF, recording the constraint that produced each clause.
+///
+/// This function behaves like [`encode_instance`], except that the returned object gives access to the provenance of the clauses through [`CnfInstance::provenance`].
+/// This allows, for example, to map an unsatisfiable core returned by a SAT solver back to the constraints of the original instance.
+///
+/// ```
+/// use pblib_rs::{encode_instance_with_provenance, EncoderConfig, OpbInstance};
+///
+/// let instance = "+1 x1 +1 x2 >= 2 ;\n+1 x1 +1 x3 >= 1 ;\n+1 x1 +1 x2 <= 1 ;".parse::<OpbInstance>().unwrap();
+/// let cnf = encode_instance_with_provenance(&instance, &EncoderConfig::default());
+/// let provenance = cnf.provenance().unwrap();
+/// // assume a SAT solver returned these clause indices as an unsatisfiable core
+/// let core = (0..cnf.formula().n_clauses())
+///     .filter(|i| provenance[*i] != 1)
+///     .collect::<Vec<_>>();
+/// let mut core_constraints = core.iter().map(|i| provenance[*i]).collect::<Vec<_>>();
+/// core_constraints.dedup();
+/// assert_eq!(vec![0, 2], core_constraints);
+/// ```
+///
+/// # Panics
+///
+/// This function panics if the instance cannot be encoded; see [`try_encode_instance`].
+#[must_use]
+pub fn encode_instance_with_provenance(
+    instance: &OpbInstance,
+    config: &EncoderConfig,
+) -> CnfInstance {
+    encode(instance, *config, true).unwrap_or_else(|e| panic!("{e}"))
+}
+
+fn encode(
+    instance: &OpbInstance,
+    config: EncoderConfig,
+    record_provenance: bool,
+) -> Result<CnfInstance, PbError> {
+    let pb2cnf = PB2CNF::with_config(config);
+    let n_vars = instance.n_vars();
+    let max_var = i32::try_from(n_vars).map_err(|_| {
+        PbError::Overflow(format!(
+            "the number of variables ({n_vars}) does not fit in an i32"
+        ))
+    })?;
+    let var_map = (1..=max_var).collect::<Vec<_>>();
+    let mut formula = CnfFormula::new();
+    formula.declare_vars(n_vars);
+    let mut provenance = record_provenance.then(Vec::new);
+    for (i, constraint) in instance.constraints().iter().enumerate() {
+        let first_aux_var = formula.next_free_var_id();
+        let next_free_var_id = pb2cnf.try_encode_constraint_buffered(constraint, first_aux_var)?;
+        formula.declare_vars(usize::try_from(next_free_var_id - 1).unwrap());
+        if let Some(p) = provenance.as_mut() {
+            p.resize(pb2cnf.n_buffered_clauses(), i);
+        }
+    }
+    pb2cnf.take_buffered_clauses_into(&mut formula);
+    let mut cnf_instance = CnfInstance {
+        formula,
+        objective: None,
+        var_map,
+        provenance,
+    };
+    cnf_instance.objective = instance.objective().map(|terms| {
+        terms
+            .iter()
+            .map(|(w, l)| (*w, cnf_instance.cnf_literal(*l)))
+            .collect()
+    });
+    Ok(cnf_instance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use splr::{Config, Solver};
+
+    #[test]
+    fn test_encode_instance() {
+        let instance = "* #variable= 4 #constraint= 2\nmin: +1 x4 ;\n+2 x1 +1 x2 +1 x3 >= 2 ;\n+1 x1 +1 x2 = 1 ;"
+            .parse::<OpbInstance>()
+            .unwrap();
+        let cnf = encode_instance(&instance, &EncoderConfig::default());
+        assert!(cnf.formula().n_vars() >= 4);
+        assert_eq!(4, cnf.n_original_vars());
+        assert_eq!(Some(&[(1, 4)][..]), cnf.objective());
+        let mut solver = Solver::try_from((Config::default(), cnf.formula().clauses())).unwrap();
+        let mut models = solver
+            .iter()
+            .map(|m| {
+                assert!(cnf.formula().is_model(&m));
+                cnf.original_assignment(&m)
+            })
+            .collect::<Vec<_>>();
+        models.sort_unstable();
+        models.dedup();
+        for m in &models {
+            let value = |v: usize| i64::from(m[v - 1] > 0);
+            assert!(2 * value(1) + value(2) + value(3) >= 2);
+            assert_eq!(1, value(1) + value(2));
+        }
+        assert_eq!(6, models.len());
+    }
+
+    #[test]
+    fn test_try_encode_instance() {
+        let instance = "+1 x1 +1 x2 >= 1 ;\n+9223372036854775807 x1 +9223372036854775807 x2 >= 1 ;"
+            .parse::<OpbInstance>()
+            .unwrap();
+        match try_encode_instance(&instance, &EncoderConfig::default()) {
+            Err(PbError::Overflow(message)) => assert!(message.contains("64-bit")),
+            _ => panic!(),
+        }
+        let instance = "+1 x1 +1 x2 >= 1 ;".parse::<OpbInstance>().unwrap();
+        assert_eq!(
+            encode_instance(&instance, &EncoderConfig::default()),
+            try_encode_instance(&instance, &EncoderConfig::default()).unwrap()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "integer overflow")]
+    fn test_encode_instance_overflow() {
+        let instance = "+9223372036854775807 x1 +9223372036854775807 x2 >= 1 ;"
+            .parse::<OpbInstance>()
+            .unwrap();
+        let _ = encode_instance(&instance, &EncoderConfig::default());
+    }
+
+    #[test]
+    fn test_write_wcnf() {
+        let instance = "soft: ;\n[2] +1 x1 >= 1 ;\n[3] +1 x2 >= 1 ;\n+1 x1 +1 x2 <= 1 ;"
+            .parse::<crate::WboInstance>()
+            .unwrap()
+            .relax()
+            .unwrap();
+        let cnf = encode_instance(&instance, &EncoderConfig::default());
+        let mut output = Vec::new();
+        cnf.write_wcnf(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let (hard, soft): (Vec<_>, Vec<_>) = output.lines().partition(|l| l.starts_with("h "));
+        assert_eq!(cnf.formula().n_clauses(), hard.len());
+        assert_eq!(vec!["2 -3 0", "3 -4 0"], soft);
+    }
+
+    #[test]
+    fn test_write_dimacs_projected() {
+        let instance = "+1 x1 +1 x2 +1 x3 +1 x4 >= 2 ;"
+            .parse::<OpbInstance>()
+            .unwrap();
+        let cnf = encode_instance(&instance, &EncoderConfig::default());
+        assert_eq!(&[1, 2, 3, 4], cnf.independent_support());
+        let mut output = Vec::new();
+        cnf.write_dimacs_projected(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let mut lines = output.lines();
+        assert!(lines.next().unwrap().starts_with("p cnf "));
+        assert_eq!(Some("c ind 1 2 3 4 0"), lines.next());
+        assert_eq!(cnf.formula().n_clauses(), lines.count());
+    }
+
+    #[test]
+    fn test_provenance() {
+        let instance = "+1 x1 +1 x2 +1 x3 <= 1 ;\n+1 x1 >= 1 ;\n+2 x1 +1 x2 +1 x3 +1 x4 >= 3 ;"
+            .parse::<OpbInstance>()
+            .unwrap();
+        assert!(encode_instance(&instance, &EncoderConfig::default())
+            .provenance()
+            .is_none());
+        let cnf = encode_instance_with_provenance(&instance, &EncoderConfig::default());
+        let provenance = cnf.provenance().unwrap();
+        assert_eq!(cnf.formula().n_clauses(), provenance.len());
+        let pb2cnf = PB2CNF::new();
+        let mut first_aux_var = 5;
+        let mut first_clause = 0;
+        for (i, constraint) in instance.constraints().iter().enumerate() {
+            let encoding = pb2cnf.encode_constraint(constraint, first_aux_var);
+            let n_clauses = encoding.clauses().len();
+            assert!(provenance[first_clause..first_clause + n_clauses]
+                .iter()
+                .all(|p| *p == i));
+            first_aux_var = encoding.next_free_var_id();
+            first_clause += n_clauses;
+        }
+    }
+
+    #[test]
+    fn test_aux_vars_do_not_collide() {
+        let instance = "+1 x1 +1 x2 +1 x3 +1 x4 +1 x5 <= 2 ;\n+1 x1 +1 x2 +1 x3 +1 x4 +1 x5 >= 2 ;"
+            .parse::<OpbInstance>()
+            .unwrap();
+        let cnf = encode_instance(&instance, &EncoderConfig::default());
+        let pb2cnf = PB2CNF::new();
+        let leq = pb2cnf.encode_constraint(&instance.constraints()[0], 6);
+        let geq = pb2cnf.encode_constraint(&instance.constraints()[1], 6);
+        let n_aux_vars = leq.stats().n_aux_vars() + geq.stats().n_aux_vars();
+        assert_eq!(5 + n_aux_vars, cnf.formula().n_vars());
+    }
+}
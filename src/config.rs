@@ -0,0 +1,295 @@
+use crate::CostModel;
+
+/// The encodings that can be used for Pseudo-Boolean constraints.
+///
+/// The values of this enum match the ones of pblib's `PB_ENCODER`, except [`Auto`](Self::Auto) which is handled by this crate.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PbEncoder {
+    /// Let pblib choose the encoding.
+    #[default]
+    Best,
+    /// Binary Decision Diagrams.
+    Bdd,
+    /// Sequential Weight Counter.
+    Swc,
+    /// Sorting networks.
+    SortingNetworks,
+    /// Adder networks.
+    Adder,
+    /// Binary merge.
+    BinaryMerge,
+    /// Choose between BDDs, adder networks and sorting networks for each constraint, using the [`CostModel`](crate::CostModel) of the configuration.
+    Auto,
+}
+
+/// The encodings that can be used for At-Most-k constraints.
+///
+/// The values of this enum match the ones of pblib's `AMK_ENCODER`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AmkEncoder {
+    /// Let pblib choose the encoding.
+    #[default]
+    Best,
+    /// Binary Decision Diagrams.
+    Bdd,
+    /// Cardinality networks.
+    Card,
+}
+
+/// The encodings that can be used for At-Most-One constraints.
+///
+/// The values of this enum match the ones of pblib's `AMO_ENCODER`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AmoEncoder {
+    /// Let pblib choose the encoding.
+    #[default]
+    Best,
+    /// Nested encoding.
+    Nested,
+    /// Binary Decision Diagrams.
+    Bdd,
+    /// Bimander encoding.
+    Bimander,
+    /// Commander encoding.
+    Commander,
+    /// k-product encoding.
+    KProduct,
+    /// Binary encoding.
+    Binary,
+    /// Pairwise (naive) encoding.
+    Pairwise,
+}
+
+/// The configuration of the encoders.
+///
+/// This structure allows to choose the encodings pblib uses for each kind of constraint.
+/// The default configuration lets pblib choose the encodings, like [`PB2CNF::new`](crate::PB2CNF::new) does.
+///
+/// ```
+/// use pblib_rs::{EncoderConfig, PbEncoder, PB2CNF};
+///
+/// let config = EncoderConfig::default().with_pb_encoder(PbEncoder::Adder);
+/// let pb2cnf = PB2CNF::with_config(config);
+/// let encoding = pb2cnf.encode_geq(&[8, 4, 2, 1], &[1, 2, 3, 4], 6, 5);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EncoderConfig {
+    pb: PbEncoder,
+    amk: AmkEncoder,
+    amo: AmoEncoder,
+    cost_model: CostModel,
+    limits: EncodeLimits,
+    var_check: bool,
+    structure_sharing: bool,
+    canonical_order: bool,
+}
+
+impl EncoderConfig {
+    /// Sets the encoding used for Pseudo-Boolean constraints.
+    #[must_use]
+    pub fn with_pb_encoder(mut self, pb_encoder: PbEncoder) -> Self {
+        self.pb = pb_encoder;
+        self
+    }
+
+    /// Sets the encoding used for At-Most-k constraints.
+    #[must_use]
+    pub fn with_amk_encoder(mut self, amk_encoder: AmkEncoder) -> Self {
+        self.amk = amk_encoder;
+        self
+    }
+
+    /// Sets the encoding used for At-Most-One constraints.
+    #[must_use]
+    pub fn with_amo_encoder(mut self, amo_encoder: AmoEncoder) -> Self {
+        self.amo = amo_encoder;
+        self
+    }
+
+    /// Sets the cost model used to choose the encodings of Pseudo-Boolean constraints when the encoder is [`PbEncoder::Auto`].
+    #[must_use]
+    pub fn with_cost_model(mut self, cost_model: CostModel) -> Self {
+        self.cost_model = cost_model;
+        self
+    }
+
+    /// Sets the limits applied by the `try_` encoding functions (e.g. [`PB2CNF::try_encode_constraint`](crate::PB2CNF::try_encode_constraint)).
+    #[must_use]
+    pub fn with_limits(mut self, limits: EncodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets whether the `try_` encoding functions check the variables of the encodings.
+    ///
+    /// When this check is enabled, each variable appearing in an encoding must either be a variable of the encoded constraint, or an auxiliary variable lying between the first auxiliary variable (included) and the next free variable id (excluded).
+    /// Otherwise, the encoding functions return a [`PbError::InvalidOutputVar`](crate::PbError::InvalidOutputVar) error.
+    /// This catches configuration or binding bugs when they occur, rather than when the formula is given to a solver.
+    /// The check is disabled by default.
+    ///
+    /// ```
+    /// use pblib_rs::{Comparator, EncoderConfig, PbConstraint, PB2CNF};
+    ///
+    /// let pb2cnf = PB2CNF::with_config(EncoderConfig::default().with_var_check(true));
+    /// let constraint = PbConstraint::new(vec![(2, 1), (3, 2), (4, 3)], Comparator::Leq, 5);
+    /// assert!(pb2cnf.try_encode_constraint(&constraint, 4).is_ok());
+    /// ```
+    #[must_use]
+    pub fn with_var_check(mut self, var_check: bool) -> Self {
+        self.var_check = var_check;
+        self
+    }
+
+    /// Sets whether an [`EncodingContext`](crate::EncodingContext) shares the structure of the constraints over the same terms.
+    ///
+    /// When this option is enabled, the context records the encoding of each inequality it encodes.
+    /// The next inequalities with the same weighted literals and the same direction (e.g. staged bounds on a cost function) only add the clauses enforcing their bound to the existing structure (sorting network, BDD, adder...), instead of encoding the whole constraint again.
+    /// pblib only builds such structures with the sequential weight counter, the adder and the cardinality networks, whatever the chosen encoders;
+    /// for small constraints, they may thus be larger than independent encodings by other means (e.g. BDDs).
+    /// The sharing is disabled by default.
+    ///
+    /// ```
+    /// use pblib_rs::{EncoderConfig, EncodingContext, PbConstraint};
+    ///
+    /// let config = EncoderConfig::default().with_structure_sharing(true);
+    /// let mut ctx = EncodingContext::with_config(4, &config);
+    /// let cost = PbConstraint::weighted([(3, 1), (2, 2), (2, 3), (1, 4)]);
+    /// for bound in [6, 4, 2] {
+    ///     cost.clone().leq(bound).encode(&mut ctx).unwrap();
+    /// }
+    /// assert_eq!(2, ctx.n_shared());
+    /// ```
+    #[must_use]
+    pub fn with_structure_sharing(mut self, structure_sharing: bool) -> Self {
+        self.structure_sharing = structure_sharing;
+        self
+    }
+
+    /// Sets whether the encodings returned by [`PB2CNF`](crate::PB2CNF) are in canonical order.
+    ///
+    /// When this option is enabled, the encoding functions returning an [`EncodingResult`](crate::EncodingResult) sort its clauses as [`EncodingResult::canonicalize`](crate::EncodingResult::canonicalize) does, so the output does not depend on the order in which pblib produces the clauses.
+    /// The functions sending the clauses to a [`ClauseSink`](crate::ClauseSink) are not affected.
+    /// The option is disabled by default.
+    #[must_use]
+    pub fn with_canonical_order(mut self, canonical_order: bool) -> Self {
+        self.canonical_order = canonical_order;
+        self
+    }
+
+    /// Returns the encoding used for Pseudo-Boolean constraints.
+    #[must_use]
+    pub fn pb_encoder(&self) -> PbEncoder {
+        self.pb
+    }
+
+    /// Returns the encoding used for At-Most-k constraints.
+    #[must_use]
+    pub fn amk_encoder(&self) -> AmkEncoder {
+        self.amk
+    }
+
+    /// Returns the encoding used for At-Most-One constraints.
+    #[must_use]
+    pub fn amo_encoder(&self) -> AmoEncoder {
+        self.amo
+    }
+
+    /// Returns the cost model used when the encoder of Pseudo-Boolean constraints is [`PbEncoder::Auto`].
+    #[must_use]
+    pub fn cost_model(&self) -> CostModel {
+        self.cost_model
+    }
+
+    /// Returns the limits applied by the `try_` encoding functions.
+    #[must_use]
+    pub fn limits(&self) -> EncodeLimits {
+        self.limits
+    }
+
+    /// Returns whether the `try_` encoding functions check the variables of the encodings.
+    #[must_use]
+    pub fn var_check(&self) -> bool {
+        self.var_check
+    }
+
+    /// Returns whether an [`EncodingContext`](crate::EncodingContext) shares the structure of the constraints over the same terms.
+    #[must_use]
+    pub fn structure_sharing(&self) -> bool {
+        self.structure_sharing
+    }
+
+    /// Returns whether the encodings returned by [`PB2CNF`](crate::PB2CNF) are in canonical order.
+    #[must_use]
+    pub fn canonical_order(&self) -> bool {
+        self.canonical_order
+    }
+}
+
+/// Limits on the size of an encoding.
+///
+/// The `try_` encoding functions (e.g. [`PB2CNF::try_encode_constraint`](crate::PB2CNF::try_encode_constraint)) abort the encoding as soon as it exceeds one of these limits, and return a [`PbError::BudgetExceeded`](crate::PbError::BudgetExceeded) error.
+/// This protects applications from constraints whose encoding is unexpectedly large.
+/// By default, no limit is set.
+///
+/// ```
+/// use pblib_rs::{Comparator, EncodeLimits, PbConstraint, PbError, PB2CNF};
+///
+/// let constraint = PbConstraint::new((1..=20).map(|i| (i, i as i32)).collect(), Comparator::Geq, 100);
+/// let limits = EncodeLimits::default().with_max_clauses(10);
+/// let mut clauses = Vec::new();
+/// match PB2CNF::new().try_encode_constraint_into(&constraint, 21, &limits, &mut clauses) {
+///     Err(PbError::BudgetExceeded(stats)) => assert_eq!(10, stats.n_clauses()),
+///     _ => panic!(),
+/// }
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EncodeLimits {
+    clauses: Option<usize>,
+    aux_vars: Option<usize>,
+    heap_bytes: Option<usize>,
+}
+
+impl EncodeLimits {
+    /// Sets the maximal number of clauses of an encoding.
+    #[must_use]
+    pub fn with_max_clauses(mut self, max_clauses: usize) -> Self {
+        self.clauses = Some(max_clauses);
+        self
+    }
+
+    /// Sets the maximal number of auxiliary variables of an encoding.
+    ///
+    /// The auxiliary variables are counted as they appear in the clauses, so variables that pblib reserves without using them are ignored.
+    #[must_use]
+    pub fn with_max_aux_vars(mut self, max_aux_vars: usize) -> Self {
+        self.aux_vars = Some(max_aux_vars);
+        self
+    }
+
+    /// Sets the maximal amount of memory needed to store the clauses of an encoding, in bytes.
+    ///
+    /// The memory is estimated as the clauses are produced (see [`EncodingStats::approx_heap_bytes`](crate::EncodingStats::approx_heap_bytes)); the memory used by pblib itself is not taken into account.
+    #[must_use]
+    pub fn with_max_heap_bytes(mut self, max_heap_bytes: usize) -> Self {
+        self.heap_bytes = Some(max_heap_bytes);
+        self
+    }
+
+    /// Returns the maximal number of clauses of an encoding, if any.
+    #[must_use]
+    pub fn max_clauses(&self) -> Option<usize> {
+        self.clauses
+    }
+
+    /// Returns the maximal number of auxiliary variables of an encoding, if any.
+    #[must_use]
+    pub fn max_aux_vars(&self) -> Option<usize> {
+        self.aux_vars
+    }
+
+    /// Returns the maximal amount of memory needed to store the clauses of an encoding, if any.
+    #[must_use]
+    pub fn max_heap_bytes(&self) -> Option<usize> {
+        self.heap_bytes
+    }
+}
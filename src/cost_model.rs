@@ -0,0 +1,172 @@
+use crate::{PbConstraint, PbEncoder};
+
+/// The cost model used by [`PbEncoder::Auto`] to choose an encoding for each Pseudo-Boolean constraint.
+///
+/// For each candidate encoding (BDD, adder networks and sorting networks), the model estimates the number of clauses of the encoding from the number of terms, the magnitude of the weights and the tightness of the bound.
+/// This estimate is then multiplied by a factor (given in percent, 100 by default) associated with the encoding, and the encoding with the lowest cost is chosen.
+/// The factors allow to favor or penalize an encoding, e.g. to prefer the propagation strength of BDDs as long as they are not much larger than the other encodings.
+///
+/// The estimates are the following, where `n` is the number of terms, `t` is the distance between the bound and the closest trivial bound (0 or the sum of the weights), `b` is the number of bits of the largest weight, and `m` is the total number of bits set in the binary representations of the weights:
+/// - BDD: `n * (t + 1)`;
+/// - adder networks: `14 * n * b`;
+/// - sorting networks: `m * ceil(log2(m))^2 / 2`.
+///
+/// ```
+/// use pblib_rs::{Comparator, CostModel, PbConstraint, PbEncoder};
+///
+/// let model = CostModel::default();
+/// // large weights: adder networks are preferred
+/// let constraint = PbConstraint::new(
+///     (1..=20).map(|i| (1000 * i + 7, i as i32)).collect(),
+///     Comparator::Leq,
+///     50_000,
+/// );
+/// assert_eq!(PbEncoder::Adder, model.choose_encoder(&constraint));
+/// // sorting networks are chosen if adder networks are penalized
+/// let model = model.with_adder_factor(150);
+/// assert_eq!(PbEncoder::SortingNetworks, model.choose_encoder(&constraint));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CostModel {
+    bdd: u32,
+    adder: u32,
+    sorting_networks: u32,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self {
+            bdd: 100,
+            adder: 100,
+            sorting_networks: 100,
+        }
+    }
+}
+
+impl CostModel {
+    /// Sets the factor (in percent) applied to the estimated size of BDD encodings.
+    #[must_use]
+    pub fn with_bdd_factor(mut self, factor: u32) -> Self {
+        self.bdd = factor;
+        self
+    }
+
+    /// Sets the factor (in percent) applied to the estimated size of adder network encodings.
+    #[must_use]
+    pub fn with_adder_factor(mut self, factor: u32) -> Self {
+        self.adder = factor;
+        self
+    }
+
+    /// Sets the factor (in percent) applied to the estimated size of sorting network encodings.
+    #[must_use]
+    pub fn with_sorting_networks_factor(mut self, factor: u32) -> Self {
+        self.sorting_networks = factor;
+        self
+    }
+
+    /// Returns the estimated cost of encoding a constraint with the given encoding.
+    ///
+    /// The cost is the estimated number of clauses multiplied by the factor of the encoding.
+    /// Encodings that are not considered by this model (i.e. other than BDD, adder networks and sorting networks) have the maximal cost.
+    #[must_use]
+    pub fn cost(&self, encoder: PbEncoder, constraint: &PbConstraint) -> u64 {
+        let (estimate, factor) = match encoder {
+            PbEncoder::Bdd => (bdd_estimate(constraint), self.bdd),
+            PbEncoder::Adder => (adder_estimate(constraint), self.adder),
+            PbEncoder::SortingNetworks => {
+                (sorting_networks_estimate(constraint), self.sorting_networks)
+            }
+            _ => return u64::MAX,
+        };
+        estimate.saturating_mul(u64::from(factor)) / 100
+    }
+
+    /// Returns the encoding with the lowest cost for a constraint.
+    ///
+    /// In case of ties, BDDs are preferred to adder networks, which are preferred to sorting networks.
+    #[must_use]
+    pub fn choose_encoder(&self, constraint: &PbConstraint) -> PbEncoder {
+        [PbEncoder::Bdd, PbEncoder::Adder, PbEncoder::SortingNetworks]
+            .into_iter()
+            .min_by_key(|e| self.cost(*e, constraint))
+            .unwrap_or(PbEncoder::Bdd)
+    }
+}
+
+fn abs_weights(constraint: &PbConstraint) -> impl Iterator<Item = u64> + '_ {
+    constraint.terms().iter().map(|t| t.0.unsigned_abs())
+}
+
+fn n_terms(constraint: &PbConstraint) -> u64 {
+    constraint.terms().len() as u64
+}
+
+fn bdd_estimate(constraint: &PbConstraint) -> u64 {
+    let positive = constraint.non_strict().with_positive_weights();
+    let total = positive
+        .terms()
+        .iter()
+        .fold(0_i64, |acc, t| acc.saturating_add(t.0));
+    let t = positive
+        .rhs()
+        .min(total.saturating_sub(positive.rhs()))
+        .max(0);
+    n_terms(constraint).saturating_mul(t.unsigned_abs().saturating_add(1))
+}
+
+fn adder_estimate(constraint: &PbConstraint) -> u64 {
+    let max_weight = abs_weights(constraint).max().unwrap_or_default();
+    let n_bits = u64::from(u64::BITS - max_weight.leading_zeros());
+    14 * n_terms(constraint) * n_bits
+}
+
+fn sorting_networks_estimate(constraint: &PbConstraint) -> u64 {
+    let m = abs_weights(constraint)
+        .map(|w| u64::from(w.count_ones()))
+        .sum::<u64>();
+    let log = u64::from(u64::BITS - m.saturating_sub(1).leading_zeros());
+    m * log * log / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Comparator;
+
+    #[test]
+    fn test_estimates() {
+        // 8x1 + 4x2 + 2x3 + 1x4 <= 6: t = 6, b = 4, m = 4
+        let constraint =
+            PbConstraint::new(vec![(8, 1), (4, 2), (2, 3), (1, 4)], Comparator::Leq, 6);
+        let model = CostModel::default();
+        assert_eq!(28, model.cost(PbEncoder::Bdd, &constraint));
+        assert_eq!(224, model.cost(PbEncoder::Adder, &constraint));
+        assert_eq!(8, model.cost(PbEncoder::SortingNetworks, &constraint));
+        assert_eq!(u64::MAX, model.cost(PbEncoder::Swc, &constraint));
+        assert_eq!(
+            PbEncoder::SortingNetworks,
+            model.choose_encoder(&constraint)
+        );
+        let model = model.with_sorting_networks_factor(1000);
+        assert_eq!(80, model.cost(PbEncoder::SortingNetworks, &constraint));
+        assert_eq!(PbEncoder::Bdd, model.choose_encoder(&constraint));
+    }
+
+    #[test]
+    fn test_bdd_tightness() {
+        let terms = (1..=10).map(|i| (i64::from(i), i)).collect::<Vec<_>>();
+        let model = CostModel::default();
+        // the sum of the weights is 55
+        let loose = PbConstraint::new(terms.clone(), Comparator::Leq, 53);
+        let tight = PbConstraint::new(terms.clone(), Comparator::Leq, 27);
+        assert_eq!(30, model.cost(PbEncoder::Bdd, &loose));
+        assert_eq!(280, model.cost(PbEncoder::Bdd, &tight));
+        let negated = PbConstraint::new(
+            terms.iter().map(|(w, l)| (-w, *l)).collect(),
+            Comparator::Geq,
+            -53,
+        );
+        assert_eq!(30, model.cost(PbEncoder::Bdd, &negated));
+    }
+}
@@ -1,50 +1,194 @@
 #include "cpblib.h"
 
-extern "C"
+#include <new>
+
+/* Magic numbers stored at the beginning of the objects shared with Rust, checked by the ffi-debug builds. */
+static const uint64_t CPB2CNF_MAGIC = 0x5042324e46504232ULL;
+static const uint64_t CLAUSE_BUFFER_MAGIC = 0x434c415542554646ULL;
+static const uint64_t INC_CONSTRAINT_MAGIC = 0x494e43434f4e5354ULL;
+
+#ifdef PBLIB_RS_FFI_DEBUG
+#include <cstdio>
+#include <cstdlib>
+
+static void check_magic(uint64_t magic, uint64_t expected, char const * what)
+{
+    if (magic != expected) {
+        fprintf(stderr, "FFI debug: invalid %s pointer (magic number is %llx)\n", what, (unsigned long long) magic);
+        abort();
+    }
+}
+
+#define CHECK_MAGIC(object, expected, what) check_magic((object)->magic, expected, what)
+#define CLEAR_MAGIC(object) ((object)->magic = 0)
+#else
+#define CHECK_MAGIC(object, expected, what)
+#define CLEAR_MAGIC(object)
+#endif
+
+#ifdef PBLIB_RS_FFI_DEBUG
+/* The Rust binaries are not linked by the C++ compiler, so the sanitizer runtime cannot come first in the list of libraries. */
+extern "C" const char* __asan_default_options()
+{
+    return "verify_asan_link_order=0";
+}
+#endif
+
+/* Returned instead of the next free variable id when pblib throws an exception. */
+static const int32_t ENCODING_FAILED = -1;
+
+/* Thrown when a callback asks to abort the encoding. */
+struct EncodingAborted
 {
-    int32_t* encode_formula(std::vector< std::vector<int32_t> > formula_vec, int32_t firstAuxiliaryVariable);
+};
 
+/* A clause database forwarding each clause to a callback, so clauses are never stored on this side of the FFI. */
+class CallbackClauseDatabase : public ClauseDatabase
+{
+private:
+    ClauseCallback callback;
+    void* callback_data;
+
+    virtual void addClauseIntern(std::vector< int32_t > const & clause)
+    {
+        if (callback(callback_data, clause.data(), clause.size()) != 0) {
+            throw EncodingAborted();
+        }
+    }
+
+public:
+    CallbackClauseDatabase(PBConfig config, ClauseCallback callback, void* callback_data)
+        : ClauseDatabase(config), callback(callback), callback_data(callback_data)
+    {
+    }
+};
+
+struct CPB2CNFData
+{
+    uint64_t magic = CPB2CNF_MAGIC;
+    PBConfig config;
+    PB2CNF pb2cnf;
+
+    CPB2CNFData(PBConfig config) : config(config), pb2cnf(this->config)
+    {
+    }
+};
+
+/* An incremental constraint, with the configuration of the encoder that produced its initial encoding. */
+struct IncConstraintData
+{
+    uint64_t magic = INC_CONSTRAINT_MAGIC;
+    PBConfig config;
+    IncPBConstraint constraint;
+
+    IncConstraintData(PBConfig config, std::vector<PBLib::WeightedLit> const & literals, PBLib::Comparator comparator, int64_t bound)
+        : config(config), constraint(literals, comparator, bound)
+    {
+    }
+};
+
+/* A clause database kept alive across encodings, so the clauses can be retrieved at once. */
+struct ClauseBuffer
+{
+    uint64_t magic = CLAUSE_BUFFER_MAGIC;
+    std::vector<int32_t> data;
+    size_t n_clauses = 0;
+};
+
+static std::vector<PBLib::WeightedLit> weighted_lits(
+    int64_t* weights,
+    int32_t weights_len,
+    int32_t* literals,
+    int32_t literals_len
+) {
+    std::vector<PBLib::WeightedLit> lits;
+    for (int32_t i = 0; i < weights_len && i < literals_len; ++i) {
+        lits.push_back(PBLib::WeightedLit(literals[i], weights[i]));
+    }
+    return lits;
+}
+
+static int32_t encode_constraint(
+    CPB2CNF* cpb2cnf,
+    PBLib::PBConstraint const & constraint,
+    int32_t firstAuxiliaryVariable,
+    ClauseCallback callback,
+    void* callback_data
+) {
+    CPB2CNFData *data = reinterpret_cast<CPB2CNFData *>(cpb2cnf);
+    CHECK_MAGIC(data, CPB2CNF_MAGIC, "PB2CNF");
+    CallbackClauseDatabase clauseDatabase(data->config, callback, callback_data);
+    AuxVarManager auxVars(firstAuxiliaryVariable);
+    try {
+        data->pb2cnf.encode(constraint, clauseDatabase, auxVars);
+    } catch (EncodingAborted const &) {
+        return 0;
+    } catch (...) {
+        return ENCODING_FAILED;
+    }
+    return auxVars.getBiggestReturnedAuxVar() + 1;
+}
+
+extern "C"
+{
     CPB2CNF* newPB2CNF()
     {
-        PB2CNF* pb2cnf = new PB2CNF();
-        return reinterpret_cast<CPB2CNF*>(pb2cnf);
+        try {
+            CPB2CNFData* data = new CPB2CNFData(std::make_shared<PBConfigClass>());
+            return reinterpret_cast<CPB2CNF*>(data);
+        } catch (std::bad_alloc const &) {
+            return NULL;
+        }
     }
 
-    int32_t* encodeLeq(
+    CPB2CNF* newPB2CNFWithConfig(
+        int32_t pb_encoder,
+        int32_t amk_encoder,
+        int32_t amo_encoder
+    ) {
+        try {
+            PBConfig config = std::make_shared<PBConfigClass>();
+            config->pb_encoder = static_cast<PB_ENCODER::PB2CNF_PB_Encoder>(pb_encoder);
+            config->amk_encoder = static_cast<AMK_ENCODER::PB2CNF_AMK_Encoder>(amk_encoder);
+            config->amo_encoder = static_cast<AMO_ENCODER::PB2CNF_AMO_Encoder>(amo_encoder);
+            CPB2CNFData* data = new CPB2CNFData(config);
+            return reinterpret_cast<CPB2CNF*>(data);
+        } catch (std::bad_alloc const &) {
+            return NULL;
+        }
+    }
+
+    int32_t encodeLeq(
         CPB2CNF* cpb2cnf,
         int64_t* weights,
         int32_t weights_len,
         int32_t* literals,
         int32_t literals_len,
         int64_t leq,
-        int32_t firstAuxiliaryVariable
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data
     ) {
-        PB2CNF *pb2cnf = reinterpret_cast<PB2CNF *>(cpb2cnf);
-        std::vector<int64_t> weights_vec(weights, weights + weights_len);
-        std::vector<int32_t> literals_vec(literals, literals + literals_len);
-        std::vector< std::vector<int32_t> > formula_vec;
-        firstAuxiliaryVariable = pb2cnf->encodeLeq(weights_vec, literals_vec, leq, formula_vec, firstAuxiliaryVariable) + 1;
-        return encode_formula(formula_vec, firstAuxiliaryVariable);
+        PBLib::PBConstraint constraint(weighted_lits(weights, weights_len, literals, literals_len), PBLib::LEQ, leq);
+        return encode_constraint(cpb2cnf, constraint, firstAuxiliaryVariable, callback, callback_data);
     }
 
-    int32_t* encodeGeq(
+    int32_t encodeGeq(
         CPB2CNF* cpb2cnf,
         int64_t* weights,
         int32_t weights_len,
         int32_t* literals,
         int32_t literals_len,
         int64_t geq,
-        int32_t firstAuxiliaryVariable
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data
     ) {
-        PB2CNF *pb2cnf = reinterpret_cast<PB2CNF *>(cpb2cnf);
-        std::vector<int64_t> weights_vec(weights, weights + weights_len);
-        std::vector<int32_t> literals_vec(literals, literals + literals_len);
-        std::vector< std::vector<int32_t> > formula_vec;
-        firstAuxiliaryVariable = pb2cnf->encodeGeq(weights_vec, literals_vec, geq, formula_vec, firstAuxiliaryVariable) + 1;
-        return encode_formula(formula_vec, firstAuxiliaryVariable);
+        PBLib::PBConstraint constraint(weighted_lits(weights, weights_len, literals, literals_len), PBLib::GEQ, geq);
+        return encode_constraint(cpb2cnf, constraint, firstAuxiliaryVariable, callback, callback_data);
     }
 
-    int32_t* encodeBoth(
+    int32_t encodeBoth(
         CPB2CNF* cpb2cnf,
         int64_t* weights,
         int32_t weights_len,
@@ -52,68 +196,219 @@ extern "C"
         int32_t literals_len,
         int64_t leq,
         int64_t geq,
-        int32_t firstAuxiliaryVariable
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data
     ) {
-        PB2CNF *pb2cnf = reinterpret_cast<PB2CNF *>(cpb2cnf);
-        std::vector<int64_t> weights_vec(weights, weights + weights_len);
-        std::vector<int32_t> literals_vec(literals, literals + literals_len);
-        std::vector< std::vector<int32_t> > formula_vec;
-        firstAuxiliaryVariable = pb2cnf->encodeBoth(weights_vec, literals_vec, leq, geq, formula_vec, firstAuxiliaryVariable) + 1;
-        return encode_formula(formula_vec, firstAuxiliaryVariable);
+        PBLib::PBConstraint constraint(weighted_lits(weights, weights_len, literals, literals_len), PBLib::BOTH, leq, geq);
+        return encode_constraint(cpb2cnf, constraint, firstAuxiliaryVariable, callback, callback_data);
     }
 
-    int32_t* encodeAtMostK(
+    int32_t encodeAtMostK(
         CPB2CNF* cpb2cnf,
         int32_t* literals,
         int32_t literals_len,
         int64_t k,
-        int32_t firstAuxiliaryVariable
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data
     ) {
-        PB2CNF *pb2cnf = reinterpret_cast<PB2CNF *>(cpb2cnf);
-        std::vector<int32_t> literals_vec(literals, literals + literals_len);
-        std::vector< std::vector<int32_t> > formula_vec;
-        firstAuxiliaryVariable = pb2cnf->encodeAtMostK(literals_vec, k, formula_vec, firstAuxiliaryVariable) + 1;
-        return encode_formula(formula_vec, firstAuxiliaryVariable);
+        std::vector<int64_t> weights(literals_len, 1);
+        PBLib::PBConstraint constraint(weighted_lits(weights.data(), literals_len, literals, literals_len), PBLib::LEQ, k);
+        return encode_constraint(cpb2cnf, constraint, firstAuxiliaryVariable, callback, callback_data);
     }
 
-    int32_t* encodeAtLeastK(
+    int32_t encodeAtLeastK(
         CPB2CNF* cpb2cnf,
         int32_t* literals,
         int32_t literals_len,
         int64_t k,
-        int32_t firstAuxiliaryVariable
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data
     ) {
-        PB2CNF *pb2cnf = reinterpret_cast<PB2CNF *>(cpb2cnf);
-        std::vector<int32_t> literals_vec(literals, literals + literals_len);
-        std::vector< std::vector<int32_t> > formula_vec;
-        firstAuxiliaryVariable = pb2cnf->encodeAtLeastK(literals_vec, k, formula_vec, firstAuxiliaryVariable) + 1;
-        return encode_formula(formula_vec, firstAuxiliaryVariable);
+        std::vector<int64_t> weights(literals_len, 1);
+        PBLib::PBConstraint constraint(weighted_lits(weights.data(), literals_len, literals, literals_len), PBLib::GEQ, k);
+        return encode_constraint(cpb2cnf, constraint, firstAuxiliaryVariable, callback, callback_data);
     }
 
-    int32_t* encode_formula(std::vector< std::vector<int32_t> > formula_vec, int32_t firstAuxiliaryVariable)
+    void deletePB2CNF(CPB2CNF* cpb2cnf)
     {
-        int32_t formula_len = 2 + formula_vec.size();
-        for(std::vector< std::vector<int32_t> >::iterator it = formula_vec.begin(); it < formula_vec.end(); it++) {
-            formula_len += it->size();
+        CPB2CNFData *data = reinterpret_cast<CPB2CNFData *>(cpb2cnf);
+        if (data != NULL) {
+            CHECK_MAGIC(data, CPB2CNF_MAGIC, "PB2CNF");
+            CLEAR_MAGIC(data);
         }
-        int32_t* formula = (int32_t*) malloc(formula_len * sizeof(int32_t));
-        int *pf = formula;
-        *pf++ = formula_len;
-        *pf++ = firstAuxiliaryVariable;
-        for(std::vector< std::vector<int32_t> >::iterator it = formula_vec.begin(); it < formula_vec.end(); it++) {
-            *pf++ = it->size();
-            copy(it->begin(), it->end(), pf);
-            pf += it->size();
+        delete data;
+    }
+
+    CIncConstraint* newIncConstraint(
+        CPB2CNF* cpb2cnf,
+        int64_t* weights,
+        int32_t weights_len,
+        int32_t* literals,
+        int32_t literals_len,
+        int32_t comparator,
+        int64_t bound,
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data,
+        int32_t* next_free_var_id
+    ) {
+        CPB2CNFData *data = reinterpret_cast<CPB2CNFData *>(cpb2cnf);
+        CHECK_MAGIC(data, CPB2CNF_MAGIC, "PB2CNF");
+        IncConstraintData* inc;
+        try {
+            inc = new IncConstraintData(
+                data->config,
+                weighted_lits(weights, weights_len, literals, literals_len),
+                comparator == 0 ? PBLib::LEQ : PBLib::GEQ,
+                bound
+            );
+        } catch (std::bad_alloc const &) {
+            return NULL;
+        }
+        CallbackClauseDatabase clauseDatabase(data->config, callback, callback_data);
+        AuxVarManager auxVars(firstAuxiliaryVariable);
+        try {
+            data->pb2cnf.encodeIncInital(inc->constraint, clauseDatabase, auxVars);
+            *next_free_var_id = auxVars.getBiggestReturnedAuxVar() + 1;
+        } catch (EncodingAborted const &) {
+            *next_free_var_id = 0;
+        } catch (...) {
+            *next_free_var_id = ENCODING_FAILED;
         }
-        return formula;
+        return reinterpret_cast<CIncConstraint*>(inc);
     }
 
-    void deletePB2CNF(CPB2CNF* cpb2cnf)
+    int32_t incConstraintEncodeNewBound(
+        CIncConstraint* cinc,
+        int64_t bound,
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data
+    ) {
+        IncConstraintData *inc = reinterpret_cast<IncConstraintData *>(cinc);
+        CHECK_MAGIC(inc, INC_CONSTRAINT_MAGIC, "incremental constraint");
+        CallbackClauseDatabase clauseDatabase(inc->config, callback, callback_data);
+        AuxVarManager auxVars(firstAuxiliaryVariable);
+        try {
+            if (inc->constraint.getComparator() == PBLib::LEQ) {
+                inc->constraint.encodeNewLeq(bound, clauseDatabase, auxVars);
+            } else {
+                inc->constraint.encodeNewGeq(bound, clauseDatabase, auxVars);
+            }
+        } catch (EncodingAborted const &) {
+            return 0;
+        } catch (...) {
+            return ENCODING_FAILED;
+        }
+        return auxVars.getBiggestReturnedAuxVar() + 1;
+    }
+
+    void deleteIncConstraint(CIncConstraint* cinc)
+    {
+        IncConstraintData *inc = reinterpret_cast<IncConstraintData *>(cinc);
+        if (inc != NULL) {
+            CHECK_MAGIC(inc, INC_CONSTRAINT_MAGIC, "incremental constraint");
+            CLEAR_MAGIC(inc);
+        }
+        delete inc;
+    }
+
+    CClauseBuffer* newClauseBuffer()
+    {
+        return reinterpret_cast<CClauseBuffer*>(new (std::nothrow) ClauseBuffer());
+    }
+
+    int32_t appendToClauseBuffer(void* buffer, const int32_t* clause, int32_t clause_len)
+    {
+        ClauseBuffer *clauseBuffer = reinterpret_cast<ClauseBuffer *>(buffer);
+        CHECK_MAGIC(clauseBuffer, CLAUSE_BUFFER_MAGIC, "clause buffer");
+        clauseBuffer->data.push_back(clause_len);
+        clauseBuffer->data.insert(clauseBuffer->data.end(), clause, clause + clause_len);
+        clauseBuffer->n_clauses++;
+        return 0;
+    }
+
+    size_t clauseBufferNClauses(CClauseBuffer* cbuffer)
+    {
+        ClauseBuffer *clauseBuffer = reinterpret_cast<ClauseBuffer *>(cbuffer);
+        CHECK_MAGIC(clauseBuffer, CLAUSE_BUFFER_MAGIC, "clause buffer");
+        return clauseBuffer->n_clauses;
+    }
+
+    const int32_t* clauseBufferData(CClauseBuffer* cbuffer)
+    {
+        ClauseBuffer *clauseBuffer = reinterpret_cast<ClauseBuffer *>(cbuffer);
+        CHECK_MAGIC(clauseBuffer, CLAUSE_BUFFER_MAGIC, "clause buffer");
+        return clauseBuffer->data.data();
+    }
+
+    size_t clauseBufferLen(CClauseBuffer* cbuffer)
+    {
+        ClauseBuffer *clauseBuffer = reinterpret_cast<ClauseBuffer *>(cbuffer);
+        CHECK_MAGIC(clauseBuffer, CLAUSE_BUFFER_MAGIC, "clause buffer");
+        return clauseBuffer->data.size();
+    }
+
+    void clearClauseBuffer(CClauseBuffer* cbuffer)
+    {
+        ClauseBuffer *clauseBuffer = reinterpret_cast<ClauseBuffer *>(cbuffer);
+        CHECK_MAGIC(clauseBuffer, CLAUSE_BUFFER_MAGIC, "clause buffer");
+        clauseBuffer->data.clear();
+        clauseBuffer->n_clauses = 0;
+    }
+
+    void deleteClauseBuffer(CClauseBuffer* cbuffer)
+    {
+        ClauseBuffer *clauseBuffer = reinterpret_cast<ClauseBuffer *>(cbuffer);
+        if (clauseBuffer != NULL) {
+            CHECK_MAGIC(clauseBuffer, CLAUSE_BUFFER_MAGIC, "clause buffer");
+            CLEAR_MAGIC(clauseBuffer);
+        }
+        delete clauseBuffer;
+    }
+
+    CPBFuzzer* newPBFuzzer(int32_t n_vars)
+    {
+        PBFuzzer* fuzzer = new (std::nothrow) PBFuzzer();
+        if (fuzzer == NULL) {
+            return NULL;
+        }
+        fuzzer->numer_of_variables = n_vars;
+        return reinterpret_cast<CPBFuzzer*>(fuzzer);
+    }
+
+    int64_t* fuzzerGenerateConstraint(CPBFuzzer* cfuzzer, uint32_t seed)
+    {
+        PBFuzzer *fuzzer = reinterpret_cast<PBFuzzer *>(cfuzzer);
+        srand(seed);
+        PBLib::PBConstraint constraint = fuzzer->generatePBConstraint();
+        std::vector<PBLib::WeightedLit> const & terms = constraint.getWeightedLiterals();
+        int64_t constraint_len = 4 + 2 * terms.size();
+        int64_t* buffer = (int64_t*) malloc(constraint_len * sizeof(int64_t));
+        if (buffer == NULL) {
+            return NULL;
+        }
+        int64_t *pb = buffer;
+        *pb++ = constraint_len;
+        *pb++ = constraint.getComparator();
+        *pb++ = constraint.getLeq();
+        *pb++ = constraint.getGeq();
+        for(std::vector<PBLib::WeightedLit>::const_iterator it = terms.begin(); it < terms.end(); it++) {
+            *pb++ = it->weight;
+            *pb++ = it->lit;
+        }
+        return buffer;
+    }
+
+    void deletePBFuzzer(CPBFuzzer* cfuzzer)
     {
-        delete reinterpret_cast<PB2CNF*>(cpb2cnf);
+        delete reinterpret_cast<PBFuzzer*>(cfuzzer);
     }
 
-    void freePtr(int32_t* ptr)
+    void freeI64Ptr(int64_t* ptr)
     {
         free(ptr);
     }
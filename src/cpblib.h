@@ -5,37 +5,53 @@
 #include <vector>
 
 #include "pblib/pb2cnf.h"
+#include "pblib/PBFuzzer.h"
 
 #ifdef __cplusplus
 extern "C"
 {
 #endif
 
+    /* The functions returning a pointer return NULL if an allocation fails. */
+
     typedef struct CPB2CNF CPB2CNF;
 
     CPB2CNF* newPB2CNF();
 
-    int32_t* encodeLeq(
+    CPB2CNF* newPB2CNFWithConfig(
+        int32_t pb_encoder,
+        int32_t amk_encoder,
+        int32_t amo_encoder
+    );
+
+    /* Returns 0 to continue the encoding, or another value to abort it; aborted encodings return 0, and encodings failing because of an exception return -1. */
+    typedef int32_t (*ClauseCallback)(void* data, const int32_t* clause, int32_t clause_len);
+
+    int32_t encodeLeq(
         CPB2CNF* cpb2cnf,
         int64_t* weights,
         int32_t weights_len,
         int32_t* literals,
         int32_t literals_len,
         int64_t leq,
-        int32_t firstAuxiliaryVariable
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data
     );
 
-    int32_t* encodeGeq(
+    int32_t encodeGeq(
         CPB2CNF* cpb2cnf,
         int64_t* weights,
         int32_t weights_len,
         int32_t* literals,
         int32_t literals_len,
         int64_t geq,
-        int32_t firstAuxiliaryVariable
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data
     );
 
-    int32_t* encodeBoth(
+    int32_t encodeBoth(
         CPB2CNF* cpb2cnf,
         int64_t* weights,
         int32_t weights_len,
@@ -43,28 +59,91 @@ extern "C"
         int32_t literals_len,
         int64_t leq,
         int64_t geq,
-        int32_t firstAuxiliaryVariable
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data
     );
 
-    int32_t* encodeAtMostK(
+    int32_t encodeAtMostK(
         CPB2CNF* cpb2cnf,
         int32_t* literals,
         int32_t literals_len,
         int64_t k,
-        int32_t firstAuxiliaryVariable
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data
     );
 
-    int32_t* encodeAtLeastK(
+    int32_t encodeAtLeastK(
         CPB2CNF* cpb2cnf,
         int32_t* literals,
         int32_t literals_len,
         int64_t k,
-        int32_t firstAuxiliaryVariable
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data
     );
 
     void deletePB2CNF(CPB2CNF* cpb2cnf);
 
-    void freePtr(int32_t* ptr);
+    typedef struct CIncConstraint CIncConstraint;
+
+    /*
+     * Encodes a constraint whose bound can then be tightened by incConstraintEncodeNewBound, reusing its structure.
+     * The comparator is 0 for LEQ and 1 for GEQ. The next free variable id is written to next_free_var_id (0 if the encoding is aborted, -1 if it fails).
+     */
+    CIncConstraint* newIncConstraint(
+        CPB2CNF* cpb2cnf,
+        int64_t* weights,
+        int32_t weights_len,
+        int32_t* literals,
+        int32_t literals_len,
+        int32_t comparator,
+        int64_t bound,
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data,
+        int32_t* next_free_var_id
+    );
+
+    /* Encodes a new bound for the constraint; looser bounds than the current one produce no clause. */
+    int32_t incConstraintEncodeNewBound(
+        CIncConstraint* cinc,
+        int64_t bound,
+        int32_t firstAuxiliaryVariable,
+        ClauseCallback callback,
+        void* callback_data
+    );
+
+    void deleteIncConstraint(CIncConstraint* cinc);
+
+    typedef struct CClauseBuffer CClauseBuffer;
+
+    CClauseBuffer* newClauseBuffer();
+
+    /* A ClauseCallback appending the clause to the buffer given as data. */
+    int32_t appendToClauseBuffer(void* buffer, const int32_t* clause, int32_t clause_len);
+
+    size_t clauseBufferNClauses(CClauseBuffer* cbuffer);
+
+    /* The clauses, stored as their length followed by their literals. */
+    const int32_t* clauseBufferData(CClauseBuffer* cbuffer);
+
+    size_t clauseBufferLen(CClauseBuffer* cbuffer);
+
+    void clearClauseBuffer(CClauseBuffer* cbuffer);
+
+    void deleteClauseBuffer(CClauseBuffer* cbuffer);
+
+    typedef struct CPBFuzzer CPBFuzzer;
+
+    CPBFuzzer* newPBFuzzer(int32_t n_vars);
+
+    int64_t* fuzzerGenerateConstraint(CPBFuzzer* cfuzzer, uint32_t seed);
+
+    void deletePBFuzzer(CPBFuzzer* cfuzzer);
+
+    void freeI64Ptr(int64_t* ptr);
 
 #ifdef __cplusplus
 }
@@ -1,4 +1,21 @@
-use std::ffi::c_void;
+use crate::{
+    dimacs::DimacsClauseWriter,
+    encoding_result::{self, EncodingResultBuilder},
+    encoding_template::EncodingTemplate,
+    encoding_view::EncodingViewBuilder,
+    ffi_debug,
+    trivial::encode_trivial,
+    ClauseSink, Comparator, EncodeLimits, EncoderConfig, EncodingResult, EncodingStats,
+    EncodingView, PbConstraint, PbEncoder, PbEncoderBackend, PbError, Progress,
+};
+use std::{
+    any::Any,
+    ffi::c_void,
+    io::Write,
+    ops::ControlFlow,
+    panic::{self, AssertUnwindSafe},
+    sync::{Mutex, MutexGuard, OnceLock, PoisonError},
+};
 
 /// The entry point for the Rust bindings.
 ///
@@ -21,7 +38,7 @@ use std::ffi::c_void;
 /// let literals = vec![1, 2, 3];
 /// let pb2cnf = PB2CNF::new();
 /// // the threshold is 2 and the first variable not in use is 4
-/// let encoding = pb2cnf.encode_at_least_k(literals, 2, 4);
+/// let encoding = pb2cnf.encode_at_least_k(&literals, 2, 4);
 /// println!("the encoding uses {} variables", encoding.next_free_var_id() - 4);
 /// println!("the encoding uses {} clauses", encoding.clauses().len());
 /// encoding.clauses().iter().enumerate().for_each(|(i,c)| println!("clause {i} is {:?}", c));
@@ -30,8 +47,8 @@ use std::ffi::c_void;
 /// # Encoding Pseudo-Boolean constraints
 ///
 /// The difference between cardinality and Pseudo-Boolean constraints is than weights are applied to literals.
-/// Thus, the functions dedicated to this kind of constraints ([`encode_geq`](Self::encode_geq), [`encode_leq`](Self::encode_leq) and [`encode_both`](Self::encode_both)) takes a vector of weights as a supplementary parameter.
-/// The vectors of weights and literals must be of same lengths, since variable at index `i` has the weight at index `i`.
+/// Thus, the functions dedicated to this kind of constraints ([`encode_geq`](Self::encode_geq), [`encode_leq`](Self::encode_leq) and [`encode_both`](Self::encode_both)) takes a slice of weights as a supplementary parameter.
+/// The slices of weights and literals must be of same lengths, since variable at index `i` has the weight at index `i`.
 ///
 /// ```
 /// use pblib_rs::PB2CNF;
@@ -41,7 +58,7 @@ use std::ffi::c_void;
 /// let literals = vec![1, 2, 3, 4];
 /// let pb2cnf = PB2CNF::new();
 /// // the threshold is 6 and the first variable not in use is 5
-/// let encoding = pb2cnf.encode_geq(weights.clone(), literals, 6, 5);
+/// let encoding = pb2cnf.encode_geq(&weights, &literals, 6, 5);
 /// println!("the encoding uses {} variables", encoding.next_free_var_id() - 4);
 /// println!("the encoding uses {} clauses", encoding.clauses().len());
 /// encoding.clauses().iter().enumerate().for_each(|(i,c)| println!("clause {i} is {:?}", c));
@@ -49,119 +66,1095 @@ use std::ffi::c_void;
 ///
 /// # Note about the encodings
 ///
-/// Contrary to pblib, pblib-rs does not allow the user to choose the encoding use for the constraint.
-/// The encoding used for the constraints are the default ones of the pblib.
+/// The encodings used for the constraints built by [`new`](Self::new) are the default ones of the pblib.
+/// Use [`with_config`](Self::with_config) to choose the encodings thanks to an [`EncoderConfig`].
 /// In particular, the encodings provided by this library are not intended to match the expected model count of the formula.
-#[repr(C)]
-pub struct PB2CNF(*mut c_void);
-
-/// The result of an encoding function.
 ///
-/// This structure contains both the clauses generated to encode the constraint and the index of the next free variable id.
-pub struct EncodingResult {
-    clauses: Vec<Vec<i32>>,
-    next_free_var_id: i32,
+/// # Trivial constraints
+///
+/// Constraints whose bounds can be decided by comparing them with the minimal and maximal values of the weighted sum are handled by this library, without calling pblib:
+///
+/// * if the constraint holds whatever the values of the literals (e.g. `k <= 0` for an At-Least-k constraint, or `k >= n` for an At-Most-k constraint over `n` literals), the encoding has no clause;
+/// * if the constraint never holds (e.g. a negative bound for an At-Most-k constraint, a bound greater than the sum of the weights for an At-Least-k constraint, or a lower bound greater than the upper bound), the encoding is made of a single empty clause;
+/// * if the constraint holds only when each term takes its extreme value (e.g. `k = 0` for an At-Most-k constraint), the encoding is made of one unit clause per term.
+///
+/// In all these cases, no auxiliary variable is used.
+/// In particular, a constraint over no literal at all has a weighted sum of 0: its encoding has no clause if 0 satisfies it, and is made of the empty clause otherwise.
+///
+/// ```
+/// use pblib_rs::PB2CNF;
+///
+/// let pb2cnf = PB2CNF::new();
+/// assert!(pb2cnf.encode_at_most_k(&[], 0, 1).clauses().is_empty());
+/// assert_eq!(&[Vec::<i32>::new()], pb2cnf.encode_at_least_k(&[], 1, 1).clauses());
+/// assert_eq!(&[vec![-1], vec![-2]], pb2cnf.encode_at_most_k(&[1, 2], 0, 3).clauses());
+/// assert!(pb2cnf.encode_leq(&[2, 3], &[1, 2], 5, 3).clauses().is_empty());
+/// ```
+///
+/// # Invalid inputs
+///
+/// The first auxiliary variable must be greater than every variable of the constraint, and the sum of the absolute values of the weights and of a bound must fit in a 64-bit integer.
+/// The `try_` functions return a [`PbError`] when these conditions are not met, while the other encoding functions panic.
+///
+/// ```should_panic
+/// use pblib_rs::PB2CNF;
+///
+/// let pb2cnf = PB2CNF::new();
+/// pb2cnf.encode_geq(&[i64::MAX, i64::MAX], &[1, 2], 1, 3);
+/// ```
+///
+/// # Determinism
+///
+/// The encodings are deterministic: the same constraint (with its terms in the same order), the same first auxiliary variable and the same configuration always produce the same clauses, in the same order.
+/// None of the encoders relies on randomness, so there is no seed to set.
+/// The bundled pblib sorts the terms with a stable algorithm, so the encodings do not depend on the C++ standard library, and are identical across platforms.
+/// This guarantee does not hold when linking against a system-installed pblib.
+///
+/// # Thread safety
+///
+/// [`PB2CNF`] is both [`Send`] and [`Sync`], so a single object can be shared between threads (e.g. through an [`Arc`](std::sync::Arc)).
+/// However, pblib relies on global state shared by all the encoder objects; for this reason, the calls to pblib are serialized by a global lock.
+/// Encoding constraints from multiple threads is thus safe, but does not run in parallel; a pool of [`PB2CNF`] objects would not help either.
+/// Note that the clauses are given to the [`ClauseSink`] of the `_into` functions while the lock is held: the sinks must not call encoding functions themselves.
+///
+/// ```
+/// use pblib_rs::PB2CNF;
+/// use std::sync::Arc;
+///
+/// let pb2cnf = Arc::new(PB2CNF::new());
+/// let handles = (0..4)
+///     .map(|i| {
+///         let pb2cnf = Arc::clone(&pb2cnf);
+///         std::thread::spawn(move || pb2cnf.encode_at_most_k(&[1, 2, 3, 4], i, 5))
+///     })
+///     .collect::<Vec<_>>();
+/// for h in handles {
+///     h.join().unwrap();
+/// }
+/// ```
+pub struct PB2CNF {
+    ptr: *mut c_void,
+    auto_ptrs: Vec<(PbEncoder, *mut c_void)>,
+    clause_buffer: *mut c_void,
+    config: EncoderConfig,
+    backend: Option<Box<dyn PbEncoderBackend + Send + Sync>>,
 }
 
-impl EncodingResult {
-    /// Returns a reference to the clauses used to encode the constraint.
+const PB2CNF_KIND: &str = "PB2CNF";
+
+const CLAUSE_BUFFER_KIND: &str = "clause buffer";
+
+const INC_CONSTRAINT_KIND: &str = "incremental constraint";
+
+const AUTO_PB_ENCODERS: [PbEncoder; 3] =
+    [PbEncoder::Bdd, PbEncoder::Adder, PbEncoder::SortingNetworks];
+
+impl PB2CNF {
+    /// Builds a new structure dedicated to the encoding of constraints.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the underlying C++ objects cannot be allocated; see [`try_new`](Self::try_new).
     #[must_use]
-    pub fn clauses(&self) -> &[Vec<i32>] {
-        &self.clauses
+    pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|e| panic!("{e}"))
     }
 
-    /// Returns the next free variable id.
+    /// Returns a structure shared by the whole process, built with the default configuration on first use.
+    ///
+    /// This allows utility code to encode constraints without passing a [`PB2CNF`] object around.
+    /// Since the calls to pblib are serialized anyway (see [thread safety](Self#thread-safety)), sharing this object between threads has no cost.
+    /// Note that the clause buffer of the [buffered encodings](Self::encode_constraint_buffered) is shared too: code relying on it should use its own object.
     ///
-    /// Encodings use auxiliary variables almost all the time.
-    /// Functions that encode constraints ask the first variable id they can use for these auxiliary variables.
-    /// In return, they tell the caller which is the lowest id that can be used after this encoding, that is, the highest auxiliary variable index plus 1.
+    /// ```
+    /// use pblib_rs::PB2CNF;
+    ///
+    /// fn at_most_one(literals: Vec<i32>, first_aux_var: i32) -> Vec<Vec<i32>> {
+    ///     PB2CNF::global().encode_at_most_k(&literals, 1, first_aux_var).into_clauses()
+    /// }
+    ///
+    /// let clauses = at_most_one(vec![1, 2, 3], 4);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the underlying C++ objects cannot be allocated; see [`try_new`](Self::try_new).
     #[must_use]
-    pub fn next_free_var_id(&self) -> i32 {
-        self.next_free_var_id
+    pub fn global() -> &'static Self {
+        static GLOBAL: OnceLock<PB2CNF> = OnceLock::new();
+        GLOBAL.get_or_init(Self::new)
     }
-}
 
-impl PB2CNF {
-    /// Builds a new structure dedicated to the encoding of constraints.
+    /// Builds a new structure dedicated to the encoding of constraints, returning an error if the underlying C++ objects cannot be allocated.
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::Ffi`] error is returned if an allocation fails on the C++ side.
+    pub fn try_new() -> Result<Self, PbError> {
+        let pb2cnf = {
+            let _lock = pblib_lock();
+            Self {
+                ptr: unsafe { newPB2CNF() },
+                auto_ptrs: Vec::new(),
+                clause_buffer: unsafe { newClauseBuffer() },
+                config: EncoderConfig::default(),
+                backend: None,
+            }
+        };
+        pb2cnf.check_allocations()
+    }
+
+    /// Builds a new structure dedicated to the encoding of constraints, using the encodings given by the configuration.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the underlying C++ objects cannot be allocated; see [`try_with_config`](Self::try_with_config).
     #[must_use]
-    pub fn new() -> Self {
-        Self(unsafe { newPB2CNF() })
+    pub fn with_config(config: EncoderConfig) -> Self {
+        Self::try_with_config(config).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Builds a new structure dedicated to the encoding of constraints using the given configuration, returning an error if the underlying C++ objects cannot be allocated.
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::Ffi`] error is returned if an allocation fails on the C++ side.
+    pub fn try_with_config(config: EncoderConfig) -> Result<Self, PbError> {
+        let new_ptr = |pb_encoder: PbEncoder| unsafe {
+            newPB2CNFWithConfig(
+                pb_encoder as i32,
+                config.amk_encoder() as i32,
+                config.amo_encoder() as i32,
+            )
+        };
+        let pb2cnf = {
+            let _lock = pblib_lock();
+            if config.pb_encoder() == PbEncoder::Auto {
+                Self {
+                    ptr: new_ptr(PbEncoder::Best),
+                    auto_ptrs: AUTO_PB_ENCODERS.iter().map(|e| (*e, new_ptr(*e))).collect(),
+                    clause_buffer: unsafe { newClauseBuffer() },
+                    config,
+                    backend: None,
+                }
+            } else {
+                Self {
+                    ptr: new_ptr(config.pb_encoder()),
+                    auto_ptrs: Vec::new(),
+                    clause_buffer: unsafe { newClauseBuffer() },
+                    config,
+                    backend: None,
+                }
+            }
+        };
+        pb2cnf.check_allocations()
+    }
+
+    /// Builds a new structure delegating the encodings to another backend.
+    ///
+    /// The functions encoding a single constraint (e.g. [`encode_constraint`](Self::encode_constraint), [`encode_leq_into`](Self::encode_leq_into) or [`encode_at_most_k`](Self::encode_at_most_k)) call the backend, once the [trivial constraints](Self#trivial-constraints) have been handled.
+    /// The functions checking limits (`try_` functions) and the buffered encodings call it too; since a backend cannot be interrupted, its whole encoding is computed before the limits are checked against its clauses.
+    /// Incremental encodings are not supported by backends, so the constraints are encoded from scratch each time their bound changes.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the underlying C++ objects cannot be allocated; see [`try_new`](Self::try_new).
+    #[must_use]
+    pub fn with_backend(backend: impl PbEncoderBackend + Send + Sync + 'static) -> Self {
+        let mut pb2cnf = Self::new();
+        pb2cnf.backend = Some(Box::new(backend));
+        pb2cnf
+    }
+
+    /// Checks that the C++ objects were allocated; if one of them is missing, the other ones are freed.
+    fn check_allocations(self) -> Result<Self, PbError> {
+        ffi_debug::track(self.ptr, PB2CNF_KIND);
+        for (_, ptr) in &self.auto_ptrs {
+            ffi_debug::track(*ptr, PB2CNF_KIND);
+        }
+        ffi_debug::track(self.clause_buffer, CLAUSE_BUFFER_KIND);
+        if self.ptr.is_null()
+            || self.clause_buffer.is_null()
+            || self.auto_ptrs.iter().any(|(_, p)| p.is_null())
+        {
+            return Err(PbError::Ffi(
+                "cannot allocate the pblib encoder".to_string(),
+            ));
+        }
+        Ok(self)
+    }
+
+    /// Builds the result of an encoding, in canonical order if the configuration asks for it.
+    pub(crate) fn build_result(
+        &self,
+        builder: EncodingResultBuilder,
+        first_aux_var: i32,
+        next_free_var_id: i32,
+    ) -> EncodingResult {
+        let mut encoding = builder.build(first_aux_var, next_free_var_id);
+        if self.config.canonical_order() {
+            encoding.canonicalize();
+        }
+        encoding
+    }
+
+    /// Returns the pblib object to use for a Pseudo-Boolean constraint, according to the cost model if the encoder is [`PbEncoder::Auto`].
+    fn pb_ptr(
+        &self,
+        weights: &[i64],
+        literals: &[i32],
+        comparator: Comparator,
+        rhs: i64,
+    ) -> *mut c_void {
+        if self.auto_ptrs.is_empty() {
+            ffi_debug::check(self.ptr, PB2CNF_KIND);
+            return self.ptr;
+        }
+        let constraint = PbConstraint::new(
+            weights
+                .iter()
+                .copied()
+                .zip(literals.iter().copied())
+                .collect(),
+            comparator,
+            rhs,
+        );
+        let encoder = self.config.cost_model().choose_encoder(&constraint);
+        let ptr = self
+            .auto_ptrs
+            .iter()
+            .find(|(e, _)| *e == encoder)
+            .map_or(self.ptr, |(_, p)| *p);
+        ffi_debug::check(ptr, PB2CNF_KIND);
+        ptr
+    }
+
+    /// Returns the configuration used by this structure.
+    #[must_use]
+    pub fn config(&self) -> &EncoderConfig {
+        &self.config
+    }
+
+    /// Encodes a [`PbConstraint`].
+    ///
+    /// Depending on the comparator of the constraint, this function calls [`encode_leq`](Self::encode_leq), [`encode_geq`](Self::encode_geq) or [`encode_both`](Self::encode_both).
+    /// Strict comparators are handled by adjusting the right hand side.
+    /// See these functions for more information on the `first_aux_var` parameter and the return type.
+    ///
+    /// ```
+    /// use pblib_rs::{Comparator, PbConstraint, PB2CNF};
+    ///
+    /// // we encode 8*x1 + 4*x2 + 2*x3 + 1*x4 >= 6
+    /// let constraint = PbConstraint::new(vec![(8, 1), (4, 2), (2, 3), (1, 4)], Comparator::Geq, 6);
+    /// let pb2cnf = PB2CNF::new();
+    /// let encoding = pb2cnf.encode_constraint(&constraint, 5);
+    /// ```
+    #[must_use]
+    pub fn encode_constraint(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+    ) -> EncodingResult {
+        let mut builder = EncodingResultBuilder::default();
+        let next_free_var_id = self.encode_constraint_into(constraint, first_aux_var, &mut builder);
+        self.build_result(builder, first_aux_var, next_free_var_id)
+    }
+
+    /// Encodes a [`PbConstraint`] into an [`EncodingView`].
+    ///
+    /// This function behaves like [`encode_constraint`](Self::encode_constraint), except that the clauses are stored in a single buffer instead of one vector per clause.
+    #[must_use]
+    pub fn encode_constraint_view(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+    ) -> EncodingView {
+        let mut builder = EncodingViewBuilder::default();
+        let next_free_var_id = self.encode_constraint_into(constraint, first_aux_var, &mut builder);
+        builder.build(first_aux_var, next_free_var_id)
+    }
+
+    /// Encodes a copy of a constraint for each step of a time-indexed model, e.g. a SAT planning problem.
+    ///
+    /// The template constraint is expressed on step-independent variables; `var_map(v, t)` gives the variable playing the role of `v` at step `t`, for `t` in `0..steps`, and the signs of the literals of the template are kept.
+    /// The template is encoded once on placeholder variables (see [`EncodingTemplate`](crate::EncodingTemplate)), and each step is obtained by substituting the literals and shifting the auxiliary variables, instead of running the encoder again.
+    /// The auxiliary variables of the steps are allocated one after the other from `first_aux_var`.
+    ///
+    /// As for the [`EncodingCache`](crate::EncodingCache), the copies are equivalent to the encodings of the substituted constraints, but pblib does not get a chance to simplify them according to their literals.
+    ///
+    /// ```
+    /// use pblib_rs::{PbConstraint, PB2CNF};
+    ///
+    /// // 3 steps, the variables of step t being 3*t+1 to 3*t+3: at most one of them is true
+    /// let template = PbConstraint::cardinality([1, 2, 3]).leq(1);
+    /// let pb2cnf = PB2CNF::new();
+    /// let encoding = pb2cnf.encode_template_over_steps(&template, 3, |v, t| 3 * t as i32 + v, 10);
+    /// ```
+    #[must_use]
+    pub fn encode_template_over_steps(
+        &self,
+        template: &PbConstraint,
+        steps: usize,
+        var_map: impl FnMut(i32, usize) -> i32,
+        first_aux_var: i32,
+    ) -> EncodingResult {
+        let mut builder = EncodingResultBuilder::default();
+        let next_free_var_id = self.encode_template_over_steps_into(
+            template,
+            steps,
+            var_map,
+            first_aux_var,
+            &mut builder,
+        );
+        self.build_result(builder, first_aux_var, next_free_var_id)
+    }
+
+    /// Encodes a copy of a constraint for each step of a time-indexed model, sending the clauses to a [`ClauseSink`].
+    ///
+    /// This function behaves like [`encode_template_over_steps`](Self::encode_template_over_steps), except that the clauses are given one by one to the sink instead of being collected.
+    /// It returns the next free variable id.
+    pub fn encode_template_over_steps_into(
+        &self,
+        template: &PbConstraint,
+        steps: usize,
+        mut var_map: impl FnMut(i32, usize) -> i32,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        let encoding_template = EncodingTemplate::new(
+            self,
+            &template.weights(),
+            template.comparator(),
+            template.rhs(),
+        );
+        let template_literals = template.literals();
+        let mut next_free_var_id = first_aux_var;
+        let mut literals = Vec::with_capacity(template_literals.len());
+        for step in 0..steps {
+            literals.clear();
+            literals.extend(
+                template_literals
+                    .iter()
+                    .map(|l| l.signum() * var_map(l.abs(), step)),
+            );
+            next_free_var_id =
+                encoding_template.instantiate_into(&literals, next_free_var_id, sink);
+        }
+        next_free_var_id
+    }
+
+    /// Encodes a [`PbConstraint`], writing the clauses in the DIMACS format as they are produced.
+    ///
+    /// See [`encode_leq_to_dimacs`](Self::encode_leq_to_dimacs) for more information on the output and the return value.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the writer fails.
+    pub fn encode_constraint_to_dimacs<W: Write>(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        writer: &mut W,
+    ) -> std::io::Result<(i32, usize)> {
+        let mut clause_writer = DimacsClauseWriter::new(writer);
+        let next_free_var_id =
+            self.encode_constraint_into(constraint, first_aux_var, &mut clause_writer);
+        Ok((next_free_var_id, clause_writer.finish()?))
+    }
+
+    /// Encodes a [`PbConstraint`], sending the clauses to a [`ClauseSink`].
+    ///
+    /// This function behaves like [`encode_constraint`](Self::encode_constraint), except that the clauses are given one by one to the sink instead of being collected.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the input is invalid (see [`PB2CNF`]).
+    pub fn encode_constraint_into(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        check_input(constraint, first_aux_var).unwrap_or_else(|e| panic!("{e}"));
+        if let Some(backend) = &self.backend {
+            let (geq, leq) = bounds(&constraint.non_strict());
+            if encode_trivial_into(constraint.terms().iter().copied(), geq, leq, sink) {
+                return first_aux_var;
+            }
+            return backend.encode_constraint_into(constraint, first_aux_var, sink);
+        }
+        with_callback(sink, None, |callback, callback_data| unsafe {
+            self.encode_constraint_raw(constraint, first_aux_var, callback, callback_data)
+        })
+    }
+
+    /// Encodes a [`PbConstraint`], aborting the encoding if it exceeds the limits of the configuration.
+    ///
+    /// This function behaves like [`encode_constraint`](Self::encode_constraint), except that the encoding is checked against the [`EncodeLimits`] given by the configuration (see [`EncoderConfig::with_limits`]).
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::BudgetExceeded`] error is returned if the encoding exceeds the limits.
+    /// See [`try_encode_constraint_into`](Self::try_encode_constraint_into) for the errors related to invalid inputs.
+    pub fn try_encode_constraint(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+    ) -> Result<EncodingResult, PbError> {
+        let mut builder = EncodingResultBuilder::default();
+        let next_free_var_id = self.try_encode_constraint_into(
+            constraint,
+            first_aux_var,
+            &self.config.limits(),
+            &mut builder,
+        )?;
+        Ok(self.build_result(builder, first_aux_var, next_free_var_id))
+    }
+
+    /// Encodes a [`PbConstraint`], sending the clauses to a [`ClauseSink`] and aborting the encoding if it exceeds the given limits.
+    ///
+    /// This function behaves like [`encode_constraint_into`](Self::encode_constraint_into), except that the input is checked before being encoded, and the encoding is checked against the limits each time a clause is produced.
+    /// When a clause would exceed the limits, it is not given to the sink and the encoding is aborted; the sink keeps the clauses received so far.
+    /// It returns the next free variable id.
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::InvalidFirstAuxVar`] error is returned if `first_aux_var` is not strictly greater than the variables of the constraint; in this case, nothing is encoded.
+    /// A [`PbError::Overflow`] error is returned if the weights or the bound are so large that the values computed by pblib would overflow; in this case, nothing is encoded.
+    /// A [`PbError::BudgetExceeded`] error is returned if the encoding exceeds the limits.
+    /// It contains the statistics of the clauses given to the sink.
+    /// If the check of the variables is enabled by the configuration (see [`EncoderConfig::with_var_check`]), a [`PbError::InvalidOutputVar`] error is returned if the encoding involves an unexpected variable; in this case, the sink has received the whole encoding.
+    /// A [`PbError::Ffi`] error is returned if pblib raises an exception during the encoding; in this case, the sink keeps the clauses received so far.
+    pub fn try_encode_constraint_into(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        limits: &EncodeLimits,
+        sink: &mut impl ClauseSink,
+    ) -> Result<i32, PbError> {
+        check_input(constraint, first_aux_var)?;
+        let mut budget = Budget::new(*limits, None, first_aux_var);
+        if self.config.var_check() {
+            budget.check_vars(constraint);
+        }
+        let backend_encoding = self.backend_encoding(constraint, first_aux_var);
+        let next_free_var_id =
+            with_callback(sink, Some(&mut budget), |callback, callback_data| unsafe {
+                self.encode_constraint_or_replay(
+                    backend_encoding.as_ref(),
+                    constraint,
+                    first_aux_var,
+                    callback,
+                    callback_data,
+                )
+            });
+        budget.result(next_free_var_id)
+    }
+
+    /// Encodes a [`PbConstraint`], sending the clauses to a [`ClauseSink`] and reporting the progress of the encoding to a callback.
+    ///
+    /// This function behaves like [`try_encode_constraint_into`](Self::try_encode_constraint_into) with the limits of the configuration, except that the progress callback is called each time a fixed number of clauses has been produced.
+    /// The callback can cancel the encoding by returning [`ControlFlow::Break`]; in this case, the sink keeps the clauses received so far.
+    /// Note that the callback is called while pblib's global lock is held, so it must not call encoding functions itself.
+    ///
+    /// ```
+    /// use pblib_rs::{Comparator, PbConstraint, PbError, PB2CNF};
+    /// use std::ops::ControlFlow;
+    ///
+    /// let constraint = PbConstraint::new((1..=40).map(|i| (i, i as i32)).collect(), Comparator::Geq, 400);
+    /// let mut clauses = Vec::new();
+    /// let result = PB2CNF::new().try_encode_constraint_with_progress(
+    ///     &constraint,
+    ///     41,
+    ///     &|progress| {
+    ///         println!("{} clauses so far", progress.n_clauses());
+    ///         ControlFlow::Break(())
+    ///     },
+    ///     &mut clauses,
+    /// );
+    /// assert!(matches!(result, Err(PbError::Cancelled(_))));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::Cancelled`] error is returned if the callback cancels the encoding.
+    /// See [`try_encode_constraint_into`](Self::try_encode_constraint_into) for the other errors.
+    pub fn try_encode_constraint_with_progress(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        progress: &dyn Fn(Progress) -> ControlFlow<()>,
+        sink: &mut impl ClauseSink,
+    ) -> Result<i32, PbError> {
+        check_input(constraint, first_aux_var)?;
+        let mut budget = Budget::new(self.config.limits(), Some(progress), first_aux_var);
+        if self.config.var_check() {
+            budget.check_vars(constraint);
+        }
+        let backend_encoding = self.backend_encoding(constraint, first_aux_var);
+        let next_free_var_id =
+            with_callback(sink, Some(&mut budget), |callback, callback_data| unsafe {
+                self.encode_constraint_or_replay(
+                    backend_encoding.as_ref(),
+                    constraint,
+                    first_aux_var,
+                    callback,
+                    callback_data,
+                )
+            });
+        budget.result(next_free_var_id)
+    }
+
+    /// Encodes a [`PbConstraint`] whose bound can then be tightened by the returned [`IncrementalEncoding`], sending the clauses to a [`ClauseSink`].
+    ///
+    /// The comparator of the constraint must not be [`Comparator::Eq`].
+    /// If the constraint is trivial (see the [type documentation](Self#trivial-constraints)), it is encoded as such and no incremental encoding is returned, since pblib does not build any structure for such constraints.
+    /// No incremental encoding is returned either if a backend is set; in this case, the constraint is encoded by the backend.
+    /// It returns the next free variable id.
+    ///
+    /// # Errors
+    ///
+    /// See [`try_encode_constraint_into`](Self::try_encode_constraint_into).
+    pub(crate) fn try_encode_incremental_into(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        limits: &EncodeLimits,
+        sink: &mut impl ClauseSink,
+    ) -> Result<(Option<IncrementalEncoding>, i32), PbError> {
+        if self.backend.is_some() {
+            let next_free_var_id =
+                self.try_encode_constraint_into(constraint, first_aux_var, limits, sink)?;
+            return Ok((None, next_free_var_id));
+        }
+        check_input(constraint, first_aux_var)?;
+        let constraint = constraint.non_strict();
+        let weights = constraint.weights();
+        let literals = constraint.literals();
+        let rhs = constraint.rhs();
+        let (comparator, geq, leq) = match constraint.comparator() {
+            Comparator::Leq => (0, None, Some(rhs)),
+            Comparator::Geq => (1, Some(rhs), None),
+            Comparator::Eq | Comparator::Lt | Comparator::Gt => {
+                unreachable!("incremental encodings need a non-strict inequality")
+            }
+        };
+        let mut budget = Budget::new(*limits, None, first_aux_var);
+        if self.config.var_check() {
+            budget.check_vars(&constraint);
+        }
+        let mut ptr = std::ptr::null_mut();
+        let mut trivial = false;
+        let next_free_var_id =
+            with_callback(sink, Some(&mut budget), |callback, callback_data| unsafe {
+                if encode_trivial(terms(&weights, &literals), geq, leq, |clause| {
+                    callback(
+                        callback_data,
+                        clause.as_ptr(),
+                        clause.len().try_into().unwrap(),
+                    ) == 0
+                }) {
+                    trivial = true;
+                    return first_aux_var;
+                }
+                let mut next_free_var_id = 0;
+                ptr = newIncConstraint(
+                    self.pb_ptr(&weights, &literals, constraint.comparator(), rhs),
+                    weights.as_ptr(),
+                    weights.len().try_into().unwrap(),
+                    literals.as_ptr(),
+                    literals.len().try_into().unwrap(),
+                    comparator,
+                    rhs,
+                    first_aux_var,
+                    callback,
+                    callback_data,
+                    &raw mut next_free_var_id,
+                );
+                ffi_debug::track(ptr, INC_CONSTRAINT_KIND);
+                next_free_var_id
+            });
+        if trivial {
+            return Ok((None, budget.result(next_free_var_id)?));
+        }
+        if ptr.is_null() {
+            return Err(PbError::Ffi(
+                "allocation of an incremental constraint failed".to_string(),
+            ));
+        }
+        let encoding = IncrementalEncoding { ptr };
+        Ok((Some(encoding), budget.result(next_free_var_id)?))
+    }
+
+    /// Encodes a [`PbConstraint`], appending the clauses to a buffer kept on the C++ side of the bindings.
+    ///
+    /// Unlike the `_into` functions, this function does not send the clauses across the FFI boundary one by one.
+    /// The buffer lives as long as this object, and the clauses of successive calls are appended to it until [`take_buffered_clauses_into`](Self::take_buffered_clauses_into) retrieves them all at once.
+    /// This reduces the overhead of encoding a large number of constraints, e.g. a whole instance.
+    /// Note that the buffer is shared by all the threads using this object.
+    ///
+    /// It returns the next free variable id.
+    ///
+    /// ```
+    /// use pblib_rs::{Comparator, PbConstraint, PB2CNF};
+    ///
+    /// let pb2cnf = PB2CNF::new();
+    /// let next_free_var_id = pb2cnf.encode_constraint_buffered(
+    ///     &PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Leq, 1),
+    ///     4,
+    /// );
+    /// let _ = pb2cnf.encode_constraint_buffered(
+    ///     &PbConstraint::new(vec![(1, 1), (1, 3)], Comparator::Geq, 1),
+    ///     next_free_var_id,
+    /// );
+    /// assert_eq!(2, pb2cnf.n_buffered_clauses());
+    /// let mut clauses = Vec::new();
+    /// pb2cnf.take_buffered_clauses_into(&mut clauses);
+    /// assert_eq!(vec![vec![-2, -1], vec![3, 1]], clauses);
+    /// assert_eq!(0, pb2cnf.n_buffered_clauses());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if pblib raises an exception during the encoding, or if the input is invalid (see [`PB2CNF`]).
+    #[must_use]
+    pub fn encode_constraint_buffered(&self, constraint: &PbConstraint, first_aux_var: i32) -> i32 {
+        self.try_encode_constraint_buffered(constraint, first_aux_var)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Encodes a [`PbConstraint`], appending the clauses to the buffer of this object, and checking the input first.
+    ///
+    /// This function behaves like [`encode_constraint_buffered`](Self::encode_constraint_buffered), except that invalid inputs and pblib exceptions are reported as errors.
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::InvalidFirstAuxVar`] or a [`PbError::Overflow`] error is returned if the input is invalid, in which case nothing is added to the buffer.
+    /// A [`PbError::Ffi`] error is returned if pblib raises an exception; the buffer may then contain a part of the clauses of the constraint.
+    pub fn try_encode_constraint_buffered(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+    ) -> Result<i32, PbError> {
+        check_input(constraint, first_aux_var)?;
+        let backend_encoding = self.backend_encoding(constraint, first_aux_var);
+        let next_free_var_id = {
+            let _lock = pblib_lock();
+            ffi_debug::check(self.clause_buffer, CLAUSE_BUFFER_KIND);
+            unsafe {
+                self.encode_constraint_or_replay(
+                    backend_encoding.as_ref(),
+                    constraint,
+                    first_aux_var,
+                    appendToClauseBuffer,
+                    self.clause_buffer,
+                )
+            }
+        };
+        if next_free_var_id == ENCODING_FAILED {
+            return Err(PbError::Ffi(ENCODING_FAILED_MESSAGE.to_string()));
+        }
+        Ok(next_free_var_id)
+    }
+
+    /// Returns the number of clauses waiting in the buffer filled by [`encode_constraint_buffered`](Self::encode_constraint_buffered).
+    #[must_use]
+    pub fn n_buffered_clauses(&self) -> usize {
+        let _lock = pblib_lock();
+        ffi_debug::check(self.clause_buffer, CLAUSE_BUFFER_KIND);
+        unsafe { clauseBufferNClauses(self.clause_buffer) }
+    }
+
+    /// Sends the clauses of the buffer filled by [`encode_constraint_buffered`](Self::encode_constraint_buffered) to a [`ClauseSink`], and empties the buffer.
+    ///
+    /// The content of the buffer is copied in a single call; the clauses are then given to the sink after the global lock has been released, so the sink may call encoding functions.
+    /// It returns the number of clauses given to the sink.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the content of the buffer is malformed; see [`try_take_buffered_clauses_into`](Self::try_take_buffered_clauses_into).
+    pub fn take_buffered_clauses_into(&self, sink: &mut impl ClauseSink) -> usize {
+        self.try_take_buffered_clauses_into(sink)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Sends the clauses of the buffer filled by [`encode_constraint_buffered`](Self::encode_constraint_buffered) to a [`ClauseSink`], and empties the buffer, checking the content of the buffer first.
+    ///
+    /// The buffer is written by the C++ side of the bindings: each clause is preceded by its length.
+    /// The whole content is decoded before any clause is given to the sink, so a malformed buffer leaves the sink untouched.
+    /// In any case, the buffer is emptied.
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::Ffi`] error is returned if a clause length is inconsistent with the size of the buffer, if a clause contains an invalid literal, or if the number of clauses does not match the one recorded by the buffer.
+    pub fn try_take_buffered_clauses_into(
+        &self,
+        sink: &mut impl ClauseSink,
+    ) -> Result<usize, PbError> {
+        let (data, n_clauses) = {
+            let _lock = pblib_lock();
+            ffi_debug::check(self.clause_buffer, CLAUSE_BUFFER_KIND);
+            unsafe {
+                let len = clauseBufferLen(self.clause_buffer);
+                let data = if len == 0 {
+                    Vec::new()
+                } else {
+                    std::slice::from_raw_parts(clauseBufferData(self.clause_buffer), len).to_vec()
+                };
+                let n_clauses = clauseBufferNClauses(self.clause_buffer);
+                clearClauseBuffer(self.clause_buffer);
+                (data, n_clauses)
+            }
+        };
+        let clauses = decode_clause_buffer(&data, n_clauses)?;
+        for clause in &clauses {
+            sink.add_clause(clause);
+        }
+        Ok(clauses.len())
+    }
+
+    /// Encodes a [`PbConstraint`] with the backend, if one is set.
+    ///
+    /// This must be done before the global lock is taken, since the backend may call encoding functions itself.
+    /// It returns the clauses and the next free variable id, to be given to [`encode_constraint_or_replay`](Self::encode_constraint_or_replay).
+    fn backend_encoding(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+    ) -> Option<(Vec<Vec<i32>>, i32)> {
+        self.backend.as_ref()?;
+        let mut clauses = Vec::new();
+        let next_free_var_id = self.encode_constraint_into(constraint, first_aux_var, &mut clauses);
+        Some((clauses, next_free_var_id))
+    }
+
+    /// Sends the clauses of an encoding computed by the backend to a callback, stopping if the callback asks for it, or calls pblib if there is no such encoding.
+    ///
+    /// # Safety
+    ///
+    /// The global lock must be held, and the callback data must be the one expected by the callback.
+    unsafe fn encode_constraint_or_replay(
+        &self,
+        backend_encoding: Option<&(Vec<Vec<i32>>, i32)>,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        callback: ClauseCallback,
+        callback_data: *mut c_void,
+    ) -> i32 {
+        let Some((clauses, next_free_var_id)) = backend_encoding else {
+            return self.encode_constraint_raw(constraint, first_aux_var, callback, callback_data);
+        };
+        for clause in clauses {
+            if callback(
+                callback_data,
+                clause.as_ptr(),
+                clause.len().try_into().unwrap(),
+            ) != 0
+            {
+                break;
+            }
+        }
+        *next_free_var_id
+    }
+
+    /// Calls the pblib function matching the comparator of a [`PbConstraint`].
+    ///
+    /// # Safety
+    ///
+    /// The global lock must be held, and the callback data must be the one expected by the callback.
+    unsafe fn encode_constraint_raw(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        callback: ClauseCallback,
+        callback_data: *mut c_void,
+    ) -> i32 {
+        let constraint = constraint.non_strict();
+        let weights = constraint.weights();
+        let literals = constraint.literals();
+        let rhs = constraint.rhs();
+        let (geq, leq) = bounds(&constraint);
+        if encode_trivial(terms(&weights, &literals), geq, leq, |clause| {
+            callback(
+                callback_data,
+                clause.as_ptr(),
+                clause.len().try_into().unwrap(),
+            ) == 0
+        }) {
+            return first_aux_var;
+        }
+        let encode = match constraint.comparator() {
+            Comparator::Leq => encodeLeq,
+            Comparator::Geq => encodeGeq,
+            Comparator::Eq => {
+                return encodeBoth(
+                    self.pb_ptr(&weights, &literals, Comparator::Leq, rhs),
+                    weights.as_ptr(),
+                    weights.len().try_into().unwrap(),
+                    literals.as_ptr(),
+                    literals.len().try_into().unwrap(),
+                    rhs,
+                    rhs,
+                    first_aux_var,
+                    callback,
+                    callback_data,
+                )
+            }
+            Comparator::Lt | Comparator::Gt => unreachable!(),
+        };
+        encode(
+            self.pb_ptr(&weights, &literals, constraint.comparator(), rhs),
+            weights.as_ptr(),
+            weights.len().try_into().unwrap(),
+            literals.as_ptr(),
+            literals.len().try_into().unwrap(),
+            rhs,
+            first_aux_var,
+            callback,
+            callback_data,
+        )
+    }
+
+    /// Encodes the constraint stating that an objective function is lower than or equal to an upper bound.
+    ///
+    /// The objective function is given as a list of terms, each of them being a couple composed of a weight and a literal, as returned by [`OpbInstance::objective`](crate::OpbInstance::objective).
+    /// This is the decision version of a minimization problem: optimization loops can call this function repeatedly with decreasing upper bounds, adding the resulting clauses to a fresh copy of the formula, until it becomes unsatisfiable.
+    ///
+    /// See [`encode_leq`](Self::encode_leq) for more information on the `first_aux_var` parameter and the return type.
+    ///
+    /// ```
+    /// use pblib_rs::PB2CNF;
+    ///
+    /// // we encode 3*x1 + 2*x2 + 1*x3 <= 4
+    /// let objective = [(3, 1), (2, 2), (1, 3)];
+    /// let pb2cnf = PB2CNF::new();
+    /// let encoding = pb2cnf.encode_objective_bound(&objective, 4, 4);
+    /// ```
+    #[must_use]
+    pub fn encode_objective_bound(
+        &self,
+        objective: &[(i64, i32)],
+        upper_bound: i64,
+        first_aux_var: i32,
+    ) -> EncodingResult {
+        let mut builder = EncodingResultBuilder::default();
+        let next_free_var_id =
+            self.encode_objective_bound_into(objective, upper_bound, first_aux_var, &mut builder);
+        self.build_result(builder, first_aux_var, next_free_var_id)
+    }
+
+    /// Encodes the constraint stating that an objective function is lower than or equal to an upper bound, sending the clauses to a [`ClauseSink`].
+    ///
+    /// This function behaves like [`encode_objective_bound`](Self::encode_objective_bound), except that the clauses are given one by one to the sink instead of being collected.
+    /// It returns the next free variable id.
+    pub fn encode_objective_bound_into(
+        &self,
+        objective: &[(i64, i32)],
+        upper_bound: i64,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        let weights = objective.iter().map(|t| t.0).collect::<Vec<_>>();
+        let literals = objective.iter().map(|t| t.1).collect::<Vec<_>>();
+        self.encode_leq_into(&weights, &literals, upper_bound, first_aux_var, sink)
     }
 
     /// Encodes an At-Most-k Pseudo-Boolean constraint.
     ///
     /// An At-Most-k constraint imposes that a weighted sum of literals is less than or equal to an integer value.
-    /// The vectors of weights and literals must be of same lengths, since variable at index `i` has the weight at index `i`.
+    /// The slices of weights and literals must be of same lengths, since variable at index `i` has the weight at index `i`.
     ///
-    /// In addition to this vectors and the threshold, the minimal variable index that can be used as an auxiliary variables must be given to the function.
+    /// In addition to these slices and the threshold, the minimal variable index that can be used as an auxiliary variables must be given to the function.
     /// The preferred value for this parameter is in most cases the highest variable index in use plus 1.
     ///
     /// The result of this function is an [`EncodingResult`] which gives both the clauses encoding the constraint and the new lowest variable index that is not in use.
     ///
     /// # Panics
     ///
-    /// In case the weights and literal vectors have not the same length, this function panics.
+    /// In case the weights and literal slices have not the same length, or if the input is invalid (see [`PB2CNF`]), this function panics.
     #[must_use]
     pub fn encode_leq(
         &self,
-        weights: Vec<i64>,
-        literals: Vec<i32>,
+        weights: &[i64],
+        literals: &[i32],
         leq: i64,
         first_aux_var: i32,
     ) -> EncodingResult {
-        assert_len_eq(&weights, &literals);
-        let formula_ptr = unsafe {
+        let mut builder = EncodingResultBuilder::default();
+        let next_free_var_id =
+            self.encode_leq_into(weights, literals, leq, first_aux_var, &mut builder);
+        self.build_result(builder, first_aux_var, next_free_var_id)
+    }
+
+    /// Encodes an At-Most-k Pseudo-Boolean constraint, sending the clauses to a [`ClauseSink`].
+    ///
+    /// This function behaves like [`encode_leq`](Self::encode_leq), except that the clauses are given one by one to the sink instead of being collected.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// In case the weights and literal slices have not the same length, or if the input is invalid (see [`PB2CNF`]), this function panics.
+    pub fn encode_leq_into(
+        &self,
+        weights: &[i64],
+        literals: &[i32],
+        leq: i64,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        assert_len_eq(weights, literals);
+        assert_valid_terms(terms(weights, literals), &[leq], first_aux_var);
+        if encode_trivial_into(terms(weights, literals), None, Some(leq), sink) {
+            return first_aux_var;
+        }
+        if let Some(backend) = &self.backend {
+            let constraint = PbConstraint::weighted(terms(weights, literals)).leq(leq);
+            return backend.encode_constraint_into(&constraint, first_aux_var, sink);
+        }
+        with_callback(sink, None, |callback, callback_data| unsafe {
             encodeLeq(
-                self.0,
+                self.pb_ptr(weights, literals, Comparator::Leq, leq),
                 weights.as_ptr(),
                 weights.len().try_into().unwrap(),
                 literals.as_ptr(),
                 literals.len().try_into().unwrap(),
                 leq,
                 first_aux_var,
+                callback,
+                callback_data,
             )
-        };
-        let result = decode_formula_data(formula_ptr);
-        unsafe { freePtr(formula_ptr.cast()) };
-        result
+        })
+    }
+
+    /// Encodes an At-Most-k Pseudo-Boolean constraint into an [`EncodingView`].
+    ///
+    /// This function behaves like [`encode_leq`](Self::encode_leq), except that the clauses are stored in a single buffer instead of one vector per clause.
+    ///
+    /// # Panics
+    ///
+    /// In case the weights and literal slices have not the same length, or if the input is invalid (see [`PB2CNF`]), this function panics.
+    #[must_use]
+    pub fn encode_leq_view(
+        &self,
+        weights: &[i64],
+        literals: &[i32],
+        leq: i64,
+        first_aux_var: i32,
+    ) -> EncodingView {
+        let mut builder = EncodingViewBuilder::default();
+        let next_free_var_id =
+            self.encode_leq_into(weights, literals, leq, first_aux_var, &mut builder);
+        builder.build(first_aux_var, next_free_var_id)
+    }
+
+    /// Encodes an At-Most-k Pseudo-Boolean constraint, writing the clauses in the DIMACS format as they are produced.
+    ///
+    /// This function behaves like [`encode_leq`](Self::encode_leq), except that the clauses are written to the writer instead of being collected.
+    /// Since the clauses are never stored, this allows to encode constraints whose CNF encoding does not fit in memory.
+    /// Only the clauses are written, each of them on its own line: the number of clauses is not known before the end of the encoding, so no `p cnf` header can be written beforehand.
+    ///
+    /// This function returns the next free variable id and the number of clauses written, which can be used to build the header afterwards.
+    ///
+    /// ```
+    /// use pblib_rs::PB2CNF;
+    ///
+    /// let pb2cnf = PB2CNF::new();
+    /// let mut output = Vec::new();
+    /// // we encode x1 + x2 <= 1
+    /// let (next_free_var_id, n_clauses) = pb2cnf
+    ///     .encode_leq_to_dimacs(&[1, 1], &[1, 2], 1, 3, &mut output)
+    ///     .unwrap();
+    /// assert_eq!((3, 1), (next_free_var_id, n_clauses));
+    /// assert_eq!("-2 -1 0\n", String::from_utf8(output).unwrap());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the writer fails.
+    ///
+    /// # Panics
+    ///
+    /// In case the weights and literal slices have not the same length, or if the input is invalid (see [`PB2CNF`]), this function panics.
+    pub fn encode_leq_to_dimacs<W: Write>(
+        &self,
+        weights: &[i64],
+        literals: &[i32],
+        leq: i64,
+        first_aux_var: i32,
+        writer: &mut W,
+    ) -> std::io::Result<(i32, usize)> {
+        let mut clause_writer = DimacsClauseWriter::new(writer);
+        let next_free_var_id =
+            self.encode_leq_into(weights, literals, leq, first_aux_var, &mut clause_writer);
+        Ok((next_free_var_id, clause_writer.finish()?))
     }
 
     /// Encodes an At-Least-k Pseudo-Boolean constraint.
     ///
     /// An At-Least-k constraint imposes that a weighted sum of literals is greater than or equal to an integer value.
-    /// The vectors of weights and literals must be of same lengths, since variable at index `i` has the weight at index `i`.
+    /// The slices of weights and literals must be of same lengths, since variable at index `i` has the weight at index `i`.
     ///
-    /// In addition to this vectors and the threshold, the minimal variable index that can be used as an auxiliary variables must be given to the function.
+    /// In addition to these slices and the threshold, the minimal variable index that can be used as an auxiliary variables must be given to the function.
     /// The preferred value for this parameter is in most cases the highest variable index in use plus 1.
     ///
     /// The result of this function is an [`EncodingResult`] which gives both the clauses encoding the constraint and the new lowest variable index that is not in use.
     ///
     /// # Panics
     ///
-    /// In case the weights and literal vectors have not the same length, this function panics.
+    /// In case the weights and literal slices have not the same length, or if the input is invalid (see [`PB2CNF`]), this function panics.
     #[must_use]
     pub fn encode_geq(
         &self,
-        weights: Vec<i64>,
-        literals: Vec<i32>,
+        weights: &[i64],
+        literals: &[i32],
         geq: i64,
         first_aux_var: i32,
     ) -> EncodingResult {
-        assert_len_eq(&weights, &literals);
-        let formula_ptr = unsafe {
+        let mut builder = EncodingResultBuilder::default();
+        let next_free_var_id =
+            self.encode_geq_into(weights, literals, geq, first_aux_var, &mut builder);
+        self.build_result(builder, first_aux_var, next_free_var_id)
+    }
+
+    /// Encodes an At-Least-k Pseudo-Boolean constraint, sending the clauses to a [`ClauseSink`].
+    ///
+    /// This function behaves like [`encode_geq`](Self::encode_geq), except that the clauses are given one by one to the sink instead of being collected.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// In case the weights and literal slices have not the same length, or if the input is invalid (see [`PB2CNF`]), this function panics.
+    pub fn encode_geq_into(
+        &self,
+        weights: &[i64],
+        literals: &[i32],
+        geq: i64,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        assert_len_eq(weights, literals);
+        assert_valid_terms(terms(weights, literals), &[geq], first_aux_var);
+        if encode_trivial_into(terms(weights, literals), Some(geq), None, sink) {
+            return first_aux_var;
+        }
+        if let Some(backend) = &self.backend {
+            let constraint = PbConstraint::weighted(terms(weights, literals)).geq(geq);
+            return backend.encode_constraint_into(&constraint, first_aux_var, sink);
+        }
+        with_callback(sink, None, |callback, callback_data| unsafe {
             encodeGeq(
-                self.0,
+                self.pb_ptr(weights, literals, Comparator::Geq, geq),
                 weights.as_ptr(),
                 weights.len().try_into().unwrap(),
                 literals.as_ptr(),
                 literals.len().try_into().unwrap(),
                 geq,
                 first_aux_var,
+                callback,
+                callback_data,
             )
-        };
-        let result = decode_formula_data(formula_ptr);
-        unsafe { freePtr(formula_ptr.cast()) };
-        result
+        })
     }
 
     /// Encodes both an At-Most-k and an At-Least-p Pseudo-Boolean constraints that refers to the same variables and weights.
@@ -171,20 +1164,71 @@ impl PB2CNF {
     ///
     /// # Panics
     ///
-    /// In case the weights and literal vectors have not the same length, this function panics.
+    /// In case the weights and literal slices have not the same length, or if the input is invalid (see [`PB2CNF`]), this function panics.
     #[must_use]
     pub fn encode_both(
         &self,
-        weights: Vec<i64>,
-        literals: Vec<i32>,
+        weights: &[i64],
+        literals: &[i32],
         less_or_eq: i64,
         greater_or_eq: i64,
         first_aux_var: i32,
     ) -> EncodingResult {
-        assert_len_eq(&weights, &literals);
-        let formula_ptr = unsafe {
+        let mut builder = EncodingResultBuilder::default();
+        let next_free_var_id = self.encode_both_into(
+            weights,
+            literals,
+            less_or_eq,
+            greater_or_eq,
+            first_aux_var,
+            &mut builder,
+        );
+        self.build_result(builder, first_aux_var, next_free_var_id)
+    }
+
+    /// Encodes both an At-Most-k and an At-Least-p Pseudo-Boolean constraints, sending the clauses to a [`ClauseSink`].
+    ///
+    /// This function behaves like [`encode_both`](Self::encode_both), except that the clauses are given one by one to the sink instead of being collected.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// In case the weights and literal slices have not the same length, or if the input is invalid (see [`PB2CNF`]), this function panics.
+    pub fn encode_both_into(
+        &self,
+        weights: &[i64],
+        literals: &[i32],
+        less_or_eq: i64,
+        greater_or_eq: i64,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        assert_len_eq(weights, literals);
+        assert_valid_terms(
+            terms(weights, literals),
+            &[less_or_eq, greater_or_eq],
+            first_aux_var,
+        );
+        if encode_trivial_into(
+            terms(weights, literals),
+            Some(greater_or_eq),
+            Some(less_or_eq),
+            sink,
+        ) {
+            return first_aux_var;
+        }
+        if let Some(backend) = &self.backend {
+            let expr = PbConstraint::weighted(terms(weights, literals));
+            let next_free_var_id = backend.encode_constraint_into(
+                &expr.clone().geq(greater_or_eq),
+                first_aux_var,
+                sink,
+            );
+            return backend.encode_constraint_into(&expr.leq(less_or_eq), next_free_var_id, sink);
+        }
+        with_callback(sink, None, |callback, callback_data| unsafe {
             encodeBoth(
-                self.0,
+                self.pb_ptr(weights, literals, Comparator::Leq, less_or_eq),
                 weights.as_ptr(),
                 weights.len().try_into().unwrap(),
                 literals.as_ptr(),
@@ -192,92 +1236,662 @@ impl PB2CNF {
                 less_or_eq,
                 greater_or_eq,
                 first_aux_var,
+                callback,
+                callback_data,
             )
-        };
-        let result = decode_formula_data(formula_ptr);
-        unsafe { freePtr(formula_ptr.cast()) };
-        result
+        })
     }
 
     /// Encodes an At-Most-k cardinality constraint.
     ///
-    /// An At-Most-k cardinality constraint imposes that at most k literals in a vector are set to true.
+    /// An At-Most-k cardinality constraint imposes that at most k literals in a slice are set to true.
     ///
-    /// In addition to this vector and the threshold, the minimal variable index that can be used as an auxiliary variables must be given to the function.
+    /// In addition to this slice and the threshold, the minimal variable index that can be used as an auxiliary variables must be given to the function.
     /// The preferred value for this parameter is in most cases the highest variable index in use plus 1.
     ///
     /// The result of this function is an [`EncodingResult`] which gives both the clauses encoding the constraint and the new lowest variable index that is not in use.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the input is invalid (see [`PB2CNF`]).
     #[must_use]
-    #[allow(clippy::missing_panics_doc)]
-    pub fn encode_at_most_k(
+    pub fn encode_at_most_k(&self, literals: &[i32], k: i64, first_aux_var: i32) -> EncodingResult {
+        let mut builder = EncodingResultBuilder::default();
+        let next_free_var_id = self.encode_at_most_k_into(literals, k, first_aux_var, &mut builder);
+        self.build_result(builder, first_aux_var, next_free_var_id)
+    }
+
+    /// Encodes an At-Most-k cardinality constraint, sending the clauses to a [`ClauseSink`].
+    ///
+    /// This function behaves like [`encode_at_most_k`](Self::encode_at_most_k), except that the clauses are given one by one to the sink instead of being collected.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the input is invalid (see [`PB2CNF`]).
+    pub fn encode_at_most_k_into(
         &self,
-        literals: Vec<i32>,
+        literals: &[i32],
         k: i64,
         first_aux_var: i32,
-    ) -> EncodingResult {
-        let formula_ptr = unsafe {
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        assert_valid_terms(literals.iter().map(|l| (1, *l)), &[k], first_aux_var);
+        if encode_trivial_into(literals.iter().map(|l| (1, *l)), None, Some(k), sink) {
+            return first_aux_var;
+        }
+        if let Some(backend) = &self.backend {
+            return backend.encode_at_most_k_into(literals, k, first_aux_var, sink);
+        }
+        ffi_debug::check(self.ptr, PB2CNF_KIND);
+        with_callback(sink, None, |callback, callback_data| unsafe {
             encodeAtMostK(
-                self.0,
+                self.ptr,
                 literals.as_ptr(),
                 literals.len().try_into().unwrap(),
                 k,
                 first_aux_var,
+                callback,
+                callback_data,
             )
-        };
-        let result = decode_formula_data(formula_ptr);
-        unsafe { freePtr(formula_ptr.cast()) };
-        result
+        })
     }
 
     /// Encodes an At-Least-k cardinality constraint.
     ///
-    /// An At-Least-k cardinality constraint imposes that at least k literals in a vector are set to true.
+    /// An At-Least-k cardinality constraint imposes that at least k literals in a slice are set to true.
     ///
-    /// In addition to this vector and the threshold, the minimal variable index that can be used as an auxiliary variables must be given to the function.
+    /// In addition to this slice and the threshold, the minimal variable index that can be used as an auxiliary variables must be given to the function.
     /// The preferred value for this parameter is in most cases the highest variable index in use plus 1.
     ///
     /// The result of this function is an [`EncodingResult`] which gives both the clauses encoding the constraint and the new lowest variable index that is not in use.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the input is invalid (see [`PB2CNF`]).
     #[must_use]
-    #[allow(clippy::missing_panics_doc)]
     pub fn encode_at_least_k(
         &self,
-        literals: Vec<i32>,
+        literals: &[i32],
         k: i64,
         first_aux_var: i32,
     ) -> EncodingResult {
-        let formula_ptr = unsafe {
+        let mut builder = EncodingResultBuilder::default();
+        let next_free_var_id =
+            self.encode_at_least_k_into(literals, k, first_aux_var, &mut builder);
+        self.build_result(builder, first_aux_var, next_free_var_id)
+    }
+
+    /// Encodes an At-Least-k cardinality constraint, sending the clauses to a [`ClauseSink`].
+    ///
+    /// This function behaves like [`encode_at_least_k`](Self::encode_at_least_k), except that the clauses are given one by one to the sink instead of being collected.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the input is invalid (see [`PB2CNF`]).
+    pub fn encode_at_least_k_into(
+        &self,
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        assert_valid_terms(literals.iter().map(|l| (1, *l)), &[k], first_aux_var);
+        if encode_trivial_into(literals.iter().map(|l| (1, *l)), Some(k), None, sink) {
+            return first_aux_var;
+        }
+        if let Some(backend) = &self.backend {
+            return backend.encode_at_least_k_into(literals, k, first_aux_var, sink);
+        }
+        ffi_debug::check(self.ptr, PB2CNF_KIND);
+        with_callback(sink, None, |callback, callback_data| unsafe {
             encodeAtLeastK(
-                self.0,
+                self.ptr,
                 literals.as_ptr(),
                 literals.len().try_into().unwrap(),
                 k,
                 first_aux_var,
+                callback,
+                callback_data,
             )
-        };
-        let result = decode_formula_data(formula_ptr);
-        unsafe { freePtr(formula_ptr.cast()) };
-        result
+        })
+    }
+
+    /// Encodes a cardinality constraint imposing that at most k literals in a slice are set to false.
+    ///
+    /// This is an At-Least-(n-k) constraint on the literals, but stating it this way is often more natural (e.g. "at most k of these tasks may be dropped").
+    /// The literals are negated before calling [`encode_at_most_k`](Self::encode_at_most_k); see this function for more information on the parameters and the return type.
+    ///
+    /// ```
+    /// use pblib_rs::PB2CNF;
+    ///
+    /// let pb2cnf = PB2CNF::new();
+    /// // at most one of x1, x2, x3 is false
+    /// let encoding = pb2cnf.encode_at_most_k_false(&[1, 2, 3], 1, 4);
+    /// assert_eq!(
+    ///     pb2cnf.encode_at_least_k(&[1, 2, 3], 2, 4).clauses(),
+    ///     encoding.clauses(),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn encode_at_most_k_false(
+        &self,
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+    ) -> EncodingResult {
+        self.encode_at_most_k(&negated(literals), k, first_aux_var)
+    }
+
+    /// Encodes a cardinality constraint imposing that at most k literals in a slice are set to false, sending the clauses to a [`ClauseSink`].
+    ///
+    /// This function behaves like [`encode_at_most_k_false`](Self::encode_at_most_k_false), except that the clauses are given one by one to the sink instead of being collected.
+    /// It returns the next free variable id.
+    pub fn encode_at_most_k_false_into(
+        &self,
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        self.encode_at_most_k_into(&negated(literals), k, first_aux_var, sink)
+    }
+
+    /// Encodes a cardinality constraint imposing that at least k literals in a slice are set to false.
+    ///
+    /// The literals are negated before calling [`encode_at_least_k`](Self::encode_at_least_k); see this function for more information on the parameters and the return type.
+    #[must_use]
+    pub fn encode_at_least_k_false(
+        &self,
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+    ) -> EncodingResult {
+        self.encode_at_least_k(&negated(literals), k, first_aux_var)
+    }
+
+    /// Encodes a cardinality constraint imposing that at least k literals in a slice are set to false, sending the clauses to a [`ClauseSink`].
+    ///
+    /// This function behaves like [`encode_at_least_k_false`](Self::encode_at_least_k_false), except that the clauses are given one by one to the sink instead of being collected.
+    /// It returns the next free variable id.
+    pub fn encode_at_least_k_false_into(
+        &self,
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        self.encode_at_least_k_into(&negated(literals), k, first_aux_var, sink)
+    }
+
+    /// Encodes a [`PbConstraint`], using the variable following the greatest variable of the constraint as the first auxiliary variable.
+    ///
+    /// This function behaves like [`encode_constraint`](Self::encode_constraint), with `first_aux_var` computed as `max(|l|) + 1` over the literals of the constraint.
+    ///
+    /// Note that this value is only correct if no variable greater than the ones of the constraint is in use, e.g. if the constraint is the only one of the problem or if it involves its greatest variable.
+    /// Otherwise, the auxiliary variables collide with the other variables of the problem, and the encoding is wrong.
+    /// When encoding several constraints, use a [`VarPool`](crate::VarPool) or an [`EncodingContext`](crate::EncodingContext) instead.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a variable of the constraint is `i32::MAX`.
+    #[must_use]
+    pub fn encode_constraint_auto(&self, constraint: &PbConstraint) -> EncodingResult {
+        self.encode_constraint(constraint, auto_first_aux_var(&constraint.literals()))
+    }
+
+    /// Encodes an At-Most-k Pseudo-Boolean constraint, using the variable following the greatest variable of the constraint as the first auxiliary variable.
+    ///
+    /// See [`encode_constraint_auto`](Self::encode_constraint_auto) for the caveats of this function, and [`encode_leq`](Self::encode_leq) for the other parameters.
+    ///
+    /// # Panics
+    ///
+    /// In case the weights and literal slices have not the same length, if a variable is `i32::MAX`, or if the input is invalid (see [`PB2CNF`]), this function panics.
+    #[must_use]
+    pub fn encode_leq_auto(&self, weights: &[i64], literals: &[i32], leq: i64) -> EncodingResult {
+        let first_aux_var = auto_first_aux_var(literals);
+        self.encode_leq(weights, literals, leq, first_aux_var)
+    }
+
+    /// Encodes an At-Least-k Pseudo-Boolean constraint, using the variable following the greatest variable of the constraint as the first auxiliary variable.
+    ///
+    /// See [`encode_constraint_auto`](Self::encode_constraint_auto) for the caveats of this function, and [`encode_geq`](Self::encode_geq) for the other parameters.
+    ///
+    /// # Panics
+    ///
+    /// In case the weights and literal slices have not the same length, if a variable is `i32::MAX`, or if the input is invalid (see [`PB2CNF`]), this function panics.
+    #[must_use]
+    pub fn encode_geq_auto(&self, weights: &[i64], literals: &[i32], geq: i64) -> EncodingResult {
+        let first_aux_var = auto_first_aux_var(literals);
+        self.encode_geq(weights, literals, geq, first_aux_var)
+    }
+
+    /// Encodes both an At-Most-k and an At-Least-p Pseudo-Boolean constraints, using the variable following the greatest variable of the constraints as the first auxiliary variable.
+    ///
+    /// See [`encode_constraint_auto`](Self::encode_constraint_auto) for the caveats of this function, and [`encode_both`](Self::encode_both) for the other parameters.
+    ///
+    /// # Panics
+    ///
+    /// In case the weights and literal slices have not the same length, if a variable is `i32::MAX`, or if the input is invalid (see [`PB2CNF`]), this function panics.
+    #[must_use]
+    pub fn encode_both_auto(
+        &self,
+        weights: &[i64],
+        literals: &[i32],
+        less_or_eq: i64,
+        greater_or_eq: i64,
+    ) -> EncodingResult {
+        let first_aux_var = auto_first_aux_var(literals);
+        self.encode_both(weights, literals, less_or_eq, greater_or_eq, first_aux_var)
+    }
+
+    /// Encodes an At-Most-k cardinality constraint, using the variable following the greatest variable of the constraint as the first auxiliary variable.
+    ///
+    /// See [`encode_constraint_auto`](Self::encode_constraint_auto) for the caveats of this function, and [`encode_at_most_k`](Self::encode_at_most_k) for the other parameters.
+    ///
+    /// ```
+    /// use pblib_rs::PB2CNF;
+    ///
+    /// // we encode x1 + x2 + x3 + x4 <= 2, the auxiliary variables starting at 5
+    /// let encoding = PB2CNF::new().encode_at_most_k_auto(&[1, 2, -3, 4], 2);
+    /// assert_eq!(5, encoding.first_aux_var());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a variable is `i32::MAX`.
+    #[must_use]
+    pub fn encode_at_most_k_auto(&self, literals: &[i32], k: i64) -> EncodingResult {
+        let first_aux_var = auto_first_aux_var(literals);
+        self.encode_at_most_k(literals, k, first_aux_var)
+    }
+
+    /// Encodes an At-Least-k cardinality constraint, using the variable following the greatest variable of the constraint as the first auxiliary variable.
+    ///
+    /// See [`encode_constraint_auto`](Self::encode_constraint_auto) for the caveats of this function, and [`encode_at_least_k`](Self::encode_at_least_k) for the other parameters.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a variable is `i32::MAX`.
+    #[must_use]
+    pub fn encode_at_least_k_auto(&self, literals: &[i32], k: i64) -> EncodingResult {
+        let first_aux_var = auto_first_aux_var(literals);
+        self.encode_at_least_k(literals, k, first_aux_var)
+    }
+}
+
+/// Returns the variable following the greatest variable involved in a list of literals, or 1 if the list is empty.
+fn auto_first_aux_var(literals: &[i32]) -> i32 {
+    literals
+        .iter()
+        .map(|l| l.checked_abs().expect("invalid literal"))
+        .max()
+        .unwrap_or_default()
+        .checked_add(1)
+        .expect("no variable is available for the auxiliary variables")
+}
+
+/// The type of the callbacks through which the C++ side sends the clauses.
+///
+/// The callbacks return 0 to continue the encoding, or another value to abort it.
+type ClauseCallback = unsafe extern "C" fn(*mut c_void, *const i32, i32) -> i32;
+
+struct CallbackData<'a, 'b, S> {
+    sink: &'a mut S,
+    budget: Option<&'a mut Budget<'b>>,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+/// The value returned by the C++ side instead of the next free variable id when pblib throws an exception.
+const ENCODING_FAILED: i32 = -1;
+
+const ENCODING_FAILED_MESSAGE: &str = "pblib raised an exception during the encoding";
+
+/// The number of clauses between two calls to a progress callback.
+const PROGRESS_INTERVAL: usize = 1024;
+
+/// Tracks the size of an encoding while its clauses are produced, checking it against [`EncodeLimits`] and reporting it to a progress callback.
+struct Budget<'a> {
+    limits: EncodeLimits,
+    progress: Option<&'a dyn Fn(Progress) -> ControlFlow<()>>,
+    first_aux_var: i32,
+    next_free_var_id: i32,
+    n_clauses: usize,
+    n_literals: usize,
+    max_clause_len: usize,
+    exceeded: bool,
+    cancelled: bool,
+    failed: bool,
+    input_vars: Option<Vec<i32>>,
+    invalid_var: Option<i32>,
+}
+
+impl<'a> Budget<'a> {
+    fn new(
+        limits: EncodeLimits,
+        progress: Option<&'a dyn Fn(Progress) -> ControlFlow<()>>,
+        first_aux_var: i32,
+    ) -> Self {
+        Self {
+            limits,
+            progress,
+            first_aux_var,
+            next_free_var_id: first_aux_var,
+            n_clauses: 0,
+            n_literals: 0,
+            max_clause_len: 0,
+            exceeded: false,
+            cancelled: false,
+            failed: false,
+            input_vars: None,
+            invalid_var: None,
+        }
+    }
+
+    /// Enables the check of the variables of the clauses against the ones of the constraint and the auxiliary ones.
+    fn check_vars(&mut self, constraint: &PbConstraint) {
+        let mut input_vars = constraint
+            .terms()
+            .iter()
+            .map(|(_, l)| l.abs())
+            .collect::<Vec<_>>();
+        input_vars.sort_unstable();
+        input_vars.dedup();
+        self.input_vars = Some(input_vars);
+    }
+
+    fn n_aux_vars(&self, next_free_var_id: i32) -> usize {
+        usize::try_from(next_free_var_id - self.first_aux_var).unwrap_or_default()
+    }
+
+    /// Accounts for a new clause, unless it makes the encoding exceed the limits.
+    fn accept(&mut self, clause: &[i32]) -> bool {
+        let next_free_var_id = clause
+            .iter()
+            .map(|l| l.abs() + 1)
+            .fold(self.next_free_var_id, i32::max);
+        if self
+            .limits
+            .max_clauses()
+            .is_some_and(|m| self.n_clauses >= m)
+            || self
+                .limits
+                .max_aux_vars()
+                .is_some_and(|m| self.n_aux_vars(next_free_var_id) > m)
+            || self.limits.max_heap_bytes().is_some_and(|m| {
+                encoding_result::heap_bytes(self.n_clauses + 1, self.n_literals + clause.len()) > m
+            })
+        {
+            self.exceeded = true;
+            return false;
+        }
+        if let Some(input_vars) = &self.input_vars {
+            if self.invalid_var.is_none() {
+                self.invalid_var = clause
+                    .iter()
+                    .map(|l| l.abs())
+                    .find(|v| *v < self.first_aux_var && input_vars.binary_search(v).is_err());
+            }
+        }
+        self.next_free_var_id = next_free_var_id;
+        self.n_clauses += 1;
+        self.n_literals += clause.len();
+        self.max_clause_len = self.max_clause_len.max(clause.len());
+        true
+    }
+
+    /// Calls the progress callback if the interval is reached, and returns `false` if it asks to cancel the encoding.
+    fn poll(&mut self) -> bool {
+        if let Some(progress) = self.progress {
+            if self.n_clauses.is_multiple_of(PROGRESS_INTERVAL) {
+                let current = Progress::new(self.n_clauses, self.n_aux_vars(self.next_free_var_id));
+                self.cancelled = progress(current).is_break();
+            }
+        }
+        !self.cancelled
+    }
+
+    /// Returns the next free variable id, or an error if the encoding was aborted.
+    fn result(&self, next_free_var_id: i32) -> Result<i32, PbError> {
+        if self.failed {
+            Err(PbError::Ffi(ENCODING_FAILED_MESSAGE.to_string()))
+        } else if self.exceeded {
+            Err(PbError::BudgetExceeded(self.stats()))
+        } else if self.cancelled {
+            Err(PbError::Cancelled(self.stats()))
+        } else if self.input_vars.is_some()
+            && (self.invalid_var.is_some() || self.next_free_var_id > next_free_var_id)
+        {
+            Err(PbError::InvalidOutputVar {
+                var: self.invalid_var.unwrap_or(self.next_free_var_id - 1),
+                first_aux_var: self.first_aux_var,
+                next_free_var_id,
+            })
+        } else {
+            Ok(next_free_var_id)
+        }
+    }
+
+    fn stats(&self) -> EncodingStats {
+        EncodingStats::new(
+            self.n_clauses,
+            self.n_literals,
+            self.n_aux_vars(self.next_free_var_id),
+            self.max_clause_len,
+        )
+    }
+}
+
+unsafe extern "C" fn add_clause_callback<S: ClauseSink>(
+    data: *mut c_void,
+    clause: *const i32,
+    clause_len: i32,
+) -> i32 {
+    let data = &mut *data.cast::<CallbackData<'_, '_, S>>();
+    let clause = if clause_len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(clause, usize::try_from(clause_len).unwrap())
+    };
+    if let Some(budget) = data.budget.as_mut() {
+        if !budget.accept(clause) {
+            return 1;
+        }
+    }
+    if let Err(e) = panic::catch_unwind(AssertUnwindSafe(|| data.sink.add_clause(clause))) {
+        data.panic = Some(e);
+        return 1;
+    }
+    match data.budget.as_mut() {
+        Some(budget) => i32::from(!budget.poll()),
+        None => 0,
+    }
+}
+
+/// pblib relies on global state (e.g. the configuration and the cache of `FormulaClass`, or the C random generator), even when distinct objects are used.
+/// This lock serializes all the calls that may access this state.
+static PBLIB_LOCK: Mutex<()> = Mutex::new(());
+
+pub(crate) fn pblib_lock() -> MutexGuard<'static, ()> {
+    PBLIB_LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Calls an encoding function, giving it a callback that sends the clauses to the sink.
+///
+/// The encoding function is called while holding the global pblib lock.
+/// If a budget is given, the encoding is aborted as soon as a clause would exceed it, or when its progress callback asks for it; the clauses that fit in the budget are sent to the sink.
+/// Panics raised by the sink cannot cross the FFI boundary: they are caught, the encoding is aborted, and the panic is resumed once the encoding function returns.
+/// If pblib raises an exception, the failure is recorded in the budget, so that it is reported as a [`PbError::Ffi`] error; without budget, this function panics.
+fn with_callback<S: ClauseSink>(
+    sink: &mut S,
+    budget: Option<&mut Budget<'_>>,
+    encode: impl FnOnce(ClauseCallback, *mut c_void) -> i32,
+) -> i32 {
+    let mut data = CallbackData {
+        sink,
+        budget,
+        panic: None,
+    };
+    let next_free_var_id = {
+        let _lock = pblib_lock();
+        encode(add_clause_callback::<S>, (&raw mut data).cast())
+    };
+    if let Some(e) = data.panic {
+        panic::resume_unwind(e);
+    }
+    if next_free_var_id == ENCODING_FAILED {
+        match data.budget {
+            Some(budget) => budget.failed = true,
+            None => panic!("{ENCODING_FAILED_MESSAGE}"),
+        }
+    }
+    next_free_var_id
+}
+
+/// Splits the content of a clause buffer into clauses, checking its consistency.
+///
+/// Each clause is preceded by its length, and `n_clauses` is the number of clauses recorded by the C++ side.
+fn decode_clause_buffer(data: &[i32], n_clauses: usize) -> Result<Vec<&[i32]>, PbError> {
+    let mut clauses = Vec::with_capacity(n_clauses.min(data.len()));
+    let mut remaining = data;
+    while let Some((clause_len, tail)) = remaining.split_first() {
+        let clause_len = usize::try_from(*clause_len)
+            .ok()
+            .filter(|l| *l <= tail.len())
+            .ok_or_else(|| {
+                PbError::Ffi(format!(
+                    "invalid clause length {clause_len} in a clause buffer with {} remaining values",
+                    tail.len()
+                ))
+            })?;
+        let (clause, tail) = tail.split_at(clause_len);
+        if let Some(l) = clause.iter().find(|l| **l == 0 || **l == i32::MIN) {
+            return Err(PbError::Ffi(format!(
+                "invalid literal {l} in a clause buffer"
+            )));
+        }
+        clauses.push(clause);
+        remaining = tail;
+    }
+    if clauses.len() == n_clauses {
+        Ok(clauses)
+    } else {
+        Err(PbError::Ffi(format!(
+            "a clause buffer records {n_clauses} clauses but contains {}",
+            clauses.len()
+        )))
+    }
+}
+
+/// Checks that a constraint can be given to pblib.
+///
+/// The auxiliary variables must not collide with the variables of the constraint.
+/// Moreover, pblib moves the terms with negative weights to the right hand side and compares the bound to the sum of the weights; these computations must not overflow.
+fn check_input(constraint: &PbConstraint, first_aux_var: i32) -> Result<(), PbError> {
+    let rhs = match constraint.comparator() {
+        Comparator::Lt => constraint.rhs().checked_sub(1),
+        Comparator::Gt => constraint.rhs().checked_add(1),
+        Comparator::Leq | Comparator::Geq | Comparator::Eq => Some(constraint.rhs()),
+    };
+    check_terms(
+        constraint.terms().iter().copied(),
+        constraint.rhs(),
+        &[rhs],
+        first_aux_var,
+    )
+}
+
+/// Checks that terms and their (non-strict) bounds can be given to pblib; see [`check_input`].
+///
+/// A bound set to `None` is one whose adjustment to a non-strict comparator overflowed, while `rhs` is the bound given by the user.
+fn check_terms(
+    mut terms: impl Iterator<Item = (i64, i32)> + Clone,
+    rhs: i64,
+    bounds: &[Option<i64>],
+    first_aux_var: i32,
+) -> Result<(), PbError> {
+    let max_var = terms
+        .clone()
+        .map(|(_, l)| i32::try_from(l.unsigned_abs()).unwrap_or(i32::MAX))
+        .max()
+        .unwrap_or_default();
+    if first_aux_var <= max_var || first_aux_var <= 0 {
+        return Err(PbError::InvalidFirstAuxVar {
+            first_aux_var,
+            max_var,
+        });
+    }
+    let overflow = || {
+        PbError::Overflow(format!(
+            "the weights and the bound of the constraint do not fit in 64-bit integers (bound is {rhs})"
+        ))
+    };
+    let weight_sum = terms
+        .try_fold(0_i64, |acc, (w, _)| acc.checked_add(w.checked_abs()?))
+        .ok_or_else(overflow)?;
+    for bound in bounds {
+        bound
+            .and_then(i64::checked_abs)
+            .and_then(|b| b.checked_add(weight_sum))
+            .ok_or_else(overflow)?;
     }
+    Ok(())
+}
+
+/// Checks the input of the functions that cannot return an error, as [`check_terms`] does.
+///
+/// # Panics
+///
+/// This function panics with the message of the error if the input is invalid.
+pub(crate) fn assert_valid_terms(
+    terms: impl Iterator<Item = (i64, i32)> + Clone,
+    bounds: &[i64],
+    first_aux_var: i32,
+) {
+    let rhs = bounds.first().copied().unwrap_or_default();
+    let bounds = bounds.iter().copied().map(Some).collect::<Vec<_>>();
+    check_terms(terms, rhs, &bounds, first_aux_var).unwrap_or_else(|e| panic!("{e}"));
+}
+
+fn negated(literals: &[i32]) -> Vec<i32> {
+    literals.iter().map(|l| -l).collect()
 }
 
-fn decode_formula_data(formula_ptr: *mut i32) -> EncodingResult {
-    let data_len =
-        usize::try_from(unsafe { std::slice::from_raw_parts(formula_ptr, 1) }[0]).unwrap();
-    let data = unsafe { std::slice::from_raw_parts(formula_ptr, data_len) };
-    let next_free_var_id = data[1];
-    let mut clauses = Vec::with_capacity(usize::try_from(data[0]).unwrap());
-    let mut i = 2;
-    while i < data_len {
-        let len = usize::try_from(data[i]).unwrap();
-        clauses.push(data[i + 1..i + 1 + len].into());
-        i += len + 1;
-    }
-    EncodingResult {
-        clauses,
-        next_free_var_id,
+fn terms<'a>(
+    weights: &'a [i64],
+    literals: &'a [i32],
+) -> impl Iterator<Item = (i64, i32)> + Clone + 'a {
+    weights.iter().copied().zip(literals.iter().copied())
+}
+
+/// Returns the lower and upper bounds of a non-strict constraint, as expected by [`encode_trivial`].
+fn bounds(constraint: &PbConstraint) -> (Option<i64>, Option<i64>) {
+    let rhs = constraint.rhs();
+    match constraint.comparator() {
+        Comparator::Leq => (None, Some(rhs)),
+        Comparator::Geq => (Some(rhs), None),
+        Comparator::Eq => (Some(rhs), Some(rhs)),
+        Comparator::Lt | Comparator::Gt => unreachable!(),
     }
 }
 
+/// Calls [`encode_trivial`], sending the clauses to a sink.
+fn encode_trivial_into<I>(
+    terms: I,
+    geq: Option<i64>,
+    leq: Option<i64>,
+    sink: &mut impl ClauseSink,
+) -> bool
+where
+    I: Iterator<Item = (i64, i32)> + Clone,
+{
+    encode_trivial(terms, geq, leq, |clause| {
+        sink.add_clause(clause);
+        true
+    })
+}
+
 fn assert_len_eq(weights: &[i64], literals: &[i32]) {
     assert_eq!(
         weights.len(),
@@ -294,15 +1908,81 @@ impl Default for PB2CNF {
     }
 }
 
+// SAFETY: the underlying C++ object is only accessed while holding the global pblib lock, which also protects the global state of pblib.
+unsafe impl Send for PB2CNF {}
+
+// SAFETY: see above; functions taking `&self` never access the C++ object without holding the lock.
+unsafe impl Sync for PB2CNF {}
+
 impl Drop for PB2CNF {
     fn drop(&mut self) {
-        unsafe { deletePB2CNF(self.0) }
+        let _lock = pblib_lock();
+        ffi_debug::untrack(self.ptr, PB2CNF_KIND);
+        unsafe { deletePB2CNF(self.ptr) }
+        for (_, ptr) in &self.auto_ptrs {
+            ffi_debug::untrack(*ptr, PB2CNF_KIND);
+            unsafe { deletePB2CNF(*ptr) }
+        }
+        ffi_debug::untrack(self.clause_buffer, CLAUSE_BUFFER_KIND);
+        unsafe { deleteClauseBuffer(self.clause_buffer) }
+    }
+}
+
+/// The encoding of a constraint whose bound can be tightened, reusing the structure built by pblib for the initial bound.
+///
+/// Incremental encodings are built by [`PB2CNF::try_encode_incremental_into`].
+pub(crate) struct IncrementalEncoding {
+    ptr: *mut c_void,
+}
+
+impl IncrementalEncoding {
+    /// Encodes a new bound for the constraint, sending the clauses to a [`ClauseSink`].
+    ///
+    /// The given constraint must have the same terms as the initial one, and a comparator in the same direction (e.g. [`Comparator::Leq`] or [`Comparator::Lt`] for an initial [`Comparator::Leq`] constraint).
+    /// Its bound replaces the previous one if it is tighter; otherwise, it is already implied and no clause is produced.
+    /// The new clauses may involve the auxiliary variables of the previous encodings of the constraint.
+    /// It returns the next free variable id.
+    ///
+    /// In case of a [`PbError::BudgetExceeded`] error, the state of the encoding is unknown, and it must be dropped.
+    ///
+    /// # Errors
+    ///
+    /// See [`PB2CNF::try_encode_constraint_into`].
+    pub(crate) fn try_encode_new_bound_into(
+        &mut self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        limits: &EncodeLimits,
+        sink: &mut impl ClauseSink,
+    ) -> Result<i32, PbError> {
+        check_input(constraint, first_aux_var)?;
+        let bound = constraint.non_strict().rhs();
+        let mut budget = Budget::new(*limits, None, first_aux_var);
+        let next_free_var_id =
+            with_callback(sink, Some(&mut budget), |callback, callback_data| unsafe {
+                ffi_debug::check(self.ptr, INC_CONSTRAINT_KIND);
+                incConstraintEncodeNewBound(self.ptr, bound, first_aux_var, callback, callback_data)
+            });
+        budget.result(next_free_var_id)
+    }
+}
+
+// SAFETY: the underlying C++ object is only accessed while holding the global pblib lock.
+unsafe impl Send for IncrementalEncoding {}
+
+impl Drop for IncrementalEncoding {
+    fn drop(&mut self) {
+        let _lock = pblib_lock();
+        ffi_debug::untrack(self.ptr, INC_CONSTRAINT_KIND);
+        unsafe { deleteIncConstraint(self.ptr) }
     }
 }
 
 extern "C" {
     pub fn newPB2CNF() -> *mut c_void;
 
+    pub fn newPB2CNFWithConfig(pb_encoder: i32, amk_encoder: i32, amo_encoder: i32) -> *mut c_void;
+
     pub fn encodeLeq(
         ptr: *mut c_void,
         weights: *const i64,
@@ -311,7 +1991,9 @@ extern "C" {
         literals_len: i32,
         leq: i64,
         firstAuxiliaryVariable: i32,
-    ) -> *mut i32;
+        callback: ClauseCallback,
+        callback_data: *mut c_void,
+    ) -> i32;
 
     pub fn encodeGeq(
         ptr: *mut c_void,
@@ -321,7 +2003,9 @@ extern "C" {
         literals_len: i32,
         geq: i64,
         firstAuxiliaryVariable: i32,
-    ) -> *mut i32;
+        callback: ClauseCallback,
+        callback_data: *mut c_void,
+    ) -> i32;
 
     pub fn encodeBoth(
         ptr: *mut c_void,
@@ -332,7 +2016,9 @@ extern "C" {
         leq: i64,
         geq: i64,
         firstAuxiliaryVariable: i32,
-    ) -> *mut i32;
+        callback: ClauseCallback,
+        callback_data: *mut c_void,
+    ) -> i32;
 
     pub fn encodeAtMostK(
         ptr: *mut c_void,
@@ -340,7 +2026,9 @@ extern "C" {
         literals_len: i32,
         k: i64,
         firstAuxiliaryVariable: i32,
-    ) -> *mut i32;
+        callback: ClauseCallback,
+        callback_data: *mut c_void,
+    ) -> i32;
 
     pub fn encodeAtLeastK(
         ptr: *mut c_void,
@@ -348,16 +2036,55 @@ extern "C" {
         literals_len: i32,
         k: i64,
         firstAuxiliaryVariable: i32,
-    ) -> *mut i32;
+        callback: ClauseCallback,
+        callback_data: *mut c_void,
+    ) -> i32;
 
     pub fn deletePB2CNF(ptr: *mut c_void);
 
-    pub fn freePtr(ptr: *mut c_void);
+    pub fn newIncConstraint(
+        ptr: *mut c_void,
+        weights: *const i64,
+        weights_len: i32,
+        literals: *const i32,
+        literals_len: i32,
+        comparator: i32,
+        bound: i64,
+        firstAuxiliaryVariable: i32,
+        callback: ClauseCallback,
+        callback_data: *mut c_void,
+        next_free_var_id: *mut i32,
+    ) -> *mut c_void;
+
+    pub fn incConstraintEncodeNewBound(
+        ptr: *mut c_void,
+        bound: i64,
+        firstAuxiliaryVariable: i32,
+        callback: ClauseCallback,
+        callback_data: *mut c_void,
+    ) -> i32;
+
+    pub fn deleteIncConstraint(ptr: *mut c_void);
+
+    pub fn newClauseBuffer() -> *mut c_void;
+
+    pub fn appendToClauseBuffer(buffer: *mut c_void, clause: *const i32, clause_len: i32) -> i32;
+
+    pub fn clauseBufferNClauses(buffer: *mut c_void) -> usize;
+
+    pub fn clauseBufferData(buffer: *mut c_void) -> *const i32;
+
+    pub fn clauseBufferLen(buffer: *mut c_void) -> usize;
+
+    pub fn clearClauseBuffer(buffer: *mut c_void);
+
+    pub fn deleteClauseBuffer(buffer: *mut c_void);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{reference, AmkEncoder, CnfFormula, ConstraintFuzzer, CostModel, EncodingOutcome};
     use splr::{Certificate, Config, SolveIF, Solver, SolverError};
 
     #[test]
@@ -365,7 +2092,7 @@ mod tests {
         let weights = vec![1, 1];
         let literals = vec![1, 2];
         let pb2cnf = PB2CNF::new();
-        let encoding = pb2cnf.encode_leq(weights, literals, 1, 3);
+        let encoding = pb2cnf.encode_leq(&weights, &literals, 1, 3);
         assert_encoding_eq(&[vec![-2, -1]], 3, &encoding);
     }
 
@@ -374,7 +2101,7 @@ mod tests {
         let weights = vec![1, 1];
         let literals = vec![1, 2];
         let pb2cnf = PB2CNF::new();
-        let encoding = pb2cnf.encode_geq(weights, literals, 1, 3);
+        let encoding = pb2cnf.encode_geq(&weights, &literals, 1, 3);
         assert_encoding_eq(&[vec![1, 2]], 3, &encoding);
     }
 
@@ -383,7 +2110,7 @@ mod tests {
         let weights = vec![1, 1];
         let literals = vec![1, 2];
         let pb2cnf = PB2CNF::new();
-        let encoding = pb2cnf.encode_both(weights, literals, 1, 1, 3);
+        let encoding = pb2cnf.encode_both(&weights, &literals, 1, 1, 3);
         assert_encoding_eq(&[vec![-2, -1], vec![1, 2]], 3, &encoding);
     }
 
@@ -391,7 +2118,7 @@ mod tests {
     fn test_at_most_one_clause() {
         let literals = vec![1, 2];
         let pb2cnf = PB2CNF::new();
-        let encoding = pb2cnf.encode_at_most_k(literals, 1, 3);
+        let encoding = pb2cnf.encode_at_most_k(&literals, 1, 3);
         assert_encoding_eq(&[vec![-2, -1]], 3, &encoding);
     }
 
@@ -399,10 +2126,11 @@ mod tests {
     fn test_at_least_one_clause() {
         let literals = vec![1, 2];
         let pb2cnf = PB2CNF::new();
-        let encoding = pb2cnf.encode_at_least_k(literals, 1, 3);
+        let encoding = pb2cnf.encode_at_least_k(&literals, 1, 3);
         assert_encoding_eq(&[vec![1, 2]], 3, &encoding);
     }
 
+    #[allow(clippy::needless_for_each)]
     fn assert_encoding_eq(
         expected_formula: &[Vec<i32>],
         expected_next_free_var_id: i32,
@@ -421,7 +2149,757 @@ mod tests {
         let weights = vec![1];
         let literals = vec![1, 2];
         let pb2cnf = PB2CNF::new();
-        let _ = pb2cnf.encode_leq(weights, literals, 1, 3);
+        let _ = pb2cnf.encode_leq(&weights, &literals, 1, 3);
+    }
+
+    struct PanickingSink(usize);
+
+    impl ClauseSink for PanickingSink {
+        fn add_clause(&mut self, _clause: &[i32]) {
+            self.0 += 1;
+            panic!("sink failure");
+        }
+    }
+
+    #[test]
+    fn test_sink_panic_is_resumed() {
+        let pb2cnf = PB2CNF::new();
+        let mut sink = PanickingSink(0);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            pb2cnf.encode_at_most_k_into(&[1, 2, 3, 4], 2, 5, &mut sink)
+        }));
+        assert_eq!(
+            Some(&"sink failure"),
+            result.unwrap_err().downcast_ref::<&str>()
+        );
+        assert_eq!(1, sink.0);
+    }
+
+    #[test]
+    fn test_pblib_exception() {
+        let mut clauses = Vec::<Vec<i32>>::new();
+        let mut budget = Budget::new(EncodeLimits::default(), None, 3);
+        let next_free_var_id =
+            with_callback(&mut clauses, Some(&mut budget), |_, _| ENCODING_FAILED);
+        assert!(matches!(
+            budget.result(next_free_var_id),
+            Err(PbError::Ffi(_))
+        ));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            with_callback(&mut clauses, None, |_, _| ENCODING_FAILED)
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_concurrent_encodings() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<PB2CNF>();
+        let pb2cnf = std::sync::Arc::new(PB2CNF::new());
+        let handles = (0..8)
+            .map(|k| {
+                let pb2cnf = std::sync::Arc::clone(&pb2cnf);
+                let expected = pb2cnf.encode_at_most_k(&[1, 2, 3, 4, 5, 6, 7, 8], k, 9);
+                std::thread::spawn(move || {
+                    let own_pb2cnf = PB2CNF::new();
+                    for _ in 0..20 {
+                        let literals = vec![1, 2, 3, 4, 5, 6, 7, 8];
+                        assert_eq!(expected, pb2cnf.encode_at_most_k(&literals, k, 9));
+                        assert_eq!(expected, own_pb2cnf.encode_at_most_k(&literals, k, 9));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for h in handles {
+            h.join().unwrap();
+        }
+    }
+
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("write failure"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_encode_to_dimacs() {
+        let pb2cnf = PB2CNF::new();
+        let constraint =
+            PbConstraint::new(vec![(8, 1), (4, 2), (2, 3), (1, 4)], Comparator::Leq, 6);
+        let encoding = pb2cnf.encode_constraint(&constraint, 5);
+        let mut expected = Vec::new();
+        encoding.write_dimacs_clauses(&mut expected).unwrap();
+        let mut output = Vec::new();
+        let (next_free_var_id, n_clauses) = pb2cnf
+            .encode_leq_to_dimacs(&[8, 4, 2, 1], &[1, 2, 3, 4], 6, 5, &mut output)
+            .unwrap();
+        assert_eq!(expected, output);
+        assert_eq!(encoding.next_free_var_id(), next_free_var_id);
+        assert_eq!(encoding.clauses().len(), n_clauses);
+        let error = pb2cnf
+            .encode_constraint_to_dimacs(&constraint, 5, &mut FailingWriter)
+            .unwrap_err();
+        assert_eq!("write failure", error.to_string());
+    }
+
+    #[test]
+    fn test_buffered_encodings() {
+        let pb2cnf = PB2CNF::with_config(EncoderConfig::default().with_pb_encoder(PbEncoder::Auto));
+        let constraints = [
+            PbConstraint::new(vec![(8, 1), (4, 2), (2, 3), (1, 4)], Comparator::Geq, 6),
+            PbConstraint::new(vec![(3, 1), (2, -2), (1, 3)], Comparator::Lt, 4),
+            PbConstraint::new(vec![(1, 1), (1, 2), (1, 4)], Comparator::Eq, 2),
+        ];
+        let mut expected = Vec::new();
+        let mut first_aux_var = 5;
+        for constraint in &constraints {
+            let next_free_var_id =
+                pb2cnf.encode_constraint_into(constraint, first_aux_var, &mut expected);
+            assert_eq!(
+                next_free_var_id,
+                pb2cnf.encode_constraint_buffered(constraint, first_aux_var)
+            );
+            assert_eq!(expected.len(), pb2cnf.n_buffered_clauses());
+            first_aux_var = next_free_var_id;
+        }
+        let mut clauses = Vec::new();
+        assert_eq!(
+            expected.len(),
+            pb2cnf.take_buffered_clauses_into(&mut clauses)
+        );
+        assert_eq!(expected, clauses);
+        assert_eq!(0, pb2cnf.n_buffered_clauses());
+        assert_eq!(
+            0,
+            pb2cnf.try_take_buffered_clauses_into(&mut clauses).unwrap()
+        );
+    }
+
+    /// A FNV-1a hash of the clauses, used to compare encodings with reference ones.
+    fn fingerprint(clauses: &[Vec<i32>]) -> u64 {
+        clauses
+            .iter()
+            .flat_map(|c| {
+                let len = i32::try_from(c.len()).unwrap();
+                std::iter::once(len).chain(c.iter().copied())
+            })
+            .flat_map(i32::to_le_bytes)
+            .fold(0xcbf2_9ce4_8422_2325, |h, b| {
+                (h ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+            })
+    }
+
+    #[test]
+    fn test_deterministic() {
+        // many terms sharing the same weight, so the order of the sorted terms matters
+        let terms = (1..=24_i32)
+            .map(|v| (i64::from(v % 3 + 1), if v % 2 == 0 { v } else { -v }))
+            .collect::<Vec<_>>();
+        let constraints = [
+            PbConstraint::new(terms.clone(), Comparator::Geq, 20),
+            PbConstraint::new(terms.clone(), Comparator::Eq, 30),
+            PbConstraint::new(
+                terms.iter().map(|(_, l)| (1, *l)).collect(),
+                Comparator::Leq,
+                5,
+            ),
+        ];
+        let configs = [
+            (
+                EncoderConfig::default(),
+                [
+                    0xa113_7d2f_4f3d_9073,
+                    0x4a81_c239_f932_892f,
+                    0xc202_d57c_bb22_e4c5,
+                ],
+            ),
+            (
+                EncoderConfig::default().with_pb_encoder(PbEncoder::Bdd),
+                [
+                    0xa113_7d2f_4f3d_9073,
+                    0x4a81_c239_f932_892f,
+                    0xc202_d57c_bb22_e4c5,
+                ],
+            ),
+            (
+                EncoderConfig::default().with_pb_encoder(PbEncoder::Swc),
+                [
+                    0x5ece_05c2_13c0_eaf0,
+                    0x56f8_fa78_54c7_c3e3,
+                    0xc202_d57c_bb22_e4c5,
+                ],
+            ),
+            (
+                EncoderConfig::default().with_pb_encoder(PbEncoder::SortingNetworks),
+                [
+                    0x6daf_b633_609f_2ba7,
+                    0x9ed2_5f92_43e4_ca71,
+                    0xc202_d57c_bb22_e4c5,
+                ],
+            ),
+            (
+                EncoderConfig::default().with_pb_encoder(PbEncoder::Adder),
+                [
+                    0x3f9d_35dc_bb12_c31b,
+                    0xb3a4_2419_d7d8_c662,
+                    0xc202_d57c_bb22_e4c5,
+                ],
+            ),
+            (
+                EncoderConfig::default().with_pb_encoder(PbEncoder::BinaryMerge),
+                [
+                    0x9286_608f_0e54_146b,
+                    0x5bba_ac9f_10d8_4dea,
+                    0xc202_d57c_bb22_e4c5,
+                ],
+            ),
+            (
+                EncoderConfig::default().with_amk_encoder(AmkEncoder::Bdd),
+                [
+                    0xa113_7d2f_4f3d_9073,
+                    0x4a81_c239_f932_892f,
+                    0x7b3a_efd4_ed51_05e3,
+                ],
+            ),
+            (
+                EncoderConfig::default().with_amk_encoder(AmkEncoder::Card),
+                [
+                    0xa113_7d2f_4f3d_9073,
+                    0x4a81_c239_f932_892f,
+                    0xc202_d57c_bb22_e4c5,
+                ],
+            ),
+        ];
+        for (config, expected) in configs {
+            let thread_constraints = constraints.clone();
+            let encodings = std::thread::spawn(move || {
+                let pb2cnf = PB2CNF::with_config(config);
+                thread_constraints
+                    .iter()
+                    .map(|c| pb2cnf.encode_constraint(c, 25))
+                    .collect::<Vec<_>>()
+            })
+            .join()
+            .unwrap();
+            let pb2cnf = PB2CNF::with_config(config);
+            for (i, constraint) in constraints.iter().enumerate() {
+                let encoding = pb2cnf.encode_constraint(constraint, 25);
+                assert_eq!(encodings[i], encoding);
+                assert_eq!(expected[i], fingerprint(encoding.clauses()), "{config:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_var_check() {
+        let constraint = PbConstraint::new(vec![(3, 1), (2, -3), (1, 5)], Comparator::Leq, 3);
+        let mut budget = Budget::new(EncodeLimits::default(), None, 6);
+        budget.check_vars(&constraint);
+        assert!(budget.accept(&[-1, 3, 6]));
+        assert_eq!(7, budget.result(7).unwrap());
+        assert!(matches!(
+            budget.result(6),
+            Err(PbError::InvalidOutputVar {
+                var: 6,
+                first_aux_var: 6,
+                next_free_var_id: 6
+            })
+        ));
+        assert!(budget.accept(&[2, 7]));
+        assert!(matches!(
+            budget.result(8),
+            Err(PbError::InvalidOutputVar { var: 2, .. })
+        ));
+        let mut fuzzer = ConstraintFuzzer::new(3, 10);
+        let encoders = [
+            PbEncoder::Best,
+            PbEncoder::Bdd,
+            PbEncoder::SortingNetworks,
+            PbEncoder::Adder,
+            PbEncoder::BinaryMerge,
+            PbEncoder::Auto,
+        ];
+        for encoder in encoders {
+            let pb2cnf = PB2CNF::with_config(
+                EncoderConfig::default()
+                    .with_pb_encoder(encoder)
+                    .with_var_check(true),
+            );
+            for constraint in (0..10).flat_map(|_| fuzzer.generate()) {
+                if encoder == PbEncoder::BinaryMerge
+                    && constraint
+                        .checked_weight_sum()
+                        .is_none_or(|s| s > i64::from(i32::MAX))
+                {
+                    continue;
+                }
+                pb2cnf.try_encode_constraint(&constraint, 11).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_auto() {
+        let pb2cnf = PB2CNF::new();
+        let constraint = PbConstraint::new(vec![(3, 1), (2, -4), (1, 2)], Comparator::Eq, 3);
+        assert_eq!(
+            pb2cnf.encode_constraint(&constraint, 5),
+            pb2cnf.encode_constraint_auto(&constraint)
+        );
+        assert_eq!(
+            pb2cnf.encode_leq(&[3, 2], &[1, -4], 3, 5),
+            pb2cnf.encode_leq_auto(&[3, 2], &[1, -4], 3)
+        );
+        assert_eq!(
+            pb2cnf.encode_geq(&[3, 2], &[1, -4], 3, 5),
+            pb2cnf.encode_geq_auto(&[3, 2], &[1, -4], 3)
+        );
+        assert_eq!(
+            pb2cnf.encode_both(&[3, 2, 1], &[1, -4, 2], 4, 2, 5),
+            pb2cnf.encode_both_auto(&[3, 2, 1], &[1, -4, 2], 4, 2)
+        );
+        assert_eq!(
+            pb2cnf.encode_at_most_k(&[1, 2, -3, 4], 2, 5),
+            pb2cnf.encode_at_most_k_auto(&[1, 2, -3, 4], 2)
+        );
+        assert_eq!(
+            pb2cnf.encode_at_least_k(&[1, 2, -3, 4], 2, 5),
+            pb2cnf.encode_at_least_k_auto(&[1, 2, -3, 4], 2)
+        );
+        assert_eq!(1, auto_first_aux_var(&[]));
+    }
+
+    #[test]
+    fn test_k_false() {
+        let pb2cnf = PB2CNF::new();
+        let literals = vec![1, -2, 3, 4];
+        for k in 0..=4 {
+            let constraints = [
+                PbConstraint::cardinality([-1, 2, -3, -4]).leq(k),
+                PbConstraint::cardinality([-1, 2, -3, -4]).geq(k),
+            ];
+            let encoding = pb2cnf.encode_at_most_k_false(&literals, k, 5);
+            assert_eq!(
+                Ok(()),
+                reference::check_encoding(&constraints[..1], encoding.clauses(), 4)
+            );
+            let mut clauses = Vec::new();
+            pb2cnf.encode_at_least_k_false_into(&literals, k, 5, &mut clauses);
+            assert_eq!(
+                Ok(()),
+                reference::check_encoding(&constraints[1..], &clauses, 4)
+            );
+        }
+    }
+
+    #[test]
+    fn test_canonical_order() {
+        let constraint =
+            PbConstraint::new(vec![(3, -2), (2, 1), (2, 4), (1, -3)], Comparator::Geq, 4);
+        let mut encoding = PB2CNF::new().encode_constraint(&constraint, 5);
+        let pb2cnf = PB2CNF::with_config(EncoderConfig::default().with_canonical_order(true));
+        let canonical = pb2cnf.encode_constraint(&constraint, 5);
+        assert!(canonical.clauses().iter().all(|c| c
+            .windows(2)
+            .all(|w| (w[0].abs(), w[0]) <= (w[1].abs(), w[1]))));
+        assert!(canonical
+            .clauses()
+            .windows(2)
+            .all(|w| w[0].len() <= w[1].len()));
+        encoding.canonicalize();
+        assert_eq!(encoding, canonical);
+    }
+
+    #[test]
+    fn test_global() {
+        assert!(std::ptr::eq(PB2CNF::global(), PB2CNF::global()));
+        let handles = (0..4)
+            .map(|k| {
+                std::thread::spawn(move || PB2CNF::global().encode_at_most_k(&[1, 2, 3], k, 4))
+            })
+            .collect::<Vec<_>>();
+        for (k, h) in (0..4).zip(handles) {
+            assert_eq!(
+                PB2CNF::new().encode_at_most_k(&[1, 2, 3], k, 4),
+                h.join().unwrap()
+            );
+        }
+        assert_eq!(&EncoderConfig::default(), PB2CNF::global().config());
+    }
+
+    #[test]
+    fn test_template_over_steps() {
+        let pb2cnf = PB2CNF::new();
+        let template = PbConstraint::new(vec![(2, 1), (1, -2), (1, 3)], Comparator::Geq, 2);
+        let var_map = |v: i32, t: usize| 3 * i32::try_from(t).unwrap() + v;
+        let encoding = pb2cnf.encode_template_over_steps(&template, 3, var_map, 10);
+        let constraints = (0..3)
+            .map(|t| {
+                let terms = template
+                    .terms()
+                    .iter()
+                    .map(|(w, l)| (*w, l.signum() * var_map(l.abs(), t)))
+                    .collect();
+                PbConstraint::new(terms, Comparator::Geq, 2)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            Ok(()),
+            reference::check_encoding(&constraints, encoding.clauses(), 9)
+        );
+        assert!(encoding
+            .clauses()
+            .iter()
+            .flatten()
+            .all(|l| l.abs() < encoding.next_free_var_id()));
+        let empty = pb2cnf.encode_template_over_steps(&template, 0, var_map, 10);
+        assert!(empty.clauses().is_empty());
+        assert_eq!(10, empty.next_free_var_id());
+    }
+
+    #[test]
+    fn test_decode_clause_buffer() {
+        assert_eq!(
+            vec![&[1, -2][..], &[][..], &[3][..]],
+            decode_clause_buffer(&[2, 1, -2, 0, 1, 3], 3).unwrap()
+        );
+        assert!(decode_clause_buffer(&[], 0).unwrap().is_empty());
+        assert!(matches!(
+            decode_clause_buffer(&[3, 1, -2], 1),
+            Err(PbError::Ffi(_))
+        ));
+        assert!(matches!(
+            decode_clause_buffer(&[-1, 1], 1),
+            Err(PbError::Ffi(_))
+        ));
+        assert!(matches!(
+            decode_clause_buffer(&[2, 1, 0], 1),
+            Err(PbError::Ffi(_))
+        ));
+        assert!(matches!(
+            decode_clause_buffer(&[1, 1, 1, 2], 1),
+            Err(PbError::Ffi(_))
+        ));
+    }
+
+    #[test]
+    fn test_encode_limits() {
+        let constraint = PbConstraint::new(
+            (1..=10).map(|i| (i, i32::try_from(i).unwrap())).collect(),
+            Comparator::Geq,
+            30,
+        );
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_constraint(&constraint, 11);
+        assert_eq!(
+            encoding,
+            pb2cnf.try_encode_constraint(&constraint, 11).unwrap()
+        );
+        let stats = encoding.stats();
+        let limits = EncodeLimits::default()
+            .with_max_clauses(stats.n_clauses())
+            .with_max_aux_vars(stats.n_aux_vars());
+        let mut clauses = Vec::new();
+        assert_eq!(
+            encoding.next_free_var_id(),
+            pb2cnf
+                .try_encode_constraint_into(&constraint, 11, &limits, &mut clauses)
+                .unwrap()
+        );
+        let pb2cnf = PB2CNF::with_config(
+            EncoderConfig::default()
+                .with_limits(EncodeLimits::default().with_max_clauses(stats.n_clauses() - 1)),
+        );
+        match pb2cnf.try_encode_constraint(&constraint, 11) {
+            Err(PbError::BudgetExceeded(partial)) => {
+                assert_eq!(stats.n_clauses() - 1, partial.n_clauses());
+            }
+            _ => panic!(),
+        }
+        let limits = EncodeLimits::default().with_max_aux_vars(1);
+        let mut clauses = Vec::new();
+        match pb2cnf.try_encode_constraint_into(&constraint, 11, &limits, &mut clauses) {
+            Err(PbError::BudgetExceeded(partial)) => {
+                assert_eq!(clauses.len(), partial.n_clauses());
+                assert!(partial.n_aux_vars() <= 1);
+                assert!(clauses.iter().flatten().all(|l| l.abs() <= 11));
+            }
+            _ => panic!(),
+        }
+        assert_eq!(encoding, pb2cnf.encode_constraint(&constraint, 11));
+        let limits = EncodeLimits::default().with_max_heap_bytes(stats.approx_heap_bytes() - 1);
+        let mut clauses = Vec::new();
+        match pb2cnf.try_encode_constraint_into(&constraint, 11, &limits, &mut clauses) {
+            Err(PbError::BudgetExceeded(partial)) => {
+                assert_eq!(clauses.len(), partial.n_clauses());
+                assert!(partial.approx_heap_bytes() < stats.approx_heap_bytes());
+            }
+            _ => panic!(),
+        }
+        let limits = EncodeLimits::default().with_max_heap_bytes(stats.approx_heap_bytes());
+        assert!(pb2cnf
+            .try_encode_constraint_into(&constraint, 11, &limits, &mut Vec::new())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_invalid_first_aux_var() {
+        let pb2cnf = PB2CNF::new();
+        let constraint = PbConstraint::new(vec![(1, 1), (2, -4), (1, 2)], Comparator::Geq, 2);
+        for first_aux_var in [-1, 0, 2, 4] {
+            let mut clauses = Vec::new();
+            match pb2cnf.try_encode_constraint_into(
+                &constraint,
+                first_aux_var,
+                &EncodeLimits::default(),
+                &mut clauses,
+            ) {
+                Err(PbError::InvalidFirstAuxVar {
+                    first_aux_var: f,
+                    max_var,
+                }) => {
+                    assert_eq!((first_aux_var, 4), (f, max_var));
+                }
+                _ => panic!(),
+            }
+            assert!(clauses.is_empty());
+        }
+        assert_eq!(
+            pb2cnf.encode_constraint(&constraint, 5),
+            pb2cnf.try_encode_constraint(&constraint, 5).unwrap()
+        );
+        let empty = PbConstraint::new(vec![], Comparator::Geq, 0);
+        assert!(pb2cnf.try_encode_constraint(&empty, 0).is_err());
+        assert!(pb2cnf.try_encode_constraint(&empty, 1).is_ok());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let pb2cnf = PB2CNF::new();
+        let limits = EncodeLimits::default();
+        for constraint in [
+            PbConstraint::new(vec![(i64::MAX, 1), (1, 2)], Comparator::Leq, 1),
+            PbConstraint::new(vec![(i64::MIN, 1)], Comparator::Geq, 0),
+            PbConstraint::new(vec![(1, 1)], Comparator::Lt, i64::MIN),
+            PbConstraint::new(vec![(-2, 1), (3, 2)], Comparator::Eq, i64::MAX - 4),
+        ] {
+            let mut clauses = Vec::new();
+            assert!(matches!(
+                pb2cnf.try_encode_constraint_into(&constraint, 3, &limits, &mut clauses),
+                Err(PbError::Overflow(_))
+            ));
+            assert!(clauses.is_empty());
+        }
+        let constraint = PbConstraint::new(vec![(-2, 1), (3, 2)], Comparator::Eq, i64::MAX - 5);
+        assert!(pb2cnf.try_encode_constraint(&constraint, 3).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "integer overflow")]
+    fn test_overflow_encode_constraint() {
+        let constraint = PbConstraint::new(vec![(i64::MAX, 1), (i64::MAX, 2)], Comparator::Geq, 1);
+        let _ = PB2CNF::new().encode_constraint(&constraint, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "integer overflow")]
+    fn test_overflow_encode_geq() {
+        let _ = PB2CNF::new().encode_geq(&[i64::MAX, i64::MAX], &[1, 2], 1, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "integer overflow")]
+    fn test_overflow_encode_leq() {
+        let _ = PB2CNF::new().encode_leq(&[i64::MAX, 1], &[1, 2], 1, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "integer overflow")]
+    fn test_overflow_encode_both() {
+        let _ = PB2CNF::new().encode_both(&[1, 2], &[1, 2], i64::MIN, 1, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "integer overflow")]
+    fn test_overflow_encode_constraint_buffered() {
+        let constraint = PbConstraint::new(vec![(i64::MAX, 1), (i64::MAX, 2)], Comparator::Geq, 1);
+        let _ = PB2CNF::new().encode_constraint_buffered(&constraint, 3);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "invalid first auxiliary variable 2: the constraint involves variable 3"
+    )]
+    fn test_invalid_first_aux_var_encode_at_most_k() {
+        let _ = PB2CNF::new().encode_at_most_k(&[1, 2, 3], 2, 2);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "invalid first auxiliary variable 4: the constraint involves variable 4"
+    )]
+    fn test_invalid_first_aux_var_encode_constraint() {
+        let constraint = PbConstraint::new(vec![(1, 1), (2, -4), (1, 2)], Comparator::Geq, 2);
+        let _ = PB2CNF::new().encode_constraint(&constraint, 4);
+    }
+
+    #[test]
+    fn test_progress() {
+        let constraint = PbConstraint::new(
+            (1..=40).map(|i| (i, i32::try_from(i).unwrap())).collect(),
+            Comparator::Geq,
+            400,
+        );
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_constraint(&constraint, 41);
+        let n_calls = std::cell::Cell::new(0);
+        let mut clauses = Vec::new();
+        let next_free_var_id = pb2cnf
+            .try_encode_constraint_with_progress(
+                &constraint,
+                41,
+                &|p| {
+                    n_calls.set(n_calls.get() + 1);
+                    assert_eq!(n_calls.get() * PROGRESS_INTERVAL, p.n_clauses());
+                    ControlFlow::Continue(())
+                },
+                &mut clauses,
+            )
+            .unwrap();
+        assert_eq!(encoding.next_free_var_id(), next_free_var_id);
+        assert_eq!(encoding.clauses(), clauses);
+        assert_eq!(clauses.len() / PROGRESS_INTERVAL, n_calls.get());
+        assert!(n_calls.get() >= 2);
+        let mut clauses = Vec::new();
+        let result = pb2cnf.try_encode_constraint_with_progress(
+            &constraint,
+            41,
+            &|p| {
+                if p.n_clauses() < 2 * PROGRESS_INTERVAL {
+                    ControlFlow::Continue(())
+                } else {
+                    ControlFlow::Break(())
+                }
+            },
+            &mut clauses,
+        );
+        match result {
+            Err(PbError::Cancelled(stats)) => {
+                assert_eq!(2 * PROGRESS_INTERVAL, stats.n_clauses());
+                assert_eq!(2 * PROGRESS_INTERVAL, clauses.len());
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_empty_literals() {
+        let pb2cnf = PB2CNF::new();
+        let check = |holds: bool, encoding: EncodingResult| {
+            if holds {
+                assert_eq!(EncodingOutcome::TriviallySat, encoding.outcome());
+            } else {
+                assert_eq!(
+                    EncodingOutcome::TriviallyUnsat { empty_clause: true },
+                    encoding.outcome()
+                );
+            }
+            assert_eq!(5, encoding.next_free_var_id());
+        };
+        for k in [-1, 0, 1] {
+            check(k >= 0, pb2cnf.encode_at_most_k(&[], k, 5));
+            check(k <= 0, pb2cnf.encode_at_least_k(&[], k, 5));
+            check(k >= 0, pb2cnf.encode_leq(&[], &[], k, 5));
+            check(k <= 0, pb2cnf.encode_geq(&[], &[], k, 5));
+            check(k == 0, pb2cnf.encode_both(&[], &[], k, k, 5));
+            check(k <= 0, pb2cnf.encode_both(&[], &[], 0, k, 5));
+            for (comparator, holds) in [
+                (Comparator::Leq, k >= 0),
+                (Comparator::Lt, k > 0),
+                (Comparator::Geq, k <= 0),
+                (Comparator::Gt, k < 0),
+                (Comparator::Eq, k == 0),
+            ] {
+                let constraint = PbConstraint::new(vec![], comparator, k);
+                check(holds, pb2cnf.encode_constraint(&constraint, 5));
+                assert_eq!(5, pb2cnf.encode_constraint_buffered(&constraint, 5));
+                let mut clauses = Vec::new();
+                assert_eq!(
+                    usize::from(!holds),
+                    pb2cnf.take_buffered_clauses_into(&mut clauses)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_trivial_bounds() {
+        let pb2cnf = PB2CNF::new();
+        let literals = vec![1, -2, 3];
+        let weights = vec![2, 3, 4];
+        let no_clause: &[Vec<i32>] = &[];
+        let empty_clause: &[Vec<i32>] = &[vec![]];
+        for encoding in [
+            pb2cnf.encode_at_least_k(&literals, 0, 4),
+            pb2cnf.encode_at_least_k(&literals, -1, 4),
+            pb2cnf.encode_at_most_k(&literals, 3, 4),
+            pb2cnf.encode_leq(&weights, &literals, 9, 4),
+            pb2cnf.encode_geq(&weights, &literals, 0, 4),
+            pb2cnf.encode_both(&weights, &literals, 10, -1, 4),
+        ] {
+            assert_eq!(no_clause, encoding.clauses());
+            assert_eq!(4, encoding.next_free_var_id());
+        }
+        for encoding in [
+            pb2cnf.encode_at_least_k(&literals, 4, 4),
+            pb2cnf.encode_at_most_k(&literals, -1, 4),
+            pb2cnf.encode_leq(&weights, &literals, -1, 4),
+            pb2cnf.encode_geq(&weights, &literals, 10, 4),
+            pb2cnf.encode_both(&weights, &literals, 3, 5, 4),
+        ] {
+            assert_eq!(empty_clause, encoding.clauses());
+        }
+        let all_false = [vec![-1], vec![2], vec![-3]];
+        let all_true = [vec![1], vec![-2], vec![3]];
+        assert_eq!(
+            &all_false,
+            pb2cnf.encode_at_most_k(&literals, 0, 4).clauses()
+        );
+        assert_eq!(
+            &all_false,
+            pb2cnf.encode_leq(&weights, &literals, 0, 4).clauses()
+        );
+        assert_eq!(
+            &all_true,
+            pb2cnf.encode_at_least_k(&literals, 3, 4).clauses()
+        );
+        assert_eq!(
+            &all_true,
+            pb2cnf.encode_geq(&weights, &literals, 9, 4).clauses()
+        );
+        let constraint = PbConstraint::new(terms(&weights, &literals).collect(), Comparator::Gt, 8);
+        assert_eq!(
+            &all_true,
+            pb2cnf.encode_constraint(&constraint, 4).clauses()
+        );
+        let constraint = PbConstraint::new(terms(&weights, &literals).collect(), Comparator::Eq, 0);
+        assert_eq!(
+            &all_false,
+            pb2cnf.encode_constraint(&constraint, 4).clauses()
+        );
+    }
+
+    #[test]
+    fn test_empty_clause() {
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_at_least_k(&[1, 2], 3, 3);
+        assert!(encoding.clauses().iter().any(Vec::is_empty));
     }
 
     fn check_models(
@@ -434,6 +2912,7 @@ mod tests {
         let mut models = solver
             .iter()
             .map(|m| {
+                assert!(encoding.is_model(&m));
                 let mut c = m.clone();
                 c.truncate(init_n_vars);
                 c
@@ -448,7 +2927,7 @@ mod tests {
     }
 
     fn check_unsat(encoding: &EncodingResult) {
-        if encoding.clauses().iter().any(Vec::is_empty) {
+        if matches!(encoding.outcome(), EncodingOutcome::TriviallyUnsat { .. }) {
             return;
         }
         let mut solver = match Solver::try_from((Config::default(), encoding.clauses())) {
@@ -481,7 +2960,7 @@ mod tests {
         let weights = vec![8, 4, 2, 1];
         let literals = vec![1, 2, 3, 4];
         let pb2cnf = PB2CNF::new();
-        let encoding = pb2cnf.encode_leq(weights.clone(), literals, 6, 5);
+        let encoding = pb2cnf.encode_leq(&weights, &literals, 6, 5);
         check_models(&encoding, 4, &|m| model_cost(&weights, m) <= 6, 7);
     }
 
@@ -490,7 +2969,7 @@ mod tests {
         let weights = vec![8, 4, 2, 1];
         let literals = vec![1, 2, 3, 4];
         let pb2cnf = PB2CNF::new();
-        let encoding = pb2cnf.encode_geq(weights.clone(), literals, 6, 5);
+        let encoding = pb2cnf.encode_geq(&weights, &literals, 6, 5);
         check_models(&encoding, 4, &|m| model_cost(&weights, m) >= 6, 10);
     }
 
@@ -499,7 +2978,7 @@ mod tests {
         let weights = vec![8, 4, 2, 1];
         let literals = vec![1, 2, 3, 4];
         let pb2cnf = PB2CNF::new();
-        let encoding = pb2cnf.encode_both(weights.clone(), literals, 7, 5, 5);
+        let encoding = pb2cnf.encode_both(&weights, &literals, 7, 5, 5);
         check_models(
             &encoding,
             4,
@@ -513,7 +2992,7 @@ mod tests {
         let weights = vec![8, 4, 2, 1];
         let literals = vec![1, 2, 3, 4];
         let pb2cnf = PB2CNF::new();
-        let encoding = pb2cnf.encode_both(weights.clone(), literals, 5, 7, 5);
+        let encoding = pb2cnf.encode_both(&weights, &literals, 5, 7, 5);
         check_unsat(&encoding);
     }
 
@@ -521,16 +3000,99 @@ mod tests {
     fn test_at_least() {
         let literals = vec![1, 2, 3];
         let pb2cnf = PB2CNF::new();
-        let encoding = pb2cnf.encode_at_least_k(literals, 2, 4);
+        let encoding = pb2cnf.encode_at_least_k(&literals, 2, 4);
         let weights = vec![1; 3];
         check_models(&encoding, 3, &|m| model_cost(&weights, m) >= 2, 4);
     }
 
+    #[test]
+    fn test_constraint_eq() {
+        let weights = vec![8, 4, 2, 1];
+        let constraint = PbConstraint::new(vec![(8, 1), (4, 2), (2, 3), (1, 4)], Comparator::Eq, 6);
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_constraint(&constraint, 5);
+        check_models(&encoding, 4, &|m| model_cost(&weights, m) == 6, 1);
+    }
+
+    #[test]
+    fn test_constraint_negated_extreme_bound() {
+        let constraint = PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Geq, i64::MIN);
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_constraint(&constraint.negate().unwrap(), 3);
+        assert!(encoding.clauses().iter().any(Vec::is_empty));
+    }
+
+    #[test]
+    fn test_objective_bound_loop() {
+        // minimize 3*x1 + 2*x2 + 1*x3 subject to x1 + x2 + x3 >= 2
+        let objective = [(3, 1), (2, 2), (1, 3)];
+        let weights = vec![3, 2, 1];
+        let pb2cnf = PB2CNF::new();
+        let mut formula = CnfFormula::new();
+        formula.add_encoding(&pb2cnf.encode_at_least_k(&[1, 2, 3], 2, 4));
+        let mut best_cost = None;
+        loop {
+            let mut bounded = formula.clone();
+            if let Some(cost) = best_cost {
+                let bound =
+                    pb2cnf.encode_objective_bound(&objective, cost - 1, formula.next_free_var_id());
+                bounded.add_encoding(&bound);
+            }
+            let Ok(mut solver) = Solver::try_from((Config::default(), bounded.clauses())) else {
+                break;
+            };
+            match solver.solve() {
+                Ok(Certificate::SAT(m)) => best_cost = Some(model_cost(&weights, &m[..3])),
+                _ => break,
+            }
+        }
+        assert_eq!(Some(3), best_cost);
+    }
+
+    #[test]
+    fn test_with_config() {
+        let weights = vec![8, 4, 2, 1];
+        let literals = vec![1, 2, 3, 4];
+        for pb_encoder in [
+            PbEncoder::Bdd,
+            PbEncoder::SortingNetworks,
+            PbEncoder::Adder,
+            PbEncoder::BinaryMerge,
+            PbEncoder::Auto,
+        ] {
+            let pb2cnf = PB2CNF::with_config(EncoderConfig::default().with_pb_encoder(pb_encoder));
+            let encoding = pb2cnf.encode_geq(&weights, &literals, 7, 5);
+            check_models(&encoding, 4, &|m| model_cost(&weights, m) >= 7, 9);
+        }
+    }
+
+    #[test]
+    fn test_auto_follows_cost_model() {
+        let weights = vec![8, 4, 2, 1];
+        let literals = vec![1, 2, 3, 4];
+        for (cost_model, pb_encoder) in [
+            (CostModel::default().with_bdd_factor(1), PbEncoder::Bdd),
+            (CostModel::default().with_adder_factor(1), PbEncoder::Adder),
+        ] {
+            let auto = PB2CNF::with_config(
+                EncoderConfig::default()
+                    .with_pb_encoder(PbEncoder::Auto)
+                    .with_cost_model(cost_model),
+            );
+            let expected =
+                PB2CNF::with_config(EncoderConfig::default().with_pb_encoder(pb_encoder));
+            assert_eq!(
+                expected.encode_leq(&weights, &literals, 6, 5),
+                auto.encode_leq(&weights, &literals, 6, 5)
+            );
+        }
+    }
+
     #[test]
     fn test_at_most() {
         let literals = vec![1, 2, 3];
         let pb2cnf = PB2CNF::new();
-        let encoding = pb2cnf.encode_at_most_k(literals, 2, 4);
+        let encoding = pb2cnf.encode_at_most_k(&literals, 2, 4);
         let weights = vec![1; 3];
         check_models(&encoding, 3, &|m| model_cost(&weights, m) <= 2, 7);
     }
@@ -0,0 +1,191 @@
+use crate::{ClauseSink, PbError};
+use std::io::Write;
+
+pub(crate) struct DimacsContent {
+    pub(crate) n_vars: usize,
+    pub(crate) clauses: Vec<Vec<i32>>,
+}
+
+pub(crate) fn read_cnf(content: &str) -> Result<DimacsContent, PbError> {
+    let mut header_n_vars = None;
+    let mut clauses = Vec::new();
+    let mut current_clause = Vec::new();
+    let mut last_line = 1;
+    for (i, line) in content.lines().enumerate() {
+        last_line = i + 1;
+        let line = line.trim();
+        if line.starts_with('c') || line.is_empty() {
+            continue;
+        }
+        if line.starts_with('%') {
+            break;
+        }
+        if let Some(header) = line.strip_prefix('p') {
+            if header_n_vars.is_some() || !clauses.is_empty() || !current_clause.is_empty() {
+                return Err(parse_error(last_line, "unexpected header"));
+            }
+            header_n_vars = Some(read_header(header, last_line)?);
+            continue;
+        }
+        for word in line.split_whitespace() {
+            let literal = word
+                .parse::<i32>()
+                .map_err(|_| parse_error(last_line, &format!("invalid literal {word}")))?;
+            if literal == 0 {
+                clauses.push(std::mem::take(&mut current_clause));
+            } else {
+                current_clause.push(literal);
+            }
+        }
+    }
+    if !current_clause.is_empty() {
+        return Err(parse_error(
+            last_line,
+            "missing 0 at the end of the last clause",
+        ));
+    }
+    Ok(DimacsContent {
+        n_vars: header_n_vars.unwrap_or_default(),
+        clauses,
+    })
+}
+
+fn read_header(header: &str, line: usize) -> Result<usize, PbError> {
+    let words = header.split_whitespace().collect::<Vec<_>>();
+    match words[..] {
+        ["cnf", n_vars, n_clauses] if n_clauses.parse::<usize>().is_ok() => n_vars
+            .parse()
+            .map_err(|_| parse_error(line, &format!("invalid number of variables {n_vars}"))),
+        _ => Err(parse_error(
+            line,
+            "expected a \"p cnf <vars> <clauses>\" header",
+        )),
+    }
+}
+
+fn parse_error(line: usize, message: &str) -> PbError {
+    PbError::Parse {
+        line,
+        message: message.to_string(),
+    }
+}
+
+pub(crate) fn write_header<W: Write>(
+    writer: &mut W,
+    n_vars: usize,
+    n_clauses: usize,
+) -> std::io::Result<()> {
+    writeln!(writer, "p cnf {n_vars} {n_clauses}")
+}
+
+/// Writes the `c ind` lines giving the variables on which the models must be projected, ten variables per line.
+pub(crate) fn write_independent_support<W: Write>(
+    writer: &mut W,
+    vars: &[i32],
+) -> std::io::Result<()> {
+    for chunk in vars.chunks(10) {
+        write!(writer, "c ind ")?;
+        write_clause(writer, chunk)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_clauses<'a, W, I>(writer: &mut W, clauses: I) -> std::io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Vec<i32>>,
+{
+    for clause in clauses {
+        write_clause(writer, clause)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_wcnf<W: Write>(
+    writer: &mut W,
+    hard_clauses: &[Vec<i32>],
+    objective: &[(i64, i32)],
+) -> std::io::Result<()> {
+    for clause in hard_clauses {
+        write!(writer, "h ")?;
+        write_clause(writer, clause)?;
+    }
+    for &(weight, literal) in objective.iter().filter(|t| t.0 != 0) {
+        if weight > 0 {
+            writeln!(writer, "{weight} {} 0", -literal)?;
+        } else {
+            writeln!(writer, "{} {literal} 0", -weight)?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn write_clause<W: Write>(writer: &mut W, clause: &[i32]) -> std::io::Result<()> {
+    for l in clause {
+        write!(writer, "{l} ")?;
+    }
+    writeln!(writer, "0")
+}
+
+/// A [`ClauseSink`] writing the clauses in the DIMACS format as they are received.
+///
+/// Since sinks cannot fail, the first I/O error is kept and the next clauses are ignored.
+pub(crate) struct DimacsClauseWriter<'a, W> {
+    writer: &'a mut W,
+    n_clauses: usize,
+    error: Option<std::io::Error>,
+}
+
+impl<'a, W: Write> DimacsClauseWriter<'a, W> {
+    pub(crate) fn new(writer: &'a mut W) -> Self {
+        Self {
+            writer,
+            n_clauses: 0,
+            error: None,
+        }
+    }
+
+    /// Returns the number of clauses written, or the first error that occurred.
+    pub(crate) fn finish(self) -> std::io::Result<usize> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.n_clauses),
+        }
+    }
+}
+
+impl<W: Write> ClauseSink for DimacsClauseWriter<'_, W> {
+    fn add_clause(&mut self, clause: &[i32]) {
+        if self.error.is_none() {
+            match write_clause(self.writer, clause) {
+                Ok(()) => self.n_clauses += 1,
+                Err(e) => self.error = Some(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_cnf() {
+        let content = read_cnf("c comment\np cnf 5 3\n1 -2 0 3\n0\n\n-4 0\n%\n0\n").unwrap();
+        assert_eq!(5, content.n_vars);
+        assert_eq!(vec![vec![1, -2], vec![3], vec![-4]], content.clauses);
+    }
+
+    #[test]
+    fn test_read_cnf_errors() {
+        let assert_error_line = |cnf: &str, expected_line: usize| match read_cnf(cnf) {
+            Err(PbError::Parse { line, .. }) => assert_eq!(expected_line, line),
+            _ => panic!("expected a parse error for {cnf:?}"),
+        };
+        assert_error_line("p cnf 1\n1 0\n", 1);
+        assert_error_line("p cnf 1 1\n1 a 0\n", 2);
+        assert_error_line("p cnf 1 1\n1 0\np cnf 1 1\n", 3);
+        assert_error_line("p dnf 1 1\n", 1);
+        assert_error_line("p cnf 2 1\n1 2\n", 2);
+    }
+}
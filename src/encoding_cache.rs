@@ -0,0 +1,209 @@
+use crate::{
+    encoding_result::EncodingResultBuilder, encoding_template::EncodingTemplate, ClauseSink,
+    Comparator, EncodingResult, PbConstraint, PB2CNF,
+};
+use std::collections::HashMap;
+
+/// A memoization cache for the encoding of structurally identical constraints.
+///
+/// Many instances contain a lot of constraints that only differ by their literals, e.g. the same cardinality constraint applied to different sets of variables.
+/// This structure encodes each constraint shape (weights, comparator and right hand side) once, on placeholder variables, and keeps the result.
+/// Next constraints with the same shape are encoded by substituting the literals in this template and shifting its auxiliary variables, instead of running the encoder again.
+///
+/// The encodings returned by the cache are equivalent to the ones computed by the underlying [`PB2CNF`], but not always identical:
+/// pblib simplifies some constraints depending on their literals (e.g. when a variable appears twice), while the cache reuses the encoding of the general shape.
+///
+/// ```
+/// use pblib_rs::{Comparator, EncodingCache, PbConstraint, PB2CNF};
+///
+/// let mut cache = EncodingCache::new(PB2CNF::new());
+/// let mut next_free_var_id = 10;
+/// for literals in [[1, 2, 3], [4, 5, 6], [7, 8, 9]] {
+///     // at most one of the literals is true
+///     let terms = literals.iter().map(|l| (1, *l)).collect();
+///     let constraint = PbConstraint::new(terms, Comparator::Leq, 1);
+///     let encoding = cache.encode_constraint(&constraint, next_free_var_id);
+///     next_free_var_id = encoding.next_free_var_id();
+/// }
+/// assert_eq!(1, cache.n_misses());
+/// assert_eq!(2, cache.n_hits());
+/// ```
+pub struct EncodingCache {
+    pb2cnf: PB2CNF,
+    templates: HashMap<(Vec<i64>, Comparator, i64), EncodingTemplate>,
+    n_hits: usize,
+    n_misses: usize,
+}
+
+impl EncodingCache {
+    /// Builds a new, empty cache upon an encoder.
+    #[must_use]
+    pub fn new(pb2cnf: PB2CNF) -> Self {
+        Self {
+            pb2cnf,
+            templates: HashMap::new(),
+            n_hits: 0,
+            n_misses: 0,
+        }
+    }
+
+    /// Returns the encoder used to compute the templates.
+    #[must_use]
+    pub fn pb2cnf(&self) -> &PB2CNF {
+        &self.pb2cnf
+    }
+
+    /// Encodes a [`PbConstraint`], reusing the encoding of a previous constraint with the same shape if possible.
+    ///
+    /// See [`PB2CNF::encode_constraint`] for more information on the parameters and the return type.
+    #[must_use]
+    pub fn encode_constraint(
+        &mut self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+    ) -> EncodingResult {
+        let mut builder = EncodingResultBuilder::default();
+        let next_free_var_id = self.encode_constraint_into(constraint, first_aux_var, &mut builder);
+        self.pb2cnf
+            .build_result(builder, first_aux_var, next_free_var_id)
+    }
+
+    /// Encodes a [`PbConstraint`], sending the clauses to a [`ClauseSink`].
+    ///
+    /// This function behaves like [`encode_constraint`](Self::encode_constraint), except that the clauses are given one by one to the sink instead of being collected.
+    /// It returns the next free variable id.
+    pub fn encode_constraint_into(
+        &mut self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        let key = (
+            constraint.weights(),
+            constraint.comparator(),
+            constraint.rhs(),
+        );
+        let template = match self.templates.entry(key) {
+            std::collections::hash_map::Entry::Occupied(e) => {
+                self.n_hits += 1;
+                e.into_mut()
+            }
+            std::collections::hash_map::Entry::Vacant(e) => {
+                self.n_misses += 1;
+                let (weights, comparator, rhs) = e.key();
+                let template = EncodingTemplate::new(&self.pb2cnf, weights, *comparator, *rhs);
+                e.insert(template)
+            }
+        };
+        template.instantiate_into(&constraint.literals(), first_aux_var, sink)
+    }
+
+    /// Returns the number of constraints whose encoding was computed from a cached template.
+    #[must_use]
+    pub fn n_hits(&self) -> usize {
+        self.n_hits
+    }
+
+    /// Returns the number of constraints that required to run the encoder.
+    #[must_use]
+    pub fn n_misses(&self) -> usize {
+        self.n_misses
+    }
+
+    /// Returns the number of constraint shapes in the cache.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Returns `true` if and only if the cache contains no constraint shape.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    /// Removes all the templates from the cache, and resets the hit and miss counters.
+    pub fn clear(&mut self) {
+        self.templates.clear();
+        self.n_hits = 0;
+        self.n_misses = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MiniSat;
+
+    fn check_equivalent(constraint: &PbConstraint, n_vars: i32, encoding: &EncodingResult) {
+        let mut solver = MiniSat::new();
+        encoding.iter().for_each(|c| solver.add_clause(c));
+        for bits in 0..1_u32 << n_vars {
+            let assignment = (1..=n_vars)
+                .map(|v| if bits & (1 << (v - 1)) == 0 { -v } else { v })
+                .collect::<Vec<_>>();
+            let sum = constraint
+                .terms()
+                .iter()
+                .filter(|(_, l)| assignment.contains(l))
+                .map(|(w, _)| w)
+                .sum::<i64>();
+            let expected = match constraint.comparator() {
+                Comparator::Leq => sum <= constraint.rhs(),
+                Comparator::Geq => sum >= constraint.rhs(),
+                Comparator::Eq => sum == constraint.rhs(),
+                Comparator::Lt => sum < constraint.rhs(),
+                Comparator::Gt => sum > constraint.rhs(),
+            };
+            assert_eq!(expected, solver.solve_with_assumptions(&assignment));
+        }
+    }
+
+    #[test]
+    fn test_cached_encodings_are_correct() {
+        let mut cache = EncodingCache::new(PB2CNF::new());
+        let mut next_free_var_id = 7;
+        for literals in [[1, 2, 3, 4], [-5, 6, -1, 2], [3, 3, -4, 5], [6, 5, 4, 3]] {
+            let terms = [3, 2, 2, 1].into_iter().zip(literals).collect();
+            let constraint = PbConstraint::new(terms, Comparator::Geq, 4);
+            let encoding = cache.encode_constraint(&constraint, next_free_var_id);
+            assert_eq!(next_free_var_id, encoding.first_aux_var());
+            assert!(encoding.iter().flatten().all(|l| l.abs() <= 6
+                || (l.abs() >= next_free_var_id && l.abs() < encoding.next_free_var_id())));
+            check_equivalent(&constraint, 6, &encoding);
+            next_free_var_id = encoding.next_free_var_id();
+        }
+        assert_eq!(1, cache.len());
+        assert_eq!(1, cache.n_misses());
+        assert_eq!(3, cache.n_hits());
+    }
+
+    #[test]
+    fn test_distinct_shapes() {
+        let mut cache = EncodingCache::new(PB2CNF::new());
+        let constraints = [
+            PbConstraint::new(vec![(1, 1), (1, 2), (1, 3)], Comparator::Leq, 1),
+            PbConstraint::new(vec![(1, 1), (1, 2), (1, 3)], Comparator::Leq, 2),
+            PbConstraint::new(vec![(1, 1), (1, 2), (1, 3)], Comparator::Eq, 2),
+            PbConstraint::new(vec![(1, 1), (2, 2), (1, 3)], Comparator::Leq, 2),
+        ];
+        for c in &constraints {
+            let _ = cache.encode_constraint(c, 4);
+        }
+        assert_eq!(4, cache.len());
+        assert_eq!(0, cache.n_hits());
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(0, cache.n_misses());
+    }
+
+    #[test]
+    fn test_matches_direct_encoding_on_placeholders() {
+        let pb2cnf = PB2CNF::new();
+        let constraint =
+            PbConstraint::new(vec![(8, 1), (4, 2), (2, 3), (1, 4)], Comparator::Geq, 6);
+        let direct = pb2cnf.encode_constraint(&constraint, 5);
+        let mut cache = EncodingCache::new(pb2cnf);
+        assert_eq!(direct, cache.encode_constraint(&constraint, 5));
+    }
+}
@@ -0,0 +1,484 @@
+use crate::{
+    cpblib::IncrementalEncoding, BoolExpr, CnfFormula, Comparator, EncodeLimits, EncoderConfig,
+    PbConstraint, PbError, SymbolTable, VarPool, PB2CNF,
+};
+use std::collections::HashMap;
+
+/// A context gathering the encodings of several constraints into a single formula.
+///
+/// A context is built given the number of variables of the problem; more problem variables can be allocated later with [`new_var`](Self::new_var).
+/// The variables are handled by a [`VarPool`], so each constraint gets its auxiliary variables after the ones in use, and the caller never has to compute the `first_aux_var` parameter of the encoding functions.
+/// The constraints must only involve the problem variables: the other variables are reserved for the auxiliary ones.
+///
+/// When structure sharing is enabled in the configuration (see [`EncoderConfig::with_structure_sharing`]), the inequalities over the same weighted literals reuse the auxiliary structure of the first one, and only add the clauses enforcing their own bound.
+///
+/// ```
+/// use pblib_rs::{EncodingContext, PbConstraint};
+///
+/// let mut ctx = EncodingContext::new(4);
+/// PbConstraint::weighted([(8, 1), (4, 2), (2, 3), (1, 4)])
+///     .geq(6)
+///     .encode(&mut ctx)
+///     .unwrap();
+/// PbConstraint::cardinality([1, 2, 3]).leq(1).encode(&mut ctx).unwrap();
+/// let x5 = ctx.new_var();
+/// PbConstraint::cardinality([-4, x5]).geq(1).encode(&mut ctx).unwrap();
+/// let formula = ctx.into_formula();
+/// let mut output = Vec::new();
+/// formula.write_dimacs(&mut output).unwrap();
+/// ```
+pub struct EncodingContext {
+    pb2cnf: PB2CNF,
+    formula: CnfFormula,
+    var_pool: VarPool,
+    symbols: SymbolTable,
+    shared: HashMap<(Vec<(i64, i32)>, Comparator), IncrementalEncoding>,
+    n_shared: usize,
+}
+
+impl EncodingContext {
+    /// Builds a new context for a problem involving `n_vars` variables, using the default encoder configuration.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the underlying C++ objects cannot be allocated.
+    #[must_use]
+    pub fn new(n_vars: usize) -> Self {
+        Self::with_config(n_vars, &EncoderConfig::default())
+    }
+
+    /// Builds a new context for a problem involving `n_vars` variables, using the given encoder configuration.
+    ///
+    /// The limits of the configuration (see [`EncoderConfig::with_limits`]) apply to each encoded constraint.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the underlying C++ objects cannot be allocated.
+    #[must_use]
+    pub fn with_config(n_vars: usize, config: &EncoderConfig) -> Self {
+        let mut var_pool = VarPool::new();
+        var_pool.new_vars(n_vars);
+        let mut formula = CnfFormula::new();
+        formula.declare_vars(n_vars);
+        Self {
+            pb2cnf: PB2CNF::with_config(*config),
+            formula,
+            var_pool,
+            symbols: SymbolTable::new(),
+            shared: HashMap::new(),
+            n_shared: 0,
+        }
+    }
+
+    /// Allocates a new problem variable.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn new_var(&mut self) -> i32 {
+        let var = self.var_pool.new_var();
+        self.formula.declare_vars(self.var_pool.n_vars());
+        var
+    }
+
+    /// Returns the problem variable associated with a name, allocating a new problem variable if the name is unknown.
+    ///
+    /// The names are recorded in the [`symbols`](Self::symbols) table of the context.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the name is invalid (see [`SymbolTable`]), or if the number of variables does not fit in an `i32`.
+    pub fn named_var(&mut self, name: &str) -> i32 {
+        if let Some(var) = self.symbols.get(name) {
+            return var;
+        }
+        let var = self.new_var();
+        self.symbols.insert(name, var);
+        var
+    }
+
+    /// Allocates `n` new problem variables.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn new_vars(&mut self, n: usize) -> Vec<i32> {
+        let vars = self.var_pool.new_vars(n);
+        self.formula.declare_vars(self.var_pool.n_vars());
+        vars
+    }
+
+    /// Encodes a constraint, adding its clauses to the formula.
+    ///
+    /// The auxiliary variables are allocated by the variable pool of the context.
+    /// In case of error, the formula is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::UndeclaredVar`] error is returned if the constraint involves a variable that is not a problem variable.
+    /// The errors of [`PB2CNF::try_encode_constraint_into`] are also returned.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn encode(&mut self, constraint: &PbConstraint) -> Result<(), PbError> {
+        self.check_vars(constraint.terms().iter().map(|(_, l)| *l))?;
+        let mut clauses = Vec::new();
+        let limits = self.pb2cnf.config().limits();
+        let next_free_var_id = match shared_direction(constraint) {
+            Some(direction) if self.pb2cnf.config().structure_sharing() => {
+                self.encode_shared(constraint, direction, &limits, &mut clauses)?
+            }
+            _ => self.pb2cnf.try_encode_constraint_into(
+                constraint,
+                self.var_pool.first_aux_var(),
+                &limits,
+                &mut clauses,
+            )?,
+        };
+        self.var_pool.commit_aux_vars(next_free_var_id);
+        self.formula.declare_vars(self.var_pool.n_vars());
+        for c in &clauses {
+            self.formula.add_clause(c);
+        }
+        Ok(())
+    }
+
+    fn encode_shared(
+        &mut self,
+        constraint: &PbConstraint,
+        direction: Comparator,
+        limits: &EncodeLimits,
+        clauses: &mut Vec<Vec<i32>>,
+    ) -> Result<i32, PbError> {
+        let mut terms = constraint.terms().to_vec();
+        terms.sort_unstable();
+        let key = (terms, direction);
+        let first_aux_var = self.var_pool.first_aux_var();
+        if let Some(encoding) = self.shared.get_mut(&key) {
+            let result =
+                encoding.try_encode_new_bound_into(constraint, first_aux_var, limits, clauses);
+            match result {
+                Ok(_) => self.n_shared += 1,
+                Err(PbError::BudgetExceeded(_)) => {
+                    self.shared.remove(&key);
+                }
+                Err(_) => {}
+            }
+            return result;
+        }
+        let (encoding, next_free_var_id) =
+            self.pb2cnf
+                .try_encode_incremental_into(constraint, first_aux_var, limits, clauses)?;
+        if let Some(encoding) = encoding {
+            self.shared.insert(key, encoding);
+        }
+        Ok(next_free_var_id)
+    }
+
+    /// Adds a clause over the problem variables to the formula.
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::UndeclaredVar`] error is returned if the clause involves a variable that is not a problem variable.
+    pub fn add_clause(&mut self, clause: &[i32]) -> Result<(), PbError> {
+        self.check_vars(clause.iter().copied())?;
+        self.formula.add_clause(clause);
+        Ok(())
+    }
+
+    /// Adds a propositional formula over the problem variables, using the Tseitin transformation.
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::UndeclaredVar`] error is returned if the formula involves a variable that is not a problem variable.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn add_expr(&mut self, expr: &BoolExpr) -> Result<(), PbError> {
+        self.check_vars(expr.literals().into_iter())?;
+        if let BoolExpr::And(es) = expr {
+            return es.iter().try_for_each(|e| self.add_expr(e));
+        }
+        let mut clauses = vec![];
+        let l = expr.encode_into(&mut self.var_pool, &mut clauses);
+        clauses.push(vec![l]);
+        self.add_definition_clauses(&clauses);
+        Ok(())
+    }
+
+    /// Allocates a new problem variable, and adds clauses stating it is equivalent to a propositional formula.
+    ///
+    /// Since the returned variable is a problem variable, it can be used in the constraints and formulas given to this context.
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::UndeclaredVar`] error is returned if the formula involves a variable that is not a problem variable.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn define(&mut self, expr: &BoolExpr) -> Result<i32, PbError> {
+        self.check_vars(expr.literals().into_iter())?;
+        let var = self.var_pool.new_var();
+        let mut clauses = vec![];
+        expr.define_into(var, &mut self.var_pool, &mut clauses);
+        self.add_definition_clauses(&clauses);
+        Ok(var)
+    }
+
+    fn add_definition_clauses(&mut self, clauses: &[Vec<i32>]) {
+        self.formula.declare_vars(self.var_pool.n_vars());
+        for c in clauses {
+            self.formula.add_clause(c);
+        }
+    }
+
+    fn check_vars(&self, mut literals: impl Iterator<Item = i32>) -> Result<(), PbError> {
+        match literals.find(|l| *l == i32::MIN || !self.var_pool.is_problem_var(l.abs())) {
+            Some(l) => Err(PbError::UndeclaredVar(l)),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the number of problem variables.
+    #[must_use]
+    pub fn n_problem_vars(&self) -> usize {
+        self.var_pool.n_problem_vars()
+    }
+
+    /// Returns the number of constraints encoded by reusing the structure of a previous constraint over the same terms.
+    ///
+    /// See [`EncoderConfig::with_structure_sharing`].
+    #[must_use]
+    pub fn n_shared(&self) -> usize {
+        self.n_shared
+    }
+
+    /// Returns the table giving the names of the variables allocated by [`named_var`](Self::named_var).
+    #[must_use]
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// Returns the variable pool of this context.
+    #[must_use]
+    pub fn var_pool(&self) -> &VarPool {
+        &self.var_pool
+    }
+
+    /// Returns the formula built so far.
+    #[must_use]
+    pub fn formula(&self) -> &CnfFormula {
+        &self.formula
+    }
+
+    /// Consumes this context and returns its formula.
+    #[must_use]
+    pub fn into_formula(self) -> CnfFormula {
+        self.formula
+    }
+}
+
+/// Returns the direction of the inequalities whose structure can be shared with this constraint, if any.
+fn shared_direction(constraint: &PbConstraint) -> Option<Comparator> {
+    match constraint.comparator() {
+        Comparator::Leq | Comparator::Lt => Some(Comparator::Leq),
+        Comparator::Geq | Comparator::Gt => Some(Comparator::Geq),
+        Comparator::Eq => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference;
+
+    #[test]
+    fn test_encode() {
+        let constraints = [
+            PbConstraint::weighted([(8, 1), (4, 2), (2, 3), (1, 4)]).geq(6),
+            PbConstraint::cardinality([1, 2, 3]).leq(1),
+            PbConstraint::cardinality([-1, 4]).eq(1),
+        ];
+        let mut ctx = EncodingContext::new(4);
+        for c in &constraints {
+            c.encode(&mut ctx).unwrap();
+        }
+        assert_eq!(4, ctx.n_problem_vars());
+        assert!(ctx.formula().n_vars() > 4);
+        assert_eq!(
+            Ok(()),
+            reference::check_encoding(&constraints, ctx.formula().clauses(), 4)
+        );
+    }
+
+    #[test]
+    fn test_undeclared_vars() {
+        let mut ctx = EncodingContext::new(2);
+        PbConstraint::cardinality([1, 2])
+            .leq(1)
+            .encode(&mut ctx)
+            .unwrap();
+        let formula = ctx.formula().clone();
+        assert!(matches!(
+            PbConstraint::cardinality([1, -3]).leq(1).encode(&mut ctx),
+            Err(PbError::UndeclaredVar(-3))
+        ));
+        assert!(matches!(
+            ctx.add_clause(&[1, 0]),
+            Err(PbError::UndeclaredVar(0))
+        ));
+        assert_eq!(&formula, ctx.formula());
+        ctx.add_clause(&[-1, 2]).unwrap();
+        assert_eq!(formula.n_clauses() + 1, ctx.formula().n_clauses());
+        let mut ctx = EncodingContext::new(4);
+        PbConstraint::cardinality([1, 2, 3, 4])
+            .eq(2)
+            .encode(&mut ctx)
+            .unwrap();
+        let aux_var = i32::try_from(ctx.formula().n_vars()).unwrap();
+        assert!(ctx.var_pool().is_aux_var(aux_var));
+        assert!(matches!(
+            ctx.add_clause(&[aux_var]),
+            Err(PbError::UndeclaredVar(v)) if v == aux_var
+        ));
+    }
+
+    #[test]
+    fn test_expr() {
+        let x = BoolExpr::lit;
+        let mut ctx = EncodingContext::new(3);
+        let y = ctx.define(&(x(1) & !x(2))).unwrap();
+        assert_eq!(4, y);
+        ctx.add_expr(&(BoolExpr::implies(x(3), x(y)) & (x(1) | x(2))))
+            .unwrap();
+        PbConstraint::cardinality([2, 3, y])
+            .geq(1)
+            .encode(&mut ctx)
+            .unwrap();
+        assert!(matches!(
+            ctx.add_expr(&(x(1) | x(5))),
+            Err(PbError::UndeclaredVar(5))
+        ));
+        let expected = [
+            BoolExpr::implies(x(3), x(1) & !x(2)),
+            x(1) | x(2),
+            x(2) | x(3) | (x(1) & !x(2)),
+        ];
+        let formula = ctx.formula();
+        for i in 0..8 {
+            let assignment = (1..=3)
+                .map(|v| if i & (1 << (v - 1)) == 0 { -v } else { v })
+                .collect::<Vec<_>>();
+            let mut solver = crate::MiniSat::new();
+            formula.clauses().iter().for_each(|c| solver.add_clause(c));
+            assert_eq!(
+                expected.iter().all(|e| e.evaluate(&assignment)),
+                solver.solve_with_assumptions(&assignment)
+            );
+        }
+    }
+
+    #[test]
+    fn test_named_var() {
+        let mut ctx = EncodingContext::new(1);
+        let a = ctx.named_var("a");
+        let b = ctx.named_var("b");
+        assert_eq!((2, 3), (a, b));
+        assert_eq!(a, ctx.named_var("a"));
+        let constraint = PbConstraint::weighted([(2, a), (1, -b), (1, 1)]).geq(2);
+        constraint.encode(&mut ctx).unwrap();
+        assert_eq!(
+            "+2 a +1 ~b +1 x1 >= 2",
+            ctx.symbols().format_constraint(&constraint)
+        );
+        assert_eq!(3, ctx.n_problem_vars());
+    }
+
+    #[test]
+    fn test_new_var() {
+        let mut ctx = EncodingContext::new(2);
+        PbConstraint::cardinality([1, 2])
+            .eq(1)
+            .encode(&mut ctx)
+            .unwrap();
+        let n_vars = i32::try_from(ctx.formula().n_vars()).unwrap();
+        let x = ctx.new_vars(2);
+        assert_eq!(vec![n_vars + 1, n_vars + 2], x);
+        assert_eq!(n_vars + 2, i32::try_from(ctx.formula().n_vars()).unwrap());
+        assert_eq!(4, ctx.n_problem_vars());
+        let constraints = [
+            PbConstraint::cardinality([1, 2]).eq(1),
+            PbConstraint::weighted([(2, 1), (1, x[0]), (1, x[1])]).geq(2),
+        ];
+        constraints[1].encode(&mut ctx).unwrap();
+        // swap the new problem variables with 3 and 4 to use the reference checker
+        let rename = |l: i32| {
+            let v = match l.abs() {
+                3 => x[0],
+                4 => x[1],
+                v if v == x[0] => 3,
+                v if v == x[1] => 4,
+                v => v,
+            };
+            v * l.signum()
+        };
+        let clauses = ctx
+            .formula()
+            .clauses()
+            .iter()
+            .map(|c| c.iter().map(|l| rename(*l)).collect())
+            .collect::<Vec<_>>();
+        let constraints = constraints.map(|c| {
+            PbConstraint::new(
+                c.terms().iter().map(|(w, l)| (*w, rename(*l))).collect(),
+                c.comparator(),
+                c.rhs(),
+            )
+        });
+        assert_eq!(Ok(()), reference::check_encoding(&constraints, &clauses, 4));
+    }
+
+    #[test]
+    fn test_structure_sharing() {
+        let weighted = PbConstraint::weighted([(3, 1), (2, -2), (2, 3), (1, 4), (4, 5)]);
+        let cardinality = PbConstraint::cardinality([1, 2, -3, 4, 5]);
+        let constraints = [
+            weighted.clone().leq(9),
+            cardinality.clone().geq(1),
+            weighted.clone().lt(7),
+            cardinality.clone().leq(3),
+            weighted.clone().leq(8),
+            cardinality.clone().gt(2),
+            cardinality.clone().leq(1),
+            weighted.clone().leq(2),
+            weighted.clone().eq(2),
+        ];
+        for pb_encoder in [
+            crate::PbEncoder::Best,
+            crate::PbEncoder::Adder,
+            crate::PbEncoder::Swc,
+        ] {
+            let config = EncoderConfig::default().with_pb_encoder(pb_encoder);
+            let mut ctx = EncodingContext::with_config(5, &config);
+            let mut shared_ctx =
+                EncodingContext::with_config(5, &config.with_structure_sharing(true));
+            for c in &constraints {
+                c.encode(&mut ctx).unwrap();
+                c.encode(&mut shared_ctx).unwrap();
+            }
+            assert_eq!(0, ctx.n_shared());
+            assert_eq!(5, shared_ctx.n_shared());
+            if pb_encoder != crate::PbEncoder::Best {
+                assert!(shared_ctx.var_pool().n_aux_vars() < ctx.var_pool().n_aux_vars());
+                assert!(shared_ctx.formula().n_clauses() < ctx.formula().n_clauses());
+            }
+            assert_eq!(
+                Ok(()),
+                reference::check_encoding(&constraints, shared_ctx.formula().clauses(), 5)
+            );
+        }
+    }
+}
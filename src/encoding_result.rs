@@ -0,0 +1,953 @@
+use crate::{assignment, dimacs, simplify, ClauseSink};
+use std::{collections::HashSet, fmt::Display, io::Write};
+
+/// The result of an encoding function.
+///
+/// This structure contains both the clauses generated to encode the constraint and the index of the next free variable id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodingResult {
+    clauses: Vec<Vec<i32>>,
+    first_aux_var: i32,
+    next_free_var_id: i32,
+    stats: EncodingStats,
+}
+
+impl EncodingResult {
+    pub(crate) fn new(
+        clauses: Vec<Vec<i32>>,
+        first_aux_var: i32,
+        next_free_var_id: i32,
+        stats: EncodingStats,
+    ) -> Self {
+        Self {
+            clauses,
+            first_aux_var,
+            next_free_var_id,
+            stats,
+        }
+    }
+
+    /// Returns a reference to the clauses used to encode the constraint.
+    #[must_use]
+    pub fn clauses(&self) -> &[Vec<i32>] {
+        &self.clauses
+    }
+
+    /// Returns the first variable id that was allowed to be used as an auxiliary variable by the encoding.
+    ///
+    /// This is the value of the `first_aux_var` parameter given to the encoding function.
+    #[must_use]
+    pub fn first_aux_var(&self) -> i32 {
+        self.first_aux_var
+    }
+
+    /// Returns the next free variable id.
+    ///
+    /// Encodings use auxiliary variables almost all the time.
+    /// Functions that encode constraints ask the first variable id they can use for these auxiliary variables.
+    /// In return, they tell the caller which is the lowest id that can be used after this encoding, that is, the highest auxiliary variable index plus 1.
+    #[must_use]
+    pub fn next_free_var_id(&self) -> i32 {
+        self.next_free_var_id
+    }
+
+    /// Returns an approximation of the number of bytes allocated on the heap by the clauses of this encoding.
+    ///
+    /// This takes into account the capacities of the vectors, not only their lengths; the memory used by the allocator for its bookkeeping is ignored.
+    /// See [`EncodingStats::approx_heap_bytes`] for the amount of memory needed by the clauses when they are collected.
+    #[must_use]
+    pub fn approx_heap_bytes(&self) -> usize {
+        self.clauses.capacity() * std::mem::size_of::<Vec<i32>>()
+            + self
+                .clauses
+                .iter()
+                .map(|c| c.capacity() * std::mem::size_of::<i32>())
+                .sum::<usize>()
+    }
+
+    /// Returns some statistics about the encoding.
+    ///
+    /// These statistics are computed while the clauses are retrieved from pblib, so calling this function has no cost.
+    #[must_use]
+    pub fn stats(&self) -> &EncodingStats {
+        &self.stats
+    }
+
+    /// Returns the outcome of the encoding, telling whether the constraint was found to be trivially satisfied or unsatisfiable.
+    ///
+    /// The outcome is computed from the clauses, so it remains valid after the encoding has been modified (e.g. by [`simplify`](Self::simplify)).
+    /// See [`EncodingOutcome`] for more information.
+    ///
+    /// ```
+    /// use pblib_rs::{EncodingOutcome, PB2CNF};
+    ///
+    /// let pb2cnf = PB2CNF::new();
+    /// assert_eq!(EncodingOutcome::TriviallySat, pb2cnf.encode_at_most_k(&[1, 2], 2, 3).outcome());
+    /// assert_eq!(
+    ///     EncodingOutcome::TriviallyUnsat { empty_clause: true },
+    ///     pb2cnf.encode_at_least_k(&[1, 2], 3, 3).outcome(),
+    /// );
+    /// assert_eq!(
+    ///     EncodingOutcome::TriviallyUnsat { empty_clause: false },
+    ///     pb2cnf.encode_at_least_k(&[1, -1], 2, 2).outcome(),
+    /// );
+    /// assert_eq!(EncodingOutcome::Encoded, pb2cnf.encode_at_most_k(&[1, 2], 1, 3).outcome());
+    /// ```
+    #[must_use]
+    pub fn outcome(&self) -> EncodingOutcome {
+        if self.clauses.is_empty() {
+            return EncodingOutcome::TriviallySat;
+        }
+        if self.clauses.iter().any(Vec::is_empty) {
+            return EncodingOutcome::TriviallyUnsat { empty_clause: true };
+        }
+        let units = self
+            .clauses
+            .iter()
+            .filter(|c| c.len() == 1)
+            .map(|c| c[0])
+            .collect::<HashSet<_>>();
+        if units.iter().any(|l| units.contains(&-l)) {
+            EncodingOutcome::TriviallyUnsat {
+                empty_clause: false,
+            }
+        } else {
+            EncodingOutcome::Encoded
+        }
+    }
+
+    /// Merges another encoding into this one, shifting its auxiliary variables by an offset.
+    ///
+    /// The variables of `other` that are lower than its [`first_aux_var`](Self::first_aux_var) are considered as problem variables and are left untouched.
+    /// The other ones are auxiliary variables, and are shifted by `offset` (their sign being preserved).
+    /// The clauses of `other` are then appended to the ones of this encoding, and the variable bounds are updated.
+    ///
+    /// The first auxiliary variable of the merged encoding is the lowest one of the two encodings (after relocation), but it is never lower than the first auxiliary variable of either side, so the problem variables of both encodings remain problem variables.
+    /// The next free variable id is the highest one of the two encodings (after relocation).
+    /// This function returns the next free variable id of the merged encoding.
+    ///
+    /// See [`merge`](Self::merge) for a function that computes the offset itself.
+    pub fn merge_with_offset(&mut self, other: EncodingResult, offset: i32) -> i32 {
+        let other_first_aux_var = other.first_aux_var;
+        let shift = |l: i32| {
+            if l.abs() >= other_first_aux_var {
+                l + l.signum() * offset
+            } else {
+                l
+            }
+        };
+        self.clauses.extend(
+            other
+                .clauses
+                .into_iter()
+                .map(|c| c.into_iter().map(shift).collect()),
+        );
+        let other_aux_vars = (other.next_free_var_id > other.first_aux_var).then_some((
+            other.first_aux_var + offset,
+            other.next_free_var_id + offset,
+        ));
+        let self_aux_vars = (self.next_free_var_id > self.first_aux_var)
+            .then_some((self.first_aux_var, self.next_free_var_id));
+        let min_first_aux_var = self.first_aux_var.max(other.first_aux_var);
+        let (first_aux_var, next_free_var_id) = match (self_aux_vars, other_aux_vars) {
+            (Some((f1, n1)), Some((f2, n2))) => (f1.min(f2), n1.max(n2)),
+            (Some(range), None) | (None, Some(range)) => range,
+            (None, None) => (min_first_aux_var, min_first_aux_var),
+        };
+        self.first_aux_var = first_aux_var.max(min_first_aux_var);
+        self.next_free_var_id = next_free_var_id
+            .max(other.next_free_var_id)
+            .max(self.first_aux_var);
+        self.stats = self.stats.merge(&other.stats);
+        self.next_free_var_id
+    }
+
+    /// Merges another encoding into this one, relocating its auxiliary variables after the ones of this encoding.
+    ///
+    /// This function is intended to be used for encodings that were computed independently, and for which the auxiliary variables may collide.
+    /// The auxiliary variables of `other` are shifted in such a way the first of them is this encoding next free variable id.
+    /// If the auxiliary variables of `other` already begin after this encoding ones, they are left untouched.
+    ///
+    /// This function returns the next free variable id of the merged encoding.
+    ///
+    /// ```
+    /// use pblib_rs::PB2CNF;
+    ///
+    /// let pb2cnf = PB2CNF::new();
+    /// // both encodings use auxiliary variables starting from 6
+    /// let mut encoding = pb2cnf.encode_at_most_k(&[1, 2, 3, 4, 5], 2, 6);
+    /// let other = pb2cnf.encode_at_least_k(&[1, 2, 3, 4, 5], 2, 6);
+    /// let n_aux_vars = encoding.stats().n_aux_vars() + other.stats().n_aux_vars();
+    /// let next_free_var_id = encoding.merge(other);
+    /// assert_eq!(6 + n_aux_vars as i32, next_free_var_id);
+    /// ```
+    pub fn merge(&mut self, other: EncodingResult) -> i32 {
+        let offset = (self.next_free_var_id - other.first_aux_var).max(0);
+        self.merge_with_offset(other, offset)
+    }
+
+    /// Applies a function to every literal of the encoding.
+    ///
+    /// This function is intended to relocate an encoding produced against a local variable numbering into a global formula.
+    /// The function is applied on literals, so it must take care of their signs (e.g. by ensuring `f(-l) == -f(l)`).
+    ///
+    /// Since the mapping may move the auxiliary variables anywhere, the variable bounds are recomputed from the mapped clauses:
+    /// the first auxiliary variable becomes the lowest variable obtained by mapping an auxiliary variable,
+    /// and the next free variable id becomes the highest variable appearing in the mapped clauses plus 1.
+    /// When the encoding uses no auxiliary variable, both bounds are kept, unless a mapped variable reaches the first auxiliary one; in this case, both are set to the highest mapped variable plus 1.
+    /// The statistics are left unchanged.
+    ///
+    /// See [`offset_vars`](Self::offset_vars) for the common case of shifting all the variables by a constant.
+    ///
+    /// ```
+    /// use pblib_rs::PB2CNF;
+    ///
+    /// let pb2cnf = PB2CNF::new();
+    /// let mut encoding = pb2cnf.encode_at_most_k(&[1, 2], 1, 3);
+    /// // x1 is mapped to x10 and x2 to x20
+    /// encoding.map_literals(|l| l * 10);
+    /// assert_eq!(vec![vec![-20, -10]], encoding.into_clauses());
+    /// ```
+    pub fn map_literals(&mut self, f: impl Fn(i32) -> i32) {
+        let mut min_aux_var = None;
+        let mut max_var = 0;
+        for clause in &mut self.clauses {
+            for l in clause.iter_mut() {
+                let mapped = f(*l);
+                if l.abs() >= self.first_aux_var {
+                    min_aux_var =
+                        Some(min_aux_var.map_or(mapped.abs(), |v: i32| v.min(mapped.abs())));
+                }
+                max_var = max_var.max(mapped.abs());
+                *l = mapped;
+            }
+        }
+        if let Some(min_aux_var) = min_aux_var {
+            self.next_free_var_id = max_var + 1;
+            self.first_aux_var = min_aux_var;
+        } else if max_var >= self.first_aux_var {
+            self.next_free_var_id = max_var + 1;
+            self.first_aux_var = self.next_free_var_id;
+        }
+    }
+
+    /// Shifts all the variables of the encoding by a constant, preserving the signs of the literals.
+    ///
+    /// Both the first auxiliary variable and the next free variable id are shifted accordingly.
+    ///
+    /// ```
+    /// use pblib_rs::PB2CNF;
+    ///
+    /// let pb2cnf = PB2CNF::new();
+    /// let mut encoding = pb2cnf.encode_at_most_k(&[1, 2], 1, 3);
+    /// encoding.offset_vars(10);
+    /// assert_eq!(13, encoding.next_free_var_id());
+    /// assert_eq!(vec![vec![-12, -11]], encoding.into_clauses());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a variable becomes lower than 1.
+    pub fn offset_vars(&mut self, delta: i32) {
+        for clause in &mut self.clauses {
+            for l in clause.iter_mut() {
+                let var = l.abs() + delta;
+                assert!(
+                    var > 0,
+                    "variable {} shifted by {delta} is not positive",
+                    l.abs()
+                );
+                *l = l.signum() * var;
+            }
+        }
+        self.first_aux_var += delta;
+        self.next_free_var_id += delta;
+    }
+
+    /// Checks whether an assignment satisfies all the clauses of the encoding.
+    ///
+    /// The assignment is given as a list of DIMACS literals, like the models returned by most SAT solvers.
+    /// Variables that do not appear in the assignment are considered as unassigned, so the clauses that only rely on such variables are not satisfied.
+    ///
+    /// ```
+    /// use pblib_rs::PB2CNF;
+    ///
+    /// let pb2cnf = PB2CNF::new();
+    /// let encoding = pb2cnf.encode_at_most_k(&[1, 2], 1, 3);
+    /// assert!(encoding.is_model(&[1, -2]));
+    /// assert!(!encoding.is_model(&[1, 2]));
+    /// ```
+    #[must_use]
+    pub fn is_model(&self, assignment: &[i32]) -> bool {
+        self.falsified_clauses(assignment).is_empty()
+    }
+
+    /// Returns the clauses of the encoding that are not satisfied by an assignment.
+    ///
+    /// See [`is_model`](Self::is_model) for more information about the assignment.
+    #[must_use]
+    pub fn falsified_clauses(&self, assignment: &[i32]) -> Vec<&[i32]> {
+        assignment::falsified_clauses(&self.clauses, assignment)
+    }
+
+    /// Sorts the literals of each clause and the clauses themselves in a canonical order.
+    ///
+    /// The literals are sorted by variable, the negative literal of a variable coming first; the clauses are sorted by length, then lexicographically according to the order of the literals.
+    /// Thus, two encodings made of the same clauses, up to the order of the clauses and of their literals, are equal once canonicalized, which allows to hash, deduplicate or diff encodings across runs.
+    /// The encoders can produce canonical encodings directly (see [`EncoderConfig::with_canonical_order`](crate::EncoderConfig::with_canonical_order)).
+    ///
+    /// ```
+    /// use pblib_rs::PB2CNF;
+    ///
+    /// let pb2cnf = PB2CNF::new();
+    /// let mut encoding = pb2cnf.encode_at_least_k(&[3, -1, 2], 3, 4);
+    /// encoding.canonicalize();
+    /// assert_eq!(&[vec![-1], vec![2], vec![3]], encoding.clauses());
+    /// ```
+    pub fn canonicalize(&mut self) {
+        sort_canonically(&mut self.clauses);
+    }
+
+    /// Shuffles the presentation of the encoding: the auxiliary variables are permuted and the clauses are reordered, pseudo-randomly.
+    ///
+    /// The permutation only involves the auxiliary variables (from [`first_aux_var`](Self::first_aux_var), included, to [`next_free_var_id`](Self::next_free_var_id), excluded) and preserves the signs of the literals, so the shuffled encoding still encodes the same constraint.
+    /// The same seed always gives the same result, which allows to measure the sensitivity of solvers to the presentation of the encodings in a reproducible way.
+    ///
+    /// ```
+    /// use pblib_rs::PB2CNF;
+    ///
+    /// let pb2cnf = PB2CNF::new();
+    /// let encoding = pb2cnf.encode_at_most_k(&[1, 2, 3, 4], 2, 5);
+    /// let mut shuffled = encoding.clone();
+    /// shuffled.shuffle(42);
+    /// assert_eq!(encoding.stats(), shuffled.stats());
+    /// assert_eq!(encoding.next_free_var_id(), shuffled.next_free_var_id());
+    /// ```
+    pub fn shuffle(&mut self, seed: u64) {
+        let mut rng = SplitMix64(seed);
+        let mut aux_vars = (self.first_aux_var..self.next_free_var_id).collect::<Vec<_>>();
+        rng.shuffle(&mut aux_vars);
+        for l in self.clauses.iter_mut().flatten() {
+            if l.abs() >= self.first_aux_var {
+                *l = l.signum() * aux_vars[(l.abs() - self.first_aux_var).unsigned_abs() as usize];
+            }
+        }
+        rng.shuffle(&mut self.clauses);
+    }
+
+    /// Removes the duplicate clauses and the clauses that are subsumed by shorter ones.
+    ///
+    /// Two clauses are considered as duplicates if they share the same set of literals, regardless of the order of the literals.
+    /// The order of the remaining clauses and of their literals is preserved, and the statistics are updated.
+    /// The number of auxiliary variables is left unchanged, even if some of them do not appear in the clauses anymore.
+    ///
+    /// This function returns the number of removed clauses.
+    pub fn simplify(&mut self) -> usize {
+        let n_removed = simplify::remove_redundant_clauses(&mut self.clauses);
+        self.stats = EncodingStats::from_clauses(&self.clauses, self.stats.n_aux_vars);
+        n_removed
+    }
+
+    /// Removes the tautological clauses and applies unit propagation on the encoding.
+    ///
+    /// Some encodings produce unit clauses, e.g. when the bound is trivial.
+    /// This function removes the clauses that are satisfied by such literals and removes the falsified literals from the other clauses, until no new unit clause is produced.
+    /// The fixed literals are kept as unit clauses at the end of the encoding, so the encoding remains equivalent to the initial one.
+    /// In case a conflict is detected, the encoding is replaced by a single empty clause.
+    ///
+    /// This function returns the fixed literals, in the order they were discovered.
+    ///
+    /// ```
+    /// use pblib_rs::PB2CNF;
+    ///
+    /// let pb2cnf = PB2CNF::new();
+    /// // x1 + x2 >= 2 implies both x1 and x2
+    /// let mut encoding = pb2cnf.encode_at_least_k(&[1, 2], 2, 3);
+    /// let mut fixed = encoding.propagate_units();
+    /// fixed.sort_unstable();
+    /// assert_eq!(vec![1, 2], fixed);
+    /// ```
+    pub fn propagate_units(&mut self) -> Vec<i32> {
+        let fixed = simplify::propagate_units(&mut self.clauses);
+        self.stats = EncodingStats::from_clauses(&self.clauses, self.stats.n_aux_vars);
+        fixed
+    }
+
+    /// Writes the encoding as a DIMACS CNF formula.
+    ///
+    /// The `p cnf` header is written first, using the provided number of variables and the number of clauses of the encoding.
+    /// The number of variables should take into account the auxiliary variables, so the preferred value is in most cases `next_free_var_id() - 1`.
+    /// Use [`write_dimacs_clauses`](Self::write_dimacs_clauses) if you only want to write the clauses, e.g. to append them to a formula that is already being written.
+    ///
+    /// ```
+    /// use pblib_rs::PB2CNF;
+    ///
+    /// let pb2cnf = PB2CNF::new();
+    /// let encoding = pb2cnf.encode_at_most_k(&[1, 2], 1, 3);
+    /// let mut output = Vec::new();
+    /// encoding.write_dimacs(&mut output, 2).unwrap();
+    /// assert_eq!("p cnf 2 1\n-2 -1 0\n", String::from_utf8(output).unwrap());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the writer fails.
+    pub fn write_dimacs<W: Write>(&self, writer: &mut W, n_vars: usize) -> std::io::Result<()> {
+        dimacs::write_header(writer, n_vars, self.clauses.len())?;
+        self.write_dimacs_clauses(writer)
+    }
+
+    /// Writes the clauses of the encoding in the DIMACS format, without any header.
+    ///
+    /// Each clause is written on its own line and terminated by a `0`.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the writer fails.
+    pub fn write_dimacs_clauses<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        dimacs::write_clauses(writer, &self.clauses)
+    }
+
+    /// Returns an iterator over the clauses of this encoding.
+    pub fn iter(&self) -> std::slice::Iter<'_, Vec<i32>> {
+        self.clauses.iter()
+    }
+
+    /// Consumes this result and returns the clauses used to encode the constraint.
+    ///
+    /// Contrary to [`clauses`](Self::clauses), this function does not require to clone the clauses to get owned values.
+    #[must_use]
+    pub fn into_clauses(self) -> Vec<Vec<i32>> {
+        self.clauses
+    }
+
+    /// Consumes this result and returns both the clauses and the next free variable id.
+    ///
+    /// See [`into_clauses`](Self::into_clauses) and [`next_free_var_id`](Self::next_free_var_id) for more information.
+    #[must_use]
+    pub fn into_parts(self) -> (Vec<Vec<i32>>, i32) {
+        (self.clauses, self.next_free_var_id)
+    }
+}
+
+/// Renders the clauses in a human-readable way, one clause per line.
+///
+/// Problem variables are written `x1`, `x2`, ... while the auxiliary variables are written `a5`, `a6`, ...
+/// Negative literals are prefixed by `¬` and the literals of a clause are joined by `∨`.
+/// An empty clause is rendered as `⊥`.
+///
+/// ```
+/// use pblib_rs::PB2CNF;
+///
+/// let pb2cnf = PB2CNF::new();
+/// let encoding = pb2cnf.encode_at_most_k(&[1, 2], 1, 3);
+/// assert_eq!("(¬x2 ∨ ¬x1)\n", format!("{encoding}"));
+/// ```
+impl Display for EncodingResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for clause in &self.clauses {
+            if clause.is_empty() {
+                writeln!(f, "⊥")?;
+                continue;
+            }
+            write!(f, "(")?;
+            for (i, l) in clause.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " ∨ ")?;
+                }
+                if *l < 0 {
+                    write!(f, "¬")?;
+                }
+                let var = l.abs();
+                let prefix = if var >= self.first_aux_var { 'a' } else { 'x' };
+                write!(f, "{prefix}{var}")?;
+            }
+            writeln!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl IntoIterator for EncodingResult {
+    type Item = Vec<i32>;
+
+    type IntoIter = std::vec::IntoIter<Vec<i32>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.clauses.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a EncodingResult {
+    type Item = &'a Vec<i32>;
+
+    type IntoIter = std::slice::Iter<'a, Vec<i32>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The outcome of an encoding, as returned by [`EncodingResult::outcome`].
+///
+/// Like pblib's classification of constraints before their encoding, this enum distinguishes the constraints that hold whatever the values of their literals, the ones that never hold, and the other ones.
+/// Callers should rely on it instead of looking for empty clauses in the encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EncodingOutcome {
+    /// The constraint was encoded by a nonempty set of clauses that is not trivially unsatisfiable.
+    Encoded,
+    /// The constraint holds whatever the values of its literals: the encoding has no clause.
+    TriviallySat,
+    /// The constraint never holds.
+    TriviallyUnsat {
+        /// Whether the encoding contains the empty clause; if not, it contains two opposite unit clauses.
+        empty_clause: bool,
+    },
+}
+
+/// A [`ClauseSink`] that collects the clauses and computes the statistics of an encoding.
+#[derive(Default)]
+pub(crate) struct EncodingResultBuilder {
+    clauses: Vec<Vec<i32>>,
+    n_literals: usize,
+    max_clause_len: usize,
+}
+
+impl EncodingResultBuilder {
+    pub(crate) fn build(self, first_aux_var: i32, next_free_var_id: i32) -> EncodingResult {
+        let n_aux_vars = usize::try_from(next_free_var_id - first_aux_var).unwrap_or_default();
+        let stats = EncodingStats::new(
+            self.clauses.len(),
+            self.n_literals,
+            n_aux_vars,
+            self.max_clause_len,
+        );
+        EncodingResult::new(self.clauses, first_aux_var, next_free_var_id, stats)
+    }
+}
+
+impl ClauseSink for EncodingResultBuilder {
+    fn add_clause(&mut self, clause: &[i32]) {
+        self.n_literals += clause.len();
+        self.max_clause_len = self.max_clause_len.max(clause.len());
+        self.clauses.push(clause.to_vec());
+    }
+}
+
+/// Statistics about an encoding.
+///
+/// Such statistics are returned by [`EncodingResult::stats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EncodingStats {
+    n_clauses: usize,
+    n_literals: usize,
+    n_aux_vars: usize,
+    max_clause_len: usize,
+}
+
+impl EncodingStats {
+    pub(crate) fn new(
+        n_clauses: usize,
+        n_literals: usize,
+        n_aux_vars: usize,
+        max_clause_len: usize,
+    ) -> Self {
+        Self {
+            n_clauses,
+            n_literals,
+            n_aux_vars,
+            max_clause_len,
+        }
+    }
+
+    /// Returns the number of clauses of the encoding.
+    #[must_use]
+    pub fn n_clauses(&self) -> usize {
+        self.n_clauses
+    }
+
+    /// Returns the total number of literals of the encoding, that is the sum of the clause lengths.
+    #[must_use]
+    pub fn n_literals(&self) -> usize {
+        self.n_literals
+    }
+
+    /// Returns the number of auxiliary variables introduced by the encoding.
+    #[must_use]
+    pub fn n_aux_vars(&self) -> usize {
+        self.n_aux_vars
+    }
+
+    /// Returns the length of the longest clause of the encoding, or 0 if there is no clause.
+    #[must_use]
+    pub fn max_clause_len(&self) -> usize {
+        self.max_clause_len
+    }
+
+    /// Returns an approximation of the number of bytes needed to store the clauses of the encoding as vectors, without spare capacity.
+    ///
+    /// Since the clauses are only added while encoding a constraint, this is also the peak of the memory used by a call to collect them; the memory used by pblib itself is not taken into account.
+    /// It is tracked as the clauses are produced, so it is available in the statistics of aborted encodings too, and can be bounded with [`EncodeLimits::with_max_heap_bytes`](crate::EncodeLimits::with_max_heap_bytes).
+    #[must_use]
+    pub fn approx_heap_bytes(&self) -> usize {
+        heap_bytes(self.n_clauses, self.n_literals)
+    }
+
+    pub(crate) fn from_clauses(clauses: &[Vec<i32>], n_aux_vars: usize) -> Self {
+        Self {
+            n_clauses: clauses.len(),
+            n_literals: clauses.iter().map(Vec::len).sum(),
+            n_aux_vars,
+            max_clause_len: clauses.iter().map(Vec::len).max().unwrap_or_default(),
+        }
+    }
+
+    pub(crate) fn merge(&self, other: &EncodingStats) -> Self {
+        Self {
+            n_clauses: self.n_clauses + other.n_clauses,
+            n_literals: self.n_literals + other.n_literals,
+            n_aux_vars: self.n_aux_vars + other.n_aux_vars,
+            max_clause_len: self.max_clause_len.max(other.max_clause_len),
+        }
+    }
+}
+
+/// The `SplitMix64` pseudo-random generator (Steele, Lea and Flood, 2014), which is small and good enough to shuffle encodings.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Shuffles a slice with the Fisher-Yates algorithm.
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = usize::try_from(self.next_u64() % (i as u64 + 1)).unwrap();
+            items.swap(i, j);
+        }
+    }
+}
+
+/// Sorts the literals of each clause by variable, and the clauses by length and then lexicographically.
+pub(crate) fn sort_canonically(clauses: &mut [Vec<i32>]) {
+    let key = |l: &i32| (l.abs(), *l);
+    for clause in clauses.iter_mut() {
+        clause.sort_unstable_by_key(key);
+    }
+    clauses.sort_unstable_by(|c1, c2| {
+        c1.len()
+            .cmp(&c2.len())
+            .then_with(|| c1.iter().map(key).cmp(c2.iter().map(key)))
+    });
+}
+
+/// Returns the number of bytes needed to store clauses as vectors, given their number and their total length.
+pub(crate) fn heap_bytes(n_clauses: usize, n_literals: usize) -> usize {
+    n_clauses * std::mem::size_of::<Vec<i32>>() + n_literals * std::mem::size_of::<i32>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{reference, PbConstraint, PB2CNF};
+
+    #[test]
+    fn test_canonicalize() {
+        let mut encoding = EncodingResult::new(
+            vec![vec![3, -1], vec![2, -2, 1], vec![-3], vec![1, -3]],
+            4,
+            4,
+            EncodingStats::default(),
+        );
+        encoding.canonicalize();
+        assert_eq!(
+            &[vec![-3], vec![-1, 3], vec![1, -3], vec![1, -2, 2]],
+            encoding.clauses()
+        );
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let pb2cnf = PB2CNF::new();
+        let constraint = PbConstraint::weighted([(3, 1), (2, -2), (2, 3), (1, 4)]).geq(4);
+        let encoding = pb2cnf.encode_constraint(&constraint, 5);
+        let mut shuffled = encoding.clone();
+        shuffled.shuffle(7);
+        let mut same_seed = encoding.clone();
+        same_seed.shuffle(7);
+        assert_eq!(shuffled, same_seed);
+        assert_eq!(encoding.first_aux_var(), shuffled.first_aux_var());
+        assert_eq!(encoding.next_free_var_id(), shuffled.next_free_var_id());
+        assert!(shuffled
+            .iter()
+            .flatten()
+            .all(|l| l.abs() < shuffled.next_free_var_id()));
+        assert_eq!(
+            Ok(()),
+            reference::check_encoding(&[constraint], shuffled.clauses(), 4)
+        );
+        let mut other_seeds = (0..8).map(|seed| {
+            let mut e = encoding.clone();
+            e.shuffle(seed);
+            e
+        });
+        assert!(other_seeds.any(|e| e != encoding));
+    }
+
+    #[test]
+    fn test_shuffle_merged_encodings() {
+        let pb2cnf = PB2CNF::new();
+        // the first encoding has no auxiliary variable, and the other ones involve more problem variables
+        let constraints = [
+            PbConstraint::cardinality([1, 2]).leq(1),
+            PbConstraint::weighted([(3, 1), (2, -2), (2, 3)]).geq(4),
+            PbConstraint::cardinality([1, 2, 3, 4, 5, 6]).leq(3),
+        ];
+        let mut encoding = pb2cnf.encode_constraint(&constraints[0], 3);
+        encoding.merge(pb2cnf.encode_constraint(&constraints[1], 7));
+        encoding.merge(pb2cnf.encode_constraint(&constraints[2], 7));
+        assert_eq!(7, encoding.first_aux_var());
+        for seed in 0..8 {
+            let mut shuffled = encoding.clone();
+            shuffled.shuffle(seed);
+            assert_eq!(
+                Ok(()),
+                reference::check_encoding(&constraints, shuffled.clauses(), 6)
+            );
+        }
+    }
+
+    #[test]
+    fn test_approx_heap_bytes() {
+        let encoding = PB2CNF::new().encode_at_most_k(&[1, 2, 3, 4, 5], 2, 6);
+        let stats = encoding.stats();
+        assert_eq!(
+            stats.n_clauses() * std::mem::size_of::<Vec<i32>>()
+                + stats.n_literals() * std::mem::size_of::<i32>(),
+            stats.approx_heap_bytes()
+        );
+        assert!(encoding.approx_heap_bytes() >= stats.approx_heap_bytes());
+        let empty = PB2CNF::new().encode_at_most_k(&[1, 2], 2, 3);
+        assert_eq!(0, empty.stats().approx_heap_bytes());
+    }
+
+    #[test]
+    fn test_into_parts() {
+        let literals = vec![1, 2];
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_at_least_k(&literals, 1, 3);
+        let (mut clauses, next_free_var_id) = encoding.into_parts();
+        clauses[0].sort_unstable();
+        assert_eq!(vec![vec![1, 2]], clauses);
+        assert_eq!(3, next_free_var_id);
+    }
+
+    #[test]
+    fn test_into_clauses() {
+        let literals = vec![1, 2];
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_at_least_k(&literals, 1, 3);
+        let expected = encoding.clauses().to_vec();
+        assert_eq!(expected, encoding.into_clauses());
+    }
+
+    #[test]
+    fn test_write_dimacs() {
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_geq(&[1, 1], &[1, 2], 1, 3);
+        let mut output = Vec::new();
+        encoding.write_dimacs(&mut output, 2).unwrap();
+        let expected = format!(
+            "p cnf 2 1\n{} {} 0\n",
+            encoding.clauses()[0][0],
+            encoding.clauses()[0][1]
+        );
+        assert_eq!(expected, String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_write_dimacs_clauses_no_header() {
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_both(&[1, 1], &[1, 2], 1, 1, 3);
+        let mut output = Vec::new();
+        encoding.write_dimacs_clauses(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(2, output.lines().count());
+        assert!(output.lines().all(|l| l.ends_with(" 0")));
+    }
+
+    #[test]
+    fn test_merge_shifts_aux_vars() {
+        let pb2cnf = PB2CNF::new();
+        let mut encoding = pb2cnf.encode_at_most_k(&[1, 2, 3, 4, 5], 2, 6);
+        let other = pb2cnf.encode_at_least_k(&[1, 2, 3, 4, 5], 2, 6);
+        let first_next_free_var_id = encoding.next_free_var_id();
+        let n_clauses = encoding.clauses().len() + other.clauses().len();
+        let other_aux_vars = other.stats().n_aux_vars();
+        let next_free_var_id = encoding.merge(other);
+        assert_eq!(n_clauses, encoding.clauses().len());
+        assert_eq!(n_clauses, encoding.stats().n_clauses());
+        assert_eq!(
+            first_next_free_var_id + i32::try_from(other_aux_vars).unwrap(),
+            next_free_var_id
+        );
+        assert!(encoding
+            .clauses()
+            .iter()
+            .flatten()
+            .all(|l| l.abs() < next_free_var_id));
+    }
+
+    #[test]
+    fn test_merge_with_offset_keeps_problem_vars() {
+        let pb2cnf = PB2CNF::new();
+        let mut encoding = pb2cnf.encode_at_most_k(&[1, 2], 1, 3);
+        let other = pb2cnf.encode_at_least_k(&[1, 2], 1, 3);
+        assert_eq!(3, encoding.merge_with_offset(other, 10));
+        let mut clauses = encoding.into_clauses();
+        for c in &mut clauses {
+            c.sort_unstable();
+        }
+        assert_eq!(vec![vec![-2, -1], vec![1, 2]], clauses);
+    }
+
+    #[test]
+    fn test_merge_keeps_problem_vars_out_of_aux_range() {
+        let pb2cnf = PB2CNF::new();
+        let constraints = [
+            PbConstraint::cardinality([1, 2]).leq(1),
+            PbConstraint::cardinality([1, 2, 3, 4, 5]).leq(2),
+        ];
+        let mut encoding = pb2cnf.encode_at_most_k(&[1, 2], 1, 3);
+        encoding.merge(pb2cnf.encode_at_most_k(&[1, 2, 3, 4, 5], 2, 6));
+        assert_eq!(6, encoding.first_aux_var());
+        for seed in 0..5 {
+            let mut shuffled = encoding.clone();
+            shuffled.shuffle(seed);
+            assert_eq!(
+                Ok(()),
+                reference::check_encoding(&constraints, shuffled.clauses(), 5)
+            );
+        }
+    }
+
+    #[test]
+    fn test_simplify() {
+        let pb2cnf = PB2CNF::new();
+        let mut encoding = pb2cnf.encode_at_most_k(&[1, 2], 1, 3);
+        encoding.merge(pb2cnf.encode_at_most_k(&[2, 1], 1, 3));
+        encoding.merge(pb2cnf.encode_at_most_k(&[1, 2, 3], 2, 4));
+        assert_eq!(2, encoding.simplify());
+        assert_eq!(1, encoding.clauses().len());
+        assert_eq!(1, encoding.stats().n_clauses());
+        assert_eq!(2, encoding.stats().n_literals());
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_both(&[1, 1], &[1, 2], 1, 1, 3);
+        let expected = encoding.clauses().to_vec();
+        assert_eq!(
+            expected,
+            (&encoding).into_iter().cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(expected, encoding.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_display_aux_vars() {
+        let encoding = EncodingResult::new(
+            vec![vec![1, -4], vec![], vec![-2]],
+            4,
+            5,
+            EncodingStats::default(),
+        );
+        assert_eq!("(x1 ∨ ¬a4)\n⊥\n(¬x2)\n", format!("{encoding}"));
+    }
+
+    #[test]
+    fn test_map_literals_relocates_aux_vars() {
+        let mut encoding = EncodingResult::new(
+            vec![vec![1, -4], vec![2, 5]],
+            4,
+            6,
+            EncodingStats::default(),
+        );
+        encoding.map_literals(|l| l.signum() * (l.abs() + if l.abs() >= 4 { 100 } else { 0 }));
+        assert_eq!(vec![vec![1, -104], vec![2, 105]], encoding.clauses());
+        assert_eq!(104, encoding.first_aux_var());
+        assert_eq!(106, encoding.next_free_var_id());
+    }
+
+    #[test]
+    fn test_map_literals_no_aux_vars() {
+        let mut encoding = EncodingResult::new(vec![vec![1, -2]], 3, 3, EncodingStats::default());
+        encoding.map_literals(|l| -l);
+        assert_eq!(vec![vec![-1, 2]], encoding.clauses());
+        assert_eq!(3, encoding.first_aux_var());
+        assert_eq!(3, encoding.next_free_var_id());
+        let mut encoding = EncodingResult::new(vec![vec![1]], 10, 10, EncodingStats::default());
+        encoding.map_literals(|l| l);
+        assert_eq!(10, encoding.first_aux_var());
+        assert_eq!(10, encoding.next_free_var_id());
+        encoding.map_literals(|l| l * 20);
+        assert_eq!(21, encoding.first_aux_var());
+        assert_eq!(21, encoding.next_free_var_id());
+    }
+
+    #[test]
+    fn test_offset_vars() {
+        let mut encoding = EncodingResult::new(vec![vec![1, -4]], 4, 6, EncodingStats::default());
+        encoding.offset_vars(2);
+        assert_eq!(vec![vec![3, -6]], encoding.clauses());
+        assert_eq!(6, encoding.first_aux_var());
+        assert_eq!(8, encoding.next_free_var_id());
+    }
+
+    #[test]
+    #[should_panic(expected = "variable 1 shifted by -1 is not positive")]
+    fn test_offset_vars_negative() {
+        let mut encoding = EncodingResult::new(vec![vec![1]], 2, 2, EncodingStats::default());
+        encoding.offset_vars(-1);
+    }
+
+    #[test]
+    fn test_stats_no_aux_vars() {
+        let literals = vec![1, 2];
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_both(&[1, 1], &literals, 1, 1, 3);
+        let stats = encoding.stats();
+        assert_eq!(2, stats.n_clauses());
+        assert_eq!(4, stats.n_literals());
+        assert_eq!(0, stats.n_aux_vars());
+        assert_eq!(2, stats.max_clause_len());
+    }
+
+    #[test]
+    fn test_stats_consistency() {
+        let weights = vec![8, 4, 2, 1];
+        let literals = vec![1, 2, 3, 4];
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_geq(&weights, &literals, 6, 5);
+        let stats = encoding.stats();
+        assert_eq!(encoding.clauses().len(), stats.n_clauses());
+        assert_eq!(
+            encoding.clauses().iter().map(Vec::len).sum::<usize>(),
+            stats.n_literals()
+        );
+        assert_eq!(
+            usize::try_from(encoding.next_free_var_id() - 5).unwrap(),
+            stats.n_aux_vars()
+        );
+        assert_eq!(
+            encoding.clauses().iter().map(Vec::len).max().unwrap(),
+            stats.max_clause_len()
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{
+    cpblib::assert_valid_terms, encoding_result::EncodingResultBuilder, ClauseSink, Comparator,
+    EncodingResult, PbConstraint, PB2CNF,
+};
+
+/// The encoding of a constraint shape, computed once on placeholder variables.
+///
+/// The placeholder variables are `1..=n`, where `n` is the number of terms; the auxiliary variables of the template start at `n + 1`.
+/// Instantiating the template replaces each placeholder by an actual literal, and shifts the auxiliary variables to a fresh range.
+/// Since substituting variables in an encoding gives an encoding of the substituted constraint, the instances are as correct as the template, provided the literals given to an instance are on distinct variables.
+///
+/// This is the mechanism used by the [`EncodingCache`](crate::EncodingCache) and by [`PB2CNF::encode_template_over_steps`].
+///
+/// ```
+/// use pblib_rs::{Comparator, EncodingTemplate, PB2CNF};
+///
+/// // 2*a + b + c >= 2, on placeholder variables
+/// let template = EncodingTemplate::new(&PB2CNF::new(), &[2, 1, 1], Comparator::Geq, 2);
+/// // 2*x1 + ~x2 + x3 >= 2, with auxiliary variables from 10
+/// let first = template.instantiate(&[1, -2, 3], 10);
+/// // 2*x4 + x5 + x6 >= 2, with the next auxiliary variables
+/// let second = template.instantiate(&[4, 5, 6], first.next_free_var_id());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodingTemplate {
+    n_terms: usize,
+    encoding: EncodingResult,
+}
+
+impl EncodingTemplate {
+    /// Encodes a constraint shape, given by the weights of its terms, its comparator and its right hand side, with the given encoder.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of terms does not fit in an `i32`.
+    #[must_use]
+    pub fn new(pb2cnf: &PB2CNF, weights: &[i64], comparator: Comparator, rhs: i64) -> Self {
+        let n_terms = weights.len();
+        let placeholders = 1..=i32::try_from(n_terms).unwrap();
+        let constraint = PbConstraint::new(
+            weights.iter().copied().zip(placeholders).collect(),
+            comparator,
+            rhs,
+        );
+        let encoding = pb2cnf.encode_constraint(&constraint, placeholder_first_aux_var(n_terms));
+        Self { n_terms, encoding }
+    }
+
+    /// Returns the number of terms of the constraint shape, that is the number of literals expected by the instances.
+    #[must_use]
+    pub fn n_terms(&self) -> usize {
+        self.n_terms
+    }
+
+    /// Returns the encoding of the template, in which the literal of the term at index `i` is the placeholder variable `i + 1`.
+    #[must_use]
+    pub fn encoding(&self) -> &EncodingResult {
+        &self.encoding
+    }
+
+    /// Instantiates the template with the given literals, one for each term of the shape.
+    ///
+    /// The auxiliary variables of the instance are numbered from `first_aux_var`.
+    /// See [`PB2CNF::encode_constraint`] for more information on the return type.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of literals does not match the number of terms of the template, or if `first_aux_var` is not greater than the variables of the literals.
+    #[must_use]
+    pub fn instantiate(&self, literals: &[i32], first_aux_var: i32) -> EncodingResult {
+        let mut builder = EncodingResultBuilder::default();
+        let next_free_var_id = self.instantiate_into(literals, first_aux_var, &mut builder);
+        builder.build(first_aux_var, next_free_var_id)
+    }
+
+    /// Instantiates the template with the given literals, sending the clauses to a [`ClauseSink`].
+    ///
+    /// This function behaves like [`instantiate`](Self::instantiate), except that the clauses are given one by one to the sink instead of being collected.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of literals does not match the number of terms of the template, or if `first_aux_var` is not greater than the variables of the literals.
+    pub fn instantiate_into(
+        &self,
+        literals: &[i32],
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        assert_eq!(
+            self.n_terms,
+            literals.len(),
+            "the number of literals ({}) does not match the number of terms of the template ({})",
+            literals.len(),
+            self.n_terms
+        );
+        assert_valid_terms(literals.iter().map(|l| (1, *l)), &[], first_aux_var);
+        let aux_offset = first_aux_var - self.encoding.first_aux_var();
+        let substitute = |l: i32| {
+            let var = l.abs();
+            let value = if var < self.encoding.first_aux_var() {
+                literals[var.unsigned_abs() as usize - 1]
+            } else {
+                var + aux_offset
+            };
+            l.signum() * value
+        };
+        let mut clause = Vec::new();
+        for template_clause in self.encoding.clauses() {
+            clause.clear();
+            clause.extend(template_clause.iter().map(|l| substitute(*l)));
+            sink.add_clause(&clause);
+        }
+        self.encoding.next_free_var_id() + aux_offset
+    }
+}
+
+fn placeholder_first_aux_var(n_terms: usize) -> i32 {
+    i32::try_from(n_terms).unwrap() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference;
+
+    #[test]
+    fn test_instantiate() {
+        let template = EncodingTemplate::new(&PB2CNF::new(), &[3, 2, 1, 1], Comparator::Leq, 3);
+        assert_eq!(4, template.n_terms());
+        assert_eq!(5, template.encoding().first_aux_var());
+        let literals = [-7, 2, 5, -1];
+        let encoding = template.instantiate(&literals, 8);
+        assert_eq!(8, encoding.first_aux_var());
+        let constraint = PbConstraint::new(
+            [3, 2, 1, 1].into_iter().zip(literals).collect(),
+            Comparator::Leq,
+            3,
+        );
+        assert_eq!(
+            Ok(()),
+            reference::check_encoding(&[constraint], encoding.clauses(), 7)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "does not match the number of terms")]
+    fn test_instantiate_wrong_length() {
+        let template = EncodingTemplate::new(&PB2CNF::new(), &[1, 1], Comparator::Geq, 1);
+        let _ = template.instantiate(&[1], 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid first auxiliary variable 3")]
+    fn test_instantiate_invalid_first_aux_var() {
+        let template = EncodingTemplate::new(&PB2CNF::new(), &[1, 1, 1], Comparator::Leq, 1);
+        let _ = template.instantiate(&[1, 2, -3], 3);
+    }
+}
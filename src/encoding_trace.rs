@@ -0,0 +1,106 @@
+use crate::ClauseSink;
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+/// A [`ClauseSink`] recording, for each clause, the rule of the encoder that produced it.
+///
+/// The rules are given by the encoders through [`ClauseSink::explain`].
+/// Only the [native](crate::native) encoders explain their clauses; the ones produced by pblib, or before the first explanation, have no rule.
+/// This is intended for teaching and for debugging encoders, since formatting the rules has a cost.
+///
+/// ```
+/// # #[cfg(feature = "native")] {
+/// use pblib_rs::EncodingTrace;
+/// use pblib_rs::native::Totalizer;
+///
+/// let mut trace = EncodingTrace::new();
+/// Totalizer::encode_at_most_k_into(&[1, 2, 3], 1, 4, &mut trace);
+/// for (clause, rule) in trace.iter() {
+///     println!("{clause:?}: {}", rule.unwrap_or("unknown rule"));
+/// }
+/// assert_eq!(Some("totalizer bound: sum <= 1"), trace.rule(trace.clauses().len() - 1));
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EncodingTrace {
+    clauses: Vec<Vec<i32>>,
+    rules: Vec<String>,
+    /// For each clause, the index of its rule.
+    clause_rules: Vec<Option<usize>>,
+}
+
+impl EncodingTrace {
+    /// Builds a new trace, with no clauses.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the clauses received by this trace.
+    #[must_use]
+    pub fn clauses(&self) -> &[Vec<i32>] {
+        &self.clauses
+    }
+
+    /// Returns the rule that produced the clause at the given index, if it is known.
+    #[must_use]
+    pub fn rule(&self, clause_index: usize) -> Option<&str> {
+        self.clause_rules
+            .get(clause_index)
+            .copied()
+            .flatten()
+            .map(|r| self.rules[r].as_str())
+    }
+
+    /// Returns an iterator over the clauses and their rules.
+    pub fn iter(&self) -> impl Iterator<Item = (&[i32], Option<&str>)> {
+        self.clauses
+            .iter()
+            .zip(&self.clause_rules)
+            .map(|(c, r)| (c.as_slice(), r.map(|r| self.rules[r].as_str())))
+    }
+
+    /// Consumes this trace and returns the clauses it received.
+    #[must_use]
+    pub fn into_clauses(self) -> Vec<Vec<i32>> {
+        self.clauses
+    }
+}
+
+impl ClauseSink for EncodingTrace {
+    fn add_clause(&mut self, clause: &[i32]) {
+        self.clauses.push(clause.to_vec());
+        self.clause_rules.push(self.rules.len().checked_sub(1));
+    }
+
+    fn explain(&mut self, rule: fmt::Arguments<'_>) {
+        self.rules.push(alloc::format!("{rule}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let mut trace = EncodingTrace::new();
+        trace.add_clause(&[1]);
+        trace.explain(format_args!("rule {}", 1));
+        trace.add_clause(&[2]);
+        trace.add_clause(&[3]);
+        trace.explain(format_args!("rule {}", 2));
+        trace.explain(format_args!("rule {}", 3));
+        trace.add_clause(&[4]);
+        assert_eq!(
+            vec![None, Some("rule 1"), Some("rule 1"), Some("rule 3")],
+            (0..4).map(|i| trace.rule(i)).collect::<Vec<_>>()
+        );
+        assert_eq!(None, trace.rule(4));
+        assert_eq!(4, trace.iter().count());
+        assert_eq!(
+            vec![vec![1], vec![2], vec![3], vec![4]],
+            trace.into_clauses()
+        );
+    }
+}
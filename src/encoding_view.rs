@@ -0,0 +1,124 @@
+use crate::ClauseSink;
+
+/// A compact representation of the result of an encoding function.
+///
+/// Unlike [`EncodingResult`](crate::EncodingResult), which stores each clause in its own vector, this structure stores all the literals in a single buffer.
+/// The clauses are given as slices into this buffer, which is freed when the view is dropped.
+/// This is intended for callers that only stream the clauses onward, and do not need owned copies of them.
+///
+/// Such objects are returned by [`encode_leq_view`](crate::PB2CNF::encode_leq_view) and [`encode_constraint_view`](crate::PB2CNF::encode_constraint_view).
+///
+/// ```
+/// use pblib_rs::PB2CNF;
+///
+/// let pb2cnf = PB2CNF::new();
+/// // we encode 8*x1 + 4*x2 + 2*x3 + 1*x4 <= 6
+/// let view = pb2cnf.encode_leq_view(&[8, 4, 2, 1], &[1, 2, 3, 4], 6, 5);
+/// for clause in view.clauses() {
+///     println!("{clause:?}");
+/// }
+/// println!("the next free variable id is {}", view.next_free_var_id());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodingView {
+    literals: Vec<i32>,
+    clause_ends: Vec<usize>,
+    first_aux_var: i32,
+    next_free_var_id: i32,
+}
+
+impl EncodingView {
+    /// Returns the number of clauses used to encode the constraint.
+    #[must_use]
+    pub fn n_clauses(&self) -> usize {
+        self.clause_ends.len()
+    }
+
+    /// Returns the clause at the given index.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the index is out of range.
+    #[must_use]
+    pub fn clause(&self, index: usize) -> &[i32] {
+        let start = if index == 0 {
+            0
+        } else {
+            self.clause_ends[index - 1]
+        };
+        &self.literals[start..self.clause_ends[index]]
+    }
+
+    /// Returns an iterator over the clauses used to encode the constraint.
+    #[must_use]
+    pub fn clauses(&self) -> impl ExactSizeIterator<Item = &[i32]> + '_ {
+        (0..self.n_clauses()).map(|i| self.clause(i))
+    }
+
+    /// Returns the first variable id that was allowed to be used as an auxiliary variable by the encoding.
+    ///
+    /// This is the value of the `first_aux_var` parameter given to the encoding function.
+    #[must_use]
+    pub fn first_aux_var(&self) -> i32 {
+        self.first_aux_var
+    }
+
+    /// Returns the next free variable id.
+    ///
+    /// See [`EncodingResult::next_free_var_id`](crate::EncodingResult::next_free_var_id) for more information.
+    #[must_use]
+    pub fn next_free_var_id(&self) -> i32 {
+        self.next_free_var_id
+    }
+}
+
+/// A [`ClauseSink`] that collects the clauses of an [`EncodingView`].
+#[derive(Default)]
+pub(crate) struct EncodingViewBuilder {
+    literals: Vec<i32>,
+    clause_ends: Vec<usize>,
+}
+
+impl EncodingViewBuilder {
+    pub(crate) fn build(self, first_aux_var: i32, next_free_var_id: i32) -> EncodingView {
+        EncodingView {
+            literals: self.literals,
+            clause_ends: self.clause_ends,
+            first_aux_var,
+            next_free_var_id,
+        }
+    }
+}
+
+impl ClauseSink for EncodingViewBuilder {
+    fn add_clause(&mut self, clause: &[i32]) {
+        self.literals.extend_from_slice(clause);
+        self.clause_ends.push(self.literals.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Comparator, PbConstraint, PB2CNF};
+
+    #[test]
+    fn test_view_matches_encoding_result() {
+        let pb2cnf = PB2CNF::new();
+        let view = pb2cnf.encode_leq_view(&[8, 4, 2, 1], &[1, 2, 3, 4], 6, 5);
+        let encoding = pb2cnf.encode_leq(&[8, 4, 2, 1], &[1, 2, 3, 4], 6, 5);
+        assert_eq!(encoding.clauses().len(), view.n_clauses());
+        assert!(encoding.clauses().iter().eq(view.clauses()));
+        assert_eq!(5, view.first_aux_var());
+        assert_eq!(encoding.next_free_var_id(), view.next_free_var_id());
+    }
+
+    #[test]
+    fn test_view_with_empty_clauses() {
+        let pb2cnf = PB2CNF::new();
+        let constraint = PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Geq, 3);
+        let view = pb2cnf.encode_constraint_view(&constraint, 3);
+        let encoding = pb2cnf.encode_constraint(&constraint, 3);
+        assert!(encoding.clauses().iter().eq(view.clauses()));
+        assert!(view.clauses().any(<[i32]>::is_empty));
+    }
+}
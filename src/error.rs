@@ -0,0 +1,117 @@
+use crate::EncodingStats;
+use std::fmt::Display;
+
+/// The errors that can be returned by the functions of this crate.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PbError {
+    /// An I/O error occurred while reading or writing data.
+    Io(std::io::Error),
+    /// An input text could not be parsed.
+    Parse {
+        /// The line (starting at 1) at which the error was detected.
+        line: usize,
+        /// A description of the error.
+        message: String,
+    },
+    /// The requested operation is not supported for the given input.
+    Unsupported(String),
+    /// An encoding was aborted because it exceeded its [`EncodeLimits`](crate::EncodeLimits).
+    ///
+    /// The statistics describe the part of the encoding produced before it was aborted.
+    BudgetExceeded(EncodingStats),
+    /// An encoding was cancelled by its progress callback.
+    ///
+    /// The statistics describe the part of the encoding produced before it was cancelled.
+    Cancelled(EncodingStats),
+    /// The first auxiliary variable given to an encoding function is not greater than all the variables of the constraint.
+    ///
+    /// Auxiliary variables would then collide with the variables of the constraint, producing a wrong encoding.
+    InvalidFirstAuxVar {
+        /// The first auxiliary variable given to the function.
+        first_aux_var: i32,
+        /// The greatest variable of the constraint.
+        max_var: i32,
+    },
+    /// An encoding involves a variable that is neither a variable of the constraint nor one of its auxiliary variables.
+    ///
+    /// This error is only returned when the check is enabled by [`EncoderConfig::with_var_check`](crate::EncoderConfig::with_var_check); it reveals a bug in the encoder or in the bindings.
+    InvalidOutputVar {
+        /// The first unexpected variable.
+        var: i32,
+        /// The first auxiliary variable given to the encoding function.
+        first_aux_var: i32,
+        /// The next free variable id returned by the encoding function.
+        next_free_var_id: i32,
+    },
+    /// A constraint or a clause involves a variable that is not a problem variable of an [`EncodingContext`](crate::EncodingContext).
+    ///
+    /// The value is the faulty literal.
+    UndeclaredVar(i32),
+    /// The weights or the bound of a constraint are too large: the values computed during the encoding would overflow 64-bit integers.
+    Overflow(String),
+    /// A call to the underlying C++ library failed, e.g. because an allocation failed or because it returned malformed data.
+    Ffi(String),
+}
+
+impl Display for PbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PbError::Io(e) => write!(f, "I/O error: {e}"),
+            PbError::Parse { line, message } => write!(f, "parse error at line {line}: {message}"),
+            PbError::Unsupported(what) => write!(f, "unsupported operation: {what}"),
+            PbError::BudgetExceeded(stats) => write!(
+                f,
+                "encoding budget exceeded after {} clauses and {} auxiliary variables",
+                stats.n_clauses(),
+                stats.n_aux_vars()
+            ),
+            PbError::InvalidFirstAuxVar {
+                first_aux_var,
+                max_var,
+            } => write!(
+                f,
+                "invalid first auxiliary variable {first_aux_var}: the constraint involves variable {max_var}"
+            ),
+            PbError::InvalidOutputVar {
+                var,
+                first_aux_var,
+                next_free_var_id,
+            } => write!(
+                f,
+                "variable {var} of the encoding is neither a variable of the constraint nor an auxiliary variable in [{first_aux_var}, {next_free_var_id})"
+            ),
+            PbError::UndeclaredVar(l) => {
+                write!(f, "literal {l} does not refer to a problem variable")
+            }
+            PbError::Overflow(what) => write!(f, "integer overflow: {what}"),
+            PbError::Ffi(what) => write!(f, "FFI error: {what}"),
+            PbError::Cancelled(stats) => {
+                write!(f, "encoding cancelled after {} clauses", stats.n_clauses())
+            }
+        }
+    }
+}
+
+impl std::error::Error for PbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PbError::Io(e) => Some(e),
+            PbError::Parse { .. }
+            | PbError::Unsupported(_)
+            | PbError::BudgetExceeded(_)
+            | PbError::Cancelled(_)
+            | PbError::InvalidFirstAuxVar { .. }
+            | PbError::InvalidOutputVar { .. }
+            | PbError::UndeclaredVar(_)
+            | PbError::Overflow(_)
+            | PbError::Ffi(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PbError {
+    fn from(e: std::io::Error) -> Self {
+        PbError::Io(e)
+    }
+}
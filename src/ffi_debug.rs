@@ -0,0 +1,93 @@
+#[cfg(feature = "ffi-debug")]
+use std::{collections::HashMap, sync::Mutex};
+
+/// The pointers that crossed the FFI boundary and are still alive, associated with the kind of object they point to.
+#[cfg(feature = "ffi-debug")]
+static LIVE_POINTERS: Mutex<Option<HashMap<usize, &'static str>>> = Mutex::new(None);
+
+#[cfg(feature = "ffi-debug")]
+fn with_live_pointers<T>(f: impl FnOnce(&mut HashMap<usize, &'static str>) -> T) -> T {
+    let mut live_pointers = LIVE_POINTERS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    f(live_pointers.get_or_insert_with(HashMap::new))
+}
+
+/// Records a pointer returned by an allocation function of the FFI.
+///
+/// Null pointers are ignored.
+/// This function does nothing unless the `ffi-debug` feature is enabled.
+pub(crate) fn track<T>(ptr: *const T, kind: &'static str) {
+    #[cfg(feature = "ffi-debug")]
+    if !ptr.is_null() {
+        if let Some(previous) = with_live_pointers(|p| p.insert(ptr as usize, kind)) {
+            panic!("FFI debug: {kind} pointer {ptr:?} allocated while a {previous} pointer at the same address is alive");
+        }
+    }
+    #[cfg(not(feature = "ffi-debug"))]
+    let _ = (ptr, kind);
+}
+
+/// Checks that a pointer given to a function of the FFI is alive, and points to the expected kind of object.
+///
+/// This function does nothing unless the `ffi-debug` feature is enabled.
+///
+/// # Panics
+///
+/// This function panics if the pointer is unknown, already freed, or of another kind.
+pub(crate) fn check<T>(ptr: *const T, kind: &'static str) {
+    #[cfg(feature = "ffi-debug")]
+    match with_live_pointers(|p| p.get(&(ptr as usize)).copied()) {
+        Some(k) if k == kind => {}
+        Some(k) => panic!("FFI debug: {ptr:?} is used as a {kind} pointer, but points to a {k}"),
+        None => panic!("FFI debug: use of an unknown or freed {kind} pointer {ptr:?}"),
+    }
+    #[cfg(not(feature = "ffi-debug"))]
+    let _ = (ptr, kind);
+}
+
+/// Forgets a pointer before it is given to a deallocation function of the FFI.
+///
+/// Null pointers are ignored.
+/// This function does nothing unless the `ffi-debug` feature is enabled.
+///
+/// # Panics
+///
+/// This function panics if the pointer is unknown, already freed, or of another kind.
+pub(crate) fn untrack<T>(ptr: *const T, kind: &'static str) {
+    #[cfg(feature = "ffi-debug")]
+    if !ptr.is_null() {
+        match with_live_pointers(|p| p.remove(&(ptr as usize))) {
+            Some(k) if k == kind => {}
+            Some(k) => {
+                panic!("FFI debug: {ptr:?} is freed as a {kind} pointer, but points to a {k}")
+            }
+            None => panic!(
+                "FFI debug: double free (or free of an unknown pointer) of {kind} pointer {ptr:?}"
+            ),
+        }
+    }
+    #[cfg(not(feature = "ffi-debug"))]
+    let _ = (ptr, kind);
+}
+
+#[cfg(all(test, feature = "ffi-debug"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_track() {
+        let value = Box::into_raw(Box::new(0_u8));
+        track(value, "byte");
+        check(value, "byte");
+        untrack(value, "byte");
+        assert!(std::panic::catch_unwind(|| check(value, "byte")).is_err());
+        assert!(std::panic::catch_unwind(|| untrack(value, "byte")).is_err());
+        track(value, "byte");
+        assert!(std::panic::catch_unwind(|| check(value, "other")).is_err());
+        untrack(value, "byte");
+        drop(unsafe { Box::from_raw(value) });
+        track(std::ptr::null::<u8>(), "byte");
+        untrack(std::ptr::null::<u8>(), "byte");
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{cpblib::pblib_lock, ffi_debug, Comparator, PbConstraint, PbError};
+use std::ffi::c_void;
+
+/// A generator of random constraints, built upon pblib's `PBFuzzer`.
+///
+/// This structure produces the random cardinality and Pseudo-Boolean constraints pblib uses to test its own encoders.
+/// It is intended to check the soundness of the encodings, e.g. by comparing the models of the encodings with the ones of the constraints.
+/// The `fuzz` directory of the repository contains a `cargo-fuzz` target doing so with the bundled [`MiniSat`](crate::MiniSat) solver.
+///
+/// The generation relies on the C random generator, which is shared by the whole process; the calls to pblib are thus serialized by a global lock (see [`PB2CNF`](crate::PB2CNF#thread-safety)).
+/// The constraints are deterministic: two fuzzers built with the same parameters produce the same sequence of constraints.
+/// Since the C random generator differs between C libraries, the sequence may however change from a platform to another.
+/// pblib may generate constraints with both a lower and an upper bound; such constraints are returned as a couple of constraints, hence the vectors returned by [`generate`](Self::generate).
+///
+/// ```
+/// use pblib_rs::{ConstraintFuzzer, PB2CNF};
+///
+/// let mut fuzzer = ConstraintFuzzer::new(0, 20);
+/// let pb2cnf = PB2CNF::new();
+/// for _ in 0..10 {
+///     for constraint in fuzzer.generate() {
+///         let encoding = pb2cnf.encode_constraint(&constraint, 21);
+///         assert!(encoding.next_free_var_id() >= 21);
+///     }
+/// }
+/// ```
+pub struct ConstraintFuzzer {
+    ptr: *mut c_void,
+    seed: u32,
+    n_generated: u32,
+}
+
+const FUZZER_KIND: &str = "PBFuzzer";
+
+impl ConstraintFuzzer {
+    /// Builds a new fuzzer given a seed and the number of variables the constraints may involve.
+    ///
+    /// The variables involved in the constraints range from `1` to `n_vars`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables is not positive, or if the underlying C++ object cannot be allocated.
+    #[must_use]
+    pub fn new(seed: u32, n_vars: i32) -> Self {
+        assert!(n_vars > 0, "the number of variables must be positive");
+        let ptr = {
+            let _lock = pblib_lock();
+            unsafe { newPBFuzzer(n_vars) }
+        };
+        assert!(!ptr.is_null(), "cannot allocate the pblib fuzzer");
+        ffi_debug::track(ptr, FUZZER_KIND);
+        Self {
+            ptr,
+            seed,
+            n_generated: 0,
+        }
+    }
+
+    /// Generates a new random constraint.
+    ///
+    /// The returned vector contains a single constraint, except if pblib generated a constraint with both a lower and an upper bound.
+    /// In this case, it contains a `>=` and a `<=` constraint, which must hold together.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if pblib fails to return a constraint; see [`try_generate`](Self::try_generate).
+    pub fn generate(&mut self) -> Vec<PbConstraint> {
+        self.try_generate().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Generates a new random constraint, returning an error if pblib fails to return it.
+    ///
+    /// See [`generate`](Self::generate) for more information on the returned constraints.
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::Ffi`] error is returned if the constraint cannot be allocated on the C++ side, or if the data returned by pblib is malformed.
+    pub fn try_generate(&mut self) -> Result<Vec<PbConstraint>, PbError> {
+        let seed = self
+            .seed
+            .wrapping_add(self.n_generated.wrapping_mul(0x9E37_79B9));
+        self.n_generated = self.n_generated.wrapping_add(1);
+        let buffer = {
+            let _lock = pblib_lock();
+            ffi_debug::check(self.ptr, FUZZER_KIND);
+            unsafe { fuzzerGenerateConstraint(self.ptr, seed) }
+        };
+        if buffer.is_null() {
+            return Err(PbError::Ffi(
+                "cannot allocate the generated constraint".to_string(),
+            ));
+        }
+        let constraints = unsafe {
+            match usize::try_from(*buffer) {
+                Ok(len) if len >= 4 && len % 2 == 0 => {
+                    decode_constraint(std::slice::from_raw_parts(buffer, len))
+                }
+                _ => Err(PbError::Ffi(format!(
+                    "invalid length {} in the header of a generated constraint",
+                    *buffer
+                ))),
+            }
+        };
+        unsafe { freeI64Ptr(buffer) };
+        constraints
+    }
+}
+
+fn decode_constraint(buffer: &[i64]) -> Result<Vec<PbConstraint>, PbError> {
+    let terms = buffer[4..]
+        .chunks_exact(2)
+        .map(|t| {
+            i32::try_from(t[1])
+                .ok()
+                .filter(|l| *l != 0)
+                .map(|l| (t[0], l))
+                .ok_or_else(|| {
+                    PbError::Ffi(format!(
+                        "invalid literal {} in a generated constraint",
+                        t[1]
+                    ))
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let (leq, geq) = (buffer[2], buffer[3]);
+    match buffer[1] {
+        0 => Ok(vec![PbConstraint::new(terms, Comparator::Leq, leq)]),
+        1 => Ok(vec![PbConstraint::new(terms, Comparator::Geq, geq)]),
+        2 if leq == geq => Ok(vec![PbConstraint::new(terms, Comparator::Eq, leq)]),
+        2 => Ok(vec![
+            PbConstraint::new(terms.clone(), Comparator::Geq, geq),
+            PbConstraint::new(terms, Comparator::Leq, leq),
+        ]),
+        c => Err(PbError::Ffi(format!(
+            "invalid comparator {c} in a generated constraint"
+        ))),
+    }
+}
+
+// SAFETY: the underlying C++ object is only accessed while holding the global pblib lock.
+unsafe impl Send for ConstraintFuzzer {}
+
+impl Drop for ConstraintFuzzer {
+    fn drop(&mut self) {
+        let _lock = pblib_lock();
+        ffi_debug::untrack(self.ptr, FUZZER_KIND);
+        unsafe { deletePBFuzzer(self.ptr) }
+    }
+}
+
+extern "C" {
+    fn newPBFuzzer(n_vars: i32) -> *mut c_void;
+
+    fn fuzzerGenerateConstraint(ptr: *mut c_void, seed: u32) -> *mut i64;
+
+    fn deletePBFuzzer(ptr: *mut c_void);
+
+    fn freeI64Ptr(ptr: *mut i64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{reference, EncoderConfig, MiniSat, PbEncoder, PB2CNF};
+
+    const N_VARS: i32 = 12;
+
+    #[test]
+    fn test_deterministic() {
+        let mut f1 = ConstraintFuzzer::new(42, N_VARS);
+        let mut f2 = ConstraintFuzzer::new(42, N_VARS);
+        for _ in 0..10 {
+            let constraints = f1.generate();
+            assert_eq!(constraints, f2.generate());
+            assert!(constraints
+                .iter()
+                .flat_map(PbConstraint::literals)
+                .all(|l| l != 0 && l.abs() <= N_VARS));
+        }
+    }
+
+    #[test]
+    fn test_generation_counter_wraps() {
+        let mut fuzzer = ConstraintFuzzer::new(42, N_VARS);
+        fuzzer.n_generated = u32::MAX;
+        let last = fuzzer.generate();
+        assert_eq!(0, fuzzer.n_generated);
+        assert_eq!(
+            ConstraintFuzzer::new(42, N_VARS).generate(),
+            fuzzer.generate()
+        );
+        assert!(!last.is_empty());
+    }
+
+    #[test]
+    fn test_decode_range() {
+        let constraints = decode_constraint(&[8, 2, 3, 1, 1, 1, 2, -2]).unwrap();
+        assert_eq!(
+            vec![
+                PbConstraint::new(vec![(1, 1), (2, -2)], Comparator::Geq, 1),
+                PbConstraint::new(vec![(1, 1), (2, -2)], Comparator::Leq, 3),
+            ],
+            constraints
+        );
+    }
+
+    #[test]
+    fn test_decode_malformed() {
+        assert!(matches!(
+            decode_constraint(&[6, 3, 1, 1, 1, 1]),
+            Err(PbError::Ffi(_))
+        ));
+        assert!(matches!(
+            decode_constraint(&[6, 0, 1, 1, 1, 0]),
+            Err(PbError::Ffi(_))
+        ));
+        assert!(matches!(
+            decode_constraint(&[6, 0, 1, 1, 1, i64::MAX]),
+            Err(PbError::Ffi(_))
+        ));
+    }
+
+    #[test]
+    fn test_encodings_are_sound() {
+        let mut fuzzer = ConstraintFuzzer::new(0, N_VARS);
+        let encoders = [
+            PbEncoder::Best,
+            PbEncoder::Bdd,
+            PbEncoder::SortingNetworks,
+            PbEncoder::Adder,
+            PbEncoder::BinaryMerge,
+        ];
+        for i in 0..100 {
+            let constraints = fuzzer.generate();
+            let config = EncoderConfig::default().with_pb_encoder(encoders[i % encoders.len()]);
+            let pb2cnf = PB2CNF::with_config(config);
+            let mut solver = MiniSat::new();
+            let mut first_aux_var = N_VARS + 1;
+            for c in &constraints {
+                first_aux_var = pb2cnf.encode_constraint_into(c, first_aux_var, &mut solver);
+            }
+            for bits in (0_u32..1 << N_VARS).step_by(97) {
+                let assignment = (1..=N_VARS)
+                    .map(|v| if bits & (1 << (v - 1)) == 0 { -v } else { v })
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    constraints
+                        .iter()
+                        .all(|c| reference::evaluate(c, &assignment)),
+                    solver.solve_with_assumptions(&assignment),
+                    "wrong encoding for {constraints:?} under {assignment:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use crate::{ClauseSink, EncoderConfig, EncodingResult, PB2CNF};
+
+/// The interface of incremental SAT solvers, as defined by the IPASIR standard.
+///
+/// This trait mirrors the functions of the IPASIR C API (`ipasir_add`, `ipasir_assume`, etc.).
+/// Implementing it for a binding to an IPASIR-compatible solver (`CaDiCaL`, `Kissat`, `Glucose`, ...) allows to stream encodings into this solver with [`load_into`] and [`IpasirSink`].
+pub trait Ipasir {
+    /// Adds a literal to the clause under construction, or terminates it if the literal is `0`.
+    fn add(&mut self, lit_or_zero: i32);
+
+    /// Adds an assumption for the next call to [`solve`](Self::solve).
+    fn assume(&mut self, lit: i32);
+
+    /// Solves the formula under the current assumptions, and clears the assumptions.
+    ///
+    /// Following the IPASIR standard, returns `10` if the formula is satisfiable, `20` if it is unsatisfiable, and `0` if the search was interrupted.
+    fn solve(&mut self) -> i32;
+
+    /// Returns the value of a literal in the model found by the last call to [`solve`](Self::solve).
+    ///
+    /// Following the IPASIR standard, returns `lit` if the literal is true, `-lit` if it is false, and `0` if its value is not relevant.
+    fn val(&mut self, lit: i32) -> i32;
+
+    /// Returns `true` if the assumption `lit` was used to prove the unsatisfiability in the last call to [`solve`](Self::solve).
+    fn failed(&mut self, lit: i32) -> bool;
+}
+
+/// A [`ClauseSink`] adding the clauses to an IPASIR solver.
+///
+/// This sink allows the encoding functions ending with `_into` to send their clauses directly to a solver implementing [`Ipasir`].
+/// An activation literal can be set: in this case, its negation is added to each clause, so the clauses are only active when the activation literal is assumed.
+/// This allows to add and retract constraints in incremental solving, like the bounds of an optimization loop.
+pub struct IpasirSink<'a, S> {
+    solver: &'a mut S,
+    activation_literal: Option<i32>,
+}
+
+impl<'a, S> IpasirSink<'a, S>
+where
+    S: Ipasir,
+{
+    /// Builds a sink adding the clauses to the given solver.
+    pub fn new(solver: &'a mut S) -> Self {
+        Self {
+            solver,
+            activation_literal: None,
+        }
+    }
+
+    /// Builds a sink adding the clauses to the given solver, guarded by an activation literal.
+    ///
+    /// The clauses are only enforced when `activation_literal` is assumed (or set) to true.
+    pub fn with_activation_literal(solver: &'a mut S, activation_literal: i32) -> Self {
+        Self {
+            solver,
+            activation_literal: Some(activation_literal),
+        }
+    }
+}
+
+impl<S> ClauseSink for IpasirSink<'_, S>
+where
+    S: Ipasir,
+{
+    fn add_clause(&mut self, clause: &[i32]) {
+        for l in clause {
+            self.solver.add(*l);
+        }
+        if let Some(a) = self.activation_literal {
+            self.solver.add(-a);
+        }
+        self.solver.add(0);
+    }
+}
+
+/// Adds the clauses of an encoding to an IPASIR solver.
+///
+/// ```
+/// use pblib_rs::{load_into, Ipasir, PB2CNF};
+///
+/// fn check_at_most_one<S: Ipasir>(solver: &mut S) -> bool {
+///     let encoding = PB2CNF::new().encode_at_most_k(&[1, 2, 3], 1, 4);
+///     load_into(solver, &encoding);
+///     solver.assume(1);
+///     solver.assume(2);
+///     solver.solve() == 20
+/// }
+/// ```
+pub fn load_into(solver: &mut impl Ipasir, encoding: &EncodingResult) {
+    let mut sink = IpasirSink::new(solver);
+    encoding.iter().for_each(|c| sink.add_clause(c));
+}
+
+/// Adds the clauses of an encoding to an IPASIR solver, guarded by an activation literal.
+///
+/// The negation of the activation literal is added to each clause.
+/// Thus, the encoded constraint is only enforced when the activation literal is assumed to be true; adding the unit clause made of its negation retracts the constraint definitively.
+/// The activation literal should not be involved in the encoding, and should be a fresh variable, e.g. the next free variable id of the encoding.
+///
+/// ```
+/// use pblib_rs::{load_into_with_activation_literal, Ipasir, PB2CNF};
+///
+/// fn check_bound<S: Ipasir>(solver: &mut S, objective: &[(i64, i32)], bound: i64, first_aux_var: i32) -> (bool, i32) {
+///     let encoding = PB2CNF::new().encode_objective_bound(objective, bound, first_aux_var);
+///     let activation_literal = encoding.next_free_var_id();
+///     load_into_with_activation_literal(solver, &encoding, activation_literal);
+///     solver.assume(activation_literal);
+///     (solver.solve() == 10, activation_literal + 1)
+/// }
+/// ```
+pub fn load_into_with_activation_literal(
+    solver: &mut impl Ipasir,
+    encoding: &EncodingResult,
+    activation_literal: i32,
+) {
+    let mut sink = IpasirSink::with_activation_literal(solver, activation_literal);
+    encoding.iter().for_each(|c| sink.add_clause(c));
+}
+
+/// A driver minimizing an objective function with an IPASIR solver, by bounds given as assumptions.
+///
+/// The search is a SAT-UNSAT one: each time a model is found, a constraint stating that the objective function is strictly lower than its cost is encoded, guarded by a fresh activation literal, and the solver is called again under the assumption of this literal.
+/// When the bound is satisfiable, the activation literal is set to true definitively, so the bound is strengthened without retracting the previous ones; when it is not, the activation literal is set to false, and the cost of the last model is the optimum.
+/// Since the bounds are never added as plain clauses, the solver keeps its learnt clauses across the calls, and remains satisfiable at the end of the search (e.g. to enumerate the optimal models).
+///
+/// The clauses of the hard constraints must be added to the solver before the search starts.
+///
+/// ```
+/// use pblib_rs::{load_into, BoundSearch, Ipasir, PB2CNF};
+///
+/// fn minimize<S: Ipasir>(solver: &mut S) -> Option<i64> {
+///     // at least two of x1, x2 and x3 are true
+///     let encoding = PB2CNF::new().encode_at_least_k(&[1, 2, 3], 2, 4);
+///     load_into(solver, &encoding);
+///     let mut search = BoundSearch::new(&[(3, 1), (2, 2), (1, 3)], encoding.next_free_var_id());
+///     search.run(solver)
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundSearch {
+    objective: Vec<(i64, i32)>,
+    config: EncoderConfig,
+    first_aux_var: i32,
+    next_free_var_id: i32,
+    best_model: Option<Vec<i32>>,
+    best_cost: Option<i64>,
+}
+
+impl BoundSearch {
+    /// Builds a driver minimizing the objective function given as a list of terms, each of them being a couple composed of a weight and a literal.
+    ///
+    /// The activation literals and the auxiliary variables of the bounds are numbered from `first_aux_var`, which must be greater than the variables of the formula loaded into the solver.
+    #[must_use]
+    pub fn new(objective: &[(i64, i32)], first_aux_var: i32) -> Self {
+        Self {
+            objective: objective.to_vec(),
+            config: EncoderConfig::default(),
+            first_aux_var,
+            next_free_var_id: first_aux_var,
+            best_model: None,
+            best_cost: None,
+        }
+    }
+
+    /// Sets the configuration used to encode the bounds.
+    #[must_use]
+    pub fn with_config(mut self, config: &EncoderConfig) -> Self {
+        self.config = *config;
+        self
+    }
+
+    /// Runs the search, and returns the optimal cost.
+    ///
+    /// `None` is returned if the formula is unsatisfiable, or if the solver was interrupted.
+    /// In the latter case, [`best_cost`](Self::best_cost) gives the cost of the best model found so far, and the search can be resumed by a new call to this function.
+    pub fn run(&mut self, solver: &mut impl Ipasir) -> Option<i64> {
+        let pb2cnf = PB2CNF::with_config(self.config);
+        let lower_bound = self.objective.iter().map(|t| t.0.min(0)).sum::<i64>();
+        if self.best_cost.is_none() {
+            match solver.solve() {
+                10 => self.record_model(solver),
+                _ => return None,
+            }
+        }
+        loop {
+            let best_cost = self.best_cost?;
+            if best_cost <= lower_bound {
+                return Some(best_cost);
+            }
+            let activation_literal = self.next_free_var_id;
+            let mut sink = IpasirSink::with_activation_literal(solver, activation_literal);
+            self.next_free_var_id = pb2cnf.encode_objective_bound_into(
+                &self.objective,
+                best_cost - 1,
+                activation_literal + 1,
+                &mut sink,
+            );
+            solver.assume(activation_literal);
+            match solver.solve() {
+                10 => {
+                    self.record_model(solver);
+                    solver.add(activation_literal);
+                    solver.add(0);
+                }
+                20 => {
+                    solver.add(-activation_literal);
+                    solver.add(0);
+                    return Some(best_cost);
+                }
+                _ => {
+                    // the bound is retracted, and encoded again if the search is resumed
+                    solver.add(-activation_literal);
+                    solver.add(0);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Returns the cost of the best model found so far, if any.
+    #[must_use]
+    pub fn best_cost(&self) -> Option<i64> {
+        self.best_cost
+    }
+
+    /// Returns the best model found so far, if any.
+    ///
+    /// The model is given as a list of DIMACS literals, one for each variable lower than the `first_aux_var` given to [`new`](Self::new); variables whose value was not relevant are set to false, and the cost is computed accordingly.
+    #[must_use]
+    pub fn best_model(&self) -> Option<&[i32]> {
+        self.best_model.as_deref()
+    }
+
+    /// Returns the lowest variable index that is not used by the activation literals and the bounds.
+    #[must_use]
+    pub fn next_free_var_id(&self) -> i32 {
+        self.next_free_var_id
+    }
+
+    fn record_model(&mut self, solver: &mut impl Ipasir) {
+        let mut is_true = |l: i32| (solver.val(l.abs()) == l.abs()) == (l > 0);
+        let model = (1..self.first_aux_var)
+            .map(|v| if is_true(v) { v } else { -v })
+            .collect::<Vec<_>>();
+        let cost = self
+            .objective
+            .iter()
+            .filter(|(_, l)| is_true(*l))
+            .map(|(w, _)| w)
+            .sum();
+        self.best_model = Some(model);
+        self.best_cost = Some(cost);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MiniSat, PB2CNF};
+
+    #[derive(Default)]
+    struct MiniSatIpasir {
+        solver: MiniSat,
+        clause: Vec<i32>,
+        assumptions: Vec<i32>,
+        model: Vec<i32>,
+    }
+
+    impl Ipasir for MiniSatIpasir {
+        fn add(&mut self, lit_or_zero: i32) {
+            if lit_or_zero == 0 {
+                self.solver.add_clause(&self.clause);
+                self.clause.clear();
+            } else {
+                self.clause.push(lit_or_zero);
+            }
+        }
+
+        fn assume(&mut self, lit: i32) {
+            self.assumptions.push(lit);
+        }
+
+        fn solve(&mut self) -> i32 {
+            let result = self.solver.solve_with_assumptions(&self.assumptions);
+            self.assumptions.clear();
+            self.model = self.solver.model().unwrap_or_default();
+            if result {
+                10
+            } else {
+                20
+            }
+        }
+
+        fn val(&mut self, lit: i32) -> i32 {
+            self.model
+                .get(lit.unsigned_abs() as usize - 1)
+                .map_or(0, |l| {
+                    if l.signum() == lit.signum() {
+                        lit
+                    } else {
+                        -lit
+                    }
+                })
+        }
+
+        fn failed(&mut self, _lit: i32) -> bool {
+            // MiniSat does not report the assumptions involved in the final conflict, and no test relies on them
+            false
+        }
+    }
+
+    #[test]
+    fn test_load_into() {
+        let mut solver = MiniSatIpasir::default();
+        let encoding = PB2CNF::new().encode_at_least_k(&[1, 2, 3], 2, 4);
+        load_into(&mut solver, &encoding);
+        solver.assume(-1);
+        assert_eq!(10, solver.solve());
+        assert_eq!(2, solver.val(2));
+        assert_eq!(3, solver.val(3));
+        solver.assume(-1);
+        solver.assume(-2);
+        assert_eq!(20, solver.solve());
+    }
+
+    #[test]
+    fn test_activation_literal() {
+        let mut solver = MiniSatIpasir::default();
+        let pb2cnf = PB2CNF::new();
+        let at_least = pb2cnf.encode_at_least_k(&[1, 2, 3], 2, 4);
+        load_into(&mut solver, &at_least);
+        let at_most = pb2cnf.encode_at_most_k(&[1, 2, 3], 1, at_least.next_free_var_id());
+        let activation_literal = at_most.next_free_var_id();
+        load_into_with_activation_literal(&mut solver, &at_most, activation_literal);
+        solver.assume(activation_literal);
+        assert_eq!(20, solver.solve());
+        assert_eq!(10, solver.solve());
+        solver.add(-activation_literal);
+        solver.add(0);
+        assert_eq!(10, solver.solve());
+    }
+
+    #[test]
+    fn test_bound_search() {
+        let mut solver = MiniSatIpasir::default();
+        let encoding = PB2CNF::new().encode_at_least_k(&[1, 2, 3], 2, 4);
+        load_into(&mut solver, &encoding);
+        let mut search = BoundSearch::new(&[(3, 1), (2, 2), (1, 3)], 4);
+        assert_eq!(Some(3), search.run(&mut solver));
+        assert_eq!(Some(3), search.best_cost());
+        assert_eq!(Some(&[-1, 2, 3][..]), search.best_model());
+        assert!(search.next_free_var_id() > 4);
+        // the bounds that were not satisfiable are retracted
+        assert_eq!(10, solver.solve());
+    }
+
+    #[test]
+    fn test_bound_search_negative_weights() {
+        let mut solver = MiniSatIpasir::default();
+        solver.solver.add_clause(&[-1, -3]);
+        solver.solver.add_clause(&[-1, 2]);
+        let mut search = BoundSearch::new(&[(-2, 1), (1, 2), (-1, 3)], 4);
+        assert_eq!(Some(-1), search.run(&mut solver));
+    }
+
+    #[test]
+    fn test_bound_search_unsat() {
+        let mut solver = MiniSatIpasir::default();
+        solver.solver.add_clause(&[1]);
+        solver.solver.add_clause(&[-1]);
+        let mut search = BoundSearch::new(&[(1, 1)], 2);
+        assert_eq!(None, search.run(&mut solver));
+        assert_eq!(None, search.best_cost());
+    }
+
+    #[test]
+    fn test_sink() {
+        let mut solver = MiniSatIpasir::default();
+        let mut sink = IpasirSink::new(&mut solver);
+        PB2CNF::new().encode_at_most_k_into(&[1, 2], 0, 3, &mut sink);
+        solver.assume(1);
+        assert_eq!(20, solver.solve());
+    }
+}
@@ -18,7 +18,7 @@
 //! let literals = vec![1, 2, 3];
 //! let pb2cnf = PB2CNF::new();
 //! // the threshold is 2 and the first variable not in use is 4
-//! let encoding = pb2cnf.encode_at_least_k(literals, 2, 4);
+//! let encoding = pb2cnf.encode_at_least_k(&literals, 2, 4);
 //! println!("the encoding uses {} variables", encoding.next_free_var_id() - 4);
 //! println!("the encoding uses {} clauses", encoding.clauses().len());
 //! encoding.clauses().iter().enumerate().for_each(|(i,c)| println!("clause {i} is {:?}", c));
@@ -34,17 +34,241 @@
 //! let literals = vec![1, 2, 3, 4];
 //! let pb2cnf = PB2CNF::new();
 //! // the threshold is 6 and the first variable not in use is 5
-//! let encoding = pb2cnf.encode_geq(weights.clone(), literals, 6, 5);
+//! let encoding = pb2cnf.encode_geq(&weights, &literals, 6, 5);
 //! println!("the encoding uses {} variables", encoding.next_free_var_id() - 4);
 //! println!("the encoding uses {} clauses", encoding.clauses().len());
 //! encoding.clauses().iter().enumerate().for_each(|(i,c)| println!("clause {i} is {:?}", c));
 //! ```
 //!
+//! # Features
+//!
+//! The `native` feature provides the `native` module, made of encoders written in Rust that do not rely on pblib.
+//!
+//! The `std` feature is enabled by default, and everything but the native cardinality encoders requires it.
+//! Without it, the crate is `no_std` (it only requires the `alloc` crate) and pblib is not built: combined with the `native` feature, it provides the native cardinality and At-Most-One encoders, for projects that cannot rely on the standard library or on the C++ runtime.
+//! The other features require the `std` feature.
+//!
+//! The `capi` feature exports a C interface, described by the `include/pblib_rs.h` header.
+//! Build the crate as a static or a dynamic library (e.g. with `cargo rustc --release --features capi --crate-type staticlib`) to use it from other languages.
+//!
+//! The `ipasir` feature provides the `Ipasir` trait and helpers to load encodings into incremental SAT solvers following the IPASIR interface, and the `BoundSearch` driver minimizing an objective function with such solvers.
+//!
+//! The `splr` feature provides helpers to check encodings with the [splr](https://crates.io/crates/splr) SAT solver, like `count_models_projected`.
+//!
+//! The `ffi-debug` feature helps diagnosing bugs at the boundary with the C++ code.
+//! The C++ code is compiled with the address and undefined behavior sanitizers of GCC/Clang, and with assertions enabled; the objects shared with Rust carry magic numbers checked at each call.
+//! On the Rust side, the pointers crossing the boundary are recorded, so that a double free or the use of a freed object (including through the `capi` interface) triggers a panic instead of a silent memory corruption.
+//! This feature slows the encodings down, and is not intended for production builds.
+//!
 //! # License
 //!
 //! pblib-rs is developed at CRIL (Univ. Artois & CNRS).
 //! It is made available under the terms of the GNU Lesser GPLv3 license.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod assignment;
+
+#[cfg(feature = "std")]
+mod backend;
+#[cfg(feature = "std")]
+pub use backend::PbEncoderBackend;
+
+#[cfg(feature = "std")]
+mod bool_expr;
+#[cfg(feature = "std")]
+pub use bool_expr::BoolExpr;
+
+#[cfg(feature = "capi")]
+mod capi;
+
+mod clause_sink;
+pub use clause_sink::ClauseSink;
+
+#[cfg(feature = "std")]
+mod cnf_formula;
+#[cfg(feature = "std")]
+pub use cnf_formula::CnfFormula;
+
+#[cfg(feature = "std")]
+mod cnf_instance;
+#[cfg(feature = "std")]
+pub use cnf_instance::encode_instance;
+#[cfg(feature = "std")]
+pub use cnf_instance::encode_instance_with_provenance;
+#[cfg(feature = "std")]
+pub use cnf_instance::try_encode_instance;
+#[cfg(feature = "std")]
+pub use cnf_instance::CnfInstance;
+
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "std")]
+pub use config::AmkEncoder;
+#[cfg(feature = "std")]
+pub use config::AmoEncoder;
+#[cfg(feature = "std")]
+pub use config::EncodeLimits;
+#[cfg(feature = "std")]
+pub use config::EncoderConfig;
+#[cfg(feature = "std")]
+pub use config::PbEncoder;
+
+#[cfg(feature = "std")]
+mod cost_model;
+#[cfg(feature = "std")]
+pub use cost_model::CostModel;
+
+#[cfg(feature = "std")]
 mod cpblib;
-pub use cpblib::EncodingResult;
+#[cfg(feature = "std")]
 pub use cpblib::PB2CNF;
+
+#[cfg(feature = "std")]
+mod dimacs;
+
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "std")]
+pub use error::PbError;
+
+#[cfg(feature = "std")]
+mod ffi_debug;
+
+#[cfg(feature = "std")]
+mod fuzzer;
+#[cfg(feature = "std")]
+pub use fuzzer::ConstraintFuzzer;
+
+#[cfg(feature = "std")]
+mod encoding_cache;
+#[cfg(feature = "std")]
+pub use encoding_cache::EncodingCache;
+
+#[cfg(feature = "std")]
+mod encoding_context;
+#[cfg(feature = "std")]
+pub use encoding_context::EncodingContext;
+
+#[cfg(feature = "std")]
+mod encoding_result;
+#[cfg(feature = "std")]
+pub use encoding_result::EncodingOutcome;
+#[cfg(feature = "std")]
+pub use encoding_result::EncodingResult;
+#[cfg(feature = "std")]
+pub use encoding_result::EncodingStats;
+
+#[cfg(feature = "std")]
+mod encoding_template;
+#[cfg(feature = "std")]
+pub use encoding_template::EncodingTemplate;
+
+mod encoding_trace;
+pub use encoding_trace::EncodingTrace;
+
+#[cfg(feature = "std")]
+mod encoding_view;
+#[cfg(feature = "std")]
+pub use encoding_view::EncodingView;
+
+#[cfg(feature = "ipasir")]
+mod ipasir;
+#[cfg(feature = "ipasir")]
+pub use ipasir::load_into;
+#[cfg(feature = "ipasir")]
+pub use ipasir::load_into_with_activation_literal;
+#[cfg(feature = "ipasir")]
+pub use ipasir::BoundSearch;
+#[cfg(feature = "ipasir")]
+pub use ipasir::Ipasir;
+#[cfg(feature = "ipasir")]
+pub use ipasir::IpasirSink;
+
+#[cfg(feature = "std")]
+mod lin_expr;
+#[cfg(feature = "std")]
+pub use lin_expr::LinExpr;
+
+#[cfg(feature = "std")]
+mod macros;
+
+#[cfg(feature = "std")]
+mod minisat;
+#[cfg(feature = "std")]
+pub use minisat::MiniSat;
+
+#[cfg(feature = "native")]
+pub mod native;
+
+#[cfg(feature = "std")]
+mod opb;
+#[cfg(feature = "std")]
+pub use opb::OpbInstance;
+
+#[cfg(feature = "std")]
+mod optimize;
+#[cfg(feature = "std")]
+pub use optimize::optimize;
+#[cfg(feature = "std")]
+pub use optimize::SearchStrategy;
+#[cfg(feature = "std")]
+pub use optimize::Solution;
+
+#[cfg(feature = "std")]
+mod pb_constraint;
+#[cfg(feature = "std")]
+pub use pb_constraint::Comparator;
+#[cfg(feature = "std")]
+pub use pb_constraint::PbConstraint;
+
+#[cfg(feature = "std")]
+mod progress;
+#[cfg(feature = "std")]
+pub use progress::Progress;
+
+#[cfg(feature = "std")]
+pub mod reference;
+
+#[cfg(feature = "std")]
+mod simplify;
+
+#[cfg(feature = "splr")]
+mod splr_support;
+#[cfg(feature = "splr")]
+pub use splr_support::count_models_projected;
+#[cfg(feature = "splr")]
+pub use splr_support::to_splr_input;
+
+#[cfg(feature = "std")]
+mod symbol_table;
+#[cfg(feature = "std")]
+pub use symbol_table::SymbolTable;
+
+#[cfg(feature = "std")]
+pub mod testing;
+
+#[cfg(feature = "std")]
+mod trivial;
+
+#[cfg(feature = "std")]
+mod var_pool;
+#[cfg(feature = "std")]
+pub use var_pool::VarPool;
+
+#[cfg(feature = "std")]
+mod veripb;
+#[cfg(feature = "std")]
+pub use veripb::write_veripb_proof;
+
+#[cfg(feature = "std")]
+mod wbo;
+#[cfg(feature = "std")]
+pub use wbo::SoftConstraint;
+#[cfg(feature = "std")]
+pub use wbo::SoftPbInstance;
+#[cfg(feature = "std")]
+pub use wbo::WboInstance;
@@ -0,0 +1,314 @@
+use crate::{Comparator, PbConstraint};
+use std::{
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+};
+
+/// A linear expression, that is a weighted sum of literals plus a constant.
+///
+/// Linear expressions are built from their terms, each of them being a couple composed of a weight and a literal (in the DIMACS format).
+/// Comparing an expression with an integer value gives a [`PbConstraint`], which avoids the need to handle parallel vectors of weights and literals.
+///
+/// ```
+/// use pblib_rs::{Comparator, PbConstraint};
+///
+/// // 8*x1 + 4*x2 + 2*x3 + 1*x4 >= 6
+/// let constraint = PbConstraint::weighted([(8, 1), (4, 2), (2, 3)])
+///     .with_term(1, 4)
+///     .geq(6);
+/// assert_eq!(
+///     PbConstraint::new(vec![(8, 1), (4, 2), (2, 3), (1, 4)], Comparator::Geq, 6),
+///     constraint,
+/// );
+/// ```
+///
+/// Expressions can also be combined with the usual arithmetic operators, the literals being introduced by [`LinExpr::lit`].
+/// Since Rust comparison operators must return booleans, the comparison itself is made by a method call.
+///
+/// ```
+/// use pblib_rs::{Comparator, LinExpr, PbConstraint};
+///
+/// let x = LinExpr::lit;
+/// // 3*x1 + 2*x2 + x3 <= 5
+/// let constraint = (3 * x(1) + 2 * x(2) + x(3)).leq(5);
+/// assert_eq!(
+///     PbConstraint::new(vec![(3, 1), (2, 2), (1, 3)], Comparator::Leq, 5),
+///     constraint,
+/// );
+/// // the constants are moved to the right hand side
+/// let constraint = (x(1) - x(-2) + 1).geq(2);
+/// assert_eq!(
+///     PbConstraint::new(vec![(1, 1), (-1, -2)], Comparator::Geq, 1),
+///     constraint,
+/// );
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct LinExpr {
+    terms: Vec<(i64, i32)>,
+    constant: i64,
+}
+
+impl LinExpr {
+    /// Builds a new, empty expression.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds the expression made of a single literal, with a weight of 1.
+    #[must_use]
+    pub fn lit(literal: i32) -> Self {
+        Self::new().with_term(1, literal)
+    }
+
+    /// Builds the expression made of a constant.
+    #[must_use]
+    pub fn constant(value: i64) -> Self {
+        Self {
+            terms: Vec::new(),
+            constant: value,
+        }
+    }
+
+    /// Adds a term to this expression.
+    #[must_use]
+    pub fn with_term(mut self, weight: i64, literal: i32) -> Self {
+        self.terms.push((weight, literal));
+        self
+    }
+
+    /// Returns the terms of the expression, as couples composed of a weight and a literal.
+    #[must_use]
+    pub fn terms(&self) -> &[(i64, i32)] {
+        &self.terms
+    }
+
+    /// Returns the constant part of the expression.
+    #[must_use]
+    pub fn constant_part(&self) -> i64 {
+        self.constant
+    }
+
+    /// Builds the constraint comparing this expression with a value.
+    ///
+    /// The constant part of the expression is subtracted from the value, so that the constraint only has terms on its left hand side.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the right hand side of the constraint does not fit in an `i64`.
+    #[must_use]
+    pub fn compare(self, comparator: Comparator, rhs: i64) -> PbConstraint {
+        let rhs = rhs
+            .checked_sub(self.constant)
+            .expect("the right hand side of the constraint overflows");
+        PbConstraint::new(self.terms, comparator, rhs)
+    }
+
+    /// Builds the constraint stating that this expression is lower than or equal to a value.
+    #[must_use]
+    pub fn leq(self, rhs: i64) -> PbConstraint {
+        self.compare(Comparator::Leq, rhs)
+    }
+
+    /// Builds the constraint stating that this expression is greater than or equal to a value.
+    #[must_use]
+    pub fn geq(self, rhs: i64) -> PbConstraint {
+        self.compare(Comparator::Geq, rhs)
+    }
+
+    /// Builds the constraint stating that this expression is equal to a value.
+    #[must_use]
+    pub fn eq(self, rhs: i64) -> PbConstraint {
+        self.compare(Comparator::Eq, rhs)
+    }
+
+    /// Builds the constraint stating that this expression is strictly lower than a value.
+    #[must_use]
+    pub fn lt(self, rhs: i64) -> PbConstraint {
+        self.compare(Comparator::Lt, rhs)
+    }
+
+    /// Builds the constraint stating that this expression is strictly greater than a value.
+    #[must_use]
+    pub fn gt(self, rhs: i64) -> PbConstraint {
+        self.compare(Comparator::Gt, rhs)
+    }
+}
+
+impl FromIterator<(i64, i32)> for LinExpr {
+    fn from_iter<T: IntoIterator<Item = (i64, i32)>>(iter: T) -> Self {
+        Self {
+            terms: iter.into_iter().collect(),
+            constant: 0,
+        }
+    }
+}
+
+impl From<i64> for LinExpr {
+    fn from(value: i64) -> Self {
+        Self::constant(value)
+    }
+}
+
+impl AddAssign for LinExpr {
+    fn add_assign(&mut self, rhs: Self) {
+        self.terms.extend(rhs.terms);
+        self.constant += rhs.constant;
+    }
+}
+
+impl AddAssign<i64> for LinExpr {
+    fn add_assign(&mut self, rhs: i64) {
+        self.constant += rhs;
+    }
+}
+
+impl SubAssign for LinExpr {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self += -rhs;
+    }
+}
+
+impl SubAssign<i64> for LinExpr {
+    fn sub_assign(&mut self, rhs: i64) {
+        self.constant -= rhs;
+    }
+}
+
+impl<T> Add<T> for LinExpr
+where
+    LinExpr: AddAssign<T>,
+{
+    type Output = LinExpr;
+
+    fn add(mut self, rhs: T) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T> Sub<T> for LinExpr
+where
+    LinExpr: SubAssign<T>,
+{
+    type Output = LinExpr;
+
+    fn sub(mut self, rhs: T) -> Self::Output {
+        self -= rhs;
+        self
+    }
+}
+
+impl Add<LinExpr> for i64 {
+    type Output = LinExpr;
+
+    fn add(self, rhs: LinExpr) -> Self::Output {
+        rhs + self
+    }
+}
+
+impl Sub<LinExpr> for i64 {
+    type Output = LinExpr;
+
+    fn sub(self, rhs: LinExpr) -> Self::Output {
+        -rhs + self
+    }
+}
+
+impl Neg for LinExpr {
+    type Output = LinExpr;
+
+    fn neg(self) -> Self::Output {
+        -1 * self
+    }
+}
+
+impl Mul<i64> for LinExpr {
+    type Output = LinExpr;
+
+    fn mul(mut self, rhs: i64) -> Self::Output {
+        self.terms.iter_mut().for_each(|(w, _)| *w *= rhs);
+        self.constant *= rhs;
+        self
+    }
+}
+
+impl Mul<LinExpr> for i64 {
+    type Output = LinExpr;
+
+    fn mul(self, rhs: LinExpr) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl Sum for LinExpr {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(LinExpr::new(), |acc, e| acc + e)
+    }
+}
+
+impl Extend<(i64, i32)> for LinExpr {
+    fn extend<T: IntoIterator<Item = (i64, i32)>>(&mut self, iter: T) {
+        self.terms.extend(iter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comparators() {
+        let expr = PbConstraint::cardinality([1, -2, 3]);
+        assert_eq!(&[(1, 1), (1, -2), (1, 3)], expr.terms());
+        for (constraint, comparator) in [
+            (expr.clone().leq(2), Comparator::Leq),
+            (expr.clone().geq(2), Comparator::Geq),
+            (expr.clone().eq(2), Comparator::Eq),
+            (expr.clone().lt(2), Comparator::Lt),
+            (expr.clone().gt(2), Comparator::Gt),
+        ] {
+            assert_eq!(
+                PbConstraint::new(expr.terms().to_vec(), comparator, 2),
+                constraint
+            );
+        }
+    }
+
+    #[test]
+    fn test_operators() {
+        let x = LinExpr::lit;
+        let expr = 3 * x(1) + 2 * x(-2) - x(3) * 4 + 5 - 1;
+        assert_eq!(&[(3, 1), (2, -2), (-4, 3)], expr.terms());
+        assert_eq!(4, expr.constant_part());
+        let expr = 10 - expr;
+        assert_eq!(&[(-3, 1), (-2, -2), (4, 3)], expr.terms());
+        assert_eq!(6, expr.constant_part());
+        let mut expr = -expr;
+        expr -= x(4) + 1;
+        expr += 2;
+        assert_eq!(&[(3, 1), (2, -2), (-4, 3), (-1, 4)], expr.terms());
+        assert_eq!(-5, expr.constant_part());
+        assert_eq!(
+            PbConstraint::new(expr.terms().to_vec(), Comparator::Eq, 5),
+            expr.eq(0)
+        );
+        let sum = (1..=3).map(|i| i64::from(i) * x(i)).sum::<LinExpr>();
+        assert_eq!(PbConstraint::weighted([(1, 1), (2, 2), (3, 3)]), sum);
+        assert_eq!(LinExpr::constant(3), LinExpr::from(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "overflows")]
+    fn test_rhs_overflow() {
+        let _ = (LinExpr::lit(1) + i64::MAX).leq(-2);
+    }
+
+    #[test]
+    fn test_build() {
+        let mut expr = LinExpr::new().with_term(3, 1);
+        expr.extend([(2, -2)]);
+        assert_eq!(expr, [(3, 1), (2, -2)].into_iter().collect());
+        assert!(LinExpr::new().terms().is_empty());
+    }
+}
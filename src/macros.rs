@@ -0,0 +1,115 @@
+/// Encodes a constraint written inline into an [`EncodingContext`](crate::EncodingContext).
+///
+/// The first argument is the context, and the second one is the constraint, written as a linear expression compared to an integer.
+/// In the expression:
+///
+/// * an identifier designates the problem variable with this name, allocated by [`EncodingContext::named_var`](crate::EncodingContext::named_var) if needed;
+/// * an expression between brackets (`[lit]`) designates the literal it evaluates to, as an `i32`;
+/// * a `!` before a variable or a bracketed literal negates it;
+/// * the other tokens (integer weights and constants, the `+`, `-` and `*` operators, parenthesized Rust expressions giving integers) are kept as is.
+///
+/// The comparator is one of `>=`, `<=`, `==`, `<` and `>`; the right hand side is an integer expression.
+/// The macro expands to calls to the [`LinExpr`](crate::LinExpr) operators followed by [`PbConstraint::encode`](crate::PbConstraint::encode), and returns its result.
+///
+/// ```
+/// use pblib_rs::{pb, EncodingContext};
+///
+/// let mut ctx = EncodingContext::new(0);
+/// pb!(ctx, 8*x1 + 4*x2 + 2*x3 >= 6).unwrap();
+/// pb!(ctx, x1 + !x2 - x3 <= 1).unwrap();
+/// let w = 3;
+/// let y = ctx.new_var();
+/// pb!(ctx, (w) * [y] + 2 * !x3 + 1 == 4).unwrap();
+/// assert_eq!(4, ctx.n_problem_vars());
+/// assert_eq!(Some(2), ctx.symbols().get("x2"));
+/// ```
+#[macro_export]
+macro_rules! pb {
+    ($ctx:expr, $($constraint:tt)+) => {{
+        let ctx: &mut $crate::EncodingContext = &mut $ctx;
+        $crate::__pb_munch!(ctx; []; $($constraint)+)
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pb_munch {
+    ($ctx:ident; [$($lhs:tt)*]; >= $($rhs:tt)+) => {
+        $crate::__pb_munch!(@encode $ctx; [$($lhs)*]; geq; $($rhs)+)
+    };
+    ($ctx:ident; [$($lhs:tt)*]; <= $($rhs:tt)+) => {
+        $crate::__pb_munch!(@encode $ctx; [$($lhs)*]; leq; $($rhs)+)
+    };
+    ($ctx:ident; [$($lhs:tt)*]; == $($rhs:tt)+) => {
+        $crate::__pb_munch!(@encode $ctx; [$($lhs)*]; eq; $($rhs)+)
+    };
+    ($ctx:ident; [$($lhs:tt)*]; < $($rhs:tt)+) => {
+        $crate::__pb_munch!(@encode $ctx; [$($lhs)*]; lt; $($rhs)+)
+    };
+    ($ctx:ident; [$($lhs:tt)*]; > $($rhs:tt)+) => {
+        $crate::__pb_munch!(@encode $ctx; [$($lhs)*]; gt; $($rhs)+)
+    };
+    (@encode $ctx:ident; [$($lhs:tt)*]; $comparator:ident; $($rhs:tt)+) => {
+        ($crate::LinExpr::new() + ($($lhs)*))
+            .$comparator($($rhs)+)
+            .encode($ctx)
+    };
+    ($ctx:ident; [$($lhs:tt)*]; ! [$($lit:tt)+] $($rest:tt)*) => {
+        $crate::__pb_munch!($ctx; [$($lhs)* $crate::LinExpr::lit(-($($lit)+))]; $($rest)*)
+    };
+    ($ctx:ident; [$($lhs:tt)*]; [$($lit:tt)+] $($rest:tt)*) => {
+        $crate::__pb_munch!($ctx; [$($lhs)* $crate::LinExpr::lit($($lit)+)]; $($rest)*)
+    };
+    ($ctx:ident; [$($lhs:tt)*]; ! $name:ident $($rest:tt)*) => {
+        $crate::__pb_munch!($ctx; [$($lhs)* $crate::LinExpr::lit(-$ctx.named_var(stringify!($name)))]; $($rest)*)
+    };
+    ($ctx:ident; [$($lhs:tt)*]; $name:ident $($rest:tt)*) => {
+        $crate::__pb_munch!($ctx; [$($lhs)* $crate::LinExpr::lit($ctx.named_var(stringify!($name)))]; $($rest)*)
+    };
+    ($ctx:ident; [$($lhs:tt)*]; $w:tt * ! $name:ident $($rest:tt)*) => {
+        $crate::__pb_munch!($ctx; [$($lhs)* $w * $crate::LinExpr::lit(-$ctx.named_var(stringify!($name)))]; $($rest)*)
+    };
+    ($ctx:ident; [$($lhs:tt)*]; $w:tt * $name:ident $($rest:tt)*) => {
+        $crate::__pb_munch!($ctx; [$($lhs)* $w * $crate::LinExpr::lit($ctx.named_var(stringify!($name)))]; $($rest)*)
+    };
+    ($ctx:ident; [$($lhs:tt)*]; $t:tt $($rest:tt)*) => {
+        $crate::__pb_munch!($ctx; [$($lhs)* $t]; $($rest)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{reference, Comparator, EncodingContext, PbConstraint, PbError};
+
+    #[test]
+    fn test_pb() {
+        let mut ctx = EncodingContext::new(0);
+        pb!(ctx, 8 * x1 + 4 * x2 + 2 * x3 + x4 >= 6).unwrap();
+        pb!(ctx, -x1 + !x2 - 3 * !x3 < 0).unwrap();
+        let x4 = ctx.symbols().get("x4").unwrap();
+        pb!(ctx, 2 * [x4] + (1 + 1) * ![x4 - 1] + 1 > 2).unwrap();
+        pb!(&mut ctx, x1 + x2 == 1).unwrap();
+        pb!(ctx, x3 + x4 - 1 <= 0).unwrap();
+        let constraints = [
+            PbConstraint::new(vec![(8, 1), (4, 2), (2, 3), (1, 4)], Comparator::Geq, 6),
+            PbConstraint::new(vec![(-1, 1), (1, -2), (-3, -3)], Comparator::Lt, 0),
+            PbConstraint::new(vec![(2, 4), (2, -3)], Comparator::Gt, 1),
+            PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Eq, 1),
+            PbConstraint::new(vec![(1, 3), (1, 4)], Comparator::Leq, 1),
+        ];
+        assert_eq!(4, ctx.n_problem_vars());
+        assert_eq!(
+            Ok(()),
+            reference::check_encoding(&constraints, ctx.formula().clauses(), 4)
+        );
+    }
+
+    #[test]
+    fn test_pb_error() {
+        let mut ctx = EncodingContext::new(1);
+        assert!(matches!(
+            pb!(ctx, [1] + [2] >= 1),
+            Err(PbError::UndeclaredVar(2))
+        ));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::{ffi_debug, ClauseSink};
+use std::ffi::c_void;
+
+/// A wrapper around the `MiniSat` solver bundled with pblib.
+///
+/// This structure allows to check the satisfiability of encodings without the need of an external SAT solver.
+/// Clauses are given in the DIMACS format; the variables are created on demand by the solver.
+/// Since this structure implements [`ClauseSink`], the encoding functions ending with `_into` can send their clauses directly to the solver.
+///
+/// ```
+/// use pblib_rs::{MiniSat, PB2CNF};
+///
+/// let pb2cnf = PB2CNF::new();
+/// let mut solver = MiniSat::new();
+/// // we encode x1 + x2 + x3 <= 1
+/// pb2cnf.encode_at_most_k_into(&[1, 2, 3], 1, 4, &mut solver);
+/// solver.add_clause(&[1, 2]);
+/// assert!(solver.solve());
+/// let model = solver.model().unwrap();
+/// assert!(model[0] > 0 || model[1] > 0);
+/// // under the assumption that x1 and x2 are both true, the formula is unsatisfiable
+/// assert!(!solver.solve_with_assumptions(&[1, 2]));
+/// assert!(solver.model().is_none());
+/// ```
+pub struct MiniSat {
+    ptr: *mut c_void,
+    satisfiable: bool,
+}
+
+const MINISAT_KIND: &str = "MiniSat";
+
+impl MiniSat {
+    /// Builds a new solver, containing no clauses.
+    #[must_use]
+    pub fn new() -> Self {
+        let ptr = unsafe { newMiniSat() };
+        ffi_debug::track(ptr, MINISAT_KIND);
+        Self {
+            ptr,
+            satisfiable: false,
+        }
+    }
+
+    /// Adds a clause to the solver.
+    ///
+    /// The literals must be given in the DIMACS format.
+    /// Adding an empty clause makes the solver unsatisfiable.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the length of the clause does not fit in an `i32`.
+    pub fn add_clause(&mut self, clause: &[i32]) {
+        ffi_debug::check(self.ptr, MINISAT_KIND);
+        unsafe {
+            miniSatAddClause(self.ptr, clause.as_ptr(), clause.len().try_into().unwrap());
+        }
+    }
+
+    /// Checks the satisfiability of the clauses added so far.
+    ///
+    /// Returns `true` if and only if the clauses are satisfiable.
+    /// In this case, a model can be retrieved with [`model`](Self::model).
+    pub fn solve(&mut self) -> bool {
+        self.solve_with_assumptions(&[])
+    }
+
+    /// Checks the satisfiability of the clauses added so far, under the assumption that the given literals are true.
+    ///
+    /// The assumptions only hold for this call: they are not kept for the next ones.
+    /// Returns `true` if and only if the clauses and the assumptions are satisfiable.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of assumptions does not fit in an `i32`.
+    pub fn solve_with_assumptions(&mut self, assumptions: &[i32]) -> bool {
+        ffi_debug::check(self.ptr, MINISAT_KIND);
+        self.satisfiable = unsafe {
+            miniSatSolve(
+                self.ptr,
+                assumptions.as_ptr(),
+                assumptions.len().try_into().unwrap(),
+            )
+        } != 0;
+        self.satisfiable
+    }
+
+    /// Returns the model found by the last call to a solving function, if this call was successful.
+    ///
+    /// The model is given as a list of DIMACS literals, containing one literal per variable, in the order of the variables.
+    /// The variables the solver left unassigned are set to false.
+    /// `None` is returned if no solving function was called yet or if the last call concluded the formula was unsatisfiable.
+    #[must_use]
+    pub fn model(&self) -> Option<Vec<i32>> {
+        if !self.satisfiable {
+            return None;
+        }
+        Some(
+            (1..=self.n_vars())
+                .map(|v| {
+                    if unsafe { miniSatModelValue(self.ptr, v) } == 1 {
+                        v
+                    } else {
+                        -v
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the number of variables known by the solver, that is the highest variable index involved in the clauses and the assumptions.
+    #[must_use]
+    pub fn n_vars(&self) -> i32 {
+        ffi_debug::check(self.ptr, MINISAT_KIND);
+        unsafe { miniSatNVars(self.ptr) }
+    }
+}
+
+impl Default for MiniSat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClauseSink for MiniSat {
+    fn add_clause(&mut self, clause: &[i32]) {
+        MiniSat::add_clause(self, clause);
+    }
+}
+
+impl Drop for MiniSat {
+    fn drop(&mut self) {
+        ffi_debug::untrack(self.ptr, MINISAT_KIND);
+        unsafe { deleteMiniSat(self.ptr) }
+    }
+}
+
+extern "C" {
+    pub fn newMiniSat() -> *mut c_void;
+
+    pub fn miniSatAddClause(ptr: *mut c_void, literals: *const i32, literals_len: i32) -> i32;
+
+    pub fn miniSatSolve(ptr: *mut c_void, assumptions: *const i32, assumptions_len: i32) -> i32;
+
+    pub fn miniSatNVars(ptr: *mut c_void) -> i32;
+
+    pub fn miniSatModelValue(ptr: *mut c_void, var: i32) -> i32;
+
+    pub fn deleteMiniSat(ptr: *mut c_void);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PB2CNF;
+
+    #[test]
+    fn test_empty_solver() {
+        let mut solver = MiniSat::new();
+        assert!(solver.model().is_none());
+        assert!(solver.solve());
+        assert_eq!(Some(vec![]), solver.model());
+    }
+
+    #[test]
+    fn test_empty_clause() {
+        let mut solver = MiniSat::new();
+        solver.add_clause(&[]);
+        assert!(!solver.solve());
+        assert!(solver.model().is_none());
+    }
+
+    #[test]
+    fn test_model() {
+        let mut solver = MiniSat::new();
+        solver.add_clause(&[1, 3]);
+        solver.add_clause(&[-1]);
+        assert!(solver.solve());
+        assert_eq!(3, solver.n_vars());
+        let model = solver.model().unwrap();
+        assert_eq!(3, model.len());
+        assert_eq!(-1, model[0]);
+        assert_eq!(3, model[2]);
+    }
+
+    #[test]
+    fn test_assumptions_are_not_kept() {
+        let mut solver = MiniSat::new();
+        solver.add_clause(&[1, 2]);
+        assert!(!solver.solve_with_assumptions(&[-1, -2]));
+        assert!(solver.solve_with_assumptions(&[-1]));
+        assert_eq!(Some(vec![-1, 2]), solver.model());
+        assert!(solver.solve());
+    }
+
+    #[test]
+    fn test_encoding() {
+        let pb2cnf = PB2CNF::new();
+        let mut solver = MiniSat::new();
+        pb2cnf.encode_geq_into(&[8, 4, 2, 1], &[1, 2, 3, 4], 6, 5, &mut solver);
+        assert!(solver.solve_with_assumptions(&[-1, 2, 3]));
+        assert!(!solver.solve_with_assumptions(&[-1, 2, -3]));
+        assert!(!solver.solve_with_assumptions(&[-1, -2]));
+    }
+}
@@ -0,0 +1,323 @@
+use super::AuxVars;
+use crate::{ClauseSink, Comparator, PbConstraint};
+use std::collections::VecDeque;
+
+/// The adder network encoding of Pseudo-Boolean constraints (Eén and Sörensson, 2006).
+///
+/// An adder network computes the weighted sum of the literals in binary, using full and half adders.
+/// Its sum bits are kept, so that several bounds can be compared with the same sum; this is typically used to bound an objective function during an optimization.
+/// The encoding is small (linear in the number of literals times the number of bits of the weights), but unit propagation is weaker than with BDDs or sorting networks.
+///
+/// Negative weights are handled by negating their literals: the sum bits represent the weighted sum plus an [`offset`](Self::offset).
+///
+/// ```
+/// use pblib_rs::native::Adder;
+///
+/// let mut clauses = Vec::new();
+/// // 5*x1 + 3*x2 + 2*x3 + 2*x4
+/// let adder = Adder::new(&[(5, 1), (3, 2), (2, 3), (2, 4)], 5, &mut clauses);
+/// assert_eq!(4, adder.sum_bits().len());
+/// // tighten the bound on the sum step by step
+/// adder.encode_leq_into(9, &mut clauses);
+/// adder.encode_leq_into(6, &mut clauses);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Adder {
+    sum_bits: Vec<i32>,
+    offset: i128,
+    next_free_var_id: i32,
+}
+
+impl Adder {
+    /// Builds an adder network computing the weighted sum of some terms, sending its clauses to a [`ClauseSink`].
+    ///
+    /// The terms are couples composed of a weight and a literal.
+    /// The sum bits are equivalent to the bits of the weighted sum: the clauses of the adders state both directions of the equivalences.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn new(terms: &[(i64, i32)], first_aux_var: i32, sink: &mut impl ClauseSink) -> Self {
+        let mut vars = AuxVars::new(first_aux_var);
+        let mut offset = 0;
+        let mut buckets: Vec<VecDeque<i32>> = Vec::new();
+        for (w, l) in terms {
+            let l = if *w < 0 {
+                offset += i128::from(w.unsigned_abs());
+                -l
+            } else {
+                *l
+            };
+            let w = w.unsigned_abs();
+            for bit in 0..u64::BITS - w.leading_zeros() {
+                if w & (1 << bit) != 0 {
+                    let bit = bit as usize;
+                    if buckets.len() <= bit {
+                        buckets.resize_with(bit + 1, VecDeque::new);
+                    }
+                    buckets[bit].push_back(l);
+                }
+            }
+        }
+        let mut sum_bits = Vec::with_capacity(buckets.len());
+        let mut bit = 0;
+        while bit < buckets.len() {
+            while buckets[bit].len() >= 2 {
+                let a = buckets[bit].pop_front().unwrap();
+                let b = buckets[bit].pop_front().unwrap();
+                let c = if buckets[bit].is_empty() {
+                    None
+                } else {
+                    buckets[bit].pop_front()
+                };
+                let (sum, carry) = add_bits(a, b, c, &mut vars, sink);
+                buckets[bit].push_back(sum);
+                if buckets.len() == bit + 1 {
+                    buckets.push(VecDeque::new());
+                }
+                buckets[bit + 1].push_back(carry);
+            }
+            let sum_bit = buckets[bit].pop_front().unwrap_or_else(|| {
+                let var = vars.new_var();
+                sink.explain(format_args!("adder constant sum bit {var}"));
+                sink.add_clause(&[-var]);
+                var
+            });
+            sum_bits.push(sum_bit);
+            bit += 1;
+        }
+        Self {
+            sum_bits,
+            offset,
+            next_free_var_id: vars.next_free_var_id(),
+        }
+    }
+
+    /// Returns the sum bits of the network, beginning with the least significant one.
+    ///
+    /// The binary number they form is the weighted sum of the terms plus the [`offset`](Self::offset).
+    #[must_use]
+    pub fn sum_bits(&self) -> &[i32] {
+        &self.sum_bits
+    }
+
+    /// Returns the value added to the weighted sum by the negation of the literals with negative weights.
+    ///
+    /// This is the sum of the absolute values of the negative weights.
+    #[must_use]
+    pub fn offset(&self) -> i128 {
+        self.offset
+    }
+
+    /// Returns the lowest variable index that is not used by the network.
+    #[must_use]
+    pub fn next_free_var_id(&self) -> i32 {
+        self.next_free_var_id
+    }
+
+    /// Sends to a [`ClauseSink`] the clauses stating that the weighted sum is lower than or equal to `k`.
+    ///
+    /// The comparison with the sum bits does not need auxiliary variables.
+    pub fn encode_leq_into(&self, k: i64, sink: &mut impl ClauseSink) {
+        sink.explain(format_args!("adder comparison: sum <= {k}"));
+        let k = i128::from(k) + self.offset;
+        if k < 0 {
+            sink.add_clause(&[]);
+            return;
+        }
+        if k >> self.sum_bits.len() != 0 {
+            return;
+        }
+        // the sum is greater than k iff, for a bit i that is 0 in k, s_i is true and the greater bits of the sum are true where k has a 1
+        for (i, s) in self.sum_bits.iter().enumerate() {
+            if k & (1 << i) == 0 {
+                let clause = std::iter::once(-s)
+                    .chain(self.greater_bits(i, k, true).map(|s| -s))
+                    .collect::<Vec<_>>();
+                sink.add_clause(&clause);
+            }
+        }
+    }
+
+    /// Sends to a [`ClauseSink`] the clauses stating that the weighted sum is greater than or equal to `k`.
+    ///
+    /// The comparison with the sum bits does not need auxiliary variables.
+    pub fn encode_geq_into(&self, k: i64, sink: &mut impl ClauseSink) {
+        sink.explain(format_args!("adder comparison: sum >= {k}"));
+        let k = i128::from(k) + self.offset;
+        if k <= 0 {
+            return;
+        }
+        if k >> self.sum_bits.len() != 0 {
+            sink.add_clause(&[]);
+            return;
+        }
+        // the sum is lower than k iff, for a bit i that is 1 in k, s_i is false and the greater bits of the sum are false where k has a 0
+        for (i, s) in self.sum_bits.iter().enumerate() {
+            if k & (1 << i) != 0 {
+                let clause = std::iter::once(*s)
+                    .chain(self.greater_bits(i, k, false))
+                    .collect::<Vec<_>>();
+                sink.add_clause(&clause);
+            }
+        }
+    }
+
+    /// Returns the sum bits greater than `i` whose bit in `k` has the given value.
+    fn greater_bits(&self, i: usize, k: i128, value: bool) -> impl Iterator<Item = i32> + '_ {
+        self.sum_bits
+            .iter()
+            .enumerate()
+            .skip(i + 1)
+            .filter(move |(j, _)| (k & (1 << j) != 0) == value)
+            .map(|(_, s)| *s)
+    }
+
+    /// Encodes a [`PbConstraint`] with an adder network, sending the clauses to a [`ClauseSink`].
+    ///
+    /// See [`PB2CNF::encode_constraint`](crate::PB2CNF::encode_constraint) for more information on the parameters.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`, or if the bound of a strict comparator overflows.
+    pub fn encode_constraint_into(
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        let constraint = constraint.non_strict();
+        let adder = Adder::new(constraint.terms(), first_aux_var, sink);
+        match constraint.comparator() {
+            Comparator::Leq => adder.encode_leq_into(constraint.rhs(), sink),
+            Comparator::Geq => adder.encode_geq_into(constraint.rhs(), sink),
+            Comparator::Eq => {
+                adder.encode_leq_into(constraint.rhs(), sink);
+                adder.encode_geq_into(constraint.rhs(), sink);
+            }
+            Comparator::Lt | Comparator::Gt => unreachable!(),
+        }
+        adder.next_free_var_id()
+    }
+}
+
+/// Adds two or three bits, and returns the sum and the carry bits.
+fn add_bits(
+    a: i32,
+    b: i32,
+    c: Option<i32>,
+    vars: &mut AuxVars,
+    sink: &mut impl ClauseSink,
+) -> (i32, i32) {
+    let sum = vars.new_var();
+    let carry = vars.new_var();
+    if let Some(c) = c {
+        sink.explain(format_args!(
+            "full adder ({a}, {b}, {c}) -> ({sum}, {carry})"
+        ));
+        // sum <-> a xor b xor c
+        for (x, y, z) in [(a, b, c), (a, -b, -c), (-a, b, -c), (-a, -b, c)] {
+            sink.add_clause(&[-x, -y, -z, sum]);
+            sink.add_clause(&[x, y, z, -sum]);
+        }
+        // carry <-> at least two of a, b, c
+        for (x, y) in [(a, b), (a, c), (b, c)] {
+            sink.add_clause(&[-x, -y, carry]);
+            sink.add_clause(&[x, y, -carry]);
+        }
+    } else {
+        sink.explain(format_args!("half adder ({a}, {b}) -> ({sum}, {carry})"));
+        // sum <-> a xor b
+        sink.add_clause(&[-a, -b, -sum]);
+        sink.add_clause(&[a, b, -sum]);
+        sink.add_clause(&[-a, b, sum]);
+        sink.add_clause(&[a, -b, sum]);
+        // carry <-> a and b
+        sink.add_clause(&[-a, -b, carry]);
+        sink.add_clause(&[a, -carry]);
+        sink.add_clause(&[b, -carry]);
+    }
+    (sum, carry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{reference, MiniSat};
+
+    const TERMS: [(i64, i32); 5] = [(5, 1), (-3, 2), (2, -3), (7, 4), (2, 5)];
+
+    #[test]
+    fn test_sum_bits() {
+        let mut clauses = Vec::new();
+        let adder = Adder::new(&TERMS, 6, &mut clauses);
+        assert_eq!(3, adder.offset());
+        let mut solver = MiniSat::new();
+        for c in &clauses {
+            solver.add_clause(c);
+        }
+        for i in 0..32 {
+            let assignment = (1..=5)
+                .map(|v| if i & (1 << (v - 1)) == 0 { -v } else { v })
+                .collect::<Vec<_>>();
+            let sum = TERMS
+                .iter()
+                .filter(|(_, l)| assignment.contains(l))
+                .map(|(w, _)| i128::from(*w))
+                .sum::<i128>()
+                + adder.offset();
+            let mut assumptions = assignment.clone();
+            assumptions.extend(adder.sum_bits().iter().enumerate().map(|(i, s)| {
+                if sum & (1 << i) == 0 {
+                    -s
+                } else {
+                    *s
+                }
+            }));
+            assert!(solver.solve_with_assumptions(&assumptions));
+            // the sum bits are determined by the inputs
+            for (i, s) in adder.sum_bits().iter().enumerate() {
+                assumptions[5 + i] = -assumptions[5 + i];
+                assert!(
+                    !solver.solve_with_assumptions(&assumptions),
+                    "{s} {assignment:?}"
+                );
+                assumptions[5 + i] = -assumptions[5 + i];
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_constraint() {
+        for comparator in [
+            Comparator::Leq,
+            Comparator::Geq,
+            Comparator::Eq,
+            Comparator::Lt,
+            Comparator::Gt,
+        ] {
+            for rhs in -5..=16 {
+                let constraint = PbConstraint::new(TERMS.to_vec(), comparator, rhs);
+                let mut clauses = Vec::new();
+                let next_free_var_id = Adder::encode_constraint_into(&constraint, 6, &mut clauses);
+                assert!(clauses.iter().flatten().all(|l| l.abs() < next_free_var_id));
+                assert_eq!(
+                    Ok(()),
+                    reference::check_encoding(&[constraint], &clauses, 5)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut clauses = Vec::new();
+        let adder = Adder::new(&[], 1, &mut clauses);
+        assert!(adder.sum_bits().is_empty());
+        adder.encode_leq_into(0, &mut clauses);
+        adder.encode_geq_into(0, &mut clauses);
+        assert!(clauses.is_empty());
+        adder.encode_geq_into(1, &mut clauses);
+        assert_eq!(vec![Vec::<i32>::new()], clauses);
+    }
+}
@@ -0,0 +1,125 @@
+use super::AuxVars;
+use crate::ClauseSink;
+
+/// Encodes an At-Most-One constraint with the ladder encoding (Gent and Nightingale, 2004), sending the clauses to a [`ClauseSink`].
+///
+/// The auxiliary variable `y[i]` is implied by the fact that one of the `i+1` first literals is true.
+/// The encoding uses `n-1` auxiliary variables and `3n-4` clauses.
+pub(crate) fn encode_ladder_into(
+    literals: &[i32],
+    first_aux_var: i32,
+    sink: &mut impl ClauseSink,
+) -> i32 {
+    let mut vars = AuxVars::new(first_aux_var);
+    let mut previous: Option<i32> = None;
+    for (i, l) in literals.iter().enumerate() {
+        sink.explain(format_args!("ladder: literal {l} at index {i}"));
+        if let Some(p) = previous {
+            sink.add_clause(&[-p, -l]);
+        }
+        if i == literals.len() - 1 {
+            break;
+        }
+        let y = vars.new_var();
+        sink.add_clause(&[-l, y]);
+        if let Some(p) = previous {
+            sink.add_clause(&[-p, y]);
+        }
+        previous = Some(y);
+    }
+    vars.next_free_var_id()
+}
+
+/// Encodes an At-Most-One constraint with the commander encoding (Klieber and Kwon, 2007), sending the clauses to a [`ClauseSink`].
+///
+/// The literals are split into groups of `group_size` literals, with pairwise At-Most-One constraints inside the groups.
+/// Each group has a commander variable implied by its literals, and the encoding is applied recursively on the commanders, until there is only one group.
+///
+/// # Panics
+///
+/// This function panics if `group_size` is lower than 2.
+pub(crate) fn encode_commander_into(
+    literals: &[i32],
+    group_size: usize,
+    first_aux_var: i32,
+    sink: &mut impl ClauseSink,
+) -> i32 {
+    assert!(group_size >= 2, "the group size must be at least 2");
+    let mut vars = AuxVars::new(first_aux_var);
+    let mut literals = literals.to_vec();
+    while literals.len() > group_size {
+        let commanders = literals
+            .chunks(group_size)
+            .map(|group| {
+                if let [l] = group {
+                    return *l;
+                }
+                encode_pairwise_into(group, sink);
+                let commander = vars.new_var();
+                sink.explain(format_args!("commander {commander} of group {group:?}"));
+                for l in group {
+                    sink.add_clause(&[-l, commander]);
+                }
+                commander
+            })
+            .collect();
+        literals = commanders;
+    }
+    encode_pairwise_into(&literals, sink);
+    vars.next_free_var_id()
+}
+
+fn encode_pairwise_into(literals: &[i32], sink: &mut impl ClauseSink) {
+    sink.explain(format_args!("pairwise at-most-one on {literals:?}"));
+    for (i, l1) in literals.iter().enumerate() {
+        for l2 in &literals[i + 1..] {
+            sink.add_clause(&[-l1, -l2]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{reference, PbConstraint};
+
+    #[test]
+    fn test_ladder() {
+        for n in 0..=8 {
+            let literals = (1..=n)
+                .map(|v| if v % 3 == 0 { -v } else { v })
+                .collect::<Vec<_>>();
+            let mut clauses = Vec::new();
+            let next_free_var_id = encode_ladder_into(&literals, n + 1, &mut clauses);
+            if n > 1 {
+                assert_eq!(n - 1, next_free_var_id - n - 1);
+                assert_eq!(3 * n - 4, i32::try_from(clauses.len()).unwrap());
+            }
+            let constraint = PbConstraint::cardinality(literals).leq(1);
+            assert_eq!(
+                Ok(()),
+                reference::check_encoding(&[constraint], &clauses, n)
+            );
+        }
+    }
+
+    #[test]
+    fn test_commander() {
+        for n in 0..=8 {
+            for group_size in 2..=4 {
+                let literals = (1..=n)
+                    .map(|v| if v % 3 == 0 { -v } else { v })
+                    .collect::<Vec<_>>();
+                let mut clauses = Vec::new();
+                let next_free_var_id =
+                    encode_commander_into(&literals, group_size, n + 1, &mut clauses);
+                assert!(clauses.iter().flatten().all(|l| l.abs() < next_free_var_id));
+                let constraint = PbConstraint::cardinality(literals).leq(1);
+                assert_eq!(
+                    Ok(()),
+                    reference::check_encoding(&[constraint], &clauses, n)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use super::{AmoEncoder, CardinalityEncoder, PbEncoder};
+use crate::{ClauseSink, PbConstraint, PbEncoderBackend};
+
+/// A [`PbEncoderBackend`] relying on the native encoders.
+///
+/// The encoders used for each kind of constraint are set like the ones of an [`EncoderConfig`](crate::EncoderConfig).
+/// At-Most-One constraints (and At-Least-(n-1) ones) are encoded by the [`AmoEncoder`], the other cardinality constraints by the [`CardinalityEncoder`], and the remaining constraints by the [`PbEncoder`].
+///
+/// ```
+/// use pblib_rs::native::{AmoEncoder, NativeBackend, PbEncoder};
+/// use pblib_rs::PB2CNF;
+///
+/// let backend = NativeBackend::default()
+///     .with_pb_encoder(PbEncoder::PolynomialWatchdog)
+///     .with_amo_encoder(AmoEncoder::Commander { group_size: 3 });
+/// let pb2cnf = PB2CNF::with_backend(backend);
+/// let encoding = pb2cnf.encode_at_most_k(&[1, 2, 3, 4, 5], 1, 6);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NativeBackend {
+    pb: PbEncoder,
+    cardinality: CardinalityEncoder,
+    amo: AmoEncoder,
+}
+
+impl NativeBackend {
+    /// Sets the encoding used for Pseudo-Boolean constraints.
+    #[must_use]
+    pub fn with_pb_encoder(mut self, pb_encoder: PbEncoder) -> Self {
+        self.pb = pb_encoder;
+        self
+    }
+
+    /// Sets the encoding used for cardinality constraints.
+    #[must_use]
+    pub fn with_cardinality_encoder(mut self, cardinality_encoder: CardinalityEncoder) -> Self {
+        self.cardinality = cardinality_encoder;
+        self
+    }
+
+    /// Sets the encoding used for At-Most-One constraints.
+    #[must_use]
+    pub fn with_amo_encoder(mut self, amo_encoder: AmoEncoder) -> Self {
+        self.amo = amo_encoder;
+        self
+    }
+
+    /// Returns the encoding used for Pseudo-Boolean constraints.
+    #[must_use]
+    pub fn pb_encoder(&self) -> PbEncoder {
+        self.pb
+    }
+
+    /// Returns the encoding used for cardinality constraints.
+    #[must_use]
+    pub fn cardinality_encoder(&self) -> CardinalityEncoder {
+        self.cardinality
+    }
+
+    /// Returns the encoding used for At-Most-One constraints.
+    #[must_use]
+    pub fn amo_encoder(&self) -> AmoEncoder {
+        self.amo
+    }
+}
+
+impl PbEncoderBackend for NativeBackend {
+    fn encode_constraint_into(
+        &self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        mut sink: &mut dyn ClauseSink,
+    ) -> i32 {
+        let normalized = constraint.normalize();
+        if normalized
+            .iter()
+            .any(|c| c.terms().iter().any(|(w, _)| *w != 1))
+        {
+            return self
+                .pb
+                .encode_constraint_into(constraint, first_aux_var, &mut sink);
+        }
+        normalized
+            .iter()
+            .fold(first_aux_var, |next_free_var_id, c| {
+                self.encode_at_least_k_into(&c.literals(), c.rhs(), next_free_var_id, sink)
+            })
+    }
+
+    fn encode_at_most_k_into(
+        &self,
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+        mut sink: &mut dyn ClauseSink,
+    ) -> i32 {
+        if k == 1 && literals.len() > 2 {
+            self.amo
+                .encode_at_most_one_into(literals, first_aux_var, &mut sink)
+        } else {
+            self.cardinality
+                .encode_at_most_k_into(literals, k, first_aux_var, &mut sink)
+        }
+    }
+
+    fn encode_at_least_k_into(
+        &self,
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+        mut sink: &mut dyn ClauseSink,
+    ) -> i32 {
+        if usize::try_from(k).is_ok_and(|k| k + 1 == literals.len()) && literals.len() > 2 {
+            let negated = literals.iter().map(|l| -l).collect::<Vec<_>>();
+            self.amo
+                .encode_at_most_one_into(&negated, first_aux_var, &mut sink)
+        } else {
+            self.cardinality
+                .encode_at_least_k_into(literals, k, first_aux_var, &mut sink)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{reference, Comparator, EncodeLimits, EncodingTrace, PbError, PB2CNF};
+    use std::ops::ControlFlow;
+
+    const TERMS: [(i64, i32); 5] = [(5, 1), (-3, 2), (2, -3), (7, 4), (2, 5)];
+
+    fn backends() -> Vec<NativeBackend> {
+        let mut backends = Vec::new();
+        for pb in [
+            PbEncoder::Bdd,
+            PbEncoder::Adder,
+            PbEncoder::PolynomialWatchdog,
+            PbEncoder::SortingNetworks,
+        ] {
+            for cardinality in [
+                CardinalityEncoder::Totalizer,
+                CardinalityEncoder::SequentialCounter,
+            ] {
+                for amo in [AmoEncoder::Ladder, AmoEncoder::Commander { group_size: 2 }] {
+                    backends.push(
+                        NativeBackend::default()
+                            .with_pb_encoder(pb)
+                            .with_cardinality_encoder(cardinality)
+                            .with_amo_encoder(amo),
+                    );
+                }
+            }
+        }
+        backends
+    }
+
+    #[test]
+    fn test_constraints() {
+        for backend in backends() {
+            let pb2cnf = PB2CNF::with_backend(backend);
+            for rhs in -1..=6 {
+                for constraint in [
+                    PbConstraint::new(TERMS.to_vec(), Comparator::Leq, rhs * 2),
+                    PbConstraint::new(TERMS.to_vec(), Comparator::Eq, rhs * 2),
+                    PbConstraint::cardinality([1, -2, 3, 4, -5]).geq(rhs),
+                    PbConstraint::cardinality([1, -2, 3, 4, -5]).eq(rhs),
+                ] {
+                    let encoding = pb2cnf.encode_constraint(&constraint, 6);
+                    assert_eq!(
+                        Ok(()),
+                        reference::check_encoding(&[constraint], encoding.clauses(), 5)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cardinality() {
+        let literals = vec![1, -2, 3, 4, -5];
+        for backend in backends() {
+            let pb2cnf = PB2CNF::with_backend(backend);
+            for k in -1..=6 {
+                let encoding = pb2cnf.encode_at_most_k(&literals, k, 6);
+                let constraint = PbConstraint::cardinality(literals.clone()).leq(k);
+                assert_eq!(
+                    Ok(()),
+                    reference::check_encoding(&[constraint], encoding.clauses(), 5)
+                );
+                let encoding = pb2cnf.encode_at_least_k(&literals, k, 6);
+                let constraint = PbConstraint::cardinality(literals.clone()).geq(k);
+                assert_eq!(
+                    Ok(()),
+                    reference::check_encoding(&[constraint], encoding.clauses(), 5)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_explained_clauses() {
+        for backend in backends() {
+            for rhs in -1..=6 {
+                for constraint in [
+                    PbConstraint::new(TERMS.to_vec(), Comparator::Eq, rhs * 2),
+                    PbConstraint::cardinality([1, -2, 3, 4, -5]).eq(rhs),
+                    PbConstraint::cardinality([1, -2, 3, 4, -5]).leq(1),
+                ] {
+                    let mut trace = EncodingTrace::new();
+                    backend.encode_constraint_into(&constraint, 6, &mut trace);
+                    assert!(trace.iter().all(|(_, rule)| rule.is_some()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_checked_and_buffered_encodings() {
+        let backend = NativeBackend::default().with_pb_encoder(PbEncoder::Adder);
+        let constraint = PbConstraint::new(TERMS.to_vec(), Comparator::Leq, 6);
+        let mut expected = Vec::new();
+        let expected_next_free_var_id =
+            backend.encode_constraint_into(&constraint, 6, &mut expected);
+        let pb2cnf = PB2CNF::with_backend(backend);
+        let encoding = pb2cnf.try_encode_constraint(&constraint, 6).unwrap();
+        assert_eq!(expected, encoding.clauses());
+        assert_eq!(expected_next_free_var_id, encoding.next_free_var_id());
+        let mut clauses = Vec::new();
+        let next_free_var_id = pb2cnf
+            .try_encode_constraint_with_progress(
+                &constraint,
+                6,
+                &|_| ControlFlow::Continue(()),
+                &mut clauses,
+            )
+            .unwrap();
+        assert_eq!(expected, clauses);
+        assert_eq!(expected_next_free_var_id, next_free_var_id);
+        let next_free_var_id = pb2cnf.encode_constraint_buffered(&constraint, 6);
+        let mut clauses = Vec::new();
+        pb2cnf.take_buffered_clauses_into(&mut clauses);
+        assert_eq!(expected, clauses);
+        assert_eq!(expected_next_free_var_id, next_free_var_id);
+        let mut clauses = Vec::new();
+        let limits = EncodeLimits::default().with_max_clauses(1);
+        assert!(matches!(
+            pb2cnf.try_encode_constraint_into(&constraint, 6, &limits, &mut clauses),
+            Err(PbError::BudgetExceeded(_))
+        ));
+        assert_eq!(&expected[..1], &clauses[..]);
+    }
+
+    #[test]
+    fn test_pb() {
+        let pb2cnf = PB2CNF::with_backend(NativeBackend::default());
+        let weights = TERMS.iter().map(|(w, _)| *w).collect::<Vec<_>>();
+        let literals = TERMS.iter().map(|(_, l)| *l).collect::<Vec<_>>();
+        let encoding = pb2cnf.encode_both(&weights, &literals, 8, 3, 6);
+        let constraints = [
+            PbConstraint::new(TERMS.to_vec(), Comparator::Leq, 8),
+            PbConstraint::new(TERMS.to_vec(), Comparator::Geq, 3),
+        ];
+        assert_eq!(
+            Ok(()),
+            reference::check_encoding(&constraints, encoding.clauses(), 5)
+        );
+    }
+}
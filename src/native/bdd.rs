@@ -0,0 +1,281 @@
+use super::{dot::DotGraph, AuxVars};
+use crate::{ClauseSink, PbConstraint};
+use std::collections::BTreeMap;
+
+/// A manager of the nodes of reduced ordered BDDs encoding Pseudo-Boolean constraints (Abío et al., 2012).
+///
+/// Each constraint is represented by a BDD whose nodes are associated with auxiliary variables.
+/// The manager keeps a table of the nodes it has created, indexed by their literal and their children: when a node of a constraint represents the same function as a node of a constraint previously encoded by the manager, the existing node is reused, and neither a new variable nor new clauses are produced.
+/// Sharing a manager among the constraints of an instance is thus useful when they have subexpressions in common, like the constraints bounding the same objective function.
+///
+/// The constraints are [normalized](PbConstraint::normalize) and their terms are ordered by decreasing weights, then by literals.
+/// The clauses state that the variable of a node implies the function it represents, which is enough to maintain generalized arc consistency by unit propagation.
+///
+/// ```
+/// use pblib_rs::PbConstraint;
+/// use pblib_rs::native::BddManager;
+///
+/// let mut clauses = Vec::new();
+/// let mut manager = BddManager::new(5);
+/// manager.encode_constraint_into(&PbConstraint::weighted([(3, 1), (2, 2), (2, 3)]).leq(4), &mut clauses);
+/// let n_nodes = manager.n_nodes();
+/// // the same constraint with another literal shares the nodes on x2 and x3
+/// manager.encode_constraint_into(&PbConstraint::weighted([(3, 4), (2, 2), (2, 3)]).leq(4), &mut clauses);
+/// assert_eq!(n_nodes + 1, manager.n_nodes());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BddManager {
+    nodes: BTreeMap<(i32, Node, Node), i32>,
+    vars: AuxVars,
+}
+
+/// A node of a BDD, designated by its auxiliary variable if it is not a terminal node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Node {
+    False,
+    True,
+    Inner(i32),
+}
+
+impl BddManager {
+    /// Builds a new manager, with no nodes, which numbers the variables of the nodes from `first_aux_var`.
+    #[must_use]
+    pub fn new(first_aux_var: i32) -> Self {
+        Self {
+            nodes: BTreeMap::new(),
+            vars: AuxVars::new(first_aux_var),
+        }
+    }
+
+    /// Returns the number of non-terminal nodes created by this manager.
+    #[must_use]
+    pub fn n_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the lowest variable index that is not used by the nodes of this manager.
+    #[must_use]
+    pub fn next_free_var_id(&self) -> i32 {
+        self.vars.next_free_var_id()
+    }
+
+    /// Returns a Graphviz DOT description of the nodes created by this manager.
+    ///
+    /// Each node is labeled by the literal it tests, with its variable as an external label.
+    /// The solid edges lead to the children for which the literal is true, and the dashed ones to the children for which it is false.
+    /// Since the nodes are shared among the constraints, the graph may have several roots.
+    ///
+    /// ```
+    /// use pblib_rs::PbConstraint;
+    /// use pblib_rs::native::BddManager;
+    ///
+    /// let mut manager = BddManager::new(3);
+    /// manager.encode_constraint_into(&PbConstraint::cardinality([1, 2]).geq(1), &mut Vec::new());
+    /// let dot = manager.to_dot();
+    /// assert!(dot.contains("\"n3\" -> \"true\";"));
+    /// ```
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let id = |node: Node| match node {
+            Node::False => String::from("false"),
+            Node::True => String::from("true"),
+            Node::Inner(v) => format!("n{v}"),
+        };
+        let mut graph = DotGraph::new();
+        for terminal in [Node::False, Node::True] {
+            if self
+                .nodes
+                .keys()
+                .any(|(_, h, l)| *h == terminal || *l == terminal)
+            {
+                graph.node(&id(terminal), "shape=box");
+            }
+        }
+        for (&(literal, high, low), &v) in &self.nodes {
+            let node = id(Node::Inner(v));
+            graph.node(&node, &format!("label=\"{literal}\", xlabel=\"{v}\""));
+            graph.edge(&node, &id(high), "");
+            graph.edge(&node, &id(low), "style=dashed");
+        }
+        graph.finish()
+    }
+
+    /// Encodes a [`PbConstraint`], sending the clauses of the new nodes and the one asserting the root to a [`ClauseSink`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`, or if the bound of a strict comparator overflows.
+    pub fn encode_constraint_into(
+        &mut self,
+        constraint: &PbConstraint,
+        sink: &mut impl ClauseSink,
+    ) {
+        for normalized in constraint.normalize() {
+            let mut terms = normalized.terms().to_vec();
+            terms.sort_unstable_by(|(w1, l1), (w2, l2)| w2.cmp(w1).then(l1.cmp(l2)));
+            let mut remaining_sums = vec![0; terms.len() + 1];
+            for (i, (w, _)) in terms.iter().enumerate().rev() {
+                remaining_sums[i] = remaining_sums[i + 1] + i128::from(*w);
+            }
+            let mut builder = Builder {
+                terms: &terms,
+                remaining_sums: &remaining_sums,
+                levels: vec![BTreeMap::new(); terms.len()],
+                manager: self,
+            };
+            let root = builder.build(0, i128::from(normalized.rhs()), sink).0;
+            sink.explain(format_args!("BDD root of {normalized}"));
+            match root {
+                Node::True => {}
+                Node::False => sink.add_clause(&[]),
+                Node::Inner(v) => sink.add_clause(&[v]),
+            }
+        }
+    }
+
+    /// Returns the node testing a literal, creating it and sending its clauses to a [`ClauseSink`] if needed.
+    fn node(&mut self, literal: i32, high: Node, low: Node, sink: &mut impl ClauseSink) -> Node {
+        if high == low {
+            return high;
+        }
+        if let Some(v) = self.nodes.get(&(literal, high, low)) {
+            return Node::Inner(*v);
+        }
+        let v = self.vars.new_var();
+        sink.explain(format_args!("BDD node {v} testing {literal}"));
+        // v -> (literal ? high : low)
+        for (condition, child) in [(-literal, high), (literal, low)] {
+            match child {
+                Node::True => {}
+                Node::False => sink.add_clause(&[-v, condition]),
+                Node::Inner(c) => sink.add_clause(&[-v, condition, c]),
+            }
+        }
+        self.nodes.insert((literal, high, low), v);
+        Node::Inner(v)
+    }
+}
+
+/// The state of the construction of the BDD of a normalized constraint `sum(w_i*l_i) >= k`.
+struct Builder<'a> {
+    terms: &'a [(i64, i32)],
+    /// The sums of the weights of the terms from each index.
+    remaining_sums: &'a [i128],
+    /// For each index, the nodes built for the terms from this index, by the lower bound of the interval of right hand sides they represent.
+    levels: Vec<BTreeMap<i128, (i128, Node)>>,
+    manager: &'a mut BddManager,
+}
+
+impl Builder<'_> {
+    /// Returns the node representing the constraint on the terms from index `i` with right hand side `k`, and the interval of right hand sides for which this node is the same.
+    fn build(&mut self, i: usize, k: i128, sink: &mut impl ClauseSink) -> (Node, (i128, i128)) {
+        if k <= 0 {
+            return (Node::True, (i128::MIN, 0));
+        }
+        if k > self.remaining_sums[i] {
+            return (Node::False, (self.remaining_sums[i] + 1, i128::MAX));
+        }
+        if let Some((&min, &(max, node))) = self.levels[i].range(..=k).next_back() {
+            if k <= max {
+                return (node, (min, max));
+            }
+        }
+        let (w, l) = self.terms[i];
+        let w = i128::from(w);
+        let (high, (high_min, high_max)) = self.build(i + 1, k - w, sink);
+        let (low, (low_min, low_max)) = self.build(i + 1, k, sink);
+        let node = self.manager.node(l, high, low, sink);
+        let interval = (
+            high_min.saturating_add(w).max(low_min),
+            high_max.saturating_add(w).min(low_max),
+        );
+        self.levels[i].insert(interval.0, (interval.1, node));
+        (node, interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{reference, Comparator};
+
+    const TERMS: [(i64, i32); 5] = [(5, 1), (-3, 2), (2, -3), (7, 4), (2, 5)];
+
+    #[test]
+    fn test_encode_constraint() {
+        for comparator in [
+            Comparator::Leq,
+            Comparator::Geq,
+            Comparator::Eq,
+            Comparator::Lt,
+            Comparator::Gt,
+        ] {
+            for rhs in -5..=16 {
+                let constraint = PbConstraint::new(TERMS.to_vec(), comparator, rhs);
+                let mut clauses = Vec::new();
+                let mut manager = BddManager::new(6);
+                manager.encode_constraint_into(&constraint, &mut clauses);
+                let next_free_var_id = manager.next_free_var_id();
+                assert!(clauses.iter().flatten().all(|l| l.abs() < next_free_var_id));
+                assert_eq!(
+                    Ok(()),
+                    reference::check_encoding(&[constraint], &clauses, 5)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_nodes() {
+        let constraints = (0..=16)
+            .map(|rhs| PbConstraint::new(TERMS.to_vec(), Comparator::Leq, rhs))
+            .collect::<Vec<_>>();
+        let mut clauses = Vec::new();
+        let mut manager = BddManager::new(6);
+        for c in &constraints {
+            manager.encode_constraint_into(c, &mut clauses);
+        }
+        let mut unshared_nodes = 0;
+        for c in &constraints {
+            let mut manager = BddManager::new(6);
+            manager.encode_constraint_into(c, &mut Vec::new());
+            unshared_nodes += manager.n_nodes();
+        }
+        assert!(manager.n_nodes() < unshared_nodes);
+        assert_eq!(Ok(()), reference::check_encoding(&constraints, &clauses, 5));
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut manager = BddManager::new(6);
+        manager.encode_constraint_into(
+            &PbConstraint::new(TERMS.to_vec(), Comparator::Leq, 4),
+            &mut Vec::new(),
+        );
+        let dot = manager.to_dot();
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(
+            manager.n_nodes(),
+            dot.lines().filter(|l| l.contains("xlabel")).count()
+        );
+        assert_eq!(
+            2 * manager.n_nodes(),
+            dot.lines().filter(|l| l.contains("->")).count()
+        );
+        assert_eq!("digraph {\n}\n", BddManager::new(1).to_dot());
+    }
+
+    #[test]
+    fn test_interval_merging() {
+        // x1 + ... + x6 <= 3 has (k+1)*(n-k) nodes at most
+        let mut clauses = Vec::new();
+        let mut manager = BddManager::new(7);
+        manager.encode_constraint_into(&PbConstraint::cardinality(1..=6).leq(3), &mut clauses);
+        assert!(manager.n_nodes() <= 12);
+        assert_eq!(
+            Ok(()),
+            reference::check_encoding(&[PbConstraint::cardinality(1..=6).leq(3)], &clauses, 6)
+        );
+    }
+}
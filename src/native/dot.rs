@@ -0,0 +1,38 @@
+use alloc::{format, string::String, vec::Vec};
+
+/// A builder for the Graphviz DOT descriptions of the structures of the encodings.
+pub(super) struct DotGraph {
+    lines: Vec<String>,
+}
+
+impl DotGraph {
+    pub(super) fn new() -> Self {
+        Self { lines: Vec::new() }
+    }
+
+    /// Adds a node, given its identifier and its attributes (e.g. `label="x1"`).
+    pub(super) fn node(&mut self, id: &str, attributes: &str) {
+        self.lines.push(format!("  \"{id}\" [{attributes}];"));
+    }
+
+    /// Adds an edge between two nodes, given their identifiers and the attributes of the edge.
+    pub(super) fn edge(&mut self, from: &str, to: &str, attributes: &str) {
+        if attributes.is_empty() {
+            self.lines.push(format!("  \"{from}\" -> \"{to}\";"));
+        } else {
+            self.lines
+                .push(format!("  \"{from}\" -> \"{to}\" [{attributes}];"));
+        }
+    }
+
+    /// Returns the DOT description of the graph.
+    pub(super) fn finish(self) -> String {
+        let mut output = String::from("digraph {\n");
+        for line in self.lines {
+            output.push_str(&line);
+            output.push('\n');
+        }
+        output.push_str("}\n");
+        output
+    }
+}
@@ -0,0 +1,244 @@
+//! Encoders written in Rust, independent of pblib.
+//!
+//! This module is enabled by the `native` feature.
+//! Its encoders follow the conventions of [`PB2CNF`](crate::PB2CNF): the literals are given in the DIMACS format, the auxiliary variables are numbered from a `first_aux_var` parameter, the clauses are sent to a [`ClauseSink`](crate::ClauseSink), and the next free variable id is returned.
+//! Unlike the pblib encodings, the structures they build can be kept and queried after the encoding, e.g. to read the output literals of a [`Totalizer`].
+//!
+//! ```
+//! use pblib_rs::PbConstraint;
+//! use pblib_rs::native::{CardinalityEncoder, PbEncoder, Totalizer};
+//!
+//! let mut clauses = Vec::new();
+//! // x1 + x2 + x3 <= 1
+//! let next_free_var_id = Totalizer::encode_at_most_k_into(&[1, 2, 3], 1, 4, &mut clauses);
+//! // x4 + x5 + x6 >= 2
+//! let next_free_var_id = CardinalityEncoder::SequentialCounter.encode_at_least_k_into(&[4, 5, 6], 2, next_free_var_id, &mut clauses);
+//! // 3*x1 + 5*x4 + 6*x5 <= 8
+//! let constraint = PbConstraint::weighted([(3, 1), (5, 4), (6, 5)]).leq(8);
+//! PbEncoder::PolynomialWatchdog.encode_constraint_into(&constraint, next_free_var_id, &mut clauses);
+//! ```
+
+use crate::ClauseSink;
+#[cfg(feature = "std")]
+use crate::PbConstraint;
+
+#[cfg(feature = "std")]
+mod adder;
+#[cfg(feature = "std")]
+pub use adder::Adder;
+
+mod at_most_one;
+
+#[cfg(feature = "std")]
+mod backend;
+#[cfg(feature = "std")]
+pub use backend::NativeBackend;
+
+#[cfg(feature = "std")]
+mod bdd;
+#[cfg(feature = "std")]
+pub use bdd::BddManager;
+
+mod dot;
+
+mod oll;
+pub use oll::Oll;
+
+mod sequential_counter;
+
+#[cfg(feature = "std")]
+mod stratification;
+#[cfg(feature = "std")]
+pub use stratification::Stratification;
+
+#[cfg(feature = "std")]
+mod sorting_network;
+#[cfg(feature = "std")]
+pub use sorting_network::SortingNetworkEncoding;
+
+mod totalizer;
+pub use totalizer::Totalizer;
+
+#[cfg(feature = "std")]
+mod watchdog;
+#[cfg(feature = "std")]
+pub use watchdog::DynamicWatchdog;
+
+/// The native encodings of cardinality constraints.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CardinalityEncoder {
+    /// The totalizer encoding; see [`Totalizer`].
+    #[default]
+    Totalizer,
+    /// Sinz's sequential counter, which uses `(n-1)*k` auxiliary variables and `O(nk)` clauses.
+    ///
+    /// Unit propagation on this encoding maintains generalized arc consistency.
+    SequentialCounter,
+}
+
+impl CardinalityEncoder {
+    /// Encodes an At-Most-k cardinality constraint, sending the clauses to a [`ClauseSink`].
+    ///
+    /// See [`PB2CNF::encode_at_most_k`](crate::PB2CNF::encode_at_most_k) for more information on the parameters.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn encode_at_most_k_into(
+        self,
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        match self {
+            CardinalityEncoder::Totalizer => {
+                Totalizer::encode_at_most_k_into(literals, k, first_aux_var, sink)
+            }
+            CardinalityEncoder::SequentialCounter => {
+                sequential_counter::encode_at_most_k_into(literals, k, first_aux_var, sink)
+            }
+        }
+    }
+
+    /// Encodes an At-Least-k cardinality constraint, sending the clauses to a [`ClauseSink`].
+    ///
+    /// See [`PB2CNF::encode_at_least_k`](crate::PB2CNF::encode_at_least_k) for more information on the parameters.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn encode_at_least_k_into(
+        self,
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        match self {
+            CardinalityEncoder::Totalizer => {
+                Totalizer::encode_at_least_k_into(literals, k, first_aux_var, sink)
+            }
+            CardinalityEncoder::SequentialCounter => {
+                sequential_counter::encode_at_least_k_into(literals, k, first_aux_var, sink)
+            }
+        }
+    }
+}
+
+/// The native encodings of At-Most-One constraints.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AmoEncoder {
+    /// The ladder (or regular) encoding, which uses `n-1` auxiliary variables and `3n-4` clauses.
+    #[default]
+    Ladder,
+    /// The commander encoding, with the given number of literals per group.
+    ///
+    /// The group size must be at least 2.
+    Commander {
+        /// The number of literals of a group, which share a commander variable.
+        group_size: usize,
+    },
+}
+
+impl AmoEncoder {
+    /// Encodes an At-Most-One constraint, sending the clauses to a [`ClauseSink`].
+    ///
+    /// See [`PB2CNF::encode_at_most_k`](crate::PB2CNF::encode_at_most_k) for more information on the parameters.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`, or if the group size of the commander encoding is lower than 2.
+    pub fn encode_at_most_one_into(
+        self,
+        literals: &[i32],
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        match self {
+            AmoEncoder::Ladder => at_most_one::encode_ladder_into(literals, first_aux_var, sink),
+            AmoEncoder::Commander { group_size } => {
+                at_most_one::encode_commander_into(literals, group_size, first_aux_var, sink)
+            }
+        }
+    }
+}
+
+/// The native encodings of Pseudo-Boolean constraints.
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PbEncoder {
+    /// The BDD encoding; see [`BddManager`].
+    #[default]
+    Bdd,
+    /// The adder network encoding; see [`Adder`].
+    Adder,
+    /// The global polynomial watchdog encoding, which combines totalizers counting the bits of the weights.
+    ///
+    /// Its size is polynomial in the number of literals and in the number of bits of the weights, so it is smaller than BDDs for large weights.
+    /// Unlike the BDD encoding, unit propagation does not maintain generalized arc consistency on it.
+    PolynomialWatchdog,
+    /// The encoding by sorting networks over a mixed-radix decomposition of the weights; see [`SortingNetworkEncoding`].
+    SortingNetworks,
+}
+
+#[cfg(feature = "std")]
+impl PbEncoder {
+    /// Encodes a [`PbConstraint`], sending the clauses to a [`ClauseSink`].
+    ///
+    /// See [`PB2CNF::encode_constraint`](crate::PB2CNF::encode_constraint) for more information on the parameters.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`, or if the bound of a strict comparator overflows.
+    pub fn encode_constraint_into(
+        self,
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        match self {
+            PbEncoder::Bdd => {
+                let mut manager = BddManager::new(first_aux_var);
+                manager.encode_constraint_into(constraint, sink);
+                manager.next_free_var_id()
+            }
+            PbEncoder::Adder => Adder::encode_constraint_into(constraint, first_aux_var, sink),
+            PbEncoder::PolynomialWatchdog => {
+                watchdog::encode_constraint_into(constraint, first_aux_var, sink)
+            }
+            PbEncoder::SortingNetworks => {
+                SortingNetworkEncoding::encode_constraint_into(constraint, first_aux_var, sink)
+                    .next_free_var_id()
+            }
+        }
+    }
+}
+
+/// An allocator of auxiliary variables, numbering them from the first auxiliary variable of an encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AuxVars {
+    next_free_var_id: i32,
+}
+
+impl AuxVars {
+    pub(crate) fn new(first_aux_var: i32) -> Self {
+        Self {
+            next_free_var_id: first_aux_var,
+        }
+    }
+
+    pub(crate) fn new_var(&mut self) -> i32 {
+        let var = self.next_free_var_id;
+        self.next_free_var_id = var.checked_add(1).expect("too many variables for an i32");
+        var
+    }
+
+    pub(crate) fn next_free_var_id(&self) -> i32 {
+        self.next_free_var_id
+    }
+}
@@ -0,0 +1,293 @@
+use super::Totalizer;
+use crate::ClauseSink;
+use alloc::{collections::BTreeMap, vec::Vec};
+
+/// The bookkeeping of the OLL core-guided `MaxSAT` algorithm (Morgado, Dodaro and Marques-Silva, 2014).
+///
+/// The soft literals are the ones that should be true, each one having a weight paid when it is false.
+/// The SAT solver is called with the [`assumptions`](Self::assumptions), which are the current soft literals.
+/// When the solver reports that the assumptions are unsatisfiable, the core it returns (a subset of the assumptions that cannot be true together) is given to [`process_core`](Self::process_core).
+/// The weights of the core literals are decreased by their minimum, which is added to the lower bound, and a totalizer counting the false literals of the core is built.
+/// The negation of its second output, stating that at most one core literal is false, becomes a new soft literal; once it appears in a core, the next output is used, and so on.
+/// When the solver finds a model under the assumptions, the lower bound is the optimal cost.
+///
+/// ```
+/// use pblib_rs::MiniSat;
+/// use pblib_rs::native::Oll;
+///
+/// let mut solver = MiniSat::new();
+/// // x1 and x2 cannot be true together, nor x2 and x3
+/// solver.add_clause(&[-1, -2]);
+/// solver.add_clause(&[-2, -3]);
+/// let mut oll = Oll::new(&[(2, 1), (3, 2), (2, 3)], 4);
+/// assert!(!solver.solve_with_assumptions(&oll.assumptions()));
+/// // this solver does not give the cores, which must be computed by other means
+/// let mut clauses = Vec::new();
+/// oll.process_core(&[1, 2], &mut clauses);
+/// for c in &clauses {
+///     solver.add_clause(c);
+/// }
+/// assert_eq!(2, oll.lower_bound());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Oll {
+    /// The soft literals given to the constructor, with their weights.
+    original: Vec<(i64, i32)>,
+    /// The current soft literals, with their remaining weights.
+    weights: BTreeMap<i32, i64>,
+    /// The totalizers built for the cores, with their weights.
+    totalizers: Vec<(Totalizer, i64)>,
+    /// The soft literals that are negations of totalizer outputs, with the indices of their totalizers and outputs.
+    outputs: BTreeMap<i32, (usize, usize)>,
+    lower_bound: i64,
+    next_free_var_id: i32,
+}
+
+impl Oll {
+    /// Builds the bookkeeping for the given soft literals, given with their weights.
+    ///
+    /// The totalizers use auxiliary variables numbered from `first_aux_var`.
+    /// The literals with a null weight are ignored; if a literal is given several times, its weights are summed.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a weight is negative.
+    #[must_use]
+    pub fn new(soft_literals: &[(i64, i32)], first_aux_var: i32) -> Self {
+        assert!(
+            soft_literals.iter().all(|(w, _)| *w >= 0),
+            "the weights of the soft literals must be nonnegative"
+        );
+        let mut weights = BTreeMap::new();
+        for (w, l) in soft_literals.iter().filter(|(w, _)| *w > 0) {
+            *weights.entry(*l).or_insert(0) += w;
+        }
+        Self {
+            original: soft_literals.to_vec(),
+            weights,
+            totalizers: Vec::new(),
+            outputs: BTreeMap::new(),
+            lower_bound: 0,
+            next_free_var_id: first_aux_var,
+        }
+    }
+
+    /// Returns the current soft literals, which must be given as assumptions to the SAT solver.
+    #[must_use]
+    pub fn assumptions(&self) -> Vec<i32> {
+        self.weights.keys().copied().collect()
+    }
+
+    /// Returns the current soft literals whose weight is at least `min_weight`.
+    ///
+    /// Solvers using the stratification strategy give these literals as assumptions, with a decreasing minimal weight, so that the cores involving the heaviest literals are found first.
+    /// Once the assumptions are satisfiable, the minimal weight is lowered; the lower bound is optimal when the assumptions containing all the soft literals are satisfiable.
+    #[must_use]
+    pub fn assumptions_with_min_weight(&self, min_weight: i64) -> Vec<i32> {
+        self.weights
+            .iter()
+            .filter(|(_, w)| **w >= min_weight)
+            .map(|(l, _)| *l)
+            .collect()
+    }
+
+    /// Returns the remaining weight of a soft literal, or `None` if it is not a current soft literal.
+    #[must_use]
+    pub fn weight(&self, literal: i32) -> Option<i64> {
+        self.weights.get(&literal).copied()
+    }
+
+    /// Returns the lower bound of the cost, that is the sum of the weights of the processed cores.
+    #[must_use]
+    pub fn lower_bound(&self) -> i64 {
+        self.lower_bound
+    }
+
+    /// Returns the lowest variable index that is not used by the totalizers.
+    #[must_use]
+    pub fn next_free_var_id(&self) -> i32 {
+        self.next_free_var_id
+    }
+
+    /// Returns the cost of a model, that is the sum of the weights of the original soft literals it falsifies.
+    ///
+    /// The model is given as a list of DIMACS literals; a soft literal whose variable is not assigned by the model is considered as false.
+    #[must_use]
+    pub fn cost(&self, model: &[i32]) -> i64 {
+        self.original
+            .iter()
+            .filter(|(_, l)| !model.contains(l))
+            .map(|(w, _)| *w)
+            .sum()
+    }
+
+    /// Reformulates a core, that is a set of soft literals that cannot be true together, sending the clauses of the new totalizer to a [`ClauseSink`].
+    ///
+    /// The clauses must be added to the SAT solver before the next call.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the core is empty, or if it contains a literal that is not a current soft literal.
+    pub fn process_core(&mut self, core: &[i32], sink: &mut impl ClauseSink) {
+        let weight = core
+            .iter()
+            .map(|l| {
+                self.weight(*l)
+                    .unwrap_or_else(|| panic!("{l} is not a soft literal"))
+            })
+            .min()
+            .expect("the core must not be empty");
+        self.lower_bound += weight;
+        for l in core {
+            let remaining = self.weights[l] - weight;
+            if remaining == 0 {
+                self.weights.remove(l);
+            } else {
+                self.weights.insert(*l, remaining);
+            }
+            // the next output of the totalizer becomes soft
+            if let Some((t, o)) = self.outputs.remove(l) {
+                let (totalizer, totalizer_weight) = &self.totalizers[t];
+                if let Some(next) = totalizer.outputs().get(o + 1) {
+                    *self.weights.entry(-next).or_insert(0) += totalizer_weight;
+                    self.outputs.insert(-next, (t, o + 1));
+                }
+            }
+        }
+        if core.len() < 2 {
+            return;
+        }
+        let negated = core.iter().map(|l| -l).collect::<Vec<_>>();
+        let totalizer = Totalizer::new(&negated, self.next_free_var_id, sink);
+        self.next_free_var_id = totalizer.next_free_var_id();
+        // at least one core literal is false, so the first output is true
+        let second = -totalizer.outputs()[1];
+        *self.weights.entry(second).or_insert(0) += weight;
+        self.outputs.insert(second, (self.totalizers.len(), 1));
+        self.totalizers.push((totalizer, weight));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MiniSat;
+
+    /// Returns a minimal core of the assumptions, or `None` if they are satisfiable.
+    fn minimal_core(solver: &mut MiniSat, assumptions: &[i32]) -> Option<Vec<i32>> {
+        if solver.solve_with_assumptions(assumptions) {
+            return None;
+        }
+        let mut core = assumptions.to_vec();
+        let mut i = 0;
+        while i < core.len() {
+            let mut reduced = core.clone();
+            reduced.remove(i);
+            if solver.solve_with_assumptions(&reduced) {
+                i += 1;
+            } else {
+                core = reduced;
+            }
+        }
+        Some(core)
+    }
+
+    fn optimize(clauses: &[Vec<i32>], soft_literals: &[(i64, i32)], n_vars: i32) -> (i64, i64) {
+        let mut solver = MiniSat::new();
+        for c in clauses {
+            solver.add_clause(c);
+        }
+        let mut oll = Oll::new(soft_literals, n_vars + 1);
+        while let Some(core) = minimal_core(&mut solver, &oll.assumptions()) {
+            let mut new_clauses = Vec::new();
+            oll.process_core(&core, &mut new_clauses);
+            for c in &new_clauses {
+                solver.add_clause(c);
+            }
+        }
+        (oll.lower_bound(), oll.cost(&solver.model().unwrap()))
+    }
+
+    fn brute_force(clauses: &[Vec<i32>], soft_literals: &[(i64, i32)], n_vars: i32) -> i64 {
+        (0_u32..1 << n_vars)
+            .map(|bits| {
+                (1..=n_vars)
+                    .map(|v| if bits & (1 << (v - 1)) == 0 { -v } else { v })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|m| clauses.iter().all(|c| c.iter().any(|l| m.contains(l))))
+            .map(|m| Oll::new(soft_literals, n_vars + 1).cost(&m))
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_optimize() {
+        let clauses = vec![
+            vec![-1, -2],
+            vec![-2, -3],
+            vec![-3, -4],
+            vec![-1, -4, -5],
+            vec![-5, -6],
+            vec![-1, -6],
+        ];
+        for soft_literals in [
+            vec![(1, 1), (1, 2), (1, 3), (1, 4), (1, 5), (1, 6)],
+            vec![(2, 1), (3, 2), (2, 3), (4, 4), (1, 5), (5, 6)],
+            vec![(7, 1), (1, 2), (1, 3), (7, 4), (3, 5), (3, 6), (2, -1)],
+        ] {
+            let (lower_bound, cost) = optimize(&clauses, &soft_literals, 6);
+            let optimum = brute_force(&clauses, &soft_literals, 6);
+            assert_eq!(optimum, lower_bound);
+            assert_eq!(optimum, cost);
+        }
+    }
+
+    #[test]
+    fn test_stratified_optimize() {
+        let clauses = vec![vec![-1, -2], vec![-2, -3], vec![-3, -4], vec![-1, -4]];
+        let soft_literals = [(8, 1), (1, 2), (1, 3), (3, 4)];
+        let mut solver = MiniSat::new();
+        for c in &clauses {
+            solver.add_clause(c);
+        }
+        let mut oll = Oll::new(&soft_literals, 5);
+        for min_weight in [8, 3, 1] {
+            while let Some(core) =
+                minimal_core(&mut solver, &oll.assumptions_with_min_weight(min_weight))
+            {
+                let mut new_clauses = Vec::new();
+                oll.process_core(&core, &mut new_clauses);
+                for c in &new_clauses {
+                    solver.add_clause(c);
+                }
+            }
+        }
+        let optimum = brute_force(&clauses, &soft_literals, 4);
+        assert_eq!(optimum, oll.lower_bound());
+        assert_eq!(optimum, oll.cost(&solver.model().unwrap()));
+    }
+
+    #[test]
+    fn test_process_core() {
+        let mut oll = Oll::new(&[(2, 1), (3, 2), (0, 3)], 4);
+        assert_eq!(vec![1, 2], oll.assumptions());
+        let mut clauses = Vec::new();
+        oll.process_core(&[1, 2], &mut clauses);
+        assert_eq!(2, oll.lower_bound());
+        assert_eq!(None, oll.weight(1));
+        assert_eq!(Some(1), oll.weight(2));
+        assert_eq!(2, oll.assumptions().len());
+        assert!(clauses
+            .iter()
+            .flatten()
+            .all(|l| l.abs() < oll.next_free_var_id()));
+    }
+
+    #[test]
+    #[should_panic(expected = "3 is not a soft literal")]
+    fn test_process_core_unknown_literal() {
+        let mut oll = Oll::new(&[(2, 1), (3, 2)], 4);
+        oll.process_core(&[1, 3], &mut Vec::new());
+    }
+}
@@ -0,0 +1,110 @@
+use super::AuxVars;
+use crate::ClauseSink;
+use alloc::vec::Vec;
+
+/// Encodes an At-Most-k cardinality constraint with Sinz's sequential counter (`LTseq`), sending the clauses to a [`ClauseSink`].
+///
+/// The register variable `s[i][j]` is implied by the fact that at least `j+1` literals among the `i+1` first ones are true.
+/// The encoding uses `(n-1)*k` auxiliary variables and `2nk+n-3k-1` clauses, and unit propagation maintains generalized arc consistency.
+pub(crate) fn encode_at_most_k_into(
+    literals: &[i32],
+    k: i64,
+    first_aux_var: i32,
+    sink: &mut impl ClauseSink,
+) -> i32 {
+    let Ok(k) = usize::try_from(k) else {
+        sink.explain(format_args!("unsatisfiable bound: sum <= {k}"));
+        sink.add_clause(&[]);
+        return first_aux_var;
+    };
+    if k >= literals.len() {
+        return first_aux_var;
+    }
+    if k == 0 {
+        sink.explain(format_args!("sequential counter: no true literal"));
+        for l in literals {
+            sink.add_clause(&[-l]);
+        }
+        return first_aux_var;
+    }
+    let mut vars = AuxVars::new(first_aux_var);
+    let n = literals.len();
+    let mut previous: Vec<i32> = Vec::new();
+    for (i, l) in literals.iter().enumerate() {
+        sink.explain(format_args!("sequential counter: literal {l} at index {i}"));
+        if i > 0 {
+            // a true literal may not overflow the counter
+            sink.add_clause(&[-l, -previous[k - 1]]);
+        }
+        if i == n - 1 {
+            break;
+        }
+        let register = (0..k).map(|_| vars.new_var()).collect::<Vec<_>>();
+        sink.add_clause(&[-l, register[0]]);
+        if i == 0 {
+            for s in &register[1..] {
+                sink.add_clause(&[-s]);
+            }
+        } else {
+            for j in 0..k {
+                sink.add_clause(&[-previous[j], register[j]]);
+                if j > 0 {
+                    sink.add_clause(&[-l, -previous[j - 1], register[j]]);
+                }
+            }
+        }
+        previous = register;
+    }
+    vars.next_free_var_id()
+}
+
+/// Encodes an At-Least-k cardinality constraint with Sinz's sequential counter, as an At-Most-(n-k) constraint on the negated literals.
+pub(crate) fn encode_at_least_k_into(
+    literals: &[i32],
+    k: i64,
+    first_aux_var: i32,
+    sink: &mut impl ClauseSink,
+) -> i32 {
+    let negated = literals.iter().map(|l| -l).collect::<Vec<_>>();
+    let n = i64::try_from(literals.len()).unwrap_or(i64::MAX);
+    encode_at_most_k_into(&negated, n.saturating_sub(k), first_aux_var, sink)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{reference, PbConstraint};
+
+    const LITERALS: [i32; 5] = [1, -2, 3, 4, -5];
+
+    #[test]
+    fn test_encode_at_most_k() {
+        for k in -1..=6 {
+            let mut clauses = Vec::new();
+            let next_free_var_id = encode_at_most_k_into(&LITERALS, k, 6, &mut clauses);
+            if (1..5).contains(&k) {
+                let k = usize::try_from(k).unwrap();
+                assert_eq!(4 * k, usize::try_from(next_free_var_id - 6).unwrap());
+                assert_eq!(10 * k + 5 - 3 * k - 1, clauses.len());
+            }
+            let constraint = PbConstraint::cardinality(LITERALS).leq(k);
+            assert_eq!(
+                Ok(()),
+                reference::check_encoding(&[constraint], &clauses, 5)
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_at_least_k() {
+        for k in -1..=6 {
+            let mut clauses = Vec::new();
+            encode_at_least_k_into(&LITERALS, k, 6, &mut clauses);
+            let constraint = PbConstraint::cardinality(LITERALS).geq(k);
+            assert_eq!(
+                Ok(()),
+                reference::check_encoding(&[constraint], &clauses, 5)
+            );
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use super::{dot::DotGraph, AuxVars};
+use crate::{ClauseSink, PbConstraint};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The prime numbers considered as the elements of the bases.
+const PRIMES: [u64; 7] = [2, 3, 5, 7, 11, 13, 17];
+
+/// The metadata of an encoding of a Pseudo-Boolean constraint by sorting networks (Eén and Sörensson, 2006).
+///
+/// Like in `MiniSat+`, the weights are written in a mixed-radix number system whose base is chosen to minimize the sum of their digits.
+/// For each digit, a sorting network counts in unary the literals weighted by their digit, the tare needed to compare the sum with a multiple of the radices, and the carry of the previous digit, that is its count divided by its radix.
+/// The constraint is then enforced by forbidding an output of the last network.
+///
+/// ```
+/// use pblib_rs::PbConstraint;
+/// use pblib_rs::native::SortingNetworkEncoding;
+///
+/// let mut clauses = Vec::new();
+/// let constraint = PbConstraint::weighted([(6, 1), (9, 2), (3, 3), (12, 4)]).leq(20);
+/// let encoding = SortingNetworkEncoding::encode_constraint_into(&constraint, 5, &mut clauses);
+/// // after the division by the GCD, the weights are 2, 3, 1 and 4, which are best written in base 2 then 2
+/// assert_eq!(&[2, 2], encoding.base());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortingNetworkEncoding {
+    base: Vec<u64>,
+    /// The comparators, as their inputs followed by their maximum and minimum outputs.
+    comparators: Vec<[i32; 4]>,
+    next_free_var_id: i32,
+}
+
+impl SortingNetworkEncoding {
+    /// Encodes a [`PbConstraint`] with sorting networks, sending the clauses to a [`ClauseSink`], and returns the metadata of the encoding.
+    ///
+    /// See [`PB2CNF::encode_constraint`](crate::PB2CNF::encode_constraint) for more information on the parameters.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`, or if the bound of a strict comparator overflows.
+    pub fn encode_constraint_into(
+        constraint: &PbConstraint,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> Self {
+        let mut vars = AuxVars::new(first_aux_var);
+        let normalized = constraint.normalize();
+        // the constraints produced by the normalization of an equality have the same weights
+        let base = normalized
+            .first()
+            .map(|c| {
+                Self::optimal_base(
+                    &c.weights()
+                        .iter()
+                        .map(|w| w.unsigned_abs())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .unwrap_or_default();
+        let mut comparators = Vec::new();
+        for c in normalized {
+            // sum(w_i*l_i) >= k is equivalent to sum(w_i*~l_i) <= sum(w_i)-k
+            let terms = c
+                .terms()
+                .iter()
+                .map(|(w, l)| (w.unsigned_abs(), -l))
+                .collect::<Vec<_>>();
+            let weight_sum = terms.iter().map(|(w, _)| i128::from(*w)).sum::<i128>();
+            let k = weight_sum - i128::from(c.rhs());
+            if k < 0 {
+                sink.explain(format_args!("unsatisfiable constraint {c}"));
+                sink.add_clause(&[]);
+            } else if k < weight_sum {
+                let mut network = Network {
+                    vars: &mut vars,
+                    sink,
+                    comparators: &mut comparators,
+                };
+                network.encode_leq(&terms, k.unsigned_abs(), &base);
+            }
+        }
+        Self {
+            base,
+            comparators,
+            next_free_var_id: vars.next_free_var_id(),
+        }
+    }
+
+    /// Returns the mixed-radix base used to decompose the weights, beginning with the radix of the least significant digit.
+    ///
+    /// The most significant digit has no radix: it is the quotient of the weights by the product of the elements of the base.
+    #[must_use]
+    pub fn base(&self) -> &[u64] {
+        &self.base
+    }
+
+    /// Returns a Graphviz DOT description of the comparators of the sorting networks.
+    ///
+    /// Each comparator is a node whose incoming edges come from its inputs, and whose outgoing edges lead to its outputs, the maximum first.
+    /// The inputs and the outputs are nodes labeled by their literals, so that the carries link the networks of consecutive digits.
+    ///
+    /// ```
+    /// use pblib_rs::PbConstraint;
+    /// use pblib_rs::native::SortingNetworkEncoding;
+    ///
+    /// let constraint = PbConstraint::cardinality([1, 2]).leq(1);
+    /// let encoding = SortingNetworkEncoding::encode_constraint_into(&constraint, 3, &mut Vec::new());
+    /// // a single comparator on x1 and x2
+    /// assert!(encoding.to_dot().contains("\"1\" -> \"c0\";"));
+    /// ```
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut graph = DotGraph::new();
+        let mut wires = BTreeSet::new();
+        for (i, comparator) in self.comparators.iter().enumerate() {
+            let id = format!("c{i}");
+            graph.node(&id, "label=\"\", shape=circle, width=0.2");
+            for (j, l) in comparator.iter().enumerate() {
+                if wires.insert(*l) {
+                    graph.node(&l.to_string(), "shape=plaintext");
+                }
+                if j < 2 {
+                    graph.edge(&l.to_string(), &id, "");
+                } else {
+                    graph.edge(&id, &l.to_string(), "");
+                }
+            }
+        }
+        graph.finish()
+    }
+
+    /// Returns the lowest variable index that is not used by the encoding.
+    #[must_use]
+    pub fn next_free_var_id(&self) -> i32 {
+        self.next_free_var_id
+    }
+
+    /// Returns the mixed-radix base minimizing the sum of the digits of some weights, which is the number of inputs of the sorting networks without the carries.
+    ///
+    /// The elements of the base are taken among the prime numbers up to 17.
+    #[must_use]
+    pub fn optimal_base(weights: &[u64]) -> Vec<u64> {
+        let mut weights = weights
+            .iter()
+            .copied()
+            .filter(|w| *w > 0)
+            .collect::<Vec<_>>();
+        weights.sort_unstable();
+        let mut memo = BTreeMap::new();
+        let mut base = optimal_base_from(weights, &mut memo).1;
+        base.reverse();
+        base
+    }
+}
+
+/// Returns the minimal sum of digits of some sorted positive weights and the corresponding base, in reverse order.
+fn optimal_base_from(
+    weights: Vec<u64>,
+    memo: &mut BTreeMap<Vec<u64>, (u128, Vec<u64>)>,
+) -> (u128, Vec<u64>) {
+    if let Some(result) = memo.get(&weights) {
+        return result.clone();
+    }
+    let mut best = (weights.iter().map(|w| u128::from(*w)).sum(), Vec::new());
+    let max_weight = weights.last().copied().unwrap_or_default();
+    for p in PRIMES.into_iter().take_while(|p| *p <= max_weight) {
+        let digit_sum = weights.iter().map(|w| u128::from(w % p)).sum::<u128>();
+        if digit_sum >= best.0 {
+            continue;
+        }
+        let quotients = weights
+            .iter()
+            .map(|w| w / p)
+            .filter(|w| *w > 0)
+            .collect::<Vec<_>>();
+        let (cost, mut base) = optimal_base_from(quotients, memo);
+        if digit_sum + cost < best.0 {
+            base.push(p);
+            best = (digit_sum + cost, base);
+        }
+    }
+    memo.insert(weights, best.clone());
+    best
+}
+
+/// The state of the construction of sorting networks.
+struct Network<'a, S> {
+    vars: &'a mut AuxVars,
+    sink: &'a mut S,
+    comparators: &'a mut Vec<[i32; 4]>,
+}
+
+impl<S: ClauseSink> Network<'_, S> {
+    /// Encodes `sum(w_i*l_i) <= k` with a mixed-radix base, given that the weights are positive and that their sum is greater than `k`.
+    fn encode_leq(&mut self, terms: &[(u64, i32)], k: u128, base: &[u64]) {
+        let radix_product = base.iter().map(|b| u128::from(*b)).product::<u128>();
+        let tare = (radix_product - (k + 1) % radix_product) % radix_product;
+        let m = (k + 1 + tare) / radix_product;
+        let tare_literal = if tare == 0 {
+            None
+        } else {
+            let t = self.vars.new_var();
+            self.sink
+                .explain(format_args!("sorting network tare {tare} on literal {t}"));
+            self.sink.add_clause(&[t]);
+            Some(t)
+        };
+        let mut weights = terms
+            .iter()
+            .map(|(w, _)| u128::from(*w))
+            .collect::<Vec<_>>();
+        let mut tare = tare;
+        let mut carries = Vec::new();
+        for i in 0..=base.len() {
+            let radix = base.get(i).map_or(u128::MAX, |b| u128::from(*b));
+            let mut inputs = Vec::new();
+            for ((_, l), w) in terms.iter().zip(weights.iter_mut()) {
+                inputs.extend(std::iter::repeat_n(Some(*l), digit(w, radix)));
+            }
+            if let Some(t) = tare_literal {
+                inputs.extend(std::iter::repeat_n(Some(t), digit(&mut tare, radix)));
+            }
+            inputs.append(&mut carries);
+            let outputs = self.sort(inputs);
+            if i == base.len() {
+                if let Some(Some(o)) = usize::try_from(m - 1).ok().and_then(|j| outputs.get(j)) {
+                    self.sink
+                        .explain(format_args!("sorting network bound: count < {m}"));
+                    self.sink.add_clause(&[-o]);
+                }
+            } else {
+                // the count divided by the radix
+                carries = outputs
+                    .into_iter()
+                    .skip(usize::try_from(radix - 1).unwrap())
+                    .step_by(usize::try_from(radix).unwrap())
+                    .flatten()
+                    .map(Some)
+                    .collect();
+            }
+        }
+    }
+
+    /// Sorts some wires with Batcher's odd-even merge sort, in decreasing order, and returns the output wires.
+    ///
+    /// A wire is a literal, or `None` for the constant false.
+    /// Only the clauses stating that the outputs are implied by the inputs are produced.
+    fn sort(&mut self, mut wires: Vec<Option<i32>>) -> Vec<Option<i32>> {
+        if wires.len() <= 1 {
+            return wires;
+        }
+        wires.resize(wires.len().next_power_of_two(), None);
+        let right = wires.split_off(wires.len() / 2);
+        let left = self.sort(wires);
+        let right = self.sort(right);
+        self.merge(&left, &right)
+    }
+
+    /// Merges two sorted sequences of wires of the same length, which is a power of two.
+    fn merge(&mut self, left: &[Option<i32>], right: &[Option<i32>]) -> Vec<Option<i32>> {
+        if left.len() == 1 {
+            let (max, min) = self.compare(left[0], right[0]);
+            return vec![max, min];
+        }
+        let even = |wires: &[Option<i32>]| wires.iter().step_by(2).copied().collect::<Vec<_>>();
+        let odd =
+            |wires: &[Option<i32>]| wires.iter().skip(1).step_by(2).copied().collect::<Vec<_>>();
+        let evens = self.merge(&even(left), &even(right));
+        let odds = self.merge(&odd(left), &odd(right));
+        let mut outputs = Vec::with_capacity(2 * left.len());
+        outputs.push(evens[0]);
+        for i in 0..odds.len() - 1 {
+            let (max, min) = self.compare(odds[i], evens[i + 1]);
+            outputs.push(max);
+            outputs.push(min);
+        }
+        outputs.push(odds[odds.len() - 1]);
+        outputs
+    }
+
+    /// Builds a comparator, and returns the maximum and the minimum of its inputs.
+    fn compare(&mut self, a: Option<i32>, b: Option<i32>) -> (Option<i32>, Option<i32>) {
+        let (Some(a), Some(b)) = (a, b) else {
+            return (a.or(b), None);
+        };
+        let max = self.vars.new_var();
+        let min = self.vars.new_var();
+        self.sink
+            .explain(format_args!("comparator ({a}, {b}) -> ({max}, {min})"));
+        self.sink.add_clause(&[-a, max]);
+        self.sink.add_clause(&[-b, max]);
+        self.sink.add_clause(&[-a, -b, min]);
+        self.comparators.push([a, b, max, min]);
+        (Some(max), Some(min))
+    }
+}
+
+/// Returns the remainder of a value by a radix, and replaces the value by the quotient.
+fn digit(value: &mut u128, radix: u128) -> usize {
+    let d = *value % radix;
+    *value /= radix;
+    usize::try_from(d).expect("too many inputs for a sorting network")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{reference, Comparator};
+
+    const TERMS: [(i64, i32); 5] = [(5, 1), (-3, 2), (2, -3), (7, 4), (2, 5)];
+
+    #[test]
+    fn test_optimal_base() {
+        assert!(SortingNetworkEncoding::optimal_base(&[]).is_empty());
+        assert!(SortingNetworkEncoding::optimal_base(&[1, 1, 1]).is_empty());
+        assert_eq!(vec![3], SortingNetworkEncoding::optimal_base(&[3, 3, 3]));
+        assert_eq!(
+            vec![2, 5, 2],
+            SortingNetworkEncoding::optimal_base(&[10, 20, 30, 2])
+        );
+        assert_eq!(
+            vec![2, 2, 2, 2, 2],
+            SortingNetworkEncoding::optimal_base(&[32, 32])
+        );
+    }
+
+    #[test]
+    fn test_encode_constraint() {
+        for comparator in [
+            Comparator::Leq,
+            Comparator::Geq,
+            Comparator::Eq,
+            Comparator::Lt,
+            Comparator::Gt,
+        ] {
+            for rhs in -5..=16 {
+                let constraint = PbConstraint::new(TERMS.to_vec(), comparator, rhs);
+                let mut clauses = Vec::new();
+                let encoding =
+                    SortingNetworkEncoding::encode_constraint_into(&constraint, 6, &mut clauses);
+                assert!(clauses
+                    .iter()
+                    .flatten()
+                    .all(|l| l.abs() < encoding.next_free_var_id()));
+                assert_eq!(
+                    Ok(()),
+                    reference::check_encoding(&[constraint], &clauses, 5)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let constraint = PbConstraint::new(TERMS.to_vec(), Comparator::Leq, 6);
+        let encoding =
+            SortingNetworkEncoding::encode_constraint_into(&constraint, 6, &mut Vec::new());
+        let dot = encoding.to_dot();
+        let n_comparators = dot.lines().filter(|l| l.contains("shape=circle")).count();
+        assert!(n_comparators > 0);
+        assert_eq!(
+            4 * n_comparators,
+            dot.lines().filter(|l| l.contains("->")).count()
+        );
+    }
+
+    #[test]
+    fn test_large_weights() {
+        let terms = vec![(1 << 40, 1), ((1 << 40) + 1, 2), (3 << 39, 3), (1, 4)];
+        let constraint = PbConstraint::new(terms, Comparator::Leq, (5 << 39) + 1);
+        let mut clauses = Vec::new();
+        let encoding = SortingNetworkEncoding::encode_constraint_into(&constraint, 5, &mut clauses);
+        assert!(encoding.next_free_var_id() < 10000);
+        assert_eq!(
+            Ok(()),
+            reference::check_encoding(&[constraint], &clauses, 4)
+        );
+    }
+}
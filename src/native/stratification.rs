@@ -0,0 +1,211 @@
+use super::{BddManager, Totalizer};
+use crate::{ClauseSink, PbConstraint};
+use std::collections::BTreeMap;
+
+/// A partition of weighted soft literals into strata of equal weights, with the encodings of the objective functions restricted to the heaviest strata.
+///
+/// Stratification is the usual strategy of weighted `MaxSAT` solvers: the soft literals with the greatest weights are considered first, and the lighter strata are added one at a time.
+/// As in [`Oll`](super::Oll), the soft literals are the ones that should be true, and their weights are paid when they are false.
+/// The strata are ordered by decreasing weights, and the objective of the stratum `i` is the cost of the soft literals of the strata `0` to `i`.
+///
+/// The structure is shared among the objectives: each stratum has a [`Totalizer`] counting its false literals, built once, and the bounds on the objectives are encoded by a single [`BddManager`] over the outputs of the totalizers.
+/// Thus, the bounds on the objective of a stratum only produce the BDD nodes they do not share with the bounds encoded before, which is the common case when the bound is tightened step by step.
+///
+/// ```
+/// use pblib_rs::native::Stratification;
+///
+/// let mut clauses = Vec::new();
+/// let mut stratification = Stratification::new(&[(5, 1), (1, 2), (5, 3), (2, 4)], 5, &mut clauses);
+/// assert_eq!(3, stratification.n_strata());
+/// assert_eq!(&[1, 3], stratification.literals(0));
+/// // at most one of x1 and x3 is false
+/// stratification.encode_upper_bound_into(0, 5, &mut clauses);
+/// // the cost of x1, x2, x3 and x4 is at most 8
+/// stratification.encode_upper_bound_into(2, 8, &mut clauses);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stratification {
+    strata: Vec<Stratum>,
+    manager: BddManager,
+}
+
+/// The soft literals sharing a weight.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Stratum {
+    weight: i64,
+    literals: Vec<i32>,
+    /// The totalizer counting the false literals.
+    totalizer: Totalizer,
+}
+
+impl Stratification {
+    /// Partitions the soft literals, given with their weights, and builds the totalizers of the strata, sending their clauses to a [`ClauseSink`].
+    ///
+    /// The auxiliary variables are numbered from `first_aux_var`.
+    /// The literals with a null weight are ignored.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a weight is negative, or if the number of variables does not fit in an `i32`.
+    pub fn new(
+        soft_literals: &[(i64, i32)],
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> Self {
+        assert!(
+            soft_literals.iter().all(|(w, _)| *w >= 0),
+            "the weights of the soft literals must be nonnegative"
+        );
+        let mut by_weight = BTreeMap::<i64, Vec<i32>>::new();
+        for (w, l) in soft_literals.iter().filter(|(w, _)| *w > 0) {
+            by_weight.entry(*w).or_default().push(*l);
+        }
+        let mut next_free_var_id = first_aux_var;
+        let strata = by_weight
+            .into_iter()
+            .rev()
+            .map(|(weight, literals)| {
+                let negated = literals.iter().map(|l| -l).collect::<Vec<_>>();
+                let totalizer = Totalizer::new(&negated, next_free_var_id, sink);
+                next_free_var_id = totalizer.next_free_var_id();
+                Stratum {
+                    weight,
+                    literals,
+                    totalizer,
+                }
+            })
+            .collect();
+        Self {
+            strata,
+            manager: BddManager::new(next_free_var_id),
+        }
+    }
+
+    /// Returns the number of strata.
+    #[must_use]
+    pub fn n_strata(&self) -> usize {
+        self.strata.len()
+    }
+
+    /// Returns the weight of the soft literals of a stratum.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the stratum does not exist.
+    #[must_use]
+    pub fn weight(&self, stratum: usize) -> i64 {
+        self.strata[stratum].weight
+    }
+
+    /// Returns the soft literals of a stratum, in the order they were given.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the stratum does not exist.
+    #[must_use]
+    pub fn literals(&self, stratum: usize) -> &[i32] {
+        &self.strata[stratum].literals
+    }
+
+    /// Returns the soft literals of the strata `0` to `stratum`, which are the assumptions of a stratified `MaxSAT` solver working on this stratum.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the stratum does not exist.
+    #[must_use]
+    pub fn assumptions(&self, stratum: usize) -> Vec<i32> {
+        self.strata[..=stratum]
+            .iter()
+            .flat_map(|s| s.literals.iter().copied())
+            .collect()
+    }
+
+    /// Returns the objective function of a stratum, expressed over the outputs of the totalizers.
+    ///
+    /// Each output of the totalizer of a stratum `s` not greater than `stratum` appears with the weight of `s`, so that the weighted sum is the cost of the soft literals of these strata.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the stratum does not exist.
+    #[must_use]
+    pub fn objective(&self, stratum: usize) -> Vec<(i64, i32)> {
+        self.strata[..=stratum]
+            .iter()
+            .flat_map(|s| s.totalizer.outputs().iter().map(|o| (s.weight, *o)))
+            .collect()
+    }
+
+    /// Sends to a [`ClauseSink`] the clauses stating that the objective function of a stratum is at most `k`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the stratum does not exist, or if the number of variables does not fit in an `i32`.
+    pub fn encode_upper_bound_into(&mut self, stratum: usize, k: i64, sink: &mut impl ClauseSink) {
+        let constraint = PbConstraint::weighted(self.objective(stratum)).leq(k);
+        self.manager.encode_constraint_into(&constraint, sink);
+    }
+
+    /// Returns the lowest variable index that is not used by the totalizers and the bounds.
+    #[must_use]
+    pub fn next_free_var_id(&self) -> i32 {
+        self.manager.next_free_var_id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference;
+
+    const SOFT_LITERALS: [(i64, i32); 6] = [(5, 1), (1, -2), (5, 3), (2, 4), (1, 5), (0, 6)];
+
+    #[test]
+    fn test_strata() {
+        let mut clauses = Vec::new();
+        let stratification = Stratification::new(&SOFT_LITERALS, 7, &mut clauses);
+        assert_eq!(3, stratification.n_strata());
+        assert_eq!(
+            vec![5, 2, 1],
+            (0..3).map(|i| stratification.weight(i)).collect::<Vec<_>>()
+        );
+        assert_eq!(&[-2, 5], stratification.literals(2));
+        assert_eq!(vec![1, 3, 4], stratification.assumptions(1));
+        assert_eq!(5, stratification.objective(2).len());
+    }
+
+    #[test]
+    fn test_upper_bounds() {
+        for stratum in 0..3 {
+            for k in -1..=14 {
+                let mut clauses = Vec::new();
+                let mut stratification = Stratification::new(&SOFT_LITERALS, 7, &mut clauses);
+                stratification.encode_upper_bound_into(stratum, k, &mut clauses);
+                assert!(clauses
+                    .iter()
+                    .flatten()
+                    .all(|l| l.abs() < stratification.next_free_var_id()));
+                let cost = SOFT_LITERALS
+                    .iter()
+                    .filter(|(w, _)| *w >= stratification.weight(stratum))
+                    .map(|(w, l)| (*w, -l))
+                    .collect::<Vec<_>>();
+                let constraint = PbConstraint::weighted(cost).leq(k);
+                assert_eq!(
+                    Ok(()),
+                    reference::check_encoding(&[constraint], &clauses, 6)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_bounds() {
+        let mut clauses = Vec::new();
+        let mut stratification = Stratification::new(&SOFT_LITERALS, 7, &mut clauses);
+        stratification.encode_upper_bound_into(2, 10, &mut clauses);
+        let n_clauses = clauses.len();
+        stratification.encode_upper_bound_into(2, 10, &mut clauses);
+        // only the unit clause asserting the root is produced again
+        assert_eq!(n_clauses + 1, clauses.len());
+    }
+}
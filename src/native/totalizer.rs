@@ -0,0 +1,426 @@
+use super::{dot::DotGraph, AuxVars};
+use crate::ClauseSink;
+use alloc::{format, string::String, vec::Vec};
+
+/// The totalizer encoding of cardinality constraints (Bailleux and Boufkhad, 2003).
+///
+/// A totalizer counts the true literals in unary: it is a binary tree whose leaves are the literals, and each node owns output variables representing the number of true literals below it.
+/// The outputs of the root are kept, so that bounds on the number of true literals can be enforced later by unit clauses.
+/// These unit clauses may be given as assumptions to an incremental SAT solver instead of being added to the formula.
+///
+/// ```
+/// use pblib_rs::native::Totalizer;
+///
+/// let mut clauses = Vec::new();
+/// let totalizer = Totalizer::new(&[1, 2, 3, 4], 5, &mut clauses);
+/// // outputs()[i] is true iff at least i+1 literals are true
+/// assert_eq!(4, totalizer.outputs().len());
+/// // tighten the bound step by step
+/// totalizer.encode_upper_bound_into(3, &mut clauses);
+/// totalizer.encode_upper_bound_into(2, &mut clauses);
+/// assert_eq!(vec![-totalizer.outputs()[2]], *clauses.last().unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Totalizer {
+    /// The nodes of the tree, each one after its children.
+    nodes: Vec<Node>,
+    next_free_var_id: i32,
+}
+
+/// A node of a totalizer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Node {
+    /// The outputs of the node, or its literal if it is a leaf.
+    outputs: Vec<i32>,
+    /// The indices of the children of the node, if it is not a leaf.
+    children: Option<(usize, usize)>,
+}
+
+impl Totalizer {
+    /// Builds a totalizer over some literals, sending its clauses to a [`ClauseSink`].
+    ///
+    /// The outputs are equivalent to the number of true literals: the clauses state both that an output is true if enough literals are, and conversely.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn new(literals: &[i32], first_aux_var: i32, sink: &mut impl ClauseSink) -> Self {
+        let mut vars = AuxVars::new(first_aux_var);
+        let directions = Directions {
+            upward: true,
+            downward: true,
+        };
+        let mut nodes = Vec::new();
+        build_node(
+            literals,
+            literals.len(),
+            directions,
+            &mut vars,
+            sink,
+            &mut nodes,
+        );
+        Self {
+            nodes,
+            next_free_var_id: vars.next_free_var_id(),
+        }
+    }
+
+    /// Returns the outputs of the totalizer.
+    ///
+    /// The output at index `i` is true if and only if at least `i+1` literals are true.
+    #[must_use]
+    pub fn outputs(&self) -> &[i32] {
+        self.nodes.last().map_or(&[], |n| &n.outputs)
+    }
+
+    /// Returns a Graphviz DOT description of the tree of the totalizer.
+    ///
+    /// The leaves are labeled by their literals, and the other nodes by their output variables, from the one counting one true literal.
+    /// The edges go from the children to their parents.
+    ///
+    /// ```
+    /// use pblib_rs::native::Totalizer;
+    ///
+    /// let totalizer = Totalizer::new(&[1, 2, 3], 4, &mut Vec::new());
+    /// let dot = totalizer.to_dot();
+    /// assert!(dot.starts_with("digraph {"));
+    /// assert!(dot.contains("label=\"6 7 8\""));
+    /// ```
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut graph = DotGraph::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let label = node
+                .outputs
+                .iter()
+                .map(|o| format!("{o}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let id = format!("n{i}");
+            match node.children {
+                None => graph.node(&id, &format!("label=\"{label}\", shape=plaintext")),
+                Some((left, right)) => {
+                    graph.node(&id, &format!("label=\"{label}\", shape=box"));
+                    graph.edge(&format!("n{left}"), &id, "");
+                    graph.edge(&format!("n{right}"), &id, "");
+                }
+            }
+        }
+        graph.finish()
+    }
+
+    /// Returns the lowest variable index that is not used by the totalizer.
+    #[must_use]
+    pub fn next_free_var_id(&self) -> i32 {
+        self.next_free_var_id
+    }
+
+    /// Sends to a [`ClauseSink`] the clauses stating that at most k literals are true.
+    ///
+    /// This is a unit clause on an output, an empty clause if `k` is negative, and no clause if `k` is at least the number of literals.
+    pub fn encode_upper_bound_into(&self, k: i64, sink: &mut impl ClauseSink) {
+        sink.explain(format_args!("totalizer bound: sum <= {k}"));
+        match usize::try_from(k) {
+            Err(_) => sink.add_clause(&[]),
+            Ok(k) => {
+                if let Some(o) = self.outputs().get(k) {
+                    sink.add_clause(&[-o]);
+                }
+            }
+        }
+    }
+
+    /// Sends to a [`ClauseSink`] the clauses stating that at least k literals are true.
+    ///
+    /// This is a unit clause on an output, an empty clause if `k` is greater than the number of literals, and no clause if `k` is not positive.
+    pub fn encode_lower_bound_into(&self, k: i64, sink: &mut impl ClauseSink) {
+        sink.explain(format_args!("totalizer bound: sum >= {k}"));
+        let Some(k) = usize::try_from(k).ok().filter(|k| *k > 0) else {
+            return;
+        };
+        match self.outputs().get(k - 1) {
+            Some(o) => sink.add_clause(&[*o]),
+            None => sink.add_clause(&[]),
+        }
+    }
+
+    /// Encodes an At-Most-k cardinality constraint with a totalizer, sending the clauses to a [`ClauseSink`].
+    ///
+    /// Only the clauses needed by the constraint are produced: the nodes count up to `k+1`, and the outputs are only implied by the literals.
+    /// See [`PB2CNF::encode_at_most_k`](crate::PB2CNF::encode_at_most_k) for more information on the parameters.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn encode_at_most_k_into(
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        let Ok(k) = usize::try_from(k) else {
+            sink.explain(format_args!("unsatisfiable bound: sum <= {k}"));
+            sink.add_clause(&[]);
+            return first_aux_var;
+        };
+        if k >= literals.len() {
+            return first_aux_var;
+        }
+        let mut vars = AuxVars::new(first_aux_var);
+        let directions = Directions {
+            upward: true,
+            downward: false,
+        };
+        let outputs = build_node(
+            literals,
+            k + 1,
+            directions,
+            &mut vars,
+            sink,
+            &mut Vec::new(),
+        );
+        sink.explain(format_args!("totalizer bound: sum <= {k}"));
+        sink.add_clause(&[-outputs[k]]);
+        vars.next_free_var_id()
+    }
+
+    /// Encodes an At-Least-k cardinality constraint with a totalizer, sending the clauses to a [`ClauseSink`].
+    ///
+    /// Only the clauses needed by the constraint are produced: the nodes count up to `k`, and the outputs only imply the literals.
+    /// See [`PB2CNF::encode_at_least_k`](crate::PB2CNF::encode_at_least_k) for more information on the parameters.
+    /// It returns the next free variable id.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn encode_at_least_k_into(
+        literals: &[i32],
+        k: i64,
+        first_aux_var: i32,
+        sink: &mut impl ClauseSink,
+    ) -> i32 {
+        let Some(k) = usize::try_from(k).ok().filter(|k| *k > 0) else {
+            return first_aux_var;
+        };
+        if k > literals.len() {
+            sink.explain(format_args!("unsatisfiable bound: sum >= {k}"));
+            sink.add_clause(&[]);
+            return first_aux_var;
+        }
+        let mut vars = AuxVars::new(first_aux_var);
+        let directions = Directions {
+            upward: false,
+            downward: true,
+        };
+        let outputs = build_node(literals, k, directions, &mut vars, sink, &mut Vec::new());
+        sink.explain(format_args!("totalizer bound: sum >= {k}"));
+        sink.add_clause(&[outputs[k - 1]]);
+        vars.next_free_var_id()
+    }
+}
+
+/// Builds a totalizer counting some literals up to `max_outputs`, in which the outputs are only implied by the literals, and returns its outputs.
+///
+/// If more than `max_outputs` literals are true, the last output is true.
+#[cfg(feature = "std")]
+pub(super) fn count_upward(
+    literals: &[i32],
+    max_outputs: usize,
+    vars: &mut AuxVars,
+    sink: &mut impl ClauseSink,
+) -> Vec<i32> {
+    let directions = Directions {
+        upward: true,
+        downward: false,
+    };
+    build_node(
+        literals,
+        max_outputs,
+        directions,
+        vars,
+        sink,
+        &mut Vec::new(),
+    )
+}
+
+/// The implications produced by the nodes of a totalizer.
+#[derive(Clone, Copy)]
+struct Directions {
+    /// The outputs are implied by the inputs.
+    upward: bool,
+    /// The outputs imply the inputs.
+    downward: bool,
+}
+
+/// Builds the subtree counting some literals up to `max_outputs`, adds its nodes to `nodes`, and returns its outputs.
+fn build_node(
+    literals: &[i32],
+    max_outputs: usize,
+    directions: Directions,
+    vars: &mut AuxVars,
+    sink: &mut impl ClauseSink,
+    nodes: &mut Vec<Node>,
+) -> Vec<i32> {
+    if literals.len() <= 1 {
+        nodes.push(Node {
+            outputs: literals.to_vec(),
+            children: None,
+        });
+        return literals.to_vec();
+    }
+    let (left, right) = literals.split_at(literals.len() / 2);
+    let left = build_node(left, max_outputs, directions, vars, sink, nodes);
+    let left_index = nodes.len() - 1;
+    let right = build_node(right, max_outputs, directions, vars, sink, nodes);
+    let right_index = nodes.len() - 1;
+    let outputs = (0..max_outputs.min(literals.len()))
+        .map(|_| vars.new_var())
+        .collect::<Vec<_>>();
+    // left[i-1] & right[j-1] -> outputs[i+j-1], with left[-1] and right[-1] being true
+    if directions.upward {
+        sink.explain(format_args!(
+            "totalizer node {outputs:?}: outputs implied by the children"
+        ));
+        for i in 0..=left.len() {
+            for j in 0..=right.len() {
+                if i + j == 0 {
+                    continue;
+                }
+                let mut clause = Vec::with_capacity(3);
+                clause.extend(i.checked_sub(1).map(|i| -left[i]));
+                clause.extend(j.checked_sub(1).map(|j| -right[j]));
+                clause.push(outputs[(i + j).min(outputs.len()) - 1]);
+                sink.add_clause(&clause);
+            }
+        }
+    }
+    // !left[i] & !right[j] -> !outputs[i+j], with left[|left|] and right[|right|] being false
+    if directions.downward {
+        sink.explain(format_args!(
+            "totalizer node {outputs:?}: outputs implying the children"
+        ));
+        for i in 0..=left.len() {
+            for j in 0..=right.len() {
+                if i + j >= outputs.len() {
+                    continue;
+                }
+                let mut clause = Vec::with_capacity(3);
+                clause.extend(left.get(i));
+                clause.extend(right.get(j));
+                clause.push(-outputs[i + j]);
+                sink.add_clause(&clause);
+            }
+        }
+    }
+    nodes.push(Node {
+        outputs: outputs.clone(),
+        children: Some((left_index, right_index)),
+    });
+    outputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{reference, MiniSat, PbConstraint};
+
+    const LITERALS: [i32; 5] = [1, -2, 3, 4, -5];
+
+    fn assignments() -> impl Iterator<Item = Vec<i32>> {
+        (0..32).map(|i| {
+            (1..=5)
+                .map(|v| if i & (1 << (v - 1)) == 0 { -v } else { v })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn test_outputs() {
+        let mut clauses = Vec::new();
+        let totalizer = Totalizer::new(&LITERALS, 6, &mut clauses);
+        assert_eq!(5, totalizer.outputs().len());
+        assert!(totalizer.next_free_var_id() > 6);
+        let mut solver = MiniSat::new();
+        for c in &clauses {
+            solver.add_clause(c);
+        }
+        for assignment in assignments() {
+            let n_true = LITERALS.iter().filter(|l| assignment.contains(l)).count();
+            for (i, o) in totalizer.outputs().iter().enumerate() {
+                let mut assumptions = assignment.clone();
+                assumptions.push(*o);
+                assert_eq!(n_true > i, solver.solve_with_assumptions(&assumptions));
+                *assumptions.last_mut().unwrap() = -o;
+                assert_eq!(n_true <= i, solver.solve_with_assumptions(&assumptions));
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let totalizer = Totalizer::new(&LITERALS, 6, &mut Vec::new());
+        let dot = totalizer.to_dot();
+        // 5 leaves and 4 inner nodes, each of them having 2 children
+        assert_eq!(5, dot.lines().filter(|l| l.contains("plaintext")).count());
+        assert_eq!(4, dot.lines().filter(|l| l.contains("shape=box")).count());
+        assert_eq!(8, dot.lines().filter(|l| l.contains("->")).count());
+        let root_label = totalizer
+            .outputs()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(dot.contains(&format!("label=\"{root_label}\", shape=box")));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut clauses = Vec::new();
+        let totalizer = Totalizer::new(&LITERALS, 6, &mut clauses);
+        for k in -1..=6 {
+            let mut at_most = clauses.clone();
+            totalizer.encode_upper_bound_into(k, &mut at_most);
+            let constraint = PbConstraint::cardinality(LITERALS).leq(k);
+            assert_eq!(
+                Ok(()),
+                reference::check_encoding(&[constraint], &at_most, 5)
+            );
+            let mut at_least = clauses.clone();
+            totalizer.encode_lower_bound_into(k, &mut at_least);
+            let constraint = PbConstraint::cardinality(LITERALS).geq(k);
+            assert_eq!(
+                Ok(()),
+                reference::check_encoding(&[constraint], &at_least, 5)
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_at_most_k() {
+        for k in -1..=6 {
+            let mut clauses = Vec::new();
+            let next_free_var_id = Totalizer::encode_at_most_k_into(&LITERALS, k, 6, &mut clauses);
+            assert!(clauses.iter().flatten().all(|l| l.abs() < next_free_var_id));
+            let constraint = PbConstraint::cardinality(LITERALS).leq(k);
+            assert_eq!(
+                Ok(()),
+                reference::check_encoding(&[constraint], &clauses, 5)
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_at_least_k() {
+        for k in -1..=6 {
+            let mut clauses = Vec::new();
+            let next_free_var_id = Totalizer::encode_at_least_k_into(&LITERALS, k, 6, &mut clauses);
+            assert!(clauses.iter().flatten().all(|l| l.abs() < next_free_var_id));
+            let constraint = PbConstraint::cardinality(LITERALS).geq(k);
+            assert_eq!(
+                Ok(()),
+                reference::check_encoding(&[constraint], &clauses, 5)
+            );
+        }
+    }
+}
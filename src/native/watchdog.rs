@@ -0,0 +1,316 @@
+use super::{totalizer, AuxVars};
+use crate::{ClauseSink, PbConstraint};
+
+/// Encodes a [`PbConstraint`] with the global polynomial watchdog encoding (Bailleux, Boufkhad and Roussel, 2009), sending the clauses to a [`ClauseSink`].
+///
+/// The constraint is written `sum(w_i*l_i) <= k` with positive weights, and a tare `t` is chosen so that `k+1+t` is a multiple `m*2^p` of the greatest power of two `2^p` not greater than the maximal weight.
+/// For each bit `b` of the weights, a totalizer counts the literals whose weight has this bit set, plus the bit of the tare, plus half the count of the previous bit: the one of the last bit counts `(sum(w_i*l_i)+t)/2^p`, and its `m`-th output is forbidden.
+/// The encoding uses `O(n*log(n)*log(w_max))` auxiliary variables and `O(n^2*log(n)*log(w_max))` clauses, independently of the value of the weights.
+pub(crate) fn encode_constraint_into(
+    constraint: &PbConstraint,
+    first_aux_var: i32,
+    sink: &mut impl ClauseSink,
+) -> i32 {
+    let mut vars = AuxVars::new(first_aux_var);
+    for normalized in constraint.normalize() {
+        // sum(w_i*l_i) >= k is equivalent to sum(w_i*~l_i) <= sum(w_i)-k
+        let terms = normalized
+            .terms()
+            .iter()
+            .map(|(w, l)| (w.unsigned_abs(), -l))
+            .collect::<Vec<_>>();
+        let weight_sum = terms.iter().map(|(w, _)| i128::from(*w)).sum::<i128>();
+        let k = weight_sum - i128::from(normalized.rhs());
+        if k < 0 {
+            sink.explain(format_args!("unsatisfiable constraint {normalized}"));
+            sink.add_clause(&[]);
+        } else if k < weight_sum {
+            encode_leq(&terms, k.unsigned_abs(), &mut vars, sink);
+        }
+    }
+    vars.next_free_var_id()
+}
+
+/// Encodes `sum(w_i*l_i) <= k`, given that the weights are positive and that their sum is greater than `k`.
+fn encode_leq(terms: &[(u64, i32)], k: u128, vars: &mut AuxVars, sink: &mut impl ClauseSink) {
+    let p = greatest_bit(terms);
+    let (tare, m) = tare(k, p);
+    let tare_literal = if tare == 0 {
+        None
+    } else {
+        let t = vars.new_var();
+        sink.explain(format_args!("watchdog tare {tare} on literal {t}"));
+        sink.add_clause(&[t]);
+        Some(t)
+    };
+    let tare_literals = (0..p)
+        .map(|b| tare_literal.filter(|_| tare & (1 << b) != 0))
+        .collect::<Vec<_>>();
+    // the last totalizer must count up to m, and each one must count up to twice the next one
+    let max_outputs = |b| usize::try_from(m << (p - b)).unwrap_or(usize::MAX);
+    let outputs = build_buckets(terms, &tare_literals, max_outputs, vars, sink);
+    if let Some(o) = outputs.get(usize::try_from(m - 1).unwrap()) {
+        sink.explain(format_args!("watchdog bound: count < {m}"));
+        sink.add_clause(&[-o]);
+    }
+}
+
+/// Returns the index of the greatest bit set in the weights.
+fn greatest_bit(terms: &[(u64, i32)]) -> u32 {
+    let max_weight = terms.iter().map(|(w, _)| *w).max().unwrap();
+    u64::BITS - 1 - max_weight.leading_zeros()
+}
+
+/// Returns the tare `t` such that `k+1+t` is a multiple of `2^p`, and the multiplier `m` such that `k+1+t = m*2^p`.
+fn tare(k: u128, p: u32) -> (u128, u128) {
+    let tare = ((1 << p) - (k + 1) % (1 << p)) % (1 << p);
+    (tare, (k + 1 + tare) >> p)
+}
+
+/// Builds the totalizers counting the bits of the weights, and returns the outputs of the last one.
+///
+/// The tare literals are given for each bit but the last one.
+fn build_buckets(
+    terms: &[(u64, i32)],
+    tare_literals: &[Option<i32>],
+    max_outputs: impl Fn(u32) -> usize,
+    vars: &mut AuxVars,
+    sink: &mut impl ClauseSink,
+) -> Vec<i32> {
+    let p = greatest_bit(terms);
+    let mut carries = Vec::new();
+    for b in 0..=p {
+        let mut inputs = terms
+            .iter()
+            .filter(|(w, _)| w & (1 << b) != 0)
+            .map(|(_, l)| *l)
+            .collect::<Vec<_>>();
+        inputs.extend(tare_literals.get(b as usize).copied().flatten());
+        inputs.append(&mut carries);
+        let outputs = totalizer::count_upward(&inputs, max_outputs(b), vars, sink);
+        if b == p {
+            return outputs;
+        }
+        carries = outputs.into_iter().skip(1).step_by(2).collect();
+    }
+    unreachable!()
+}
+
+/// The dynamic polynomial watchdog encoding of an upper bound on a weighted sum (Paxian, Reimer and Becker, 2018).
+///
+/// This is the [polynomial watchdog encoding](super::PbEncoder::PolynomialWatchdog) in which the tare is not fixed: each of its bits is represented by a free literal.
+/// The structure is built once, and any upper bound can then be enforced by assuming the tare literals corresponding to the bound and the negation of an output.
+/// This makes it suitable for `MaxSAT` solvers tightening the bound on the objective function with an incremental SAT solver.
+///
+/// Negative weights are handled by negating their literals, as in an [`Adder`](super::Adder).
+///
+/// ```
+/// use pblib_rs::MiniSat;
+/// use pblib_rs::native::DynamicWatchdog;
+///
+/// let mut clauses = Vec::new();
+/// // 5*x1 + 3*x2 + 2*x3 + 2*x4
+/// let watchdog = DynamicWatchdog::new(&[(5, 1), (3, 2), (2, 3), (2, 4)], 5, &mut clauses);
+/// let mut solver = MiniSat::new();
+/// for c in &clauses {
+///     solver.add_clause(c);
+/// }
+/// // x1 and x2 cannot both be true if the sum is at most 7
+/// let mut assumptions = watchdog.upper_bound_assumptions(7).unwrap();
+/// assumptions.extend([1, 2]);
+/// assert!(!solver.solve_with_assumptions(&assumptions));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicWatchdog {
+    outputs: Vec<i32>,
+    tare_literals: Vec<i32>,
+    greatest_bit: u32,
+    offset: i128,
+    weight_sum: i128,
+    next_free_var_id: i32,
+}
+
+impl DynamicWatchdog {
+    /// Builds the totalizers of the encoding over some terms, sending their clauses to a [`ClauseSink`].
+    ///
+    /// The terms are couples composed of a weight and a literal.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn new(terms: &[(i64, i32)], first_aux_var: i32, sink: &mut impl ClauseSink) -> Self {
+        let mut vars = AuxVars::new(first_aux_var);
+        let mut offset = 0;
+        let terms = terms
+            .iter()
+            .filter(|(w, _)| *w != 0)
+            .map(|(w, l)| {
+                if *w < 0 {
+                    offset += i128::from(w.unsigned_abs());
+                    (w.unsigned_abs(), -l)
+                } else {
+                    (w.unsigned_abs(), *l)
+                }
+            })
+            .collect::<Vec<_>>();
+        let weight_sum = terms.iter().map(|(w, _)| i128::from(*w)).sum();
+        let (outputs, tare_literals, greatest_bit) = if terms.is_empty() {
+            (Vec::new(), Vec::new(), 0)
+        } else {
+            let p = greatest_bit(&terms);
+            let tare_literals = (0..p).map(|_| vars.new_var()).collect::<Vec<_>>();
+            let optional_tare_literals =
+                tare_literals.iter().copied().map(Some).collect::<Vec<_>>();
+            let outputs = build_buckets(
+                &terms,
+                &optional_tare_literals,
+                |_| usize::MAX,
+                &mut vars,
+                sink,
+            );
+            (outputs, tare_literals, p)
+        };
+        Self {
+            outputs,
+            tare_literals,
+            greatest_bit,
+            offset,
+            weight_sum,
+            next_free_var_id: vars.next_free_var_id(),
+        }
+    }
+
+    /// Returns the outputs of the encoding.
+    ///
+    /// If `t` is the value of the tare and `2^p` the greatest power of two not greater than the maximal weight, the output at index `i` is implied by the fact that `(sum+offset+t)/2^p` is at least `i+1`.
+    #[must_use]
+    pub fn outputs(&self) -> &[i32] {
+        &self.outputs
+    }
+
+    /// Returns the literals representing the bits of the tare, beginning with the least significant one.
+    #[must_use]
+    pub fn tare_literals(&self) -> &[i32] {
+        &self.tare_literals
+    }
+
+    /// Returns the value added to the weighted sum by the negation of the literals with negative weights.
+    #[must_use]
+    pub fn offset(&self) -> i128 {
+        self.offset
+    }
+
+    /// Returns the lowest variable index that is not used by the encoding.
+    #[must_use]
+    pub fn next_free_var_id(&self) -> i32 {
+        self.next_free_var_id
+    }
+
+    /// Returns the assumptions enforcing that the weighted sum is lower than or equal to `k`.
+    ///
+    /// They are composed of the tare literals set to true for this bound, and of the negation of an output.
+    /// An empty list is returned if the bound is always satisfied, and `None` if it cannot be satisfied.
+    #[must_use]
+    pub fn upper_bound_assumptions(&self, k: i64) -> Option<Vec<i32>> {
+        let k = i128::from(k) + self.offset;
+        if k < 0 {
+            return None;
+        }
+        if k >= self.weight_sum {
+            return Some(Vec::new());
+        }
+        let (tare, m) = tare(k.unsigned_abs(), self.greatest_bit);
+        let mut assumptions = self
+            .tare_literals
+            .iter()
+            .enumerate()
+            .filter(|(b, _)| tare & (1 << b) != 0)
+            .map(|(_, t)| *t)
+            .collect::<Vec<_>>();
+        assumptions.extend(
+            usize::try_from(m - 1)
+                .ok()
+                .and_then(|i| self.outputs.get(i))
+                .map(|o| -o),
+        );
+        Some(assumptions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{reference, Comparator, MiniSat};
+
+    const TERMS: [(i64, i32); 5] = [(5, 1), (-3, 2), (2, -3), (7, 4), (2, 5)];
+
+    #[test]
+    fn test_encode_constraint() {
+        for comparator in [
+            Comparator::Leq,
+            Comparator::Geq,
+            Comparator::Eq,
+            Comparator::Lt,
+            Comparator::Gt,
+        ] {
+            for rhs in -5..=16 {
+                let constraint = PbConstraint::new(TERMS.to_vec(), comparator, rhs);
+                let mut clauses = Vec::new();
+                let next_free_var_id = encode_constraint_into(&constraint, 6, &mut clauses);
+                assert!(clauses.iter().flatten().all(|l| l.abs() < next_free_var_id));
+                assert_eq!(
+                    Ok(()),
+                    reference::check_encoding(&[constraint], &clauses, 5)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_weights() {
+        let terms = vec![(1 << 40, 1), ((1 << 40) + 1, 2), (3 << 39, 3), (1, 4)];
+        let constraint = PbConstraint::new(terms, Comparator::Leq, (5 << 39) + 1);
+        let mut clauses = Vec::new();
+        let next_free_var_id = encode_constraint_into(&constraint, 5, &mut clauses);
+        assert!(next_free_var_id < 1000);
+        assert_eq!(
+            Ok(()),
+            reference::check_encoding(&[constraint], &clauses, 4)
+        );
+    }
+
+    #[test]
+    fn test_dynamic_watchdog() {
+        let mut clauses = Vec::new();
+        let watchdog = DynamicWatchdog::new(&TERMS, 6, &mut clauses);
+        assert_eq!(3, watchdog.offset());
+        assert_eq!(2, watchdog.tare_literals().len());
+        assert!(clauses
+            .iter()
+            .flatten()
+            .all(|l| l.abs() < watchdog.next_free_var_id()));
+        let mut solver = MiniSat::new();
+        for c in &clauses {
+            solver.add_clause(c);
+        }
+        for k in -5..=16 {
+            let Some(bound) = watchdog.upper_bound_assumptions(k) else {
+                assert!(k < -3);
+                continue;
+            };
+            for i in 0..32 {
+                let assignment = (1..=5)
+                    .map(|v| if i & (1 << (v - 1)) == 0 { -v } else { v })
+                    .collect::<Vec<_>>();
+                let sum = TERMS
+                    .iter()
+                    .filter(|(_, l)| assignment.contains(l))
+                    .map(|(w, _)| *w)
+                    .sum::<i64>();
+                let mut assumptions = assignment.clone();
+                assumptions.extend(&bound);
+                assert_eq!(sum <= k, solver.solve_with_assumptions(&assumptions));
+            }
+        }
+    }
+}
@@ -0,0 +1,940 @@
+use crate::{symbol_table::is_valid_name, Comparator, PbConstraint, PbError, SymbolTable};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io::{Read, Write},
+    str::FromStr,
+};
+
+/// A Pseudo-Boolean instance, as described by an OPB file.
+///
+/// An instance is made of a set of constraints and an optional objective function to minimize.
+/// Instances are usually read from OPB files (the format used by the Pseudo-Boolean competitions) with [`parse`](Self::parse).
+///
+/// ```
+/// use pblib_rs::OpbInstance;
+///
+/// let opb = "* #variable= 3 #constraint= 2
+/// min: +1 x1 +2 x2 +3 x3 ;
+/// +1 x1 +1 x2 +1 x3 >= 2 ;
+/// +1 x1 -1 ~x3 = 0 ;
+/// ";
+/// let instance = OpbInstance::parse(opb.as_bytes()).unwrap();
+/// assert_eq!(3, instance.n_vars());
+/// assert_eq!(Some(&[(1, 1), (2, 2), (3, 3)][..]), instance.objective());
+/// assert_eq!(2, instance.constraints().len());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpbInstance {
+    n_vars: usize,
+    objective: Option<Vec<(i64, i32)>>,
+    constraints: Vec<PbConstraint>,
+    products: Vec<(i32, Vec<i32>)>,
+}
+
+impl OpbInstance {
+    /// Builds a new instance given its number of variables, its objective function and its constraints.
+    ///
+    /// The objective function is given as a list of terms, each of them being a couple composed of a weight and a literal.
+    /// The number of variables is increased if needed to take into account all the variables involved in the objective function and the constraints.
+    #[must_use]
+    pub fn new(
+        n_vars: usize,
+        objective: Option<Vec<(i64, i32)>>,
+        constraints: Vec<PbConstraint>,
+    ) -> Self {
+        let max_var = objective
+            .iter()
+            .flatten()
+            .chain(constraints.iter().flat_map(PbConstraint::terms))
+            .map(|t| t.1.unsigned_abs() as usize)
+            .max()
+            .unwrap_or_default();
+        Self {
+            n_vars: n_vars.max(max_var),
+            objective,
+            constraints,
+            products: Vec::new(),
+        }
+    }
+
+    /// Reads an instance in the OPB format.
+    ///
+    /// The number of variables is taken from the `#variable=` field of the header, if any, or computed from the variables involved in the instance.
+    /// In addition to the `>=` and `=` comparators of the OPB format, the `<=`, `<` and `>` comparators are accepted.
+    /// The constraints keep their strict comparators; they are turned into non-strict ones with adjusted bounds when they are encoded or written (see [`PbConstraint::non_strict`]).
+    /// Constraints without terms, such as the `>= 1 ;` written for a constraint that never holds, are accepted as well.
+    ///
+    /// Nonlinear instances are supported: each product of literals is replaced by a fresh variable, numbered after the variables of the instance.
+    /// The constraints defining the fresh variables as the conjunctions of the literals are added at the end of the constraints, and the definitions are reported by [`products`](Self::products).
+    ///
+    /// ```
+    /// use pblib_rs::OpbInstance;
+    ///
+    /// let instance = "+3 x1 x2 +2 ~x3 >= 1 ;".parse::<OpbInstance>().unwrap();
+    /// assert_eq!(4, instance.n_vars());
+    /// assert_eq!(&[(4, vec![1, 2])], instance.products());
+    /// // the initial constraint, and 3 constraints defining x4 as x1 * x2
+    /// assert_eq!(4, instance.constraints().len());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the reader fails or if the content is not a valid OPB instance.
+    pub fn parse<R: Read>(mut reader: R) -> Result<Self, PbError> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        Parser::new(&content, None)?.parse()
+    }
+
+    /// Reads an instance in the OPB format, in which variables are designated by names.
+    ///
+    /// Any identifier made of letters, digits and underscores (and not beginning by a digit) is accepted as a variable, including the `x1`, `x2`, ... names of the standard format.
+    /// The variables are taken from the symbol table; unknown names are added to it (see [`SymbolTable::var`]).
+    ///
+    /// ```
+    /// use pblib_rs::{OpbInstance, SymbolTable};
+    ///
+    /// let mut symbols = SymbolTable::new();
+    /// let instance = OpbInstance::parse_with_symbols(
+    ///     "min: +1 cost ;\n+1 a +1 ~b >= 1 ;\n+1 cost -1 a >= 0 ;".as_bytes(),
+    ///     &mut symbols,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(3, instance.n_vars());
+    /// assert_eq!(Some(&[(1, 1)][..]), instance.objective());
+    /// assert_eq!(Some(2), symbols.get("a"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the reader fails or if the content is not a valid OPB instance.
+    pub fn parse_with_symbols<R: Read>(
+        mut reader: R,
+        symbols: &mut SymbolTable,
+    ) -> Result<Self, PbError> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        Parser::new(&content, Some(symbols))?.parse()
+    }
+
+    /// Writes the instance in the OPB format.
+    ///
+    /// The output begins with a header giving the number of variables and constraints, followed by the objective function (if any) and the constraints.
+    /// Since the OPB format only allows the `>=` and `=` comparators, the other constraints are rewritten into equivalent `>=` ones.
+    /// The output can be read back by [`parse`](Self::parse).
+    /// The [`Display`] implementation of instances produces the same text.
+    ///
+    /// ```
+    /// use pblib_rs::OpbInstance;
+    ///
+    /// let instance = "min: +1 x1 -2 ~x2 ;\n+1 x1 +1 x2 <= 1 ;".parse::<OpbInstance>().unwrap();
+    /// let mut output = Vec::new();
+    /// instance.write(&mut output).unwrap();
+    /// assert_eq!(
+    ///     "* #variable= 2 #constraint= 1\nmin: +1 x1 -2 ~x2 ;\n-1 x1 -1 x2 >= -1 ;\n",
+    ///     String::from_utf8(output).unwrap(),
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the writer fails.
+    pub fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.write_with_symbols(writer, &SymbolTable::new())
+    }
+
+    /// Writes the instance in the OPB format, using the names of the variables given by a symbol table.
+    ///
+    /// The variables without names are written as in the standard format, unless their standard name is associated with another variable (see [`SymbolTable::literal_name`]).
+    /// The output can be read back by [`parse_with_symbols`](Self::parse_with_symbols).
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the writer fails.
+    pub fn write_with_symbols<W: Write>(
+        &self,
+        writer: &mut W,
+        symbols: &SymbolTable,
+    ) -> std::io::Result<()> {
+        write!(
+            writer,
+            "{}",
+            OpbText {
+                value: self,
+                symbols
+            }
+        )
+    }
+
+    /// Returns the number of variables of the instance.
+    #[must_use]
+    pub fn n_vars(&self) -> usize {
+        self.n_vars
+    }
+
+    /// Returns the terms of the objective function to minimize, if any.
+    #[must_use]
+    pub fn objective(&self) -> Option<&[(i64, i32)]> {
+        self.objective.as_deref()
+    }
+
+    /// Returns the definitions of the variables introduced to linearize the products of literals, if any.
+    ///
+    /// Each definition is a couple composed of the introduced variable and the literals of the product it replaces.
+    #[must_use]
+    pub fn products(&self) -> &[(i32, Vec<i32>)] {
+        &self.products
+    }
+
+    /// Returns the constraints of the instance.
+    #[must_use]
+    pub fn constraints(&self) -> &[PbConstraint] {
+        &self.constraints
+    }
+
+    /// Returns an equivalent instance in which all the constraints are normalized.
+    ///
+    /// See [`PbConstraint::normalize`] for a description of the normal form.
+    /// The number of variables and the objective function are left unchanged.
+    #[must_use]
+    pub fn normalize(&self) -> OpbInstance {
+        OpbInstance {
+            n_vars: self.n_vars,
+            objective: self.objective.clone(),
+            constraints: self
+                .constraints
+                .iter()
+                .flat_map(PbConstraint::normalize)
+                .collect(),
+            products: self.products.clone(),
+        }
+    }
+}
+
+impl FromStr for OpbInstance {
+    type Err = PbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s.as_bytes())
+    }
+}
+
+/// Writes the instance in the OPB format; see [`OpbInstance::write`].
+impl Display for OpbInstance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        OpbText {
+            value: self,
+            symbols: &SymbolTable::new(),
+        }
+        .fmt(f)
+    }
+}
+
+/// A value formatted in the OPB syntax, with the variable names of a symbol table.
+pub(crate) struct OpbText<'a, T> {
+    pub(crate) value: &'a T,
+    pub(crate) symbols: &'a SymbolTable,
+}
+
+impl Display for OpbText<'_, OpbInstance> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let instance = self.value;
+        writeln!(
+            f,
+            "* #variable= {} #constraint= {}",
+            instance.n_vars,
+            instance.constraints.len()
+        )?;
+        if let Some(objective) = &instance.objective {
+            write!(f, "min: ")?;
+            write_terms(f, objective, self.symbols)?;
+            writeln!(f, ";")?;
+        }
+        for constraint in &instance.constraints {
+            let text = OpbText {
+                value: constraint,
+                symbols: self.symbols,
+            };
+            writeln!(f, "{text}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for OpbText<'_, PbConstraint> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let constraint = self.value;
+        let rhs = i128::from(constraint.rhs());
+        let (comparator, sign, rhs) = match constraint.comparator() {
+            Comparator::Geq => (Comparator::Geq, 1, rhs),
+            Comparator::Gt => (Comparator::Geq, 1, rhs + 1),
+            Comparator::Leq => (Comparator::Geq, -1, -rhs),
+            Comparator::Lt => (Comparator::Geq, -1, 1 - rhs),
+            Comparator::Eq => (Comparator::Eq, 1, rhs),
+        };
+        let terms = constraint
+            .terms()
+            .iter()
+            .map(|&(w, l)| i64::try_from(sign * i128::from(w)).map(|w| (w, l)))
+            .collect::<Result<Vec<_>, _>>();
+        if let (Ok(terms), Ok(rhs)) = (terms, i64::try_from(rhs)) {
+            write_terms(f, &terms, self.symbols)?;
+            write!(f, "{comparator} {rhs} ;")
+        } else {
+            // the constraint cannot be written with >= in 64-bit integers, so its own comparator is kept
+            write_terms(f, constraint.terms(), self.symbols)?;
+            write!(f, "{} {} ;", constraint.comparator(), constraint.rhs())
+        }
+    }
+}
+
+fn write_terms(
+    f: &mut std::fmt::Formatter<'_>,
+    terms: &[(i64, i32)],
+    symbols: &SymbolTable,
+) -> std::fmt::Result {
+    for (w, l) in terms {
+        write!(f, "{w:+} {} ", symbols.literal_name(*l))?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Token {
+    Min,
+    Soft,
+    SoftWeight(i64),
+    Integer(i64),
+    Literal(i32),
+    Comparator(Comparator),
+    Semicolon,
+}
+
+type ProductTerm = (i64, Vec<i32>);
+
+type ProductConstraint = (Vec<ProductTerm>, Comparator, i64);
+
+struct Linearizer {
+    line: usize,
+    next_var: i32,
+    product_vars: HashMap<Vec<i32>, i32>,
+    products: Vec<(i32, Vec<i32>)>,
+}
+
+impl Linearizer {
+    fn new(n_vars: usize, line: usize) -> Result<Self, PbError> {
+        let next_var = i32::try_from(n_vars)
+            .ok()
+            .and_then(|n| n.checked_add(1))
+            .ok_or_else(|| parse_error(line, "too many variables"))?;
+        Ok(Self {
+            line,
+            next_var,
+            product_vars: HashMap::new(),
+            products: Vec::new(),
+        })
+    }
+
+    fn linearize(&mut self, terms: Vec<ProductTerm>) -> Result<Vec<(i64, i32)>, PbError> {
+        terms
+            .into_iter()
+            .map(|(weight, mut literals)| {
+                literals.sort_unstable();
+                literals.dedup();
+                if let [l] = literals[..] {
+                    return Ok((weight, l));
+                }
+                if let Some(var) = self.product_vars.get(&literals) {
+                    return Ok((weight, *var));
+                }
+                let var = self.next_var;
+                self.next_var = var
+                    .checked_add(1)
+                    .ok_or_else(|| parse_error(self.line, "too many product variables"))?;
+                self.products.push((var, literals.clone()));
+                self.product_vars.insert(literals, var);
+                Ok((weight, var))
+            })
+            .collect()
+    }
+
+    fn definitions(&self) -> Vec<PbConstraint> {
+        let mut definitions = Vec::new();
+        for (var, literals) in &self.products {
+            for l in literals {
+                definitions.push(PbConstraint::new(
+                    vec![(1, -var), (1, *l)],
+                    Comparator::Geq,
+                    1,
+                ));
+            }
+            definitions.push(PbConstraint::new(
+                std::iter::once((1, *var))
+                    .chain(literals.iter().map(|l| (1, -l)))
+                    .collect(),
+                Comparator::Geq,
+                1,
+            ));
+        }
+        definitions
+    }
+}
+
+pub(crate) struct Parser {
+    pub(crate) header_n_vars: Option<usize>,
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+    pub(crate) last_line: usize,
+}
+
+impl Parser {
+    pub(crate) fn new(
+        content: &str,
+        mut symbols: Option<&mut SymbolTable>,
+    ) -> Result<Self, PbError> {
+        let mut header_n_vars = None;
+        let mut tokens = Vec::new();
+        let mut last_line = 1;
+        for (i, line) in content.lines().enumerate() {
+            last_line = i + 1;
+            if let Some(comment) = line.strip_prefix('*') {
+                if header_n_vars.is_none() {
+                    header_n_vars = read_header_n_vars(comment, last_line)?;
+                }
+                continue;
+            }
+            tokenize_line(line, last_line, &mut tokens, symbols.as_deref_mut())?;
+        }
+        Ok(Self {
+            header_n_vars,
+            tokens,
+            next: 0,
+            last_line,
+        })
+    }
+
+    fn parse(mut self) -> Result<OpbInstance, PbError> {
+        let mut objective = None;
+        if let Some((Token::Min, _)) = self.peek() {
+            self.next += 1;
+            let (terms, line) = self.read_product_terms()?;
+            match self.pop() {
+                Some((Token::Semicolon, _)) => {}
+                _ => {
+                    return Err(parse_error(
+                        line,
+                        "expected a ';' at the end of the objective",
+                    ))
+                }
+            }
+            objective = Some(terms);
+        }
+        let mut constraints = Vec::new();
+        while self.peek().is_some() {
+            constraints.push(self.read_product_constraint()?);
+        }
+        let max_var = objective
+            .iter()
+            .flatten()
+            .chain(constraints.iter().flat_map(|c| &c.0))
+            .flat_map(|t| &t.1)
+            .map(|l| l.unsigned_abs() as usize)
+            .max()
+            .unwrap_or_default();
+        let n_vars = self.header_n_vars.unwrap_or_default().max(max_var);
+        let mut linearizer = Linearizer::new(n_vars, self.last_line)?;
+        let objective = objective
+            .map(|terms| linearizer.linearize(terms))
+            .transpose()?;
+        let mut constraints = constraints
+            .into_iter()
+            .map(|(terms, comparator, rhs)| {
+                Ok(PbConstraint::new(
+                    linearizer.linearize(terms)?,
+                    comparator,
+                    rhs,
+                ))
+            })
+            .collect::<Result<Vec<_>, PbError>>()?;
+        constraints.append(&mut linearizer.definitions());
+        let mut instance = OpbInstance::new(n_vars, objective, constraints);
+        instance.products = linearizer.products;
+        Ok(instance)
+    }
+
+    pub(crate) fn read_constraint(&mut self) -> Result<PbConstraint, PbError> {
+        let line = self.peek().map_or(self.last_line, |t| t.1);
+        let (terms, comparator, rhs) = self.read_product_constraint()?;
+        let terms = terms
+            .into_iter()
+            .map(|(weight, literals)| match literals[..] {
+                [l] => Ok((weight, l)),
+                _ => Err(parse_error(line, "nonlinear terms are not supported")),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(PbConstraint::new(terms, comparator, rhs))
+    }
+
+    fn read_product_constraint(&mut self) -> Result<ProductConstraint, PbError> {
+        let (terms, _) = self.read_product_terms()?;
+        let comparator = match self.pop() {
+            Some((Token::Comparator(c), _)) => c,
+            Some((_, l)) => return Err(parse_error(l, "expected a comparator")),
+            None => return Err(parse_error(self.last_line, "unexpected end of input")),
+        };
+        let rhs = match self.pop() {
+            Some((Token::Integer(n), _)) => n,
+            Some((_, l)) => return Err(parse_error(l, "expected an integer right hand side")),
+            None => return Err(parse_error(self.last_line, "unexpected end of input")),
+        };
+        match self.pop() {
+            Some((Token::Semicolon, _)) => {}
+            Some((_, l)) => return Err(parse_error(l, "expected a ';'")),
+            None => return Err(parse_error(self.last_line, "unexpected end of input")),
+        }
+        Ok((terms, comparator, rhs))
+    }
+
+    fn read_product_terms(&mut self) -> Result<(Vec<ProductTerm>, usize), PbError> {
+        let first_line = self.peek().map_or(self.last_line, |t| t.1);
+        let mut terms = Vec::new();
+        while let Some((Token::Integer(weight), line)) = self.peek() {
+            self.next += 1;
+            let mut literals = Vec::new();
+            while let Some((Token::Literal(l), _)) = self.peek() {
+                self.next += 1;
+                literals.push(l);
+            }
+            if literals.is_empty() {
+                return Err(parse_error(line, "expected a literal after a coefficient"));
+            }
+            terms.push((weight, literals));
+        }
+        Ok((terms, first_line))
+    }
+
+    pub(crate) fn peek(&self) -> Option<(Token, usize)> {
+        self.tokens.get(self.next).copied()
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<(Token, usize)> {
+        let t = self.peek();
+        self.next += 1;
+        t
+    }
+}
+
+fn read_header_n_vars(comment: &str, line: usize) -> Result<Option<usize>, PbError> {
+    let mut words = comment.split_whitespace();
+    while let Some(w) = words.next() {
+        if w == "#variable=" {
+            return words
+                .next()
+                .and_then(|n| n.parse().ok())
+                .map(Some)
+                .ok_or_else(|| parse_error(line, "invalid #variable= field"));
+        }
+    }
+    Ok(None)
+}
+
+fn tokenize_line(
+    line: &str,
+    line_index: usize,
+    tokens: &mut Vec<(Token, usize)>,
+    mut symbols: Option<&mut SymbolTable>,
+) -> Result<(), PbError> {
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ';' => {
+                i += 1;
+                Token::Semicolon
+            }
+            '>' | '<' => {
+                let non_strict = chars.get(i + 1) == Some(&'=');
+                i += if non_strict { 2 } else { 1 };
+                Token::Comparator(match (c, non_strict) {
+                    ('>', true) => Comparator::Geq,
+                    ('>', false) => Comparator::Gt,
+                    (_, true) => Comparator::Leq,
+                    (_, false) => Comparator::Lt,
+                })
+            }
+            '=' => {
+                i += 1;
+                Token::Comparator(Comparator::Eq)
+            }
+            'm' if chars[i..].starts_with(&['m', 'i', 'n', ':']) => {
+                i += 4;
+                Token::Min
+            }
+            's' if chars[i..].starts_with(&['s', 'o', 'f', 't', ':']) => {
+                i += 5;
+                Token::Soft
+            }
+            c if symbols.is_some() && (c == '~' || c.is_alphabetic() || c == '_') => {
+                let (literal, len) =
+                    read_named_literal(&chars[i..], line_index, symbols.as_deref_mut().unwrap())?;
+                i += len;
+                Token::Literal(literal)
+            }
+            'm' => return Err(parse_error(line_index, "expected \"min:\"")),
+            's' => return Err(parse_error(line_index, "expected \"soft:\"")),
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .ok_or_else(|| parse_error(line_index, "expected a ']'"))?;
+                let text = chars[i + 1..i + end].iter().collect::<String>();
+                i += end + 1;
+                Token::SoftWeight(text.trim().parse().map_err(|_| {
+                    parse_error(
+                        line_index,
+                        &format!("invalid soft constraint weight {text}"),
+                    )
+                })?)
+            }
+            '~' | 'x' => {
+                let negative = c == '~';
+                if negative {
+                    i += 1;
+                }
+                if chars.get(i) != Some(&'x') {
+                    return Err(parse_error(line_index, "expected a variable"));
+                }
+                let digits = read_digits(&chars, i + 1);
+                i += 1 + digits.len();
+                let var = digits
+                    .parse::<i32>()
+                    .ok()
+                    .filter(|v| *v > 0)
+                    .ok_or_else(|| {
+                        parse_error(line_index, &format!("invalid variable x{digits}"))
+                    })?;
+                Token::Literal(if negative { -var } else { var })
+            }
+            '+' | '-' | '0'..='9' => {
+                let sign_len = usize::from(c == '+' || c == '-');
+                let digits = read_digits(&chars, i + sign_len);
+                let text = format!("{}{digits}", if c == '-' { "-" } else { "" });
+                i += sign_len + digits.len();
+                Token::Integer(
+                    text.parse()
+                        .map_err(|_| parse_error(line_index, &format!("invalid integer {text}")))?,
+                )
+            }
+            _ => {
+                return Err(parse_error(
+                    line_index,
+                    &format!("unexpected character {c}"),
+                ))
+            }
+        };
+        tokens.push((token, line_index));
+    }
+    Ok(())
+}
+
+fn read_named_literal(
+    chars: &[char],
+    line_index: usize,
+    symbols: &mut SymbolTable,
+) -> Result<(i32, usize), PbError> {
+    let negative = chars[0] == '~';
+    let from = usize::from(negative);
+    let name = chars[from..]
+        .iter()
+        .take_while(|c| c.is_alphanumeric() || **c == '_')
+        .collect::<String>();
+    if !is_valid_name(&name) {
+        return Err(parse_error(line_index, "expected a variable"));
+    }
+    let var = symbols.var(&name);
+    Ok((
+        if negative { -var } else { var },
+        from + name.chars().count(),
+    ))
+}
+
+fn read_digits(chars: &[char], from: usize) -> String {
+    chars[from.min(chars.len())..]
+        .iter()
+        .take_while(|c| c.is_ascii_digit())
+        .collect()
+}
+
+pub(crate) fn parse_error(line: usize, message: &str) -> PbError {
+    PbError::Parse {
+        line,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_instance() {
+        let opb = "* #variable= 4 #constraint= 2\n* comment\nmin: +1 x1 -2 ~x2 ;\n+1 x1 +2 x2\n+3 x3 >= 2 ;\n-1 x1 +1 x3 = 0;\n";
+        let instance = OpbInstance::parse(opb.as_bytes()).unwrap();
+        assert_eq!(4, instance.n_vars());
+        assert_eq!(Some(&[(1, 1), (-2, -2)][..]), instance.objective());
+        assert_eq!(
+            &[
+                PbConstraint::new(vec![(1, 1), (2, 2), (3, 3)], Comparator::Geq, 2),
+                PbConstraint::new(vec![(-1, 1), (1, 3)], Comparator::Eq, 0),
+            ],
+            instance.constraints()
+        );
+    }
+
+    #[test]
+    fn test_parse_no_header_no_objective() {
+        let instance = "1 x1 1 x5 <= 1;".parse::<OpbInstance>().unwrap();
+        assert_eq!(5, instance.n_vars());
+        assert!(instance.objective().is_none());
+        assert_eq!(Comparator::Leq, instance.constraints()[0].comparator());
+    }
+
+    #[test]
+    fn test_parse_strict() {
+        let instance = "+1 x1 +2 x2 > 1 ;\n+1 x1 -1 x2 < 0 ;\n+1 x1 +1 x2 >= 1 ;"
+            .parse::<OpbInstance>()
+            .unwrap();
+        assert_eq!(
+            &[
+                PbConstraint::new(vec![(1, 1), (2, 2)], Comparator::Gt, 1),
+                PbConstraint::new(vec![(1, 1), (-1, 2)], Comparator::Lt, 0),
+                PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Geq, 1),
+            ],
+            instance.constraints()
+        );
+        let mut output = Vec::new();
+        instance.write(&mut output).unwrap();
+        assert_eq!(
+            "* #variable= 2 #constraint= 3\n+1 x1 +2 x2 >= 2 ;\n-1 x1 +1 x2 >= 1 ;\n+1 x1 +1 x2 >= 1 ;\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_objective() {
+        let instance = "min: ;\n+1 x1 >= 1 ;".parse::<OpbInstance>().unwrap();
+        assert_eq!(Some(&[][..]), instance.objective());
+    }
+
+    #[test]
+    fn test_normalize() {
+        let instance = "* #variable= 3\n+2 x1 +2 x2 >= 1 ;\n+1 x1 +1 x2 = 1 ;\n+1 x3 <= 1 ;"
+            .parse::<OpbInstance>()
+            .unwrap()
+            .normalize();
+        assert_eq!(3, instance.n_vars());
+        assert_eq!(
+            &[
+                PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Geq, 1),
+                PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Geq, 1),
+                PbConstraint::new(vec![(1, -1), (1, -2)], Comparator::Geq, 1),
+            ],
+            instance.constraints()
+        );
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let instance = OpbInstance::new(
+            5,
+            Some(vec![(3, 1), (-1, -4)]),
+            vec![
+                PbConstraint::new(vec![(2, 1), (-3, -2)], Comparator::Geq, -1),
+                PbConstraint::new(vec![(1, 2), (1, 3)], Comparator::Eq, 1),
+                PbConstraint::new(vec![(1, 1), (1, 3)], Comparator::Lt, 2),
+            ],
+        );
+        let mut output = Vec::new();
+        instance.write(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            "* #variable= 5 #constraint= 3\nmin: +3 x1 -1 ~x4 ;\n+2 x1 -3 ~x2 >= -1 ;\n+1 x2 +1 x3 = 1 ;\n-1 x1 -1 x3 >= -1 ;\n",
+            output
+        );
+        let parsed = output.parse::<OpbInstance>().unwrap();
+        assert_eq!(instance.n_vars(), parsed.n_vars());
+        assert_eq!(instance.objective(), parsed.objective());
+        assert_eq!(&instance.constraints()[..2], &parsed.constraints()[..2]);
+    }
+
+    #[test]
+    fn test_display() {
+        let instance = OpbInstance::new(
+            3,
+            Some(vec![(1, 2)]),
+            vec![
+                PbConstraint::new(vec![(1, 1), (2, -3)], Comparator::Leq, 2),
+                PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Gt, 0),
+            ],
+        );
+        let mut output = Vec::new();
+        instance.write(&mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), instance.to_string());
+        assert_eq!(
+            "-1 x1 -2 ~x3 >= -2 ;",
+            instance.constraints()[0].to_string()
+        );
+        let constraints = instance
+            .constraints()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let parsed = constraints.parse::<OpbInstance>().unwrap();
+        assert_eq!(
+            instance.normalize().constraints(),
+            parsed.normalize().constraints()
+        );
+    }
+
+    #[test]
+    fn test_display_extreme_values() {
+        for opb in [
+            "+1 x1 < -9223372036854775808 ;",
+            "-9223372036854775808 x1 <= 0 ;",
+            "+1 x1 > 9223372036854775807 ;",
+        ] {
+            let constraint = opb.parse::<OpbInstance>().unwrap().constraints()[0].clone();
+            assert_eq!(opb, constraint.to_string());
+        }
+        let constraint = PbConstraint::new(vec![(1, 1)], Comparator::Lt, i64::MAX);
+        assert_eq!("-1 x1 >= -9223372036854775806 ;", constraint.to_string());
+    }
+
+    #[test]
+    fn test_write_empty_objective() {
+        let instance = OpbInstance::new(1, Some(vec![]), vec![]);
+        let mut output = Vec::new();
+        instance.write(&mut output).unwrap();
+        assert_eq!(
+            "* #variable= 1 #constraint= 0\nmin: ;\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_empty_constraints() {
+        let instance = OpbInstance::new(
+            0,
+            None,
+            vec![
+                PbConstraint::new(vec![], Comparator::Geq, 1),
+                PbConstraint::new(vec![], Comparator::Leq, 0),
+            ],
+        );
+        let output = instance.to_string();
+        assert_eq!("* #variable= 0 #constraint= 2\n>= 1 ;\n>= 0 ;\n", output);
+        let parsed = output.parse::<OpbInstance>().unwrap();
+        assert_eq!(0, parsed.n_vars());
+        assert_eq!(
+            &[
+                PbConstraint::new(vec![], Comparator::Geq, 1),
+                PbConstraint::new(vec![], Comparator::Geq, 0),
+            ],
+            parsed.constraints()
+        );
+    }
+
+    #[test]
+    fn test_parse_nonlinear() {
+        let opb = "* #variable= 3 #constraint= 2 #product= 2\nmin: +1 x1 x2 ;\n+3 x2 x1 +2 ~x3 >= 1 ;\n+1 x1 ~x3 x1 -1 x2 <= 0 ;\n";
+        let instance = OpbInstance::parse(opb.as_bytes()).unwrap();
+        assert_eq!(5, instance.n_vars());
+        assert_eq!(&[(4, vec![1, 2]), (5, vec![-3, 1])], instance.products());
+        assert_eq!(Some(&[(1, 4)][..]), instance.objective());
+        assert_eq!(
+            &[
+                PbConstraint::new(vec![(3, 4), (2, -3)], Comparator::Geq, 1),
+                PbConstraint::new(vec![(1, 5), (-1, 2)], Comparator::Leq, 0),
+                PbConstraint::new(vec![(1, -4), (1, 1)], Comparator::Geq, 1),
+                PbConstraint::new(vec![(1, -4), (1, 2)], Comparator::Geq, 1),
+                PbConstraint::new(vec![(1, 4), (1, -1), (1, -2)], Comparator::Geq, 1),
+                PbConstraint::new(vec![(1, -5), (1, -3)], Comparator::Geq, 1),
+                PbConstraint::new(vec![(1, -5), (1, 1)], Comparator::Geq, 1),
+                PbConstraint::new(vec![(1, 5), (1, 3), (1, -1)], Comparator::Geq, 1),
+            ],
+            instance.constraints()
+        );
+    }
+
+    #[test]
+    fn test_symbols_round_trip() {
+        let opb = "min: +2 cost ;\n+1 a +1 ~b_2 +1 x1 >= 2 ;\n+1 min_a +1 soft_b -1 cost a = 0 ;\n";
+        let mut symbols = SymbolTable::new();
+        let instance = OpbInstance::parse_with_symbols(opb.as_bytes(), &mut symbols).unwrap();
+        assert_eq!(
+            vec![
+                ("cost", 1),
+                ("a", 2),
+                ("b_2", 3),
+                ("x1", 4),
+                ("min_a", 5),
+                ("soft_b", 6)
+            ],
+            symbols.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(&[(7, vec![1, 2])], instance.products());
+        assert_eq!(
+            PbConstraint::new(vec![(1, 2), (1, -3), (1, 4)], Comparator::Geq, 2),
+            instance.constraints()[0]
+        );
+        let mut output = Vec::new();
+        instance.write_with_symbols(&mut output, &symbols).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("+1 a +1 ~b_2 +1 x1 >= 2 ;\n+1 min_a +1 soft_b -1 x7 = 0 ;\n"));
+        let parsed = OpbInstance::parse_with_symbols(output.as_bytes(), &mut symbols).unwrap();
+        assert_eq!(instance.objective(), parsed.objective());
+        assert_eq!(instance.constraints(), parsed.constraints());
+        assert_eq!(Some(7), symbols.get("x7"));
+    }
+
+    #[test]
+    fn test_symbols_round_trip_name_collision() {
+        let instance = "+1 x1 +2 x2 >= 2 ;".parse::<OpbInstance>().unwrap();
+        let mut symbols = SymbolTable::new();
+        symbols.insert("x1", 2);
+        let mut output = Vec::new();
+        instance.write_with_symbols(&mut output, &symbols).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.ends_with("\n+1 x1_1 +2 x1 >= 2 ;\n"));
+        let parsed = OpbInstance::parse_with_symbols(output.as_bytes(), &mut symbols).unwrap();
+        assert_eq!(Some(3), symbols.get("x1_1"));
+        assert_eq!(
+            &[PbConstraint::new(vec![(1, 3), (2, 2)], Comparator::Geq, 2)],
+            parsed.constraints()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let assert_error_line = |opb: &str, expected_line: usize| match opb.parse::<OpbInstance>() {
+            Err(PbError::Parse { line, .. }) => assert_eq!(expected_line, line),
+            _ => panic!("expected a parse error for {opb:?}"),
+        };
+        assert_error_line("+1 x1 >= 1", 1);
+        assert_error_line("+1 x1 +1 >= 1 ;", 1);
+        assert_error_line("+1 x1 <> 1 ;", 1);
+        assert_error_line("+1 x0 >= 1 ;", 1);
+        assert_error_line("+1 y1 >= 1 ;", 1);
+        assert_error_line("+1 x1 >= 99999999999999999999 ;", 1);
+        assert_error_line("* #variable= 3000000000\n+1 x1 >= 1 ;", 2);
+        assert_error_line("+1 x2147483647 >= 1 ;", 1);
+        assert_error_line("+1 x2147483646 +1 x1 x2 +1 x1 x3 >= 1 ;", 1);
+    }
+}
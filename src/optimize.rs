@@ -0,0 +1,272 @@
+use crate::{
+    minisat::MiniSat, try_encode_instance, ClauseSink, Comparator, EncodeLimits, EncoderConfig,
+    OpbInstance, PbConstraint, PbError, PB2CNF,
+};
+
+/// The strategies that can be used by [`optimize`] to reach an optimal solution.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SearchStrategy {
+    /// SAT-UNSAT search: each time a solution is found, the solver is asked for a strictly better one, until no such solution exists.
+    #[default]
+    Linear,
+    /// Binary search over the values of the objective function, between a lower bound and the cost of the best known solution.
+    Binary,
+}
+
+/// A solution of a Pseudo-Boolean instance, returned by [`optimize`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    model: Vec<i32>,
+    cost: i64,
+}
+
+impl Solution {
+    /// Returns the model, as a list of DIMACS literals.
+    ///
+    /// The model contains exactly one literal for each variable of the instance, in the order of the variables.
+    #[must_use]
+    pub fn model(&self) -> &[i32] {
+        &self.model
+    }
+
+    /// Consumes this solution and returns its model.
+    #[must_use]
+    pub fn into_model(self) -> Vec<i32> {
+        self.model
+    }
+
+    /// Returns the value of the objective function for this solution.
+    ///
+    /// If the instance has no objective function, this value is 0.
+    #[must_use]
+    pub fn cost(&self) -> i64 {
+        self.cost
+    }
+}
+
+/// Solves a Pseudo-Boolean instance, minimizing its objective function.
+///
+/// The instance is encoded into CNF with the default configuration, and the resulting formula is given to the `MiniSat` solver bundled with pblib.
+/// The objective function is then minimized according to the given strategy, by adding constraints bounding its value.
+/// If the instance has no objective function, the first solution found is returned.
+///
+/// This function returns an optimal solution, or `None` if the instance is unsatisfiable.
+/// It is intended for small instances; dedicated Pseudo-Boolean solvers should be preferred for larger ones.
+///
+/// # Errors
+///
+/// The instance is encoded with [`try_encode_instance`](crate::try_encode_instance), whose errors are returned by this function.
+/// In addition, a [`PbError::Overflow`] error is returned if the bounds on the objective function cannot be encoded, that is if twice the sum of the absolute values of its weights does not fit in a 64-bit integer.
+///
+/// ```
+/// use pblib_rs::{optimize, OpbInstance, SearchStrategy};
+///
+/// let instance = "min: +3 x1 +2 x2 +1 x3 ;\n+1 x1 +1 x2 +1 x3 >= 2 ;"
+///     .parse::<OpbInstance>()
+///     .unwrap();
+/// let solution = optimize(&instance, SearchStrategy::Linear).unwrap().unwrap();
+/// assert_eq!(3, solution.cost());
+/// assert_eq!(&[-1, 2, 3], solution.model());
+/// ```
+#[allow(clippy::missing_panics_doc)]
+pub fn optimize(
+    instance: &OpbInstance,
+    strategy: SearchStrategy,
+) -> Result<Option<Solution>, PbError> {
+    let cnf = try_encode_instance(instance, &EncoderConfig::default())?;
+    let objective = cnf.objective().unwrap_or_default().to_vec();
+    objective
+        .iter()
+        .try_fold(0_i64, |acc, (w, _)| acc.checked_add(w.checked_abs()?))
+        .and_then(|s| s.checked_mul(2))
+        .ok_or_else(|| {
+            PbError::Overflow(
+                "the bounds on the objective function do not fit in 64-bit integers".to_string(),
+            )
+        })?;
+    let cost = |model: &[i32]| {
+        objective
+            .iter()
+            .filter(|(_, l)| model[l.unsigned_abs() as usize - 1] == *l)
+            .map(|(w, _)| w)
+            .sum::<i64>()
+    };
+    let objective_bound =
+        |upper_bound| PbConstraint::new(objective.clone(), Comparator::Leq, upper_bound);
+    let mut solver = MiniSat::new();
+    cnf.formula().iter().for_each(|c| solver.add_clause(c));
+    if !solver.solve_with_assumptions(&[]) {
+        return Ok(None);
+    }
+    let n_vars = cnf.formula().n_vars();
+    let mut best_model = complete_model(solver.model().unwrap(), n_vars);
+    let mut best_cost = cost(&best_model);
+    let pb2cnf = PB2CNF::new();
+    let limits = EncodeLimits::default();
+    let mut next_free_var_id = cnf.formula().next_free_var_id();
+    match strategy {
+        SearchStrategy::Linear => loop {
+            next_free_var_id = pb2cnf.try_encode_constraint_into(
+                &objective_bound(best_cost - 1),
+                next_free_var_id,
+                &limits,
+                &mut solver,
+            )?;
+            if !solver.solve_with_assumptions(&[]) {
+                break;
+            }
+            best_model = complete_model(solver.model().unwrap(), n_vars);
+            best_cost = cost(&best_model);
+        },
+        SearchStrategy::Binary => {
+            let mut lower_bound = objective.iter().map(|t| t.0.min(0)).sum::<i64>();
+            while lower_bound < best_cost {
+                let mid = lower_bound + (best_cost - 1 - lower_bound) / 2;
+                let selector = next_free_var_id;
+                let mut guarded_solver = GuardedSink {
+                    guard: selector,
+                    sink: &mut solver,
+                };
+                next_free_var_id = pb2cnf.try_encode_constraint_into(
+                    &objective_bound(mid),
+                    selector + 1,
+                    &limits,
+                    &mut guarded_solver,
+                )?;
+                if solver.solve_with_assumptions(&[selector]) {
+                    best_model = complete_model(solver.model().unwrap(), n_vars);
+                    best_cost = cost(&best_model);
+                    solver.add_clause(&[selector]);
+                } else {
+                    lower_bound = mid + 1;
+                    solver.add_clause(&[-selector]);
+                }
+            }
+        }
+    }
+    let original_model = cnf.original_assignment(&best_model);
+    Ok(Some(Solution {
+        model: (1..)
+            .take(cnf.n_original_vars())
+            .map(|v| if original_model.contains(&v) { v } else { -v })
+            .collect(),
+        cost: best_cost,
+    }))
+}
+
+/// Extends a model returned by the solver to the variables of the formula it has not seen, setting them to false.
+fn complete_model(mut model: Vec<i32>, n_vars: usize) -> Vec<i32> {
+    let n_vars = i32::try_from(n_vars).unwrap();
+    model.extend((i32::try_from(model.len()).unwrap() + 1..=n_vars).map(|v| -v));
+    model
+}
+
+struct GuardedSink<'a, S> {
+    guard: i32,
+    sink: &'a mut S,
+}
+
+impl<S> ClauseSink for GuardedSink<'_, S>
+where
+    S: ClauseSink,
+{
+    fn add_clause(&mut self, clause: &[i32]) {
+        let mut guarded_clause = clause.to_vec();
+        guarded_clause.push(-self.guard);
+        self.sink.add_clause(&guarded_clause);
+    }
+
+    fn explain(&mut self, rule: std::fmt::Arguments<'_>) {
+        self.sink.explain(rule);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRATEGIES: [SearchStrategy; 2] = [SearchStrategy::Linear, SearchStrategy::Binary];
+
+    #[test]
+    fn test_optimize() {
+        let instance =
+            "min: +5 x1 +4 x2 +3 x3 +2 x4 ;\n+2 x1 +2 x2 +1 x3 +1 x4 >= 3 ;\n+1 x1 +1 x4 <= 1 ;"
+                .parse::<OpbInstance>()
+                .unwrap();
+        for strategy in STRATEGIES {
+            let solution = optimize(&instance, strategy).unwrap().unwrap();
+            assert_eq!(6, solution.cost());
+            assert_eq!(&[-1, 2, -3, 4], solution.model());
+        }
+    }
+
+    #[test]
+    fn test_optimize_negative_weights() {
+        let instance = "min: -2 x1 +1 x2 -1 x3 ;\n+1 x1 +1 x3 <= 1 ;\n+1 ~x1 +1 x2 >= 1 ;"
+            .parse::<OpbInstance>()
+            .unwrap();
+        for strategy in STRATEGIES {
+            let solution = optimize(&instance, strategy).unwrap().unwrap();
+            assert_eq!(-1, solution.cost());
+        }
+    }
+
+    #[test]
+    fn test_optimize_no_objective() {
+        let instance = "* #variable= 3\n+1 x1 +1 x2 >= 2 ;"
+            .parse::<OpbInstance>()
+            .unwrap();
+        for strategy in STRATEGIES {
+            let solution = optimize(&instance, strategy).unwrap().unwrap();
+            assert_eq!(0, solution.cost());
+            assert_eq!(3, solution.model().len());
+            assert_eq!(&[1, 2], &solution.model()[..2]);
+        }
+    }
+
+    #[test]
+    fn test_optimize_unconstrained_objective_var() {
+        let instance = "* #variable= 3\nmin: +2 ~x3 +1 x1 ;\n+1 x1 +1 x2 >= 1 ;"
+            .parse::<OpbInstance>()
+            .unwrap();
+        for strategy in STRATEGIES {
+            let solution = optimize(&instance, strategy).unwrap().unwrap();
+            assert_eq!(0, solution.cost());
+            assert_eq!(&[-1, 2, 3], solution.model());
+        }
+    }
+
+    #[test]
+    fn test_optimize_overflow() {
+        for instance in [
+            "min: +1 x1 ;\n+9223372036854775807 x1 +9223372036854775807 x2 >= 1 ;",
+            "min: +4611686018427387904 x1 +1 x2 ;\n+1 x1 +1 x2 >= 1 ;",
+        ] {
+            let instance = instance.parse::<OpbInstance>().unwrap();
+            for strategy in STRATEGIES {
+                assert!(matches!(
+                    optimize(&instance, strategy),
+                    Err(PbError::Overflow(_))
+                ));
+            }
+        }
+        let instance = "min: +4611686018427387902 x1 -1 x2 ;\n+1 x1 +1 x2 >= 1 ;"
+            .parse::<OpbInstance>()
+            .unwrap();
+        for strategy in STRATEGIES {
+            let solution = optimize(&instance, strategy).unwrap().unwrap();
+            assert_eq!(-1, solution.cost());
+            assert_eq!(&[-1, 2], solution.model());
+        }
+    }
+
+    #[test]
+    fn test_optimize_unsat() {
+        let instance = "min: +1 x1 ;\n+1 x1 +1 x2 >= 2 ;\n+1 x1 +1 x2 <= 1 ;"
+            .parse::<OpbInstance>()
+            .unwrap();
+        for strategy in STRATEGIES {
+            assert!(optimize(&instance, strategy).unwrap().is_none());
+        }
+    }
+}
@@ -0,0 +1,575 @@
+use crate::{opb::OpbText, EncodingContext, LinExpr, PbError, SymbolTable};
+use std::fmt::Display;
+
+/// The comparison operator of a Pseudo-Boolean constraint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Comparator {
+    /// The weighted sum must be less than or equal to the right hand side.
+    Leq,
+    /// The weighted sum must be greater than or equal to the right hand side.
+    Geq,
+    /// The weighted sum must be equal to the right hand side.
+    Eq,
+    /// The weighted sum must be strictly less than the right hand side.
+    Lt,
+    /// The weighted sum must be strictly greater than the right hand side.
+    Gt,
+}
+
+impl Display for Comparator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Comparator::Leq => write!(f, "<="),
+            Comparator::Geq => write!(f, ">="),
+            Comparator::Eq => write!(f, "="),
+            Comparator::Lt => write!(f, "<"),
+            Comparator::Gt => write!(f, ">"),
+        }
+    }
+}
+
+/// A Pseudo-Boolean constraint.
+///
+/// A Pseudo-Boolean constraint is made of terms, a comparator and a right hand side.
+/// Each term is a couple composed of a weight and a literal (in the DIMACS format).
+///
+/// ```
+/// use pblib_rs::{Comparator, PbConstraint};
+///
+/// // 8*x1 + 4*x2 + 2*x3 + 1*x4 >= 6
+/// let constraint = PbConstraint::new(vec![(8, 1), (4, 2), (2, 3), (1, 4)], Comparator::Geq, 6);
+/// assert_eq!(vec![8, 4, 2, 1], constraint.weights());
+/// assert_eq!(vec![1, 2, 3, 4], constraint.literals());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PbConstraint {
+    terms: Vec<(i64, i32)>,
+    comparator: Comparator,
+    rhs: i64,
+}
+
+impl PbConstraint {
+    /// Builds a new constraint given its terms, its comparator and its right hand side.
+    #[must_use]
+    pub fn new(terms: Vec<(i64, i32)>, comparator: Comparator, rhs: i64) -> Self {
+        Self {
+            terms,
+            comparator,
+            rhs,
+        }
+    }
+
+    /// Starts building a constraint from weighted literals.
+    ///
+    /// The terms are couples composed of a weight and a literal; the constraint is obtained by comparing the returned expression with a value.
+    ///
+    /// ```
+    /// use pblib_rs::{Comparator, PbConstraint};
+    ///
+    /// let constraint = PbConstraint::weighted([(8, 1), (4, 2)]).geq(6);
+    /// assert_eq!(PbConstraint::new(vec![(8, 1), (4, 2)], Comparator::Geq, 6), constraint);
+    /// ```
+    #[must_use]
+    pub fn weighted(terms: impl IntoIterator<Item = (i64, i32)>) -> LinExpr {
+        terms.into_iter().collect()
+    }
+
+    /// Starts building a cardinality constraint, in which each literal has a weight of 1.
+    ///
+    /// ```
+    /// use pblib_rs::{Comparator, PbConstraint};
+    ///
+    /// let constraint = PbConstraint::cardinality([1, -2, 3]).leq(1);
+    /// assert_eq!(PbConstraint::new(vec![(1, 1), (1, -2), (1, 3)], Comparator::Leq, 1), constraint);
+    /// ```
+    #[must_use]
+    pub fn cardinality(literals: impl IntoIterator<Item = i32>) -> LinExpr {
+        literals.into_iter().map(|l| (1, l)).collect()
+    }
+
+    /// Encodes this constraint into an [`EncodingContext`].
+    ///
+    /// See [`EncodingContext::encode`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// The errors are the ones of [`EncodingContext::encode`].
+    pub fn encode(&self, ctx: &mut EncodingContext) -> Result<(), PbError> {
+        ctx.encode(self)
+    }
+
+    /// Returns the terms of the constraint, as couples composed of a weight and a literal.
+    #[must_use]
+    pub fn terms(&self) -> &[(i64, i32)] {
+        &self.terms
+    }
+
+    /// Returns the comparator of the constraint.
+    #[must_use]
+    pub fn comparator(&self) -> Comparator {
+        self.comparator
+    }
+
+    /// Returns the right hand side of the constraint.
+    #[must_use]
+    pub fn rhs(&self) -> i64 {
+        self.rhs
+    }
+
+    /// Returns the weights of the terms, in the order of the terms.
+    #[must_use]
+    pub fn weights(&self) -> Vec<i64> {
+        self.terms.iter().map(|t| t.0).collect()
+    }
+
+    /// Returns the literals of the terms, in the order of the terms.
+    #[must_use]
+    pub fn literals(&self) -> Vec<i32> {
+        self.terms.iter().map(|t| t.1).collect()
+    }
+
+    /// Returns the sum of the absolute values of the weights, or `None` if it does not fit in an `i64`.
+    ///
+    /// ```
+    /// use pblib_rs::{Comparator, PbConstraint};
+    ///
+    /// let constraint = PbConstraint::new(vec![(2, 1), (-3, 2)], Comparator::Geq, 1);
+    /// assert_eq!(Some(5), constraint.checked_weight_sum());
+    /// let constraint = PbConstraint::new(vec![(i64::MAX, 1), (1, 2)], Comparator::Geq, 1);
+    /// assert_eq!(None, constraint.checked_weight_sum());
+    /// ```
+    #[must_use]
+    pub fn checked_weight_sum(&self) -> Option<i64> {
+        self.terms
+            .iter()
+            .try_fold(0_i64, |acc, (w, _)| acc.checked_add(w.checked_abs()?))
+    }
+
+    /// Returns an equivalent constraint that does not involve a strict comparator.
+    ///
+    /// Since weights are integers, `>` and `<` comparators are replaced by `>=` and `<=` after adjusting the right hand side.
+    /// If the adjusted right hand side does not fit in an `i64`, the constraint is trivial, and it is replaced by a constraint without terms that is always or never satisfied.
+    /// Other constraints are returned unchanged.
+    ///
+    /// ```
+    /// use pblib_rs::{Comparator, PbConstraint};
+    ///
+    /// let constraint = PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Lt, 2);
+    /// assert_eq!(
+    ///     PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Leq, 1),
+    ///     constraint.non_strict(),
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the adjusted right hand side does not fit in an `i64` while the constraint is not trivial, which requires the sum of the absolute values of the weights not to fit in an `i64` either.
+    #[must_use]
+    pub fn non_strict(&self) -> PbConstraint {
+        let rhs = i128::from(self.rhs);
+        let (comparator, rhs) = match self.comparator {
+            Comparator::Lt => (Comparator::Leq, rhs - 1),
+            Comparator::Gt => (Comparator::Geq, rhs + 1),
+            c => (c, rhs),
+        };
+        checked_from_wide(self.terms.clone(), comparator, rhs)
+            .expect("the right hand side of the constraint does not fit in 64-bit integers")
+    }
+
+    /// Returns the negation of this constraint, or `None` if it uses the `=` comparator.
+    ///
+    /// Since weights are integers, the negation of `>= k` is `<= k-1`, the one of `<= k` is `>= k+1`, the one of `> k` is `<= k` and the one of `< k` is `>= k`.
+    /// If the adjusted right hand side does not fit in an `i64`, the negation is trivial, and it is replaced by a constraint without terms that is always or never satisfied.
+    /// The negation of an equality is a disjunction of two constraints (`< k` or `> k`), which cannot be expressed as a single constraint.
+    ///
+    /// ```
+    /// use pblib_rs::{Comparator, PbConstraint};
+    ///
+    /// let constraint = PbConstraint::weighted([(2, 1), (3, 2)]).geq(3);
+    /// assert_eq!(Some(PbConstraint::weighted([(2, 1), (3, 2)]).leq(2)), constraint.negate());
+    /// assert_eq!(None, PbConstraint::cardinality([1, 2]).eq(1).negate());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the adjusted right hand side does not fit in an `i64` while the negation is not trivial, which requires the sum of the absolute values of the weights not to fit in an `i64` either.
+    #[must_use]
+    pub fn negate(&self) -> Option<PbConstraint> {
+        let rhs = i128::from(self.rhs);
+        let (comparator, rhs) = match self.comparator {
+            Comparator::Geq => (Comparator::Leq, rhs - 1),
+            Comparator::Leq => (Comparator::Geq, rhs + 1),
+            Comparator::Gt => (Comparator::Leq, rhs),
+            Comparator::Lt => (Comparator::Geq, rhs),
+            Comparator::Eq => return None,
+        };
+        Some(
+            checked_from_wide(self.terms.clone(), comparator, rhs)
+                .expect("the right hand side of the constraint does not fit in 64-bit integers"),
+        )
+    }
+
+    /// Returns an equivalent constraint in which all the weights are positive.
+    ///
+    /// A term with a negative weight `-w*l` is replaced by `w*~l`, and the right hand side is increased by `w`.
+    /// Terms with a null weight are removed, and a term whose weight is `i64::MIN` is replaced by two terms whose weights sum to its opposite.
+    /// If the increased right hand side does not fit in an `i64`, the constraint is trivial, and it is replaced by a constraint without terms that is always or never satisfied.
+    ///
+    /// ```
+    /// use pblib_rs::{Comparator, PbConstraint};
+    ///
+    /// let constraint = PbConstraint::new(vec![(2, 1), (-3, 2)], Comparator::Geq, 1);
+    /// assert_eq!(
+    ///     PbConstraint::new(vec![(2, 1), (3, -2)], Comparator::Geq, 4),
+    ///     constraint.with_positive_weights(),
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the increased right hand side does not fit in an `i64` while the constraint is not trivial, which requires the sum of the absolute values of the weights not to fit in an `i64` either.
+    #[must_use]
+    pub fn with_positive_weights(&self) -> PbConstraint {
+        let mut rhs = i128::from(self.rhs);
+        let mut terms = Vec::with_capacity(self.terms.len());
+        for &(w, l) in self.terms.iter().filter(|(w, _)| *w != 0) {
+            if w == i64::MIN {
+                rhs -= i128::from(w);
+                terms.extend([(i64::MAX, -l), (1, -l)]);
+            } else if w < 0 {
+                rhs -= i128::from(w);
+                terms.push((-w, -l));
+            } else {
+                terms.push((w, l));
+            }
+        }
+        checked_from_wide(terms, self.comparator, rhs)
+            .expect("the right hand side of the constraint does not fit in 64-bit integers")
+    }
+
+    /// Returns a list of constraints equivalent to this one, written in a normal form.
+    ///
+    /// The returned constraints use the `>=` comparator, involve each variable at most once, have positive weights, and the greatest common divisor of their weights is 1.
+    /// Constraints using the `=` comparator are split into two constraints.
+    /// Constraints that are satisfied by any assignment are discarded, so an empty list may be returned.
+    /// Unsatisfiable constraints are kept, but they may have no more terms.
+    /// The computations are made on 128-bit integers, so extreme weights and bounds are handled without overflow.
+    ///
+    /// ```
+    /// use pblib_rs::{Comparator, PbConstraint};
+    ///
+    /// // 2*x1 + 4*x2 - 2*x1 < 3 is normalized into 4*~x2 >= 2, and then to ~x2 >= 1
+    /// let constraint = PbConstraint::new(vec![(2, 1), (4, 2), (-2, 1)], Comparator::Lt, 3);
+    /// assert_eq!(
+    ///     vec![PbConstraint::new(vec![(1, -2)], Comparator::Geq, 1)],
+    ///     constraint.normalize(),
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a normalized constraint does not fit in 64-bit integers, which requires the sum of the absolute values of the weights not to fit in an `i64`.
+    #[must_use]
+    pub fn normalize(&self) -> Vec<PbConstraint> {
+        let terms = self
+            .terms
+            .iter()
+            .map(|&(w, l)| (i128::from(w), l))
+            .collect::<Vec<_>>();
+        let negated_terms = || terms.iter().map(|&(w, l)| (-w, l)).collect::<Vec<_>>();
+        let rhs = i128::from(self.rhs);
+        let geq_constraints = match self.comparator {
+            Comparator::Geq => vec![(terms.clone(), rhs)],
+            Comparator::Gt => vec![(terms.clone(), rhs + 1)],
+            Comparator::Leq => vec![(negated_terms(), -rhs)],
+            Comparator::Lt => vec![(negated_terms(), 1 - rhs)],
+            Comparator::Eq => vec![(terms.clone(), rhs), (negated_terms(), -rhs)],
+        };
+        geq_constraints
+            .into_iter()
+            .flat_map(|(terms, rhs)| normalize_geq(&terms, rhs))
+            .collect()
+    }
+}
+
+/// Formats the constraint in the OPB syntax, as a line of an OPB file without its line break.
+///
+/// Since the OPB format only allows the `>=` and `=` comparators, the other constraints are rewritten into equivalent `>=` ones.
+///
+/// ```
+/// use pblib_rs::PbConstraint;
+///
+/// let constraint = PbConstraint::weighted([(2, 1), (-3, -2)]).lt(1);
+/// assert_eq!("-2 x1 +3 ~x2 >= 0 ;", constraint.to_string());
+/// ```
+impl Display for PbConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        OpbText {
+            value: self,
+            symbols: &SymbolTable::new(),
+        }
+        .fmt(f)
+    }
+}
+
+/// Builds a constraint whose right hand side was computed on 128-bit integers.
+///
+/// If the right hand side does not fit in an `i64`, the constraint is replaced by a trivial one when the extreme values of the weighted sum decide it, and `None` is returned otherwise.
+fn checked_from_wide(
+    terms: Vec<(i64, i32)>,
+    comparator: Comparator,
+    rhs: i128,
+) -> Option<PbConstraint> {
+    if let Ok(rhs) = i64::try_from(rhs) {
+        return Some(PbConstraint::new(terms, comparator, rhs));
+    }
+    let min_lhs = terms.iter().map(|t| i128::from(t.0.min(0))).sum::<i128>();
+    let max_lhs = terms.iter().map(|t| i128::from(t.0.max(0))).sum::<i128>();
+    let satisfied = match comparator {
+        Comparator::Geq if max_lhs < rhs => false,
+        Comparator::Geq if min_lhs >= rhs => true,
+        Comparator::Leq if min_lhs > rhs => false,
+        Comparator::Leq if max_lhs <= rhs => true,
+        Comparator::Eq if rhs < min_lhs || rhs > max_lhs => false,
+        Comparator::Lt => return checked_from_wide(terms, Comparator::Leq, rhs - 1),
+        Comparator::Gt => return checked_from_wide(terms, Comparator::Geq, rhs + 1),
+        _ => return None,
+    };
+    Some(trivial_constraint(satisfied))
+}
+
+/// Returns a constraint without terms that is always satisfied, or never satisfied.
+fn trivial_constraint(satisfied: bool) -> PbConstraint {
+    PbConstraint::new(vec![], Comparator::Geq, i64::from(!satisfied))
+}
+
+fn normalize_geq(terms: &[(i128, i32)], mut rhs: i128) -> Vec<PbConstraint> {
+    let mut vars = Vec::new();
+    let mut var_weights = std::collections::HashMap::new();
+    for &(w, l) in terms {
+        let weight = var_weights.entry(l.abs()).or_insert_with(|| {
+            vars.push(l.abs());
+            0
+        });
+        if l > 0 {
+            *weight += w;
+        } else {
+            *weight -= w;
+            rhs -= w;
+        }
+    }
+    let mut terms = Vec::with_capacity(vars.len());
+    for v in vars {
+        let w = var_weights[&v];
+        if w < 0 {
+            rhs -= w;
+            terms.push((-w, -v));
+        } else if w > 0 {
+            terms.push((w, v));
+        }
+    }
+    if rhs <= 0 {
+        return vec![];
+    }
+    let mut rhs = divide_by_gcd(&mut terms, rhs);
+    let mut constraints = Vec::new();
+    let fits = |w: i128| i64::try_from(w).is_ok();
+    if !fits(rhs) || terms.iter().any(|t| !fits(t.0)) {
+        if terms.iter().map(|t| t.0).sum::<i128>() < rhs {
+            return vec![trivial_constraint(false)];
+        }
+        // weights greater than the right hand side can be lowered to it
+        for t in &mut terms {
+            t.0 = t.0.min(rhs);
+        }
+        // literals weighting more than the slack must be satisfied on their own
+        let slack = terms.iter().map(|t| t.0).sum::<i128>() - rhs;
+        terms.retain(|&(w, l)| {
+            if w > slack {
+                constraints.push(PbConstraint::new(vec![(1, l)], Comparator::Geq, 1));
+                rhs -= w;
+                false
+            } else {
+                true
+            }
+        });
+        if rhs <= 0 {
+            return constraints;
+        }
+        rhs = divide_by_gcd(&mut terms, rhs);
+    }
+    let narrow = |w: i128| {
+        i64::try_from(w).expect("the normalized constraint does not fit in 64-bit integers")
+    };
+    constraints.push(PbConstraint::new(
+        terms.into_iter().map(|(w, l)| (narrow(w), l)).collect(),
+        Comparator::Geq,
+        narrow(rhs),
+    ));
+    constraints
+}
+
+/// Divides the positive weights of a `>=` constraint by their greatest common divisor, and returns the right hand side rounded up accordingly.
+fn divide_by_gcd(terms: &mut [(i128, i32)], rhs: i128) -> i128 {
+    let gcd = terms.iter().fold(0, |acc, t| gcd(acc, t.0));
+    if gcd <= 1 {
+        return rhs;
+    }
+    for t in terms.iter_mut() {
+        t.0 /= gcd;
+    }
+    (rhs + gcd - 1) / gcd
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference;
+
+    #[test]
+    fn test_non_strict() {
+        let constraint = PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Gt, 0);
+        assert_eq!(
+            PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Geq, 1),
+            constraint.non_strict()
+        );
+        let constraint = PbConstraint::new(vec![(1, 1)], Comparator::Eq, 1);
+        assert_eq!(constraint, constraint.non_strict());
+    }
+
+    #[test]
+    fn test_negate() {
+        let terms = vec![(3, 1), (-2, 2), (1, -3)];
+        for comparator in [
+            Comparator::Leq,
+            Comparator::Geq,
+            Comparator::Lt,
+            Comparator::Gt,
+        ] {
+            for rhs in -4..=5 {
+                let constraint = PbConstraint::new(terms.clone(), comparator, rhs);
+                let negation = constraint.negate().unwrap();
+                assert!(matches!(
+                    negation.comparator(),
+                    Comparator::Leq | Comparator::Geq
+                ));
+                let models = reference::models(&constraint, 3);
+                let negation_models = reference::models(&negation, 3);
+                assert_eq!(8, models.len() + negation_models.len());
+                assert!(models.iter().all(|m| !negation_models.contains(m)));
+            }
+        }
+        let unsat = PbConstraint::new(vec![], Comparator::Geq, 1);
+        let constraint = PbConstraint::new(terms.clone(), Comparator::Geq, i64::MIN);
+        assert_eq!(Some(unsat.clone()), constraint.negate());
+        let constraint = PbConstraint::new(terms, Comparator::Leq, i64::MAX);
+        assert_eq!(Some(unsat), constraint.negate());
+    }
+
+    #[test]
+    fn test_with_positive_weights() {
+        let constraint = PbConstraint::new(vec![(-1, 1), (0, 2), (-2, -3)], Comparator::Leq, -1);
+        assert_eq!(
+            PbConstraint::new(vec![(1, -1), (2, 3)], Comparator::Leq, 2),
+            constraint.with_positive_weights()
+        );
+    }
+
+    #[test]
+    fn test_normalize_leq() {
+        // 3*x1 + 6*~x2 <= 4 <=> 3*~x1 + 6*x2 >= 5 <=> ~x1 + 2*x2 >= 2
+        let constraint = PbConstraint::new(vec![(3, 1), (6, -2)], Comparator::Leq, 4);
+        assert_eq!(
+            vec![PbConstraint::new(vec![(1, -1), (2, 2)], Comparator::Geq, 2)],
+            constraint.normalize()
+        );
+    }
+
+    #[test]
+    fn test_normalize_eq() {
+        let constraint = PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Eq, 1);
+        assert_eq!(
+            vec![
+                PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Geq, 1),
+                PbConstraint::new(vec![(1, -1), (1, -2)], Comparator::Geq, 1),
+            ],
+            constraint.normalize()
+        );
+    }
+
+    #[test]
+    fn test_extreme_bounds() {
+        // x1 < i64::MIN and -x1 <= i64::MIN never hold
+        let unsat = PbConstraint::new(vec![], Comparator::Geq, 1);
+        let constraint = PbConstraint::new(vec![(1, 1)], Comparator::Lt, i64::MIN);
+        assert_eq!(unsat, constraint.non_strict());
+        assert_eq!(vec![unsat.clone()], constraint.normalize());
+        let constraint = PbConstraint::new(vec![(-1, 1)], Comparator::Leq, i64::MIN);
+        assert_eq!(vec![unsat.clone()], constraint.normalize());
+        // x1 > i64::MAX never holds, x1 < i64::MAX always holds
+        let constraint = PbConstraint::new(vec![(2, 1)], Comparator::Gt, i64::MAX);
+        assert_eq!(unsat, constraint.non_strict());
+        let constraint = PbConstraint::new(vec![(-2, 1)], Comparator::Lt, i64::MAX);
+        assert!(constraint.normalize().is_empty());
+        // -x1 >= i64::MAX never holds, even with positive weights
+        let constraint = PbConstraint::new(vec![(-1, 1)], Comparator::Geq, i64::MAX);
+        assert_eq!(unsat, constraint.with_positive_weights());
+    }
+
+    #[test]
+    fn test_extreme_weights() {
+        // i64::MIN*x1 >= 0 <=> ~x1 >= 1
+        let constraint = PbConstraint::new(vec![(i64::MIN, 1)], Comparator::Geq, 0);
+        assert_eq!(
+            vec![PbConstraint::new(vec![(1, -1)], Comparator::Geq, 1)],
+            constraint.normalize()
+        );
+        let constraint = PbConstraint::new(vec![(i64::MIN, 1)], Comparator::Geq, -1);
+        assert_eq!(
+            PbConstraint::new(vec![(i64::MAX, -1), (1, -1)], Comparator::Geq, i64::MAX),
+            constraint.with_positive_weights()
+        );
+        // the weights of x1 sum to 2*i64::MAX, so x1 alone satisfies the bound
+        let constraint = PbConstraint::new(
+            vec![(i64::MAX, 1), (i64::MAX, 1), (1, 2)],
+            Comparator::Geq,
+            i64::MAX,
+        );
+        assert_eq!(
+            vec![PbConstraint::new(vec![(1, 1)], Comparator::Geq, 1)],
+            constraint.normalize()
+        );
+        for comparator in [Comparator::Leq, Comparator::Geq, Comparator::Eq] {
+            for rhs in -3..=3 {
+                let constraint = PbConstraint::new(vec![(i64::MIN, 1), (1, 2)], comparator, rhs);
+                let normalized = constraint.normalize();
+                for model in reference::models(&PbConstraint::new(vec![], Comparator::Geq, 0), 2) {
+                    assert_eq!(
+                        reference::evaluate(&constraint, &model),
+                        normalized.iter().all(|c| reference::evaluate(c, &model))
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_normalize_opposite_literals() {
+        // x1 + ~x1 >= 1 is always satisfied
+        let constraint = PbConstraint::new(vec![(1, 1), (1, -1)], Comparator::Geq, 1);
+        assert!(constraint.normalize().is_empty());
+        // x1 + ~x1 >= 2 is unsatisfiable
+        let constraint = PbConstraint::new(vec![(1, 1), (1, -1)], Comparator::Geq, 2);
+        assert_eq!(
+            vec![PbConstraint::new(vec![], Comparator::Geq, 1)],
+            constraint.normalize()
+        );
+    }
+}
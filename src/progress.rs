@@ -0,0 +1,27 @@
+/// The progress of an encoding, given to the callback of [`PB2CNF::try_encode_constraint_with_progress`](crate::PB2CNF::try_encode_constraint_with_progress).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    n_clauses: usize,
+    n_aux_vars: usize,
+}
+
+impl Progress {
+    pub(crate) fn new(n_clauses: usize, n_aux_vars: usize) -> Self {
+        Self {
+            n_clauses,
+            n_aux_vars,
+        }
+    }
+
+    /// Returns the number of clauses produced so far.
+    #[must_use]
+    pub fn n_clauses(&self) -> usize {
+        self.n_clauses
+    }
+
+    /// Returns the number of auxiliary variables used by the clauses produced so far.
+    #[must_use]
+    pub fn n_aux_vars(&self) -> usize {
+        self.n_aux_vars
+    }
+}
@@ -0,0 +1,226 @@
+//! Reference semantics of Pseudo-Boolean constraints, computed by enumeration.
+//!
+//! The functions of this module do not rely on pblib: they evaluate the constraints directly, and check encodings by enumerating all the assignments of the input variables.
+//! They are intended to test encodings on small inputs, e.g. to cross-check new encoders against the existing ones.
+//!
+//! ```
+//! use pblib_rs::{reference, Comparator, EncoderConfig, PbConstraint, PbEncoder};
+//!
+//! let constraint = PbConstraint::new(vec![(3, 1), (2, -2), (1, 3)], Comparator::Geq, 3);
+//! assert_eq!(5, reference::models(&constraint, 3).len());
+//! let config = EncoderConfig::default().with_pb_encoder(PbEncoder::Adder);
+//! assert_eq!(Ok(()), reference::cross_check(&config, &constraint, 3));
+//! ```
+
+use crate::{assignment::Assignment, Comparator, EncoderConfig, MiniSat, PbConstraint, PB2CNF};
+
+/// The maximal number of variables the enumeration functions accept.
+pub const MAX_VARS: i32 = 20;
+
+/// Returns `true` iff the constraint is satisfied by the assignment.
+///
+/// The assignment is given as a list of DIMACS literals; a literal of the constraint whose variable does not appear in the assignment is considered as false.
+/// The weighted sum is computed without overflow.
+#[must_use]
+pub fn evaluate(constraint: &PbConstraint, assignment: &[i32]) -> bool {
+    let assignment = Assignment::new(assignment);
+    let sum = constraint
+        .terms()
+        .iter()
+        .filter(|(_, l)| assignment.satisfies(*l))
+        .map(|(w, _)| i128::from(*w))
+        .sum::<i128>();
+    let rhs = i128::from(constraint.rhs());
+    match constraint.comparator() {
+        Comparator::Leq => sum <= rhs,
+        Comparator::Geq => sum >= rhs,
+        Comparator::Eq => sum == rhs,
+        Comparator::Lt => sum < rhs,
+        Comparator::Gt => sum > rhs,
+    }
+}
+
+/// Returns the assignments of the variables `1` to `n_vars` that satisfy the constraint.
+///
+/// The assignments are given as lists of DIMACS literals, ordered by variable.
+///
+/// # Panics
+///
+/// This function panics if `n_vars` is negative or greater than [`MAX_VARS`].
+#[must_use]
+pub fn models(constraint: &PbConstraint, n_vars: i32) -> Vec<Vec<i32>> {
+    assignments(n_vars)
+        .filter(|a| evaluate(constraint, a))
+        .collect()
+}
+
+/// Checks that a set of clauses encodes a conjunction of constraints.
+///
+/// For each assignment of the variables `1` to `n_vars`, the clauses must be satisfiable under this assignment if and only if all the constraints are satisfied by it.
+/// The variables greater than `n_vars` are considered as auxiliary variables, and are existentially quantified.
+/// In case the check fails, the first assignment for which the clauses and the constraints disagree is returned.
+///
+/// # Errors
+///
+/// An assignment of the input variables is returned if the clauses do not encode the constraints.
+///
+/// # Panics
+///
+/// This function panics if `n_vars` is negative or greater than [`MAX_VARS`].
+pub fn check_encoding(
+    constraints: &[PbConstraint],
+    clauses: &[Vec<i32>],
+    n_vars: i32,
+) -> Result<(), Vec<i32>> {
+    let mut solver = MiniSat::new();
+    for clause in clauses {
+        solver.add_clause(clause);
+    }
+    for assignment in assignments(n_vars) {
+        let expected = constraints.iter().all(|c| evaluate(c, &assignment));
+        if solver.solve_with_assumptions(&assignment) != expected {
+            return Err(assignment);
+        }
+    }
+    Ok(())
+}
+
+/// Encodes a constraint with the given configuration, and checks the encoding against the reference semantics.
+///
+/// The variables of the constraint must range from `1` to `n_vars`; the auxiliary variables start at `n_vars + 1`.
+/// See [`check_encoding`] for more information.
+///
+/// # Errors
+///
+/// An assignment of the input variables is returned if the encoding is wrong.
+///
+/// # Panics
+///
+/// This function panics if `n_vars` is negative or greater than [`MAX_VARS`].
+pub fn cross_check(
+    config: &EncoderConfig,
+    constraint: &PbConstraint,
+    n_vars: i32,
+) -> Result<(), Vec<i32>> {
+    let pb2cnf = PB2CNF::with_config(*config);
+    let encoding = pb2cnf.encode_constraint(constraint, n_vars + 1);
+    check_encoding(std::slice::from_ref(constraint), encoding.clauses(), n_vars)
+}
+
+fn assignments(n_vars: i32) -> impl Iterator<Item = Vec<i32>> {
+    assert!(
+        (0..=MAX_VARS).contains(&n_vars),
+        "the number of variables must be between 0 and {MAX_VARS}"
+    );
+    (0_u32..1 << n_vars).map(move |bits| {
+        (1..=n_vars)
+            .map(|v| if bits & (1 << (v - 1)) == 0 { -v } else { v })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AmkEncoder, AmoEncoder, ConstraintFuzzer, PbEncoder};
+
+    const N_VARS: i32 = 8;
+
+    #[test]
+    fn test_evaluate() {
+        let constraint = PbConstraint::new(vec![(2, 1), (-3, 2), (1, -3)], Comparator::Lt, 0);
+        assert!(evaluate(&constraint, &[-1, 2, 3]));
+        assert!(!evaluate(&constraint, &[1, 2, -3]));
+        assert!(!evaluate(&constraint, &[]));
+        let constraint =
+            PbConstraint::new(vec![(i64::MAX, 1), (i64::MAX, 2)], Comparator::Gt, i64::MAX);
+        assert!(evaluate(&constraint, &[1, 2]));
+    }
+
+    #[test]
+    fn test_models() {
+        let constraint = PbConstraint::new(vec![(1, 1), (1, 2), (1, 3)], Comparator::Eq, 1);
+        assert_eq!(
+            vec![vec![1, -2, -3], vec![-1, 2, -3], vec![-1, -2, 3]],
+            models(&constraint, 3)
+        );
+        assert_eq!(
+            vec![Vec::<i32>::new()],
+            models(&PbConstraint::new(vec![], Comparator::Leq, 0), 0)
+        );
+    }
+
+    #[test]
+    fn test_check_encoding() {
+        let constraint = PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Leq, 1);
+        assert_eq!(
+            Ok(()),
+            check_encoding(std::slice::from_ref(&constraint), &[vec![-1, -2]], 2)
+        );
+        assert_eq!(
+            Err(vec![1, -2]),
+            check_encoding(std::slice::from_ref(&constraint), &[vec![-1]], 2)
+        );
+        assert_eq!(Err(vec![1, 2]), check_encoding(&[constraint], &[], 2));
+    }
+
+    #[test]
+    fn test_all_configurations() {
+        let pb_encoders = [
+            PbEncoder::Best,
+            PbEncoder::Bdd,
+            PbEncoder::Swc,
+            PbEncoder::SortingNetworks,
+            PbEncoder::Adder,
+            PbEncoder::BinaryMerge,
+            PbEncoder::Auto,
+        ];
+        let amk_encoders = [AmkEncoder::Best, AmkEncoder::Bdd, AmkEncoder::Card];
+        let at_most_one_encoders = [
+            AmoEncoder::Best,
+            AmoEncoder::Nested,
+            AmoEncoder::Bdd,
+            AmoEncoder::Bimander,
+            AmoEncoder::Commander,
+            AmoEncoder::KProduct,
+            AmoEncoder::Binary,
+            AmoEncoder::Pairwise,
+        ];
+        let mut fuzzer = ConstraintFuzzer::new(1, N_VARS);
+        let amo = PbConstraint::new((1..=N_VARS).map(|v| (1, v)).collect(), Comparator::Leq, 1);
+        let amk = PbConstraint::new((1..=N_VARS).map(|v| (1, v)).collect(), Comparator::Leq, 3);
+        for pb_encoder in pb_encoders {
+            for amk_encoder in amk_encoders {
+                for amo_encoder in at_most_one_encoders {
+                    let config = EncoderConfig::default()
+                        .with_pb_encoder(pb_encoder)
+                        .with_amk_encoder(amk_encoder)
+                        .with_amo_encoder(amo_encoder);
+                    let mut constraints = vec![amo.clone(), amk.clone()];
+                    for _ in 0..3 {
+                        constraints.extend(fuzzer.generate());
+                    }
+                    for constraint in &constraints {
+                        // pblib's SWC encoding grows with the weights, and its binary merge overflows on large sums
+                        let max_weight_sum = match pb_encoder {
+                            PbEncoder::Swc => 1 << 12,
+                            PbEncoder::BinaryMerge => i64::from(i32::MAX),
+                            _ => i64::MAX,
+                        };
+                        if constraint
+                            .checked_weight_sum()
+                            .is_none_or(|s| s > max_weight_sum)
+                        {
+                            continue;
+                        }
+                        assert_eq!(
+                            Ok(()),
+                            cross_check(&config, constraint, N_VARS),
+                            "wrong encoding of {constraint:?} with {config:?}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::{HashMap, HashSet};
+
+/// Removes the duplicate clauses and the clauses that are subsumed by other ones.
+///
+/// The order of the remaining clauses is preserved, as well as the order of the literals in the clauses.
+/// Returns the number of removed clauses.
+pub(crate) fn remove_redundant_clauses(clauses: &mut Vec<Vec<i32>>) -> usize {
+    let normalized = clauses
+        .iter()
+        .map(|c| {
+            let mut n = c.clone();
+            n.sort_unstable();
+            n.dedup();
+            n
+        })
+        .collect::<Vec<_>>();
+    let mut order = (0..clauses.len()).collect::<Vec<_>>();
+    order.sort_by(|&i, &j| {
+        normalized[i]
+            .len()
+            .cmp(&normalized[j].len())
+            .then(i.cmp(&j))
+    });
+    let mut kept = vec![false; clauses.len()];
+    let mut occurrences: HashMap<i32, Vec<usize>> = HashMap::new();
+    let mut has_empty_clause = false;
+    for i in order {
+        let clause = &normalized[i];
+        let subsumed = has_empty_clause
+            || clause.iter().any(|l| {
+                occurrences
+                    .get(l)
+                    .is_some_and(|occ| occ.iter().any(|&j| is_subset(&normalized[j], clause)))
+            });
+        if subsumed {
+            continue;
+        }
+        kept[i] = true;
+        match clause.first() {
+            Some(l) => occurrences.entry(*l).or_default().push(i),
+            None => has_empty_clause = true,
+        }
+    }
+    let n_clauses = clauses.len();
+    let mut kept_iter = kept.iter();
+    clauses.retain(|_| *kept_iter.next().unwrap());
+    n_clauses - clauses.len()
+}
+
+/// Removes the tautological clauses and applies unit propagation until a fixpoint is reached.
+///
+/// The clauses satisfied by the fixed literals are removed, and the falsified literals are removed from the remaining clauses.
+/// The fixed literals are then added back as unit clauses at the end of the formula, so that it remains equivalent to the initial one.
+/// In case a conflict is detected, the formula is replaced by a single empty clause.
+/// Returns the fixed literals, in the order they were discovered.
+pub(crate) fn propagate_units(clauses: &mut Vec<Vec<i32>>) -> Vec<i32> {
+    clauses.retain(|c| !is_tautology(c));
+    let mut fixed = HashSet::new();
+    let mut fixed_order = Vec::new();
+    loop {
+        let mut new_units = false;
+        let mut reduced_clauses = Vec::with_capacity(clauses.len());
+        for clause in std::mem::take(clauses) {
+            if clause.iter().any(|l| fixed.contains(l)) {
+                continue;
+            }
+            let reduced = clause
+                .into_iter()
+                .filter(|l| !fixed.contains(&-l))
+                .collect::<Vec<_>>();
+            match reduced.len() {
+                0 => {
+                    *clauses = vec![vec![]];
+                    return fixed_order;
+                }
+                1 => {
+                    if fixed.insert(reduced[0]) {
+                        fixed_order.push(reduced[0]);
+                        new_units = true;
+                    }
+                }
+                _ => reduced_clauses.push(reduced),
+            }
+        }
+        *clauses = reduced_clauses;
+        if !new_units {
+            break;
+        }
+    }
+    clauses.extend(fixed_order.iter().map(|l| vec![*l]));
+    fixed_order
+}
+
+fn is_tautology(clause: &[i32]) -> bool {
+    let mut literals = HashSet::with_capacity(clause.len());
+    clause.iter().any(|l| {
+        literals.insert(*l);
+        literals.contains(&-l)
+    })
+}
+
+fn is_subset(small: &[i32], big: &[i32]) -> bool {
+    let mut big_iter = big.iter();
+    small.iter().all(|l| big_iter.any(|b| b == l))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_duplicates() {
+        let mut clauses = vec![vec![1, 2], vec![2, 1], vec![-1, 3], vec![1, 2]];
+        assert_eq!(2, remove_redundant_clauses(&mut clauses));
+        assert_eq!(vec![vec![1, 2], vec![-1, 3]], clauses);
+    }
+
+    #[test]
+    fn test_remove_subsumed() {
+        let mut clauses = vec![vec![1, 2, 3], vec![-1, 2], vec![2, 3], vec![-1, 2, -3]];
+        assert_eq!(2, remove_redundant_clauses(&mut clauses));
+        assert_eq!(vec![vec![-1, 2], vec![2, 3]], clauses);
+    }
+
+    #[test]
+    fn test_empty_clause_subsumes_all() {
+        let mut clauses = vec![vec![1, 2], vec![], vec![-1]];
+        assert_eq!(2, remove_redundant_clauses(&mut clauses));
+        assert_eq!(vec![Vec::<i32>::new()], clauses);
+    }
+
+    #[test]
+    fn test_propagate_units() {
+        let mut clauses = vec![
+            vec![1, -1, 2],
+            vec![-1, 2],
+            vec![1],
+            vec![-2, 3, 4],
+            vec![2, 5],
+        ];
+        assert_eq!(vec![1, 2], propagate_units(&mut clauses));
+        assert_eq!(vec![vec![3, 4], vec![1], vec![2]], clauses);
+    }
+
+    #[test]
+    fn test_propagate_units_conflict() {
+        let mut clauses = vec![vec![1], vec![-1, 2], vec![-2, -1], vec![3, 4]];
+        propagate_units(&mut clauses);
+        assert_eq!(vec![Vec::<i32>::new()], clauses);
+    }
+
+    #[test]
+    fn test_propagate_no_units() {
+        let mut clauses = vec![vec![1, 2], vec![-1, 2, 1]];
+        assert!(propagate_units(&mut clauses).is_empty());
+        assert_eq!(vec![vec![1, 2]], clauses);
+    }
+
+    #[test]
+    fn test_no_redundancy() {
+        let mut clauses = vec![vec![1, 2], vec![-1, -2]];
+        assert_eq!(0, remove_redundant_clauses(&mut clauses));
+        assert_eq!(vec![vec![1, 2], vec![-1, -2]], clauses);
+    }
+}
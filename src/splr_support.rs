@@ -0,0 +1,97 @@
+use crate::{EncodingOutcome, EncodingResult};
+use splr::{Config, Solver};
+
+/// Returns the input expected by `splr` to build a solver for the clauses of an encoding.
+///
+/// The result can be given to `Solver::try_from` to build a solver with the default configuration.
+///
+/// ```
+/// use pblib_rs::{to_splr_input, PB2CNF};
+/// use splr::{Certificate, SolveIF, Solver};
+///
+/// let encoding = PB2CNF::new().encode_at_least_k(&[1, 2, 3], 2, 4);
+/// let mut solver = Solver::try_from(to_splr_input(&encoding)).unwrap();
+/// assert!(matches!(solver.solve(), Ok(Certificate::SAT(_))));
+/// ```
+#[must_use]
+pub fn to_splr_input(encoding: &EncodingResult) -> (Config, &[Vec<i32>]) {
+    (Config::default(), encoding.clauses())
+}
+
+/// Counts the models of an encoding, projected on the variables `1` to `n_vars`.
+///
+/// Two models are considered as the same if they only differ on variables greater than `n_vars`, like the auxiliary variables of the encoding.
+/// Thus, for an encoding using only auxiliary variables greater than `n_vars`, this function returns the number of assignments of the problem variables that satisfy the encoded constraint.
+/// Problem variables that do not appear in the encoding are free; they are taken into account in the count.
+///
+/// The models are enumerated by `splr`, so this function is intended for small encodings.
+///
+/// ```
+/// use pblib_rs::{count_models_projected, PB2CNF};
+///
+/// // x1 + x2 + x3 >= 2
+/// let encoding = PB2CNF::new().encode_at_least_k(&[1, 2, 3], 2, 4);
+/// assert_eq!(4, count_models_projected(&encoding, 3));
+/// ```
+///
+/// # Panics
+///
+/// This function panics if the number of free variables is too large for the count to fit in an `usize`.
+#[must_use]
+pub fn count_models_projected(encoding: &EncodingResult, n_vars: usize) -> usize {
+    let free_vars_count = |n_known_vars: usize| {
+        1_usize
+            .checked_shl(u32::try_from(n_vars.saturating_sub(n_known_vars)).unwrap())
+            .unwrap()
+    };
+    match encoding.outcome() {
+        EncodingOutcome::TriviallySat => return free_vars_count(0),
+        EncodingOutcome::TriviallyUnsat { .. } => return 0,
+        EncodingOutcome::Encoded => {}
+    }
+    let Ok(mut solver) = Solver::try_from(to_splr_input(encoding)) else {
+        return 0;
+    };
+    let mut models = solver
+        .iter()
+        .map(|mut m| {
+            m.truncate(n_vars);
+            m
+        })
+        .collect::<Vec<_>>();
+    models.sort_unstable();
+    models.dedup();
+    models.iter().map(|m| free_vars_count(m.len())).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PB2CNF;
+
+    #[test]
+    fn test_count_models_projected() {
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_geq(&[8, 4, 2, 1], &[1, 2, 3, 4], 6, 5);
+        assert_eq!(10, count_models_projected(&encoding, 4));
+        let encoding = pb2cnf.encode_leq(&[8, 4, 2, 1], &[1, 2, 3, 4], 6, 5);
+        assert_eq!(7, count_models_projected(&encoding, 4));
+    }
+
+    #[test]
+    fn test_count_models_free_vars() {
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_at_least_k(&[1, 2], 1, 6);
+        assert_eq!(3 * 8, count_models_projected(&encoding, 5));
+        let encoding = pb2cnf.encode_at_least_k(&[1, 2], 0, 3);
+        assert!(encoding.clauses().is_empty());
+        assert_eq!(4, count_models_projected(&encoding, 2));
+    }
+
+    #[test]
+    fn test_count_models_unsat() {
+        let pb2cnf = PB2CNF::new();
+        let encoding = pb2cnf.encode_both(&[8, 4, 2, 1], &[1, 2, 3, 4], 5, 7, 5);
+        assert_eq!(0, count_models_projected(&encoding, 4));
+    }
+}
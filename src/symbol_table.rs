@@ -0,0 +1,206 @@
+use crate::PbConstraint;
+use std::collections::{BTreeMap, HashMap};
+
+/// A table associating names to variables, and variables to names.
+///
+/// Symbol tables allow to write and debug models using meaningful names instead of raw variable indices.
+/// They are used by [`OpbInstance::parse_with_symbols`](crate::OpbInstance::parse_with_symbols) and [`OpbInstance::write_with_symbols`](crate::OpbInstance::write_with_symbols) to read and write OPB files with named variables, and by [`EncodingContext::named_var`](crate::EncodingContext::named_var) to allocate named problem variables.
+///
+/// A name is made of letters, digits and underscores, and cannot begin with a digit.
+///
+/// ```
+/// use pblib_rs::{PbConstraint, SymbolTable};
+///
+/// let mut symbols = SymbolTable::new();
+/// let a = symbols.var("a");
+/// let b = symbols.var("b");
+/// assert_eq!(a, symbols.var("a"));
+/// let constraint = PbConstraint::weighted([(2, a), (1, -b)]).geq(1);
+/// assert_eq!("+2 a +1 ~b >= 1", symbols.format_constraint(&constraint));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SymbolTable {
+    vars: HashMap<String, i32>,
+    names: BTreeMap<i32, String>,
+}
+
+impl SymbolTable {
+    /// Builds a new, empty table.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the variable associated with a name, associating a new variable to it if needed.
+    ///
+    /// The new variables are numbered after the greatest variable of the table.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the name is invalid.
+    pub fn var(&mut self, name: &str) -> i32 {
+        if let Some(var) = self.get(name) {
+            return var;
+        }
+        let var = self.names.last_key_value().map_or(1, |(v, _)| v + 1);
+        self.insert(name, var);
+        var
+    }
+
+    /// Associates a name with a variable.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the name is invalid, if the variable is not positive, or if the name or the variable is already associated with another variable or name.
+    pub fn insert(&mut self, name: &str, var: i32) {
+        assert!(is_valid_name(name), "invalid variable name {name:?}");
+        assert!(var > 0, "invalid variable {var}");
+        match (self.vars.get(name), self.names.get(&var)) {
+            (None, None) => {
+                self.vars.insert(name.to_string(), var);
+                self.names.insert(var, name.to_string());
+            }
+            (Some(v), _) if *v == var => {}
+            (Some(v), _) => panic!("name {name:?} is already associated with variable {v}"),
+            (None, Some(n)) => panic!("variable {var} is already named {n:?}"),
+        }
+    }
+
+    /// Returns the variable associated with a name, if any.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<i32> {
+        self.vars.get(name).copied()
+    }
+
+    /// Returns the name of a variable, if any.
+    #[must_use]
+    pub fn name(&self, var: i32) -> Option<&str> {
+        self.names.get(&var).map(String::as_str)
+    }
+
+    /// Returns a printable name for a literal.
+    ///
+    /// Negative literals are prefixed by a `~`, as in the OPB format.
+    /// Variables without names are printed as `x` followed by their index.
+    /// If this name is associated with another variable, a suffix `_1`, `_2`, ... is appended to it until it is not in the table, so that distinct variables never share a name.
+    #[must_use]
+    pub fn literal_name(&self, literal: i32) -> String {
+        let negation = if literal < 0 { "~" } else { "" };
+        let var = literal.abs();
+        if let Some(name) = self.name(var) {
+            return format!("{negation}{name}");
+        }
+        let mut name = format!("x{var}");
+        let mut suffix = 0;
+        while self.vars.contains_key(&name) {
+            suffix += 1;
+            name = format!("x{var}_{suffix}");
+        }
+        format!("{negation}{name}")
+    }
+
+    /// Formats a constraint using the names of its variables, in a syntax close to the OPB one.
+    #[must_use]
+    pub fn format_constraint(&self, constraint: &PbConstraint) -> String {
+        let mut words = constraint
+            .terms()
+            .iter()
+            .map(|(w, l)| format!("{w:+} {}", self.literal_name(*l)))
+            .collect::<Vec<_>>();
+        words.push(format!("{} {}", constraint.comparator(), constraint.rhs()));
+        words.join(" ")
+    }
+
+    /// Returns the couples composed of a name and a variable, in increasing order of the variables.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, i32)> + '_ {
+        self.names.iter().map(|(v, n)| (n.as_str(), *v))
+    }
+
+    /// Returns the number of names in the table.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns `true` iff the table contains no names.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_var() {
+        let mut symbols = SymbolTable::new();
+        assert!(symbols.is_empty());
+        assert_eq!(1, symbols.var("a"));
+        symbols.insert("c", 5);
+        assert_eq!(6, symbols.var("b"));
+        assert_eq!(1, symbols.var("a"));
+        symbols.insert("c", 5);
+        assert_eq!(Some(5), symbols.get("c"));
+        assert_eq!(None, symbols.get("d"));
+        assert_eq!(Some("b"), symbols.name(6));
+        assert_eq!(None, symbols.name(2));
+        assert_eq!(
+            vec![("a", 1), ("c", 5), ("b", 6)],
+            symbols.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(3, symbols.len());
+    }
+
+    #[test]
+    fn test_literal_name() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert("_a1", 1);
+        assert_eq!("_a1", symbols.literal_name(1));
+        assert_eq!("~_a1", symbols.literal_name(-1));
+        assert_eq!("~x2", symbols.literal_name(-2));
+        symbols.insert("x3", 4);
+        symbols.insert("x3_1", 5);
+        assert_eq!("x3", symbols.literal_name(4));
+        assert_eq!("~x3_2", symbols.literal_name(-3));
+    }
+
+    #[test]
+    fn test_valid_names() {
+        assert!(is_valid_name("x_1"));
+        assert!(is_valid_name("_"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("1x"));
+        assert!(!is_valid_name("a b"));
+        assert!(!is_valid_name("~a"));
+    }
+
+    #[test]
+    #[should_panic(expected = "already named")]
+    fn test_insert_named_var() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert("a", 1);
+        symbols.insert("b", 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "already associated")]
+    fn test_insert_bound_name() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert("a", 1);
+        symbols.insert("a", 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid variable name")]
+    fn test_insert_invalid_name() {
+        SymbolTable::new().var("a-b");
+    }
+}
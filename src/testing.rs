@@ -0,0 +1,504 @@
+//! Utilities to test encodings on small inputs.
+//!
+//! Unlike the [`reference`](crate::reference) module, which checks that encodings are correct, this module gives tools to study their properties, like their models or the strength of unit propagation.
+//!
+//! ```
+//! use pblib_rs::{testing, EncoderConfig, PbConstraint, PbEncoder};
+//!
+//! let constraint = PbConstraint::weighted([(3, 1), (2, 2), (2, 3), (1, 4)]).geq(4);
+//! let config = EncoderConfig::default().with_pb_encoder(PbEncoder::Bdd);
+//! assert_eq!(Ok(()), testing::check_gac_with_config(&config, &constraint, 4));
+//! ```
+
+use crate::{
+    encoding_result, reference, simplify, EncoderConfig, EncodingResult, MiniSat, PbConstraint,
+    PbEncoder, PB2CNF,
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+/// A partial assignment for which unit propagation on an encoding is weaker than generalized arc consistency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GacViolation {
+    /// The partial assignment cannot be extended to a model of the constraints, but unit propagation does not detect a conflict.
+    MissedConflict(Vec<i32>),
+    /// The constraints imply a literal under the partial assignment, but unit propagation does not fix it.
+    MissedImplication {
+        /// The partial assignment, as a list of DIMACS literals.
+        assignment: Vec<i32>,
+        /// The implied literal.
+        literal: i32,
+    },
+}
+
+/// Checks that unit propagation on a set of clauses maintains generalized arc consistency for a conjunction of constraints.
+///
+/// The variables of the constraints must range from `1` to `n_vars`; the other variables of the clauses are considered as auxiliary variables.
+/// For each partial assignment of the input variables, unit propagation on the clauses must detect a conflict if the assignment cannot be extended to a model of the constraints, and must fix each input literal that is implied by the constraints under this assignment.
+/// The check enumerates the `3^n_vars` partial assignments, so it is only intended for small constraints.
+/// The clauses are not checked to encode the constraints; see [`reference::check_encoding`] for this purpose.
+///
+/// # Errors
+///
+/// The first partial assignment for which unit propagation is too weak is returned.
+///
+/// # Panics
+///
+/// This function panics if `n_vars` is negative or greater than [`reference::MAX_VARS`].
+pub fn check_gac(
+    constraints: &[PbConstraint],
+    clauses: &[Vec<i32>],
+    n_vars: i32,
+) -> Result<(), GacViolation> {
+    assert!(
+        (0..=reference::MAX_VARS).contains(&n_vars),
+        "the number of variables must be between 0 and {}",
+        reference::MAX_VARS
+    );
+    let n_partial_assignments = 3_u64.pow(n_vars.unsigned_abs());
+    for index in 0..n_partial_assignments {
+        let assignment = partial_assignment(index, n_vars);
+        let models = completions(&assignment, n_vars)
+            .filter(|a| constraints.iter().all(|c| reference::evaluate(c, a)))
+            .collect::<Vec<_>>();
+        let propagated = propagate(clauses, &assignment);
+        let Some(propagated) = propagated else {
+            continue;
+        };
+        if models.is_empty() {
+            return Err(GacViolation::MissedConflict(assignment));
+        }
+        for literal in models[0].iter().filter(|l| !assignment.contains(l)) {
+            if models.iter().all(|m| m.contains(literal)) && !propagated.contains(literal) {
+                return Err(GacViolation::MissedImplication {
+                    assignment,
+                    literal: *literal,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Encodes a constraint with the given configuration, and checks that unit propagation on the encoding maintains generalized arc consistency.
+///
+/// The variables of the constraint must range from `1` to `n_vars`; the auxiliary variables start at `n_vars + 1`.
+/// See [`check_gac`] for more information.
+///
+/// # Errors
+///
+/// The first partial assignment for which unit propagation is too weak is returned.
+///
+/// # Panics
+///
+/// This function panics if `n_vars` is negative or greater than [`reference::MAX_VARS`].
+pub fn check_gac_with_config(
+    config: &EncoderConfig,
+    constraint: &PbConstraint,
+    n_vars: i32,
+) -> Result<(), GacViolation> {
+    let pb2cnf = PB2CNF::with_config(*config);
+    let encoding = pb2cnf.encode_constraint(constraint, n_vars + 1);
+    check_gac(std::slice::from_ref(constraint), encoding.clauses(), n_vars)
+}
+
+/// Checks whether each Pseudo-Boolean encoder maintains generalized arc consistency on a constraint.
+///
+/// For each value of [`PbEncoder`], the constraint is encoded with the default configuration using this encoder, and the result of [`check_gac_with_config`] is returned.
+/// Note that pblib may use other encoders for some constraints (e.g. cardinality constraints), whatever the Pseudo-Boolean encoder.
+///
+/// ```
+/// use pblib_rs::{testing, PbConstraint};
+///
+/// let constraint = PbConstraint::weighted([(3, 1), (2, 2), (2, 3), (1, 4)]).leq(4);
+/// for (encoder, result) in testing::gac_report(&constraint, 4) {
+///     println!("{encoder:?}: {}", if result.is_ok() { "GAC" } else { "not GAC" });
+/// }
+/// ```
+///
+/// # Panics
+///
+/// This function panics if `n_vars` is negative or greater than [`reference::MAX_VARS`].
+#[must_use]
+pub fn gac_report(
+    constraint: &PbConstraint,
+    n_vars: i32,
+) -> Vec<(PbEncoder, Result<(), GacViolation>)> {
+    [
+        PbEncoder::Best,
+        PbEncoder::Bdd,
+        PbEncoder::Swc,
+        PbEncoder::SortingNetworks,
+        PbEncoder::Adder,
+        PbEncoder::BinaryMerge,
+        PbEncoder::Auto,
+    ]
+    .into_iter()
+    .map(|encoder| {
+        let config = EncoderConfig::default().with_pb_encoder(encoder);
+        (encoder, check_gac_with_config(&config, constraint, n_vars))
+    })
+    .collect()
+}
+
+/// Returns the models of a set of clauses, projected on the variables `1` to `n_original_vars`.
+///
+/// The clauses are given to [`MiniSat`], and each projected model is forbidden by a blocking clause once it is found, until the clauses become unsatisfiable.
+/// The variables greater than `n_original_vars` are considered as auxiliary variables, so each projected model is returned once, whatever the number of its extensions to these variables.
+/// The models are given as lists of DIMACS literals ordered by variable, and are sorted in lexicographic order.
+///
+/// Since the number of models may be exponential in the number of variables, this function is only intended for small instances.
+///
+/// ```
+/// use pblib_rs::{testing, PB2CNF};
+///
+/// let pb2cnf = PB2CNF::new();
+/// let encoding = pb2cnf.encode_at_most_k(&[1, 2, 3], 1, 4);
+/// assert_eq!(4, testing::enumerate_models(encoding.clauses(), 3).len());
+/// ```
+#[must_use]
+pub fn enumerate_models(clauses: &[Vec<i32>], n_original_vars: i32) -> Vec<Vec<i32>> {
+    let mut solver = MiniSat::new();
+    for clause in clauses {
+        solver.add_clause(clause);
+    }
+    let mut models = Vec::new();
+    while solver.solve() {
+        let model = solver.model().unwrap_or_default();
+        // the variables unknown to the solver are free, and are first set to false
+        let projected = (1..=n_original_vars)
+            .map(|v| if model.contains(&v) { v } else { -v })
+            .collect::<Vec<_>>();
+        let blocking_clause = projected.iter().map(|l| -l).collect::<Vec<_>>();
+        models.push(projected);
+        if blocking_clause.is_empty() {
+            break;
+        }
+        solver.add_clause(&blocking_clause);
+    }
+    models.sort_unstable();
+    models
+}
+
+/// Returns `true` iff two encodings have the same models once projected on the variables `1` to `n_vars`.
+///
+/// The variables greater than `n_vars` are considered as auxiliary variables, so the encodings may use different auxiliary variables, or even share their indices.
+/// This is useful to compare the encodings of a constraint given by different configurations, or to validate a custom encoder against pblib.
+/// The projected models are computed by [`enumerate_models`], so this function is only intended for small instances.
+///
+/// ```
+/// use pblib_rs::{testing, AmoEncoder, EncoderConfig, PB2CNF};
+///
+/// let nested = PB2CNF::with_config(EncoderConfig::default().with_amo_encoder(AmoEncoder::Nested));
+/// let bimander = PB2CNF::with_config(EncoderConfig::default().with_amo_encoder(AmoEncoder::Bimander));
+/// let a = nested.encode_at_most_k(&[1, 2, 3, 4, 5], 1, 6);
+/// let b = bimander.encode_at_most_k(&[1, 2, 3, 4, 5], 1, 6);
+/// assert!(testing::equivalent_projected(&a, &b, 5));
+/// let c = bimander.encode_at_most_k(&[1, 2, 3, 4, 5], 2, 6);
+/// assert!(!testing::equivalent_projected(&a, &c, 5));
+/// ```
+#[must_use]
+pub fn equivalent_projected(a: &EncodingResult, b: &EncodingResult, n_vars: i32) -> bool {
+    enumerate_models(a.clauses(), n_vars) == enumerate_models(b.clauses(), n_vars)
+}
+
+/// Returns a canonical serialization of an encoding, suitable for snapshot tests.
+///
+/// The auxiliary variables, that is the ones from [`first_aux_var`](EncodingResult::first_aux_var), are renumbered from it by order of first occurrence in the clauses, so that unused indices are skipped.
+/// The literals of each clause are then sorted by variable, and the clauses are sorted by length, then in lexicographic order.
+/// The result is written in the DIMACS format, with one clause per line, preceded by a comment giving the first auxiliary variable.
+///
+/// Thus, encoders producing the same clauses in the same order have the same snapshots, whatever the indices of their auxiliary variables.
+/// Snapshots can be stored with the tests and checked with [`assert_snapshot`], so that changes in pblib or in the configurations appear as explicit diffs.
+///
+/// ```
+/// use pblib_rs::{testing, PB2CNF};
+///
+/// let pb2cnf = PB2CNF::new();
+/// let encoding = pb2cnf.encode_at_most_k(&[1, 2], 1, 10);
+/// assert_eq!("c first_aux_var 10\np cnf 9 1\n-1 -2 0\n", testing::snapshot(&encoding));
+/// ```
+#[must_use]
+pub fn snapshot(encoding: &EncodingResult) -> String {
+    let first_aux_var = encoding.first_aux_var();
+    let mut aux_vars = HashMap::new();
+    let mut next_aux_var = first_aux_var;
+    let mut clauses = encoding
+        .clauses()
+        .iter()
+        .map(|clause| {
+            clause
+                .iter()
+                .map(|l| {
+                    if l.abs() < first_aux_var {
+                        return *l;
+                    }
+                    let v = *aux_vars.entry(l.abs()).or_insert_with(|| {
+                        next_aux_var += 1;
+                        next_aux_var - 1
+                    });
+                    if *l > 0 {
+                        v
+                    } else {
+                        -v
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    encoding_result::sort_canonically(&mut clauses);
+    let n_vars = usize::try_from(first_aux_var - 1).unwrap_or_default() + aux_vars.len();
+    let mut output = format!(
+        "c first_aux_var {first_aux_var}\np cnf {n_vars} {}\n",
+        clauses.len()
+    );
+    for clause in clauses {
+        for l in clause {
+            output.push_str(&l.to_string());
+            output.push(' ');
+        }
+        output.push_str("0\n");
+    }
+    output
+}
+
+/// Returns the differences between two snapshots, as the lines that only appear in the expected one, prefixed by `- `, and the lines that only appear in the actual one, prefixed by `+ `.
+///
+/// Since the clauses of the [snapshots](snapshot) are sorted, the lines are compared as multisets rather than as sequences.
+/// An empty vector is returned iff the snapshots have the same lines.
+#[must_use]
+pub fn diff_snapshots(expected: &str, actual: &str) -> Vec<String> {
+    let mut counts = BTreeMap::new();
+    for line in expected.lines() {
+        *counts.entry(line).or_insert(0) -= 1;
+    }
+    for line in actual.lines() {
+        *counts.entry(line).or_insert(0) += 1;
+    }
+    let removed = expected
+        .lines()
+        .filter(|l| counts.get(l).is_some_and(|c| *c < 0))
+        .map(|l| format!("- {l}"));
+    let added = actual
+        .lines()
+        .filter(|l| counts.get(l).is_some_and(|c| *c > 0))
+        .map(|l| format!("+ {l}"));
+    let mut diff = removed.chain(added).collect::<Vec<_>>();
+    diff.dedup();
+    diff
+}
+
+/// Checks that the [snapshot] of an encoding matches the one stored in a file.
+///
+/// If the file does not exist, or if the `PBLIB_UPDATE_SNAPSHOTS` environment variable is set, the snapshot is written to the file instead, and the check succeeds.
+/// This way, new snapshots are created by running the tests once, and the existing ones are updated after reviewing the diffs.
+///
+/// ```no_run
+/// use pblib_rs::{testing, PbConstraint, PB2CNF};
+///
+/// let pb2cnf = PB2CNF::new();
+/// let constraint = PbConstraint::weighted([(3, 1), (2, 2), (1, 3)]).leq(3);
+/// testing::assert_snapshot(&pb2cnf.encode_constraint(&constraint, 4), "tests/snapshots/leq.cnf");
+/// ```
+///
+/// # Panics
+///
+/// This function panics with the [diff](diff_snapshots) of the snapshots if they differ, or if the file cannot be read or written.
+pub fn assert_snapshot(encoding: &EncodingResult, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let actual = snapshot(encoding);
+    if std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some() || !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(path).unwrap();
+    let diff = diff_snapshots(&expected, &actual);
+    assert!(
+        diff.is_empty(),
+        "the snapshot {} differs (set {UPDATE_SNAPSHOTS_VAR} to update it):\n{}",
+        path.display(),
+        diff.join("\n")
+    );
+}
+
+/// The environment variable asking [`assert_snapshot`] to update the snapshots.
+const UPDATE_SNAPSHOTS_VAR: &str = "PBLIB_UPDATE_SNAPSHOTS";
+
+/// Returns the partial assignment of the variables `1` to `n_vars` whose index is given, reading it in base 3 (0 for unassigned, 1 for false, 2 for true).
+fn partial_assignment(mut index: u64, n_vars: i32) -> Vec<i32> {
+    let mut assignment = Vec::new();
+    for v in 1..=n_vars {
+        match index % 3 {
+            1 => assignment.push(-v),
+            2 => assignment.push(v),
+            _ => {}
+        }
+        index /= 3;
+    }
+    assignment
+}
+
+/// Returns the full assignments of the variables `1` to `n_vars` extending a partial one.
+fn completions(partial: &[i32], n_vars: i32) -> impl Iterator<Item = Vec<i32>> + '_ {
+    let free = (1..=n_vars)
+        .filter(|v| !partial.contains(v) && !partial.contains(&-v))
+        .collect::<Vec<_>>();
+    (0_u32..1 << free.len()).map(move |bits| {
+        let mut assignment = partial.to_vec();
+        for (i, v) in free.iter().enumerate() {
+            assignment.push(if bits & (1 << i) == 0 { -v } else { *v });
+        }
+        assignment
+    })
+}
+
+/// Applies unit propagation on the clauses under the assumptions, and returns the fixed literals, or `None` in case of conflict.
+fn propagate(clauses: &[Vec<i32>], assumptions: &[i32]) -> Option<HashSet<i32>> {
+    let mut formula = clauses.to_vec();
+    formula.extend(assumptions.iter().map(|l| vec![*l]));
+    let fixed = simplify::propagate_units(&mut formula);
+    if formula.iter().any(Vec::is_empty) {
+        return None;
+    }
+    Some(fixed.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AmoEncoder;
+
+    #[test]
+    fn test_check_gac() {
+        let constraint = PbConstraint::cardinality([1, 2]).geq(1);
+        assert_eq!(
+            Ok(()),
+            check_gac(std::slice::from_ref(&constraint), &[vec![1, 2]], 2)
+        );
+        assert_eq!(
+            Err(GacViolation::MissedImplication {
+                assignment: vec![-1],
+                literal: 2
+            }),
+            check_gac(&[constraint], &[vec![1, 2, 3], vec![1, 2, -3]], 2)
+        );
+        let constraint = PbConstraint::cardinality([1, 2]).geq(3);
+        assert_eq!(
+            Err(GacViolation::MissedConflict(vec![])),
+            check_gac(&[constraint], &[vec![1, 3], vec![2, -3]], 2)
+        );
+    }
+
+    #[test]
+    fn test_check_gac_with_config() {
+        let constraint = PbConstraint::weighted([(3, 1), (2, 2), (2, 3), (1, 4)]).geq(4);
+        let config = EncoderConfig::default().with_pb_encoder(PbEncoder::Bdd);
+        assert_eq!(Ok(()), check_gac_with_config(&config, &constraint, 4));
+        let amo = PbConstraint::cardinality(1..=5).leq(1);
+        let config = EncoderConfig::default().with_amo_encoder(AmoEncoder::Pairwise);
+        assert_eq!(Ok(()), check_gac_with_config(&config, &amo, 5));
+    }
+
+    #[test]
+    fn test_enumerate_models() {
+        let constraint = PbConstraint::weighted([(3, 1), (-2, 2), (1, -3), (2, 4)]).geq(2);
+        let encoding = PB2CNF::new().encode_constraint(&constraint, 5);
+        let mut expected = reference::models(&constraint, 4);
+        expected.sort_unstable();
+        assert_eq!(expected, enumerate_models(encoding.clauses(), 4));
+    }
+
+    #[test]
+    fn test_enumerate_models_free_vars() {
+        assert_eq!(
+            vec![
+                vec![-1, -2, 3],
+                vec![-1, 2, 3],
+                vec![1, -2, 3],
+                vec![1, 2, 3]
+            ],
+            enumerate_models(&[vec![3]], 3)
+        );
+        assert_eq!(vec![Vec::<i32>::new()], enumerate_models(&[vec![1]], 0));
+        assert!(enumerate_models(&[vec![1], vec![-1]], 1).is_empty());
+    }
+
+    #[test]
+    fn test_equivalent_projected() {
+        let constraint = PbConstraint::weighted([(3, 1), (-2, 2), (1, -3), (2, 4)]).geq(2);
+        let reference = PB2CNF::new().encode_constraint(&constraint, 5);
+        for encoder in [PbEncoder::Bdd, PbEncoder::SortingNetworks, PbEncoder::Adder] {
+            let pb2cnf = PB2CNF::with_config(EncoderConfig::default().with_pb_encoder(encoder));
+            let encoding = pb2cnf.encode_constraint(&constraint, 5);
+            assert!(equivalent_projected(&reference, &encoding, 4));
+            let other = PbConstraint::weighted([(3, 1), (-2, 2), (1, -3), (2, 4)]).geq(3);
+            let other = pb2cnf.encode_constraint(&other, 5);
+            assert!(!equivalent_projected(&reference, &other, 4));
+        }
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut encoding = PB2CNF::new().encode_at_most_k(&[1, 2, 3, 4, 5], 2, 6);
+        let expected = snapshot(&encoding);
+        assert!(expected.starts_with("c first_aux_var 6\n"));
+        // reverse the numbering of the auxiliary variables
+        let last_aux_var = encoding.next_free_var_id() - 1;
+        encoding.map_literals(|l| {
+            if l.abs() > 5 {
+                l.signum() * (6 + last_aux_var - l.abs())
+            } else {
+                l
+            }
+        });
+        assert_eq!(expected, snapshot(&encoding));
+        assert_eq!(
+            "c first_aux_var 3\np cnf 2 1\n-1 -2 0\n",
+            snapshot(&PB2CNF::new().encode_at_most_k(&[1, 2], 1, 3))
+        );
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let expected = "p cnf 3 3\n-1 -2 0\n-1 3 0\n-1 3 0\n";
+        let actual = "p cnf 3 2\n-1 -2 0\n-1 3 0\n-2 3 0\n";
+        assert!(diff_snapshots(expected, expected).is_empty());
+        assert_eq!(
+            vec!["- p cnf 3 3", "- -1 3 0", "+ p cnf 3 2", "+ -2 3 0"],
+            diff_snapshots(expected, actual)
+        );
+    }
+
+    #[test]
+    fn test_assert_snapshot() {
+        let dir = std::env::temp_dir().join(format!("pblib-rs-snapshots-{}", std::process::id()));
+        let path = dir.join("amk.cnf");
+        let encoding = PB2CNF::new().encode_at_most_k(&[1, 2, 3], 1, 4);
+        assert_snapshot(&encoding, &path);
+        assert_eq!(snapshot(&encoding), fs::read_to_string(&path).unwrap());
+        assert_snapshot(&encoding, &path);
+        let other = PB2CNF::new().encode_at_most_k(&[1, 2, 3], 2, 4);
+        let result = std::panic::catch_unwind(|| assert_snapshot(&other, &path));
+        fs::remove_dir_all(dir).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_gac_report() {
+        let constraint = PbConstraint::weighted([(5, 1), (4, 2), (3, 3), (2, 4)]).leq(7);
+        let report = gac_report(&constraint, 4);
+        assert_eq!(7, report.len());
+        assert!(report
+            .iter()
+            .any(|(e, r)| *e == PbEncoder::Bdd && r.is_ok()));
+        assert!(report
+            .iter()
+            .any(|(e, r)| *e == PbEncoder::Adder && r.is_err()));
+    }
+}
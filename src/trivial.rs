@@ -0,0 +1,172 @@
+/// The status of a bound of a constraint, given the minimal and maximal values of its weighted sum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BoundStatus {
+    /// The bound holds whatever the values of the literals.
+    Satisfied,
+    /// The bound holds only if each term takes its extreme value.
+    Forced,
+    /// The bound never holds.
+    Falsified,
+    /// The bound depends on the values of the literals in a nontrivial way.
+    Other,
+}
+
+/// Encodes a constraint whose bounds make it trivial, without calling pblib.
+///
+/// The constraint states that the weighted sum of the terms lies between `geq` and `leq`; a missing bound is not checked.
+/// Each bound is compared with the minimal and maximal values of the weighted sum:
+/// a bound that always holds produces no clause, a bound that never holds produces the empty clause, and a bound that can only be reached when each term takes its extreme value produces one unit clause per term.
+/// In any case, no auxiliary variable is used.
+///
+/// The clauses are given to `add_clause`, which may return `false` to stop the encoding.
+/// A single bound that never holds, or a lower bound greater than the upper bound, is enough to make the constraint trivial.
+/// This function returns `false`, without producing any clause, if one of the bounds is not trivial (or if the extreme values of the sum do not fit in an `i64`).
+pub(crate) fn encode_trivial<I>(
+    terms: I,
+    geq: Option<i64>,
+    leq: Option<i64>,
+    mut add_clause: impl FnMut(&[i32]) -> bool,
+) -> bool
+where
+    I: Iterator<Item = (i64, i32)> + Clone,
+{
+    let Some((min_sum, max_sum)) = terms
+        .clone()
+        .try_fold((0_i64, 0_i64), |(min, max), (w, _)| {
+            if w < 0 {
+                Some((min.checked_add(w)?, max))
+            } else {
+                Some((min, max.checked_add(w)?))
+            }
+        })
+    else {
+        return false;
+    };
+    let geq_status = geq.map_or(BoundStatus::Satisfied, |geq| {
+        if geq <= min_sum {
+            BoundStatus::Satisfied
+        } else if geq > max_sum {
+            BoundStatus::Falsified
+        } else if geq == max_sum {
+            BoundStatus::Forced
+        } else {
+            BoundStatus::Other
+        }
+    });
+    let leq_status = leq.map_or(BoundStatus::Satisfied, |leq| {
+        if leq >= max_sum {
+            BoundStatus::Satisfied
+        } else if leq < min_sum {
+            BoundStatus::Falsified
+        } else if leq == min_sum {
+            BoundStatus::Forced
+        } else {
+            BoundStatus::Other
+        }
+    });
+    let empty_range = geq.zip(leq).is_some_and(|(geq, leq)| geq > leq);
+    if empty_range || geq_status == BoundStatus::Falsified || leq_status == BoundStatus::Falsified {
+        add_clause(&[]);
+        return true;
+    }
+    if geq_status == BoundStatus::Other || leq_status == BoundStatus::Other {
+        return false;
+    }
+    // the sum is maximal (resp. minimal) when the literals with a positive (resp. negative) weight are satisfied
+    let forced = terms.filter(|(w, _)| *w != 0).flat_map(|(w, l)| {
+        let maximal = if w > 0 { l } else { -l };
+        let geq_unit = (geq_status == BoundStatus::Forced).then_some(maximal);
+        let leq_unit = (leq_status == BoundStatus::Forced).then_some(-maximal);
+        geq_unit.into_iter().chain(leq_unit)
+    });
+    for unit in forced {
+        if !add_clause(&[unit]) {
+            break;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trivial_clauses(
+        terms: &[(i64, i32)],
+        geq: Option<i64>,
+        leq: Option<i64>,
+    ) -> Option<Vec<Vec<i32>>> {
+        let mut clauses = Vec::new();
+        encode_trivial(terms.iter().copied(), geq, leq, |c| {
+            clauses.push(c.to_vec());
+            true
+        })
+        .then_some(clauses)
+    }
+
+    #[test]
+    fn test_cardinality() {
+        let terms = [(1, 1), (1, -2), (1, 3)];
+        assert_eq!(Some(vec![]), trivial_clauses(&terms, Some(0), None));
+        assert_eq!(Some(vec![]), trivial_clauses(&terms, None, Some(3)));
+        assert_eq!(
+            Some(vec![vec![-1], vec![2], vec![-3]]),
+            trivial_clauses(&terms, None, Some(0))
+        );
+        assert_eq!(
+            Some(vec![vec![1], vec![-2], vec![3]]),
+            trivial_clauses(&terms, Some(3), None)
+        );
+        assert_eq!(Some(vec![vec![]]), trivial_clauses(&terms, Some(4), None));
+        assert_eq!(Some(vec![vec![]]), trivial_clauses(&terms, None, Some(-1)));
+        assert_eq!(None, trivial_clauses(&terms, Some(1), None));
+        assert_eq!(None, trivial_clauses(&terms, Some(0), Some(2)));
+    }
+
+    #[test]
+    fn test_negative_weights() {
+        // the sum of 2*x1 - 3*x2 ranges from -3 to 2
+        let terms = [(2, 1), (-3, 2), (0, 3)];
+        assert_eq!(Some(vec![]), trivial_clauses(&terms, Some(-3), Some(2)));
+        assert_eq!(
+            Some(vec![vec![-1], vec![2]]),
+            trivial_clauses(&terms, None, Some(-3))
+        );
+        assert_eq!(
+            Some(vec![vec![1], vec![-2]]),
+            trivial_clauses(&terms, Some(2), Some(5))
+        );
+        assert_eq!(
+            Some(vec![vec![]]),
+            trivial_clauses(&terms, Some(0), Some(-4))
+        );
+        assert_eq!(None, trivial_clauses(&terms, Some(-2), None));
+        assert_eq!(
+            Some(vec![vec![]]),
+            trivial_clauses(&terms, Some(1), Some(0))
+        );
+    }
+
+    #[test]
+    fn test_stop() {
+        let mut n_clauses = 0;
+        assert!(encode_trivial(
+            [(1, 1), (1, 2)].into_iter(),
+            Some(2),
+            None,
+            |_| {
+                n_clauses += 1;
+                false
+            }
+        ));
+        assert_eq!(1, n_clauses);
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(
+            None,
+            trivial_clauses(&[(i64::MAX, 1), (1, 2)], None, Some(0))
+        );
+    }
+}
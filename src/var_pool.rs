@@ -0,0 +1,226 @@
+use crate::{ClauseSink, EncodingResult, PbConstraint, PB2CNF};
+use std::ops::Range;
+
+/// A pool of variables, allocating both the problem variables and the auxiliary variables of the encodings.
+///
+/// The variables are allocated in increasing order, starting at 1.
+/// Problem variables are allocated with [`new_var`](Self::new_var) or [`new_vars`](Self::new_vars), and the auxiliary variables of an encoding are reserved by giving the pool's [`first_aux_var`](Self::first_aux_var) to the encoding function, then [`commit_aux_vars`](Self::commit_aux_vars) with the returned next free variable id.
+/// The encoding functions of the pool (e.g. [`encode_constraint`](Self::encode_constraint)) do both steps at once.
+/// Problem and auxiliary variables can then be freely interleaved, and [`n_vars`](Self::n_vars) always gives the number of variables to write in a DIMACS header.
+///
+/// ```
+/// use pblib_rs::{CnfFormula, PbConstraint, VarPool, PB2CNF};
+///
+/// let pb2cnf = PB2CNF::new();
+/// let mut pool = VarPool::new();
+/// let mut formula = CnfFormula::new();
+/// let x = pool.new_vars(3);
+/// pool.encode_constraint_into(&pb2cnf, &PbConstraint::cardinality(x.clone()).leq(1), &mut formula);
+/// // a variable allocated after an encoding does not collide with its auxiliary variables
+/// let y = pool.new_var();
+/// assert!(!pool.is_aux_var(y));
+/// pool.encode_constraint_into(&pb2cnf, &PbConstraint::cardinality([x[0], y]).geq(1), &mut formula);
+/// formula.declare_vars(pool.n_vars());
+/// let mut output = Vec::new();
+/// formula.write_dimacs(&mut output).unwrap();
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct VarPool {
+    n_vars: i32,
+    aux_vars: Vec<Range<i32>>,
+}
+
+impl VarPool {
+    /// Builds a new pool, in which no variable is allocated.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates a new problem variable.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn new_var(&mut self) -> i32 {
+        self.n_vars = self
+            .n_vars
+            .checked_add(1)
+            .expect("too many variables for an i32");
+        self.n_vars
+    }
+
+    /// Allocates `n` new problem variables.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn new_vars(&mut self, n: usize) -> Vec<i32> {
+        (0..n).map(|_| self.new_var()).collect()
+    }
+
+    /// Returns the first auxiliary variable to give to an encoding function.
+    ///
+    /// The auxiliary variables used by the encoding must then be reserved with [`commit_aux_vars`](Self::commit_aux_vars).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    #[must_use]
+    pub fn first_aux_var(&self) -> i32 {
+        self.n_vars
+            .checked_add(1)
+            .expect("too many variables for an i32")
+    }
+
+    /// Reserves the auxiliary variables of an encoding, given the next free variable id returned by the encoding function.
+    ///
+    /// The variables from [`first_aux_var`](Self::first_aux_var) (included) to `next_free_var_id` (excluded) are marked as auxiliary ones.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `next_free_var_id` is lower than [`first_aux_var`](Self::first_aux_var).
+    pub fn commit_aux_vars(&mut self, next_free_var_id: i32) {
+        let first_aux_var = self.first_aux_var();
+        assert!(
+            next_free_var_id >= first_aux_var,
+            "the next free variable id {next_free_var_id} is lower than the first auxiliary variable {first_aux_var}"
+        );
+        if next_free_var_id > first_aux_var {
+            match self.aux_vars.last_mut() {
+                Some(last) if last.end == first_aux_var => last.end = next_free_var_id,
+                _ => self.aux_vars.push(first_aux_var..next_free_var_id),
+            }
+            self.n_vars = next_free_var_id - 1;
+        }
+    }
+
+    /// Allocates a single auxiliary variable.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn new_aux_var(&mut self) -> i32 {
+        let var = self.first_aux_var();
+        self.commit_aux_vars(var + 1);
+        var
+    }
+
+    /// Encodes a constraint, reserving its auxiliary variables.
+    ///
+    /// See [`PB2CNF::encode_constraint`] for more information.
+    pub fn encode_constraint(
+        &mut self,
+        pb2cnf: &PB2CNF,
+        constraint: &PbConstraint,
+    ) -> EncodingResult {
+        let encoding = pb2cnf.encode_constraint(constraint, self.first_aux_var());
+        self.commit_aux_vars(encoding.next_free_var_id());
+        encoding
+    }
+
+    /// Encodes a constraint, sending the clauses to a [`ClauseSink`] and reserving the auxiliary variables.
+    ///
+    /// See [`PB2CNF::encode_constraint_into`] for more information.
+    pub fn encode_constraint_into(
+        &mut self,
+        pb2cnf: &PB2CNF,
+        constraint: &PbConstraint,
+        sink: &mut impl ClauseSink,
+    ) {
+        let next_free_var_id =
+            pb2cnf.encode_constraint_into(constraint, self.first_aux_var(), sink);
+        self.commit_aux_vars(next_free_var_id);
+    }
+
+    /// Returns the number of allocated variables, including the auxiliary ones.
+    ///
+    /// This is the number of variables to write in the header of a DIMACS file.
+    #[must_use]
+    pub fn n_vars(&self) -> usize {
+        usize::try_from(self.n_vars).unwrap_or_default()
+    }
+
+    /// Returns the number of allocated auxiliary variables.
+    #[must_use]
+    pub fn n_aux_vars(&self) -> usize {
+        self.aux_vars.iter().map(ExactSizeIterator::len).sum()
+    }
+
+    /// Returns the number of allocated problem variables.
+    #[must_use]
+    pub fn n_problem_vars(&self) -> usize {
+        self.n_vars() - self.n_aux_vars()
+    }
+
+    /// Returns `true` iff the variable is an allocated problem variable.
+    #[must_use]
+    pub fn is_problem_var(&self, var: i32) -> bool {
+        (1..=self.n_vars).contains(&var) && !self.is_aux_var(var)
+    }
+
+    /// Returns `true` iff the variable is a reserved auxiliary variable.
+    #[must_use]
+    pub fn is_aux_var(&self, var: i32) -> bool {
+        let i = self.aux_vars.partition_point(|r| r.end <= var);
+        self.aux_vars.get(i).is_some_and(|r| r.contains(&var))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocation() {
+        let mut pool = VarPool::new();
+        assert_eq!(0, pool.n_vars());
+        assert_eq!(vec![1, 2], pool.new_vars(2));
+        assert_eq!(3, pool.first_aux_var());
+        pool.commit_aux_vars(6);
+        pool.commit_aux_vars(6);
+        assert_eq!(6, pool.new_var());
+        pool.commit_aux_vars(9);
+        assert_eq!(8, pool.n_vars());
+        assert_eq!(5, pool.n_aux_vars());
+        assert_eq!(3, pool.n_problem_vars());
+        let problem_vars = (0..=9)
+            .filter(|v| pool.is_problem_var(*v))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![1, 2, 6], problem_vars);
+        let aux_vars = (0..=9).filter(|v| pool.is_aux_var(*v)).collect::<Vec<_>>();
+        assert_eq!(vec![3, 4, 5, 7, 8], aux_vars);
+        assert_eq!(9, pool.new_aux_var());
+        assert_eq!(6, pool.n_aux_vars());
+        assert!(pool.is_aux_var(9));
+    }
+
+    #[test]
+    #[should_panic(expected = "lower than the first auxiliary variable")]
+    fn test_commit_lower() {
+        let mut pool = VarPool::new();
+        pool.new_vars(3);
+        pool.commit_aux_vars(2);
+    }
+
+    #[test]
+    fn test_encode() {
+        let pb2cnf = PB2CNF::new();
+        let mut pool = VarPool::new();
+        let x = pool.new_vars(4);
+        let encoding = pool.encode_constraint(&pb2cnf, &PbConstraint::cardinality(x).eq(2));
+        assert_eq!(5, encoding.first_aux_var());
+        assert_eq!(encoding.next_free_var_id(), pool.first_aux_var());
+        let mut clauses = Vec::new();
+        let y = pool.new_vars(3);
+        pool.encode_constraint_into(
+            &pb2cnf,
+            &PbConstraint::cardinality(y.clone()).geq(2),
+            &mut clauses,
+        );
+        assert!(clauses
+            .iter()
+            .flatten()
+            .all(|l| y.contains(&l.abs()) || pool.is_aux_var(l.abs())));
+    }
+}
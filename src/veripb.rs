@@ -0,0 +1,207 @@
+use crate::{Comparator, EncodingResult, PbConstraint, PbError};
+use std::io::Write;
+
+/// Writes a `VeriPB` proof stating that the clauses of an encoding are implied by the encoded constraint.
+///
+/// The proof is intended to be checked against an OPB file containing only the encoded constraint, as written by [`OpbInstance::write`](crate::OpbInstance::write).
+/// `VeriPB` loads a constraint using the `=` comparator as two inequalities, so the proof declares two input constraints in this case, and one otherwise.
+/// Each clause is derived by reverse unit propagation (`rup`) from the constraint, which allows a proof checker to certify the translation step of a solving pipeline.
+///
+/// Only encodings that do not involve auxiliary variables are supported, since clauses involving auxiliary variables are not implied by the constraint itself.
+/// Such encodings are produced by pblib for small constraints, or when the constraint can be simplified.
+/// Supporting the other encodings requires to introduce the auxiliary variables with redundance-based strengthening (`red`) steps giving their definitions; since these definitions depend on the encoder, this is not done yet.
+///
+/// ```
+/// use pblib_rs::{write_veripb_proof, Comparator, PbConstraint, PB2CNF};
+///
+/// // x1 + x2 <= 1
+/// let constraint = PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Leq, 1);
+/// let encoding = PB2CNF::new().encode_constraint(&constraint, 3);
+/// let mut proof = Vec::new();
+/// write_veripb_proof(&mut proof, &constraint, &encoding).unwrap();
+/// assert_eq!(
+///     "pseudo-Boolean proof version 2.0\nf 1\nrup +1 ~x2 +1 ~x1 >= 1 ;\noutput NONE\nconclusion NONE\nend pseudo-Boolean proof\n",
+///     String::from_utf8(proof).unwrap(),
+/// );
+/// ```
+///
+/// # Errors
+///
+/// An error is returned if the writer fails or if the encoding involves auxiliary variables.
+pub fn write_veripb_proof<W: Write>(
+    writer: &mut W,
+    constraint: &PbConstraint,
+    encoding: &EncodingResult,
+) -> Result<(), PbError> {
+    if encoding
+        .clauses()
+        .iter()
+        .flatten()
+        .any(|l| l.abs() >= encoding.first_aux_var())
+    {
+        return Err(PbError::Unsupported(
+            "proofs for encodings involving auxiliary variables".to_string(),
+        ));
+    }
+    let n_constraints = match constraint.comparator() {
+        Comparator::Eq => 2,
+        _ => 1,
+    };
+    writeln!(writer, "pseudo-Boolean proof version 2.0")?;
+    writeln!(writer, "f {n_constraints}")?;
+    for clause in encoding.clauses() {
+        write!(writer, "rup")?;
+        for l in clause {
+            write!(writer, " +1 {}", veripb_literal(*l))?;
+        }
+        writeln!(writer, " >= 1 ;")?;
+    }
+    writeln!(writer, "output NONE")?;
+    writeln!(writer, "conclusion NONE")?;
+    writeln!(writer, "end pseudo-Boolean proof")?;
+    Ok(())
+}
+
+fn veripb_literal(literal: i32) -> String {
+    if literal < 0 {
+        format!("~x{}", -literal)
+    } else {
+        format!("x{literal}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OpbInstance, PB2CNF};
+    use std::collections::HashMap;
+
+    /// Checks a proof written by [`write_veripb_proof`] against an OPB instance, as `VeriPB` would.
+    ///
+    /// The constraints of the instance are loaded with the equalities split into two inequalities, and each `rup` step is checked by unit propagation.
+    fn check_proof(opb: &str, proof: &str) -> Result<(), String> {
+        let instance = opb.parse::<OpbInstance>().unwrap();
+        let n_loaded = instance
+            .constraints()
+            .iter()
+            .map(|c| {
+                if c.comparator() == Comparator::Eq {
+                    2
+                } else {
+                    1
+                }
+            })
+            .sum::<usize>();
+        let mut lines = proof.lines();
+        assert_eq!(Some("pseudo-Boolean proof version 2.0"), lines.next());
+        if lines.next() != Some(format!("f {n_loaded}").as_str()) {
+            return Err("wrong number of input constraints".to_string());
+        }
+        let mut database = instance
+            .constraints()
+            .iter()
+            .flat_map(PbConstraint::normalize)
+            .collect::<Vec<_>>();
+        for line in lines.by_ref() {
+            let Some(rup) = line.strip_prefix("rup ") else {
+                assert_eq!("output NONE", line);
+                break;
+            };
+            let clause = rup.parse::<OpbInstance>().unwrap().constraints()[0].clone();
+            if !propagates_to_conflict(&database, clause.literals()) {
+                return Err(format!("cannot derive {rup} by unit propagation"));
+            }
+            database.extend(clause.normalize());
+        }
+        assert_eq!(
+            vec!["conclusion NONE", "end pseudo-Boolean proof"],
+            lines.collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    fn propagates_to_conflict(database: &[PbConstraint], falsified: Vec<i32>) -> bool {
+        let mut values = falsified
+            .into_iter()
+            .map(|l| (l.abs(), l < 0))
+            .collect::<HashMap<_, _>>();
+        let is_false = |values: &HashMap<i32, bool>, l: i32| values.get(&l.abs()) == Some(&(l < 0));
+        loop {
+            let mut changed = false;
+            for constraint in database {
+                let slack = constraint
+                    .terms()
+                    .iter()
+                    .filter(|(_, l)| !is_false(&values, *l))
+                    .map(|(w, _)| w)
+                    .sum::<i64>()
+                    - constraint.rhs();
+                if slack < 0 {
+                    return true;
+                }
+                for (w, l) in constraint.terms() {
+                    if *w > slack && !values.contains_key(&l.abs()) {
+                        values.insert(l.abs(), *l > 0);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                return false;
+            }
+        }
+    }
+
+    #[test]
+    fn test_proof() {
+        let constraint = PbConstraint::new(vec![(1, 1), (1, -2), (1, 3)], Comparator::Geq, 1);
+        let encoding = PB2CNF::new().encode_constraint(&constraint, 4);
+        let mut proof = Vec::new();
+        write_veripb_proof(&mut proof, &constraint, &encoding).unwrap();
+        let proof = String::from_utf8(proof).unwrap();
+        let lines = proof.lines().collect::<Vec<_>>();
+        assert_eq!("f 1", lines[1]);
+        assert_eq!(encoding.clauses().len() + 5, lines.len());
+        for (clause, line) in encoding.clauses().iter().zip(&lines[2..]) {
+            assert!(line.starts_with("rup "));
+            assert!(line.ends_with(" >= 1 ;"));
+            assert_eq!(clause.len(), line.matches("+1 ").count());
+        }
+        let opb = OpbInstance::new(3, None, vec![constraint]).to_string();
+        assert_eq!(Ok(()), check_proof(&opb, &proof));
+    }
+
+    #[test]
+    fn test_proof_eq() {
+        let constraint = PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Eq, 1);
+        let encoding = PB2CNF::new().encode_constraint(&constraint, 3);
+        let mut proof = Vec::new();
+        write_veripb_proof(&mut proof, &constraint, &encoding).unwrap();
+        let proof = String::from_utf8(proof).unwrap();
+        assert!(proof.contains("\nf 2\n"));
+        let opb = OpbInstance::new(2, None, vec![constraint]).to_string();
+        assert!(opb.ends_with("\n+1 x1 +1 x2 = 1 ;\n"));
+        assert_eq!(Ok(()), check_proof(&opb, &proof));
+        let opb = "+1 x1 +1 x2 >= 1 ;\n-1 x1 -1 x2 >= -1 ;\n";
+        assert_eq!(Ok(()), check_proof(opb, &proof));
+        assert!(check_proof("+1 x1 +1 x2 >= 1 ;\n", &proof).is_err());
+        assert!(check_proof("+1 x1 +1 x2 >= 1 ;\n+1 x1 >= 0 ;\n", &proof).is_err());
+    }
+
+    #[test]
+    fn test_proof_with_aux_vars() {
+        let constraint = PbConstraint::new(
+            (1..=10).map(|l| (i64::from(l), l)).collect(),
+            Comparator::Leq,
+            20,
+        );
+        let encoding = PB2CNF::new().encode_constraint(&constraint, 11);
+        assert!(encoding.stats().n_aux_vars() > 0);
+        let mut proof = Vec::new();
+        assert!(matches!(
+            write_veripb_proof(&mut proof, &constraint, &encoding),
+            Err(PbError::Unsupported(_))
+        ));
+        assert!(proof.is_empty());
+    }
+}
@@ -0,0 +1,543 @@
+use crate::{
+    encode_instance,
+    opb::{parse_error, Parser, Token},
+    CnfInstance, Comparator, EncoderConfig, OpbInstance, PbConstraint, PbError,
+};
+use std::{
+    io::{Read, Write},
+    str::FromStr,
+};
+
+/// A soft constraint, that is a Pseudo-Boolean constraint that may be violated at the price of a weight.
+///
+/// ```
+/// use pblib_rs::{Comparator, PbConstraint, SoftConstraint};
+///
+/// // [3] x1 + x2 >= 1
+/// let soft = SoftConstraint::new(3, PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Geq, 1));
+/// // x1 + x2 + x3 >= 1, where x3 is the relaxation literal
+/// assert_eq!(
+///     vec![PbConstraint::new(vec![(1, 1), (1, 2), (1, 3)], Comparator::Geq, 1)],
+///     soft.relax(3).unwrap(),
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SoftConstraint {
+    weight: i64,
+    constraint: PbConstraint,
+}
+
+impl SoftConstraint {
+    /// Builds a new soft constraint given the weight paid when it is violated and the underlying constraint.
+    #[must_use]
+    pub fn new(weight: i64, constraint: PbConstraint) -> Self {
+        Self { weight, constraint }
+    }
+
+    /// Returns the weight paid when this constraint is violated.
+    #[must_use]
+    pub fn weight(&self) -> i64 {
+        self.weight
+    }
+
+    /// Returns the underlying constraint.
+    #[must_use]
+    pub fn constraint(&self) -> &PbConstraint {
+        &self.constraint
+    }
+
+    /// Returns hard constraints that are satisfied as soon as the relaxation literal is set to true, and equivalent to this constraint otherwise.
+    ///
+    /// The relaxation literal is added to the constraint with a weight large enough to satisfy it whatever the values of the other literals.
+    /// Constraints involving the `=` comparator are split into two constraints sharing the relaxation literal, and strict comparators are replaced by non-strict ones.
+    /// The relaxation literal is not added if the constraint is always satisfied.
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::Overflow`] error is returned if the weight of the relaxation literal does not fit in an `i64`.
+    pub fn relax(&self, relaxation_literal: i32) -> Result<Vec<PbConstraint>, PbError> {
+        let overflow = || {
+            PbError::Overflow(format!(
+                "the relaxation weight of the soft constraint {} does not fit in 64-bit integers",
+                self.constraint
+            ))
+        };
+        let terms = self.constraint.terms();
+        let min_lhs = terms
+            .iter()
+            .try_fold(0_i64, |acc, t| acc.checked_add(t.0.min(0)))
+            .ok_or_else(overflow)?;
+        let max_lhs = terms
+            .iter()
+            .try_fold(0_i64, |acc, t| acc.checked_add(t.0.max(0)))
+            .ok_or_else(overflow)?;
+        let constraint = self.constraint.non_strict();
+        let terms = constraint.terms();
+        let rhs = constraint.rhs();
+        let geq_relaxation = || {
+            rhs.checked_sub(min_lhs)
+                .map(|w| relaxed_constraint(terms, Comparator::Geq, rhs, w, relaxation_literal))
+                .ok_or_else(overflow)
+        };
+        let leq_relaxation = || {
+            rhs.checked_sub(max_lhs)
+                .map(|w| relaxed_constraint(terms, Comparator::Leq, rhs, w, relaxation_literal))
+                .ok_or_else(overflow)
+        };
+        match constraint.comparator() {
+            Comparator::Geq => Ok(vec![geq_relaxation()?]),
+            Comparator::Leq => Ok(vec![leq_relaxation()?]),
+            Comparator::Eq => Ok(vec![geq_relaxation()?, leq_relaxation()?]),
+            Comparator::Lt | Comparator::Gt => unreachable!(),
+        }
+    }
+}
+
+fn relaxed_constraint(
+    terms: &[(i64, i32)],
+    comparator: Comparator,
+    rhs: i64,
+    relaxation_weight: i64,
+    relaxation_literal: i32,
+) -> PbConstraint {
+    let mut terms = terms.to_vec();
+    let needs_relaxation = match comparator {
+        Comparator::Geq => relaxation_weight > 0,
+        Comparator::Leq => relaxation_weight < 0,
+        Comparator::Eq | Comparator::Lt | Comparator::Gt => unreachable!(),
+    };
+    if needs_relaxation {
+        terms.push((relaxation_weight, relaxation_literal));
+    }
+    PbConstraint::new(terms, comparator, rhs)
+}
+
+/// A Pseudo-Boolean instance involving soft constraints, as described by a WBO file.
+///
+/// An instance is made of hard constraints, that must be satisfied, and soft constraints, that may be violated at the price of their weights.
+/// An optional upper bound (the top cost) can be given: the sum of the weights of the violated soft constraints must be strictly lower than this bound.
+///
+/// ```
+/// use pblib_rs::WboInstance;
+///
+/// let wbo = "* #variable= 2 #constraint= 3 #soft= 2
+/// soft: 4 ;
+/// [2] +1 x1 >= 1 ;
+/// [3] +1 x2 >= 1 ;
+/// +1 x1 +1 x2 <= 1 ;
+/// ";
+/// let instance = WboInstance::parse(wbo.as_bytes()).unwrap();
+/// assert_eq!(Some(4), instance.top());
+/// assert_eq!(1, instance.hard_constraints().len());
+/// assert_eq!(2, instance.soft_constraints().len());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WboInstance {
+    n_vars: usize,
+    top: Option<i64>,
+    hard_constraints: Vec<PbConstraint>,
+    soft_constraints: Vec<SoftConstraint>,
+}
+
+impl WboInstance {
+    /// Builds a new instance given its number of variables, its top cost, its hard constraints and its soft constraints.
+    ///
+    /// The number of variables is increased if needed to take into account all the variables involved in the constraints.
+    #[must_use]
+    pub fn new(
+        n_vars: usize,
+        top: Option<i64>,
+        hard_constraints: Vec<PbConstraint>,
+        soft_constraints: Vec<SoftConstraint>,
+    ) -> Self {
+        let max_var = hard_constraints
+            .iter()
+            .chain(soft_constraints.iter().map(SoftConstraint::constraint))
+            .flat_map(PbConstraint::terms)
+            .map(|t| t.1.unsigned_abs() as usize)
+            .max()
+            .unwrap_or_default();
+        Self {
+            n_vars: n_vars.max(max_var),
+            top,
+            hard_constraints,
+            soft_constraints,
+        }
+    }
+
+    /// Reads an instance in the WBO format.
+    ///
+    /// The instance must begin with a `soft:` line, optionally giving the top cost.
+    /// Soft constraints are prefixed by their weight between square brackets; the other constraints are hard ones.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the reader fails or if the content is not a valid WBO instance.
+    pub fn parse<R: Read>(mut reader: R) -> Result<Self, PbError> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        let mut parser = Parser::new(&content, None)?;
+        match parser.pop() {
+            Some((Token::Soft, _)) => {}
+            Some((_, l)) => return Err(parse_error(l, "expected \"soft:\"")),
+            None => return Err(parse_error(parser.last_line, "unexpected end of input")),
+        }
+        let mut top = None;
+        if let Some((Token::Integer(n), _)) = parser.peek() {
+            parser.pop();
+            top = Some(n);
+        }
+        match parser.pop() {
+            Some((Token::Semicolon, _)) => {}
+            Some((_, l)) => return Err(parse_error(l, "expected a ';' after the top cost")),
+            None => return Err(parse_error(parser.last_line, "unexpected end of input")),
+        }
+        let mut hard_constraints = Vec::new();
+        let mut soft_constraints = Vec::new();
+        while let Some((token, line)) = parser.peek() {
+            if let Token::SoftWeight(weight) = token {
+                if weight <= 0 {
+                    return Err(parse_error(
+                        line,
+                        "soft constraint weights must be positive",
+                    ));
+                }
+                parser.pop();
+                soft_constraints.push(SoftConstraint::new(weight, parser.read_constraint()?));
+            } else {
+                hard_constraints.push(parser.read_constraint()?);
+            }
+        }
+        Ok(WboInstance::new(
+            parser.header_n_vars.unwrap_or_default(),
+            top,
+            hard_constraints,
+            soft_constraints,
+        ))
+    }
+
+    /// Returns the number of variables of the instance.
+    #[must_use]
+    pub fn n_vars(&self) -> usize {
+        self.n_vars
+    }
+
+    /// Returns the top cost, if any.
+    ///
+    /// When set, the sum of the weights of the violated soft constraints must be strictly lower than this value.
+    #[must_use]
+    pub fn top(&self) -> Option<i64> {
+        self.top
+    }
+
+    /// Returns the hard constraints of the instance.
+    #[must_use]
+    pub fn hard_constraints(&self) -> &[PbConstraint] {
+        &self.hard_constraints
+    }
+
+    /// Returns the soft constraints of the instance.
+    #[must_use]
+    pub fn soft_constraints(&self) -> &[SoftConstraint] {
+        &self.soft_constraints
+    }
+
+    /// Translates this instance into an optimization instance without soft constraints.
+    ///
+    /// A relaxation variable is created for each soft constraint; the one of the soft constraint at index `i` is the variable `n_vars() + i + 1`.
+    /// Each soft constraint is replaced by its relaxed version (see [`SoftConstraint::relax`]), and the objective is to minimize the weighted sum of the relaxation variables.
+    /// If a top cost is set, a constraint is added to ensure that this sum is strictly lower than the top cost.
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::Overflow`] error is returned if a soft constraint cannot be relaxed (see [`SoftConstraint::relax`]), or if the top cost is `i64::MIN`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn relax(&self) -> Result<OpbInstance, PbError> {
+        let first_relaxation_var = i32::try_from(self.n_vars + 1).unwrap();
+        let objective = self
+            .soft_constraints
+            .iter()
+            .zip(first_relaxation_var..)
+            .map(|(s, r)| (s.weight(), r))
+            .collect::<Vec<_>>();
+        let mut constraints = self.hard_constraints.clone();
+        for (soft, relaxation_var) in self.soft_constraints.iter().zip(first_relaxation_var..) {
+            constraints.append(&mut soft.relax(relaxation_var)?);
+        }
+        if let Some(top) = self.top {
+            let bound = top.checked_sub(1).ok_or_else(|| {
+                PbError::Overflow(format!(
+                    "the top cost {top} minus 1 does not fit in 64-bit integers"
+                ))
+            })?;
+            constraints.push(PbConstraint::new(objective.clone(), Comparator::Leq, bound));
+        }
+        Ok(OpbInstance::new(
+            self.n_vars + self.soft_constraints.len(),
+            Some(objective),
+            constraints,
+        ))
+    }
+}
+
+/// A Pseudo-Boolean optimization model made of hard constraints and weighted soft constraints, built constraint by constraint.
+///
+/// This is the programmatic counterpart of [`WboInstance`], without top cost: the number of variables is computed from the constraints.
+/// The model is translated into a `MaxSAT` instance in a single call to [`encode_wcnf`](Self::encode_wcnf) or [`write_wcnf`](Self::write_wcnf).
+///
+/// ```
+/// use pblib_rs::{EncoderConfig, PbConstraint, SoftPbInstance};
+///
+/// let mut instance = SoftPbInstance::new();
+/// instance.add_hard(PbConstraint::cardinality([1, 2]).leq(1));
+/// instance.add_soft(2, PbConstraint::cardinality([1]).geq(1));
+/// instance.add_soft(3, PbConstraint::cardinality([2]).geq(1));
+/// let mut output = Vec::new();
+/// instance.write_wcnf(&EncoderConfig::default(), &mut output).unwrap();
+/// // the relaxation variables of the soft constraints are x3 and x4
+/// assert!(String::from_utf8(output).unwrap().ends_with("2 -3 0\n3 -4 0\n"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SoftPbInstance {
+    hard_constraints: Vec<PbConstraint>,
+    soft_constraints: Vec<SoftConstraint>,
+}
+
+impl SoftPbInstance {
+    /// Builds a new instance without constraints.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a hard constraint, that must be satisfied.
+    pub fn add_hard(&mut self, constraint: PbConstraint) {
+        self.hard_constraints.push(constraint);
+    }
+
+    /// Adds a soft constraint, that may be violated at the price of its weight.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the weight is not positive.
+    pub fn add_soft(&mut self, weight: i64, constraint: PbConstraint) {
+        assert!(
+            weight > 0,
+            "the weight of a soft constraint must be positive"
+        );
+        self.soft_constraints
+            .push(SoftConstraint::new(weight, constraint));
+    }
+
+    /// Returns the hard constraints of the instance.
+    #[must_use]
+    pub fn hard_constraints(&self) -> &[PbConstraint] {
+        &self.hard_constraints
+    }
+
+    /// Returns the soft constraints of the instance.
+    #[must_use]
+    pub fn soft_constraints(&self) -> &[SoftConstraint] {
+        &self.soft_constraints
+    }
+
+    /// Returns the number of variables of the instance, that is the highest variable index involved in its constraints.
+    #[must_use]
+    pub fn n_vars(&self) -> usize {
+        self.to_wbo().n_vars()
+    }
+
+    /// Translates this instance into CNF, with the objective function set to the weighted sum of the relaxation variables.
+    ///
+    /// The soft constraints are relaxed as in [`WboInstance::relax`]: the relaxation variable of the soft constraint at index `i` is the variable `n_vars() + i + 1`.
+    /// The hard constraints and the relaxed soft constraints are then encoded using the given configuration (see [`encode_instance`]).
+    /// Writing the result with [`CnfInstance::write_wcnf`] gives the encodings as hard clauses, and a soft unit clause per relaxation variable.
+    ///
+    /// # Errors
+    ///
+    /// A [`PbError::Overflow`] error is returned if a soft constraint cannot be relaxed (see [`SoftConstraint::relax`]).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn encode_wcnf(&self, config: &EncoderConfig) -> Result<CnfInstance, PbError> {
+        Ok(encode_instance(&self.to_wbo().relax()?, config))
+    }
+
+    /// Translates this instance into CNF and writes it in the (new) WCNF format used by `MaxSAT` solvers.
+    ///
+    /// See [`encode_wcnf`](Self::encode_wcnf) for more information.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if a soft constraint cannot be relaxed, or if the writer fails.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of variables does not fit in an `i32`.
+    pub fn write_wcnf<W: Write>(
+        &self,
+        config: &EncoderConfig,
+        writer: &mut W,
+    ) -> Result<(), PbError> {
+        Ok(self.encode_wcnf(config)?.write_wcnf(writer)?)
+    }
+
+    fn to_wbo(&self) -> WboInstance {
+        WboInstance::new(
+            0,
+            None,
+            self.hard_constraints.clone(),
+            self.soft_constraints.clone(),
+        )
+    }
+}
+
+impl FromStr for WboInstance {
+    type Err = PbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode_instance, EncoderConfig};
+    use splr::{Config, Solver};
+
+    #[test]
+    fn test_parse_instance() {
+        let wbo = "* #variable= 3 #constraint= 3 #soft= 2\nsoft: ;\n[2] +1 x1 +1 x2 >= 1 ;\n[ 1 ] -1 x3 = 0 ;\n+1 x1 +1 ~x3 <= 1 ;\n";
+        let instance = WboInstance::parse(wbo.as_bytes()).unwrap();
+        assert_eq!(3, instance.n_vars());
+        assert_eq!(None, instance.top());
+        assert_eq!(
+            &[PbConstraint::new(vec![(1, 1), (1, -3)], Comparator::Leq, 1)],
+            instance.hard_constraints()
+        );
+        assert_eq!(
+            &[
+                SoftConstraint::new(
+                    2,
+                    PbConstraint::new(vec![(1, 1), (1, 2)], Comparator::Geq, 1)
+                ),
+                SoftConstraint::new(1, PbConstraint::new(vec![(-1, 3)], Comparator::Eq, 0)),
+            ],
+            instance.soft_constraints()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let assert_error_line = |wbo: &str, expected_line: usize| match wbo.parse::<WboInstance>() {
+            Err(PbError::Parse { line, .. }) => assert_eq!(expected_line, line),
+            _ => panic!("expected a parse error for {wbo:?}"),
+        };
+        assert_error_line("+1 x1 >= 1 ;", 1);
+        assert_error_line("soft: 3\n+1 x1 >= 1 ;", 2);
+        assert_error_line("soft: ;\n[0] +1 x1 >= 1 ;", 2);
+        assert_error_line("soft: ;\n[2 +1 x1 >= 1 ;", 2);
+        assert_error_line("soft: ;\n[a] +1 x1 >= 1 ;", 2);
+        assert_error_line("", 1);
+        assert_error_line("soft: ;\n[1] +1 x1 x2 >= 1 ;", 2);
+    }
+
+    #[test]
+    fn test_relax() {
+        let soft = SoftConstraint::new(
+            1,
+            PbConstraint::new(vec![(2, 1), (-1, 2)], Comparator::Eq, 1),
+        );
+        assert_eq!(
+            vec![
+                PbConstraint::new(vec![(2, 1), (-1, 2), (2, 3)], Comparator::Geq, 1),
+                PbConstraint::new(vec![(2, 1), (-1, 2), (-1, 3)], Comparator::Leq, 1),
+            ],
+            soft.relax(3).unwrap()
+        );
+        let trivial = SoftConstraint::new(1, PbConstraint::new(vec![(1, 1)], Comparator::Leq, 1));
+        assert_eq!(
+            vec![trivial.constraint().clone()],
+            trivial.relax(2).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_relax_overflow() {
+        let soft = SoftConstraint::new(
+            1,
+            PbConstraint::new(vec![(i64::MIN, 1), (-1, 2)], Comparator::Geq, 0),
+        );
+        assert!(matches!(soft.relax(3), Err(PbError::Overflow(_))));
+        let soft = SoftConstraint::new(
+            1,
+            PbConstraint::new(vec![(-2, 1)], Comparator::Geq, i64::MAX),
+        );
+        assert!(matches!(soft.relax(2), Err(PbError::Overflow(_))));
+        let soft = SoftConstraint::new(1, PbConstraint::new(vec![(1, 1)], Comparator::Leq, 0));
+        let instance = WboInstance::new(1, Some(i64::MIN), vec![], vec![soft]);
+        assert!(matches!(instance.relax(), Err(PbError::Overflow(_))));
+    }
+
+    #[test]
+    fn test_relax_instance() {
+        let instance = "soft: 3 ;\n[2] +1 x1 >= 1 ;\n[3] +1 x2 >= 1 ;\n+1 x1 +1 x2 <= 1 ;"
+            .parse::<WboInstance>()
+            .unwrap();
+        let relaxed = instance.relax().unwrap();
+        assert_eq!(4, relaxed.n_vars());
+        assert_eq!(Some(&[(2, 3), (3, 4)][..]), relaxed.objective());
+        let cnf = encode_instance(&relaxed, &EncoderConfig::default());
+        let mut solver = Solver::try_from((Config::default(), cnf.formula().clauses())).unwrap();
+        let mut models = solver
+            .iter()
+            .map(|m| cnf.original_assignment(&m))
+            .collect::<Vec<_>>();
+        models.sort_unstable();
+        models.dedup();
+        assert_eq!(vec![vec![-1, 2, 3, -4]], models);
+    }
+
+    #[test]
+    fn test_soft_pb_wcnf() {
+        let mut instance = SoftPbInstance::new();
+        instance.add_hard(PbConstraint::new(
+            vec![(2, 1), (1, 2), (1, 3)],
+            Comparator::Leq,
+            2,
+        ));
+        instance.add_soft(4, PbConstraint::cardinality([1]).geq(1));
+        instance.add_soft(1, PbConstraint::cardinality([2, 3]).eq(2));
+        assert_eq!(3, instance.n_vars());
+        let cnf = instance.encode_wcnf(&EncoderConfig::default()).unwrap();
+        assert_eq!(Some(&[(4, 4), (1, 5)][..]), cnf.objective());
+        let mut output = Vec::new();
+        cnf.write_wcnf(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let soft_lines = output
+            .lines()
+            .filter(|l| !l.starts_with('h'))
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["4 -4 0", "1 -5 0"], soft_lines);
+        // the optimal models violate the second soft constraint only
+        let mut solver = Solver::try_from((Config::default(), cnf.formula().clauses())).unwrap();
+        let optimum = solver
+            .iter()
+            .map(|m| cnf.original_assignment(&m))
+            .map(|m| {
+                cnf.objective()
+                    .unwrap()
+                    .iter()
+                    .filter(|(_, l)| m.contains(l))
+                    .map(|(w, _)| w)
+                    .sum::<i64>()
+            })
+            .min();
+        assert_eq!(Some(1), optimum);
+    }
+}